@@ -1,12 +1,22 @@
+use crate::config::station::RepoMapSettings;
+use crate::config::Config;
+use crate::diagnostics::Diagnostics;
 use crate::llm::anthropic::AnthropicClient;
 use crate::llm::types::{ContentBlock, Message, StreamChunk, ToolUse};
-use crate::process::BackgroundShellManager;
-use crate::tool::base::ToolContext;
+use crate::process::notification::format_reminder;
+use crate::process::{BackgroundShellManager, EnvFilter, ResourceLimits, Sandbox, ShellSession};
+use crate::repo_map::{self, RepoMap};
+use crate::subagent::SubagentEvent;
+use crate::tool::base::{ToolContext, ToolProgress};
+use crate::tool::file_state::FileStateTracker;
+use crate::tool::output_limit::OutputLimiter;
 use crate::tool::ToolRegistry;
 use futures::StreamExt;
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::{mpsc, oneshot, Mutex};
 
 #[derive(Debug, Clone)]
 pub enum AgentEvent {
@@ -41,11 +51,32 @@ pub enum AgentEvent {
         tool_use_id: String,
         answers: std::collections::HashMap<String, String>,
     },
+    /// `bash` wants to run a risky command; it waits until the UI calls
+    /// [`AgentRunner::respond_approval`] with the same `tool_use_id`.
+    CommandApprovalRequest {
+        tool_use_id: String,
+        command: String,
+        reasons: String,
+    },
     /// Plan approval requested (ExitPlanMode tool awaiting approval).
     PlanApprovalRequest {
         plan_content: String,
         plan_file: std::path::PathBuf,
     },
+    /// Progress from a subagent running inside the Task tool call `parent_tool_use_id`.
+    SubagentProgress {
+        parent_tool_use_id: String,
+        event: SubagentEvent,
+    },
+    /// Output of a tool call that is still running (e.g. bash lines). Always followed by the
+    /// call's `ToolResult`, which carries the complete output.
+    ToolProgress {
+        tool_use_id: String,
+        tool_name: String,
+        progress: ToolProgress,
+    },
+    /// Diagnostics checkers are running for `files` modified files (the user may cancel them).
+    DiagnosticsStart { files: usize },
 }
 
 /// Question definition for AskUserQuestion tool
@@ -71,14 +102,46 @@ pub struct AgentRunner {
     llm_client: AnthropicClient,
     tool_registry: Arc<ToolRegistry>,
     shell_manager: Arc<BackgroundShellManager>,
+    /// Files read this session; edit/write check it before touching a file
+    file_states: FileStateTracker,
+    /// Checkers from `.ok/diagnostics.toml`, run after tools modify files
+    diagnostics: Option<Arc<Diagnostics>>,
+    /// Checkers of a project the user has not trusted yet (`/trust`); not run
+    untrusted_diagnostics: Option<Diagnostics>,
+    /// Whether the user trusts the project's own code to run: its diagnostics checkers and
+    /// language servers (`[diagnostics] trusted_projects`)
+    trusted_project: bool,
+    /// Sandbox for shell commands (`[sandbox] enabled = true`)
+    sandbox: Option<Arc<Sandbox>>,
+    /// Shell kept across `bash` calls (`[bash] persistent_session = true`)
+    shell_session: Option<Arc<ShellSession>>,
+    /// Which environment variables shell commands inherit (`[bash] env_allow/env_deny`)
+    env_filter: Arc<EnvFilter>,
+    /// CPU, memory and output limits for shell commands (`[bash]`)
+    resource_limits: ResourceLimits,
+    /// Cuts long tool results and saves the full output (`[tool_output]`)
+    output_limiter: Arc<OutputLimiter>,
+    /// `[repo_map]` settings: the map starts the conversation and is refreshed after large edits
+    repo_map: RepoMapSettings,
+    /// Files modified since the repository map was last given to the model
+    repo_map_edits: Arc<AtomicUsize>,
     working_dir: PathBuf,
     session_id: String,
     agent_name: String,
     conversation: Arc<Mutex<Vec<Message>>>,
+    /// Commands waiting for the user's answer, by tool call
+    pending_approvals: PendingApprovals,
 }
 
+type PendingApprovals = Arc<std::sync::Mutex<HashMap<String, oneshot::Sender<bool>>>>;
+
 impl AgentRunner {
     pub fn new(llm_client: AnthropicClient) -> Self {
+        Self::with_config(llm_client, &Config::default())
+    }
+
+    /// Create a runner that applies the user's configuration (e.g. `[subagents.*]` overrides).
+    pub fn with_config(llm_client: AnthropicClient, config: &Config) -> Self {
         let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
         let working_dir = cwd.canonicalize().unwrap_or(cwd);
 
@@ -86,19 +149,134 @@ impl AgentRunner {
         let mut registry = ToolRegistry::new();
         registry.insert_tool(
             "task".to_string(),
-            Arc::new(crate::tool::task::TaskTool::new(Arc::new(
-                llm_client.clone(),
-            ))),
+            Arc::new(
+                crate::tool::task::TaskTool::new(Arc::new(llm_client.clone()))
+                    .with_subagent_settings(config.subagents.clone()),
+            ),
         );
+        registry.insert_tool(
+            "code_intel".to_string(),
+            Arc::new(
+                crate::tool::code_intel::CodeIntelTool::new()
+                    .with_server_settings(config.lsp.clone()),
+            ),
+        );
+
+        let diagnostics = match Diagnostics::load(&working_dir) {
+            Ok(diagnostics) => diagnostics,
+            Err(e) => {
+                tracing::warn!(error = %e, "ignoring invalid diagnostics config");
+                None
+            }
+        };
+        // The checkers come with the repository: only run them once the user trusts it
+        let trusted_project = config.diagnostics.trusts(&working_dir);
+        let (diagnostics, untrusted_diagnostics) = match diagnostics {
+            Some(d) if trusted_project => (Some(d), None),
+            untrusted => (None, untrusted),
+        };
+
+        let env_filter = Arc::new(EnvFilter::new(&config.bash));
+        let resource_limits = ResourceLimits::new(&config.bash);
+        let sandbox = config
+            .sandbox
+            .enabled
+            .then(|| Arc::new(Sandbox::new(&config.sandbox)));
 
         Self {
             llm_client,
             tool_registry: Arc::new(registry),
             shell_manager: Arc::new(BackgroundShellManager::new()),
+            file_states: FileStateTracker::new(),
+            diagnostics: diagnostics.map(|d| Arc::new(Self::confine(d, &env_filter, &sandbox, resource_limits))),
+            untrusted_diagnostics,
+            trusted_project,
+            sandbox,
+            shell_session: config
+                .bash
+                .persistent_session
+                .then(|| {
+                    Arc::new(
+                        ShellSession::new()
+                            .with_env_filter(env_filter.clone())
+                            .with_limits(resource_limits),
+                    )
+                }),
+            env_filter,
+            resource_limits,
+            output_limiter: Arc::new(OutputLimiter::new(config.tool_output.clone())),
+            repo_map: config.repo_map.clone(),
+            repo_map_edits: Arc::new(AtomicUsize::new(0)),
             working_dir,
             session_id: "session_1".to_string(),
             agent_name: "ok".to_string(),
             conversation: Arc::new(Mutex::new(Vec::new())),
+            pending_approvals: Arc::default(),
+        }
+    }
+
+    /// Run checkers with the environment, sandbox and resource limits of shell commands
+    fn confine(
+        diagnostics: Diagnostics,
+        env_filter: &Arc<EnvFilter>,
+        sandbox: &Option<Arc<Sandbox>>,
+        limits: ResourceLimits,
+    ) -> Diagnostics {
+        let diagnostics = diagnostics
+            .with_env_filter(env_filter.clone())
+            .with_resource_limits(limits);
+        match sandbox {
+            Some(sandbox) => diagnostics.with_sandbox(sandbox.clone()),
+            None => diagnostics,
+        }
+    }
+
+    /// What the user should know about the project's diagnostics checkers before they run:
+    /// the commands waiting for `/trust`, if any
+    pub fn diagnostics_notice(&self) -> Option<String> {
+        let diagnostics = self.untrusted_diagnostics.as_ref()?;
+        let commands: Vec<String> = diagnostics
+            .commands()
+            .iter()
+            .map(|c| format!("  {}", c))
+            .collect();
+        Some(format!(
+            "This project's {} wants to run these commands after file edits:\n{}\n\
+             They are not run unless you trust the project: /trust",
+            crate::diagnostics::CONFIG_PATH,
+            commands.join("\n")
+        ))
+    }
+
+    /// Let the project's own code run from the next turn on: its diagnostics checkers and
+    /// language servers. Returns false if it was already trusted.
+    pub fn trust_project(&mut self) -> bool {
+        if self.trusted_project {
+            return false;
+        }
+        self.trusted_project = true;
+        if let Some(diagnostics) = self.untrusted_diagnostics.take() {
+            self.diagnostics = Some(Arc::new(Self::confine(diagnostics, &self.env_filter, &self.sandbox, self.resource_limits)));
+        }
+        true
+    }
+
+    /// The session's working directory
+    pub fn working_dir(&self) -> &std::path::Path {
+        &self.working_dir
+    }
+
+    /// Answer a [`AgentEvent::CommandApprovalRequest`]: run the command or refuse it
+    pub fn respond_approval(&self, tool_use_id: &str, approved: bool) {
+        if let Some(reply) = self.pending_approvals.lock().unwrap().remove(tool_use_id) {
+            let _ = reply.send(approved);
+        }
+    }
+
+    /// Stop the diagnostics checkers running for the current turn (and its subagents)
+    pub fn cancel_diagnostics(&self) {
+        if let Some(diagnostics) = &self.diagnostics {
+            diagnostics.cancel();
         }
     }
 
@@ -110,24 +288,69 @@ impl AgentRunner {
         self.shell_manager.clone()
     }
 
+    /// Whether background shells have reported something the agent has not seen yet.
+    pub fn has_shell_notifications(&self) -> bool {
+        self.shell_manager.has_notifications(&self.session_id)
+    }
+
+    /// Build the repository map of the working directory with the configured budget
+    /// (used by `/map`, whether or not the map is given to the model).
+    pub fn build_repo_map(&self) -> impl std::future::Future<Output = RepoMap> + Send + 'static {
+        repo_map::build_async(self.working_dir.clone(), self.repo_map.token_budget)
+    }
+
     /// Submit a user message and start the agent turn.
     ///
     /// Returns a receiver of `AgentEvent`s for UI consumption.
     pub fn start_turn(&self, user_text: String) -> mpsc::UnboundedReceiver<AgentEvent> {
+        self.spawn_turn(Message::user(user_text))
+    }
+
+    /// Start a turn that reports pending background shell notifications to the model
+    /// (used when the agent is idle). Returns `None` if nothing is pending.
+    pub fn start_notification_turn(&self) -> Option<mpsc::UnboundedReceiver<AgentEvent>> {
+        let notifications = self.shell_manager.take_notifications(&self.session_id);
+        if notifications.is_empty() {
+            return None;
+        }
+        Some(self.spawn_turn(Message::user(format_reminder(&notifications))))
+    }
+
+    fn spawn_turn(&self, user_message: Message) -> mpsc::UnboundedReceiver<AgentEvent> {
         let (tx, rx) = mpsc::unbounded_channel();
 
         let llm_client = self.llm_client.clone();
         let registry = self.tool_registry.clone();
         let shell_manager = self.shell_manager.clone();
+        let file_states = self.file_states.clone();
+        let diagnostics = self.diagnostics.clone();
+        let sandbox = self.sandbox.clone();
+        let shell_session = self.shell_session.clone();
+        let env_filter = self.env_filter.clone();
+        let resource_limits = self.resource_limits;
+        let trusted_project = self.trusted_project;
+        let output_limiter = self.output_limiter.clone();
+        let repo_map_settings = self.repo_map.clone();
+        let repo_map_edits = self.repo_map_edits.clone();
         let working_dir = self.working_dir.clone();
         let session_id = self.session_id.clone();
         let agent_name = self.agent_name.clone();
         let conversation = self.conversation.clone();
+        let pending_approvals = self.pending_approvals.clone();
 
         tokio::spawn(async move {
+            // The first message of the session carries the repository map (requests have no
+            // system prompt; see `repo_map` for why the map is not moved there)
+            let mut user_message = user_message;
+            if repo_map_settings.enabled && conversation.lock().await.is_empty() {
+                let map = repo_map::build_async(working_dir.clone(), repo_map_settings.token_budget).await;
+                user_message.append_text(map.reminder(
+                    "Repository map (files and their most referenced symbols):",
+                ));
+            }
             {
                 let mut convo = conversation.lock().await;
-                convo.push(Message::user(user_text));
+                convo.push(user_message);
             }
 
             loop {
@@ -135,7 +358,17 @@ impl AgentRunner {
                     return;
                 }
 
-                let conversation_snapshot = { conversation.lock().await.clone() };
+                // Report background shells that finished or matched their watch pattern
+                let notifications = shell_manager.take_notifications(&session_id);
+                let conversation_snapshot = {
+                    let mut convo = conversation.lock().await;
+                    if !notifications.is_empty() {
+                        if let Some(last) = convo.last_mut() {
+                            last.append_text(format_reminder(&notifications));
+                        }
+                    }
+                    convo.clone()
+                };
                 let tool_definitions = Some(registry.list_tool_definitions());
 
                 let mut stream = match llm_client
@@ -235,22 +468,87 @@ impl AgentRunner {
                         }
                     };
 
-                    let ctx = ToolContext::new(
+                    let (subagent_tx, mut subagent_rx) = mpsc::unbounded_channel();
+                    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+                    let (approval_tx, mut approval_rx) = mpsc::unbounded_channel();
+                    let mut ctx = ToolContext::new(
                         session_id.clone(),
                         tool_use_id.clone(),
                         agent_name.clone(),
                         working_dir.clone(),
                         shell_manager.clone(),
-                    );
+                    )
+                    .with_subagent_events(subagent_tx)
+                    .with_progress(progress_tx)
+                    .with_approvals(approval_tx)
+                    .with_file_states(file_states.clone())
+                    .with_output_limiter(output_limiter.clone())
+                    .with_env_filter(env_filter.clone())
+                    .with_resource_limits(resource_limits)
+                    .with_trusted_project(trusted_project);
+                    if let Some(diagnostics) = &diagnostics {
+                        ctx = ctx.with_diagnostics(diagnostics.clone());
+                    }
+                    if let Some(sandbox) = &sandbox {
+                        ctx = ctx.with_sandbox(sandbox.clone());
+                    }
+                    if let Some(session) = &shell_session {
+                        ctx = ctx.with_shell_session(session.clone());
+                    }
 
-                    let result = tool.execute(tool_use.input, &ctx).await;
+                    // Forward subagent and output progress while the tool runs; drain the rest
+                    // before the tool result so the UI sees events in order.
+                    let tool_progress = |progress| AgentEvent::ToolProgress {
+                        tool_use_id: tool_use_id.clone(),
+                        tool_name: tool_name.clone(),
+                        progress,
+                    };
+                    let execution = tool.execute(tool_use.input, &ctx);
+                    tokio::pin!(execution);
+                    let result = loop {
+                        tokio::select! {
+                            result = &mut execution => break result,
+                            Some(event) = subagent_rx.recv() => {
+                                let _ = tx.send(AgentEvent::SubagentProgress {
+                                    parent_tool_use_id: tool_use_id.clone(),
+                                    event,
+                                });
+                            }
+                            Some(progress) = progress_rx.recv() => {
+                                let _ = tx.send(tool_progress(progress));
+                            }
+                            // The tool waits for the user's answer (see `respond_approval`)
+                            Some(request) = approval_rx.recv() => {
+                                pending_approvals
+                                    .lock()
+                                    .unwrap()
+                                    .insert(tool_use_id.clone(), request.reply);
+                                let _ = tx.send(AgentEvent::CommandApprovalRequest {
+                                    tool_use_id: tool_use_id.clone(),
+                                    command: request.command,
+                                    reasons: request.reasons,
+                                });
+                            }
+                        }
+                    };
+                    while let Ok(event) = subagent_rx.try_recv() {
+                        let _ = tx.send(AgentEvent::SubagentProgress {
+                            parent_tool_use_id: tool_use_id.clone(),
+                            event,
+                        });
+                    }
+                    while let Ok(progress) = progress_rx.try_recv() {
+                        let _ = tx.send(tool_progress(progress));
+                    }
                     let (result_content, is_error) = match result {
                         Ok(tool_result) => {
+                            let output =
+                                output_limiter.limit(&tool_name, &tool_use_id, tool_result.output);
                             let formatted = format!(
                                 "Tool: {}\nWorking directory: {}\nOutput:\n{}",
                                 tool_result.title,
                                 working_dir.display(),
-                                tool_result.output
+                                output
                             );
                             (formatted, false)
                         }
@@ -272,6 +570,34 @@ impl AgentRunner {
                     ));
                 }
 
+                // Check the files this batch of tools modified; the report rides along with the
+                // last tool result.
+                let modified = file_states.take_modified();
+                if let Some(diagnostics) = diagnostics.as_ref().filter(|d| d.applies_to(&modified)) {
+                    let _ = tx.send(AgentEvent::DiagnosticsStart { files: modified.len() });
+                    if let Some(report) = diagnostics.check(&modified).await {
+                        let mut convo = conversation.lock().await;
+                        if let Some(last) = convo.last_mut() {
+                            last.append_text(report);
+                        }
+                    }
+                }
+
+                // After large edits the map given at session start is stale; send a fresh one
+                if repo_map_settings.enabled && !modified.is_empty() {
+                    let edits = repo_map_edits.fetch_add(modified.len(), Ordering::SeqCst) + modified.len();
+                    if edits >= repo_map_settings.refresh_after_files {
+                        repo_map_edits.store(0, Ordering::SeqCst);
+                        let map = repo_map::build_async(working_dir.clone(), repo_map_settings.token_budget).await;
+                        let mut convo = conversation.lock().await;
+                        if let Some(last) = convo.last_mut() {
+                            last.append_text(map.reminder(
+                                "Repository map, refreshed after the recent edits:",
+                            ));
+                        }
+                    }
+                }
+
                 // Continue loop: call LLM again with updated conversation.
             }
         });
@@ -45,11 +45,14 @@ pub async fn run() -> Result<()> {
     let mut terminal = setup_terminal()?;
 
     // Create app state
-    let mut app = App::new(llm_client);
+    let mut app = App::new(llm_client, &config);
 
     // Run the application
     let result = run_app(&mut terminal, &mut app).await;
 
+    // Don't leave background jobs running after the UI is gone
+    app.shutdown().await;
+
     // Restore terminal
     restore_terminal(&mut terminal)?;
 
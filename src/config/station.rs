@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +29,34 @@ pub struct Config {
     /// Available LLM stations
     #[serde(default)]
     pub stations: Vec<Station>,
+
+    /// Per-subagent-type overrides, keyed by subagent type name (e.g. "Explore").
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub subagents: HashMap<String, SubagentSettings>,
+
+    /// Language servers keyed by language (e.g. "rust"), overriding the built-in defaults.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub lsp: HashMap<String, LspServerSettings>,
+
+    /// Repository map given to the model at session start
+    #[serde(default, skip_serializing_if = "RepoMapSettings::is_default")]
+    pub repo_map: RepoMapSettings,
+
+    /// Run `bash` commands and background shells in a sandbox (Linux only)
+    #[serde(default, skip_serializing_if = "SandboxSettings::is_default")]
+    pub sandbox: SandboxSettings,
+
+    /// Behaviour of the `bash` tool
+    #[serde(default, skip_serializing_if = "BashSettings::is_default")]
+    pub bash: BashSettings,
+
+    /// Size limits for tool results given to the model
+    #[serde(default, skip_serializing_if = "ToolOutputSettings::is_default")]
+    pub tool_output: ToolOutputSettings,
+
+    /// Projects whose `.ok/diagnostics.toml` checkers may run
+    #[serde(default, skip_serializing_if = "DiagnosticsSettings::is_default")]
+    pub diagnostics: DiagnosticsSettings,
 }
 
 impl Default for Config {
@@ -49,6 +79,13 @@ impl Default for Config {
                     temperature: Some(1.0),
                 },
             ],
+            subagents: HashMap::new(),
+            lsp: HashMap::new(),
+            repo_map: RepoMapSettings::default(),
+            sandbox: SandboxSettings::default(),
+            bash: BashSettings::default(),
+            tool_output: ToolOutputSettings::default(),
+            diagnostics: DiagnosticsSettings::default(),
         }
     }
 }
@@ -104,6 +141,221 @@ impl Provider {
     }
 }
 
+/// Overrides for one subagent type (`[subagents.<type>]`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SubagentSettings {
+    /// Maximum number of tool-calling turns before the subagent is asked to wrap up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_turns: Option<usize>,
+}
+
+/// Language server for one language (`[lsp.<language>]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LspServerSettings {
+    /// Server executable (speaks LSP over stdio)
+    pub command: String,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+
+    /// File extensions handled by this server (without the dot)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<String>,
+
+    /// `languageId` sent when opening files (defaults to the section name)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language_id: Option<String>,
+}
+
+/// Repository map settings (`[repo_map]`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepoMapSettings {
+    /// Include the map in the model's context at session start
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Approximate size of the map in tokens
+    #[serde(default = "default_repo_map_token_budget")]
+    pub token_budget: usize,
+
+    /// Rebuild the map once tools have modified this many files since it was last built
+    #[serde(default = "default_repo_map_refresh_after_files")]
+    pub refresh_after_files: usize,
+}
+
+impl Default for RepoMapSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            token_budget: default_repo_map_token_budget(),
+            refresh_after_files: default_repo_map_refresh_after_files(),
+        }
+    }
+}
+
+impl RepoMapSettings {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Shell sandbox settings (`[sandbox]`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SandboxSettings {
+    /// Confine shell commands: writes only under the working directory and temp roots
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Keep network access inside the sandbox (off by default)
+    #[serde(default)]
+    pub network: bool,
+
+    #[serde(default)]
+    pub backend: SandboxBackend,
+}
+
+impl SandboxSettings {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// `bash` tool settings (`[bash]`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BashSettings {
+    /// Run commands in one long-lived shell per session, so `cd` and `export` carry over
+    #[serde(default)]
+    pub persistent_session: bool,
+
+    /// Only these of the agent's environment variables reach shell commands (`*` wildcards;
+    /// empty = all of them)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env_allow: Vec<String>,
+
+    /// Environment variables removed from shell commands (`*` wildcards), even if allowed
+    #[serde(default = "default_bash_env_deny")]
+    pub env_deny: Vec<String>,
+
+    /// CPU seconds each process a command starts may use
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_seconds: Option<u64>,
+
+    /// Memory (address space, in MiB) each process a command starts may use
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_mb: Option<u64>,
+
+    /// Output a command may print before it is stopped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_bytes: Option<u64>,
+}
+
+impl Default for BashSettings {
+    fn default() -> Self {
+        Self {
+            persistent_session: false,
+            env_allow: Vec::new(),
+            env_deny: default_bash_env_deny(),
+            cpu_seconds: None,
+            memory_mb: None,
+            max_output_bytes: None,
+        }
+    }
+}
+
+impl BashSettings {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Post-edit diagnostics settings (`[diagnostics]`).
+///
+/// A project's `.ok/diagnostics.toml` comes with the repository, so its commands only run once
+/// the user has trusted the project here (or with `/trust`). The same trust lets language
+/// servers, which run the project's build scripts, start.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticsSettings {
+    /// Project directories whose checkers and language servers may run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trusted_projects: Vec<PathBuf>,
+}
+
+impl DiagnosticsSettings {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether the checkers of the project in `dir` may run
+    pub fn trusts(&self, dir: &Path) -> bool {
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        self.trusted_projects
+            .iter()
+            .any(|p| p.canonicalize().unwrap_or_else(|_| p.clone()) == dir)
+    }
+
+    /// Trust the project in `dir`
+    pub fn trust(&mut self, dir: &Path) {
+        if !self.trusts(dir) {
+            self.trusted_projects.push(dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf()));
+        }
+    }
+}
+
+/// Tool result size limits (`[tool_output]`).
+///
+/// A longer result keeps its first and last lines; the full output is saved to a file the model
+/// can page through with `read`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolOutputSettings {
+    /// Largest result, in bytes, given to the model as is
+    #[serde(default = "default_tool_output_max_bytes")]
+    pub max_bytes: usize,
+
+    /// Per-tool overrides of `max_bytes`, keyed by tool name (0 = no limit)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub limits: HashMap<String, usize>,
+}
+
+impl Default for ToolOutputSettings {
+    fn default() -> Self {
+        Self {
+            max_bytes: default_tool_output_max_bytes(),
+            limits: HashMap::new(),
+        }
+    }
+}
+
+impl ToolOutputSettings {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Limit for `tool`'s results, `None` if they are never cut. `read` pages through files
+    /// itself (including saved outputs), so it has no limit unless one is configured.
+    pub fn limit_for(&self, tool: &str) -> Option<usize> {
+        let limit = match self.limits.get(tool) {
+            Some(&limit) => limit,
+            None if tool == "read" => 0,
+            None => self.max_bytes,
+        };
+        (limit > 0).then_some(limit)
+    }
+}
+
+/// How the sandbox is set up.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SandboxBackend {
+    /// bubblewrap when it is installed, otherwise unshare
+    #[default]
+    Auto,
+    /// `bwrap`
+    Bubblewrap,
+    /// Namespaces set up by the agent (unprivileged user namespaces), a seccomp filter and
+    /// util-linux `setpriv`
+    Unshare,
+}
+
 /// Debug log rotation strategy.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
@@ -116,3 +368,29 @@ pub enum DebugLogRotation {
 fn default_station_id() -> String {
     "claude".to_string()
 }
+
+fn default_repo_map_token_budget() -> usize {
+    1024
+}
+
+fn default_repo_map_refresh_after_files() -> usize {
+    10
+}
+
+fn default_bash_env_deny() -> Vec<String> {
+    [
+        "*_API_KEY",
+        "*_AUTH_TOKEN",
+        "*_ACCESS_TOKEN",
+        "*_SECRET",
+        "*_SECRET_KEY",
+        "*_SECRET_ACCESS_KEY",
+        "*_PASSWORD",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+fn default_tool_output_max_bytes() -> usize {
+    30_000
+}
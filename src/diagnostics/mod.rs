@@ -0,0 +1,464 @@
+//! Post-edit diagnostics: run project checkers after the agent modifies files
+//!
+//! Configured per project in `.ok/diagnostics.toml`, mapping globs to commands whose output is
+//! parsed into diagnostics for the modified files and reported to the model. The file comes with
+//! the repository, so its commands only run for projects the user trusts.
+
+pub mod parse;
+
+use anyhow::Context;
+use globset::{Glob, GlobMatcher};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::{Notify, OnceCell};
+
+use crate::process::group::{self, OutputCap, ProcessGroup, ResourceLimits};
+use crate::process::{EnvFilter, Sandbox};
+use crate::tool::base::allowed_roots;
+
+/// Project-relative location of the diagnostics config
+pub const CONFIG_PATH: &str = ".ok/diagnostics.toml";
+
+/// Contents of `.ok/diagnostics.toml`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiagnosticsConfig {
+    /// Wait this long after a batch of modifications before running checkers
+    #[serde(default = "default_debounce_ms")]
+    pub debounce_ms: u64,
+
+    /// Give up on a checker after this many seconds
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+
+    /// Maximum diagnostics reported per checker run
+    #[serde(default = "default_max_diagnostics")]
+    pub max_diagnostics: usize,
+
+    #[serde(default)]
+    pub checkers: Vec<CheckerConfig>,
+}
+
+fn default_debounce_ms() -> u64 {
+    200
+}
+
+fn default_timeout_secs() -> u64 {
+    120
+}
+
+fn default_max_diagnostics() -> usize {
+    30
+}
+
+/// One `[[checkers]]` entry
+#[derive(Debug, Clone, Deserialize)]
+pub struct CheckerConfig {
+    /// Files that trigger this checker (relative to the project root, e.g. `*.rs`)
+    pub glob: String,
+    /// Shell command run from the project root
+    pub command: String,
+    #[serde(default)]
+    pub format: OutputFormat,
+}
+
+/// How a checker's output is parsed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// rustc/cargo `--message-format=json`
+    RustcJson,
+    /// `file:line[:col]: [severity:] message`
+    #[default]
+    Generic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+}
+
+/// A problem reported by a checker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: Option<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Runs the configured checkers for modified files
+///
+/// The checkers come from the repository, so they run like `bash` commands: with the filtered
+/// environment and resource limits, in the sandbox when it is enabled, and in a process group of
+/// their own that is stopped on timeout, cancellation or too much output. Whether they run at all is the user's call (see
+/// `[diagnostics] trusted_projects`).
+pub struct Diagnostics {
+    working_dir: PathBuf,
+    config: DiagnosticsConfig,
+    matchers: Vec<GlobMatcher>,
+    /// Environment checkers inherit (`[bash] env_allow/env_deny`)
+    env_filter: Arc<EnvFilter>,
+    /// Sandbox checkers run in (`[sandbox]`), if enabled
+    sandbox: Option<Arc<Sandbox>>,
+    /// CPU, memory and output limits of checkers (`[bash]`)
+    limits: ResourceLimits,
+    /// Modifications waiting to be checked, shared by concurrent `check` calls
+    pending: Mutex<Option<Arc<Batch>>>,
+    /// Wakes the `check` calls in progress when the user cancels them
+    cancel: Notify,
+}
+
+/// Files modified by one or more `check` calls (the agent's and its subagents') that are
+/// checked together by one run of the checkers
+struct Batch {
+    files: Mutex<BTreeSet<PathBuf>>,
+    /// When files were last added; the run starts once none have been for `debounce_ms`
+    changed: Mutex<Instant>,
+    results: OnceCell<Vec<CheckerResult>>,
+}
+
+/// A triggered checker (by index) with its diagnostics, or why it could not be run
+type CheckerResult = (usize, Result<Vec<Diagnostic>, String>);
+
+/// Read a checker's output until it ends or `cap` is reached
+async fn read_capped<R: AsyncRead + Unpin>(reader: &mut R, cap: &OutputCap) -> std::io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut chunk = [0u8; 8192];
+    loop {
+        let n = reader.read(&mut chunk).await?;
+        if n == 0 || !cap.add(n) {
+            return Ok(output);
+        }
+        output.extend_from_slice(&chunk[..n]);
+    }
+}
+
+impl Diagnostics {
+    /// Load `.ok/diagnostics.toml` from the project; `Ok(None)` if it doesn't exist
+    pub fn load(working_dir: &Path) -> anyhow::Result<Option<Self>> {
+        let path = working_dir.join(CONFIG_PATH);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let config: DiagnosticsConfig = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        Self::new(working_dir.to_path_buf(), config).map(Some)
+    }
+
+    pub fn new(working_dir: PathBuf, config: DiagnosticsConfig) -> anyhow::Result<Self> {
+        let matchers = config
+            .checkers
+            .iter()
+            .map(|checker| {
+                Glob::new(&checker.glob)
+                    .map(|glob| glob.compile_matcher())
+                    .with_context(|| format!("Invalid diagnostics glob '{}'", checker.glob))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            working_dir,
+            config,
+            matchers,
+            env_filter: Arc::new(EnvFilter::default()),
+            sandbox: None,
+            limits: ResourceLimits::default(),
+            pending: Mutex::new(None),
+            cancel: Notify::new(),
+        })
+    }
+
+    /// Filter the environment checkers inherit, as for shell commands
+    pub fn with_env_filter(mut self, env_filter: Arc<EnvFilter>) -> Self {
+        self.env_filter = env_filter;
+        self
+    }
+
+    /// Run checkers in the shell sandbox
+    pub fn with_sandbox(mut self, sandbox: Arc<Sandbox>) -> Self {
+        self.sandbox = Some(sandbox);
+        self
+    }
+
+    /// Limit what checkers may use, as for shell commands
+    pub fn with_resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The configured checker commands, for asking the user whether to trust them
+    pub fn commands(&self) -> Vec<&str> {
+        self.config.checkers.iter().map(|c| c.command.as_str()).collect()
+    }
+
+    /// Whether a checker applies to one of `modified` (i.e. [`check`](Self::check) will run one)
+    pub fn applies_to(&self, modified: &[PathBuf]) -> bool {
+        !self.triggered(modified).is_empty()
+    }
+
+    /// Stop the checks in progress; their callers get a note that diagnostics were skipped
+    pub fn cancel(&self) {
+        self.cancel.notify_waiters();
+    }
+
+    /// Run every checker matching one of `modified` and report their diagnostics for those files
+    ///
+    /// Modifications reported by other calls within `debounce_ms` of each other are checked by
+    /// one run. Returns a `<system-reminder>` block for the model, or None if no checker applies.
+    pub async fn check(&self, modified: &[PathBuf]) -> Option<String> {
+        if !self.applies_to(modified) {
+            return None;
+        }
+
+        let cancelled = self.cancel.notified();
+        tokio::pin!(cancelled);
+        cancelled.as_mut().enable();
+
+        let batch = self.join_batch(modified);
+        let results = tokio::select! {
+            results = self.wait_and_run(&batch) => results,
+            _ = cancelled => {
+                tracing::debug!(files = modified.len(), "diagnostics cancelled");
+                return Some(
+                    "<system-reminder>\nDiagnostics after your last file changes were cancelled by \
+                     the user.\n</system-reminder>"
+                        .to_string(),
+                );
+            }
+        };
+
+        // Each caller only hears about the checkers and files its own changes triggered
+        let triggered = self.triggered(modified);
+        let sections: Vec<String> = results
+            .iter()
+            .filter(|(index, _)| triggered.contains(index))
+            .map(|(index, result)| {
+                let checker = &self.config.checkers[*index];
+                match result {
+                    Ok(diagnostics) => self.format_section(checker, modified, diagnostics.clone()),
+                    Err(e) => format!("`{}` could not be run: {}", checker.command, e),
+                }
+            })
+            .collect();
+
+        Some(format!(
+            "<system-reminder>\nDiagnostics after your last file changes:\n{}\n</system-reminder>",
+            sections.join("\n")
+        ))
+    }
+
+    /// Indices of the checkers matching one of `modified`
+    fn triggered(&self, modified: &[PathBuf]) -> Vec<usize> {
+        let relative: Vec<PathBuf> = modified.iter().map(|p| self.relative(p)).collect();
+        self.matchers
+            .iter()
+            .enumerate()
+            .filter(|(_, matcher)| relative.iter().any(|p| matcher.is_match(p)))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Add `modified` to the batch waiting to run, starting one if none is
+    fn join_batch(&self, modified: &[PathBuf]) -> Arc<Batch> {
+        let mut pending = self.pending.lock().unwrap();
+        let batch = pending.get_or_insert_with(|| {
+            Arc::new(Batch {
+                files: Mutex::new(BTreeSet::new()),
+                changed: Mutex::new(Instant::now()),
+                results: OnceCell::new(),
+            })
+        });
+        batch.files.lock().unwrap().extend(modified.iter().cloned());
+        *batch.changed.lock().unwrap() = Instant::now();
+        batch.clone()
+    }
+
+    /// Wait until no files have joined `batch` for `debounce_ms`, then run its checkers once
+    async fn wait_and_run<'a>(
+        &self,
+        batch: &'a Batch,
+    ) -> &'a Vec<CheckerResult> {
+        let debounce = Duration::from_millis(self.config.debounce_ms);
+        loop {
+            let quiet_at = *batch.changed.lock().unwrap() + debounce;
+            if Instant::now() >= quiet_at {
+                break;
+            }
+            tokio::time::sleep_until(quiet_at.into()).await;
+        }
+
+        batch
+            .results
+            .get_or_init(|| async {
+                // Later modifications start a new batch
+                {
+                    let mut pending = self.pending.lock().unwrap();
+                    if pending.as_ref().is_some_and(|p| std::ptr::eq(p.as_ref(), batch)) {
+                        *pending = None;
+                    }
+                }
+                let files: Vec<PathBuf> = batch.files.lock().unwrap().iter().cloned().collect();
+
+                let mut results = Vec::new();
+                for index in self.triggered(&files) {
+                    let checker = &self.config.checkers[index];
+                    tracing::debug!(command = %checker.command, files = files.len(), "running diagnostics");
+                    let result = self.run(checker).await.map_err(|e| {
+                        tracing::warn!(command = %checker.command, error = %e, "diagnostics command failed");
+                        e.to_string()
+                    });
+                    results.push((index, result));
+                }
+                results
+            })
+            .await
+    }
+
+    /// Run a checker and parse its combined output
+    async fn run(&self, checker: &CheckerConfig) -> anyhow::Result<Vec<Diagnostic>> {
+        let mut command = match &self.sandbox {
+            Some(sandbox) => {
+                sandbox.ensure_available().await?;
+                sandbox.command("bash", &checker.command, &self.working_dir, &allowed_roots(&self.working_dir))
+            }
+            None => {
+                let mut command = tokio::process::Command::new("bash");
+                command.arg("-c").arg(&checker.command);
+                command
+            }
+        };
+        command.current_dir(&self.working_dir).kill_on_drop(true);
+        self.env_filter.apply(&mut command);
+        self.limits.apply(&mut command);
+
+        // In a group of its own, so a timeout or cancellation also stops what the checker started
+        group::new_group(&mut command);
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let mut group = ProcessGroup::of(&child);
+        let cap = OutputCap::new(self.limits.output_bytes, &group);
+
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let result = tokio::time::timeout(timeout, async {
+            let mut stdout = child.stdout.take().expect("Failed to capture stdout");
+            let mut stderr = child.stderr.take().expect("Failed to capture stderr");
+            let (out, err) = tokio::join!(read_capped(&mut stdout, &cap), read_capped(&mut stderr, &cap));
+            let (out, err) = (out?, err?);
+            child.wait().await?;
+            Ok::<_, std::io::Error>((out, err))
+        })
+        .await;
+
+        let (stdout, stderr) = match result {
+            Ok(output) => {
+                group.release();
+                output?
+            }
+            Err(_) => {
+                group.terminate(&mut child).await;
+                anyhow::bail!("timed out after {}s", self.config.timeout_secs);
+            }
+        };
+        // Cut-off output would give a partial list of problems
+        if let Some(note) = cap.note() {
+            anyhow::bail!("{}", note);
+        }
+
+        // Checkers exit non-zero when they find problems, so the status is not an error
+        let mut text = String::from_utf8_lossy(&stdout).into_owned();
+        text.push('\n');
+        text.push_str(&String::from_utf8_lossy(&stderr));
+
+        Ok(match checker.format {
+            OutputFormat::RustcJson => parse::parse_rustc_json(&text, &self.working_dir),
+            OutputFormat::Generic => parse::parse_generic(&text, &self.working_dir),
+        })
+    }
+
+    /// Summarize one checker's diagnostics for the modified files
+    fn format_section(
+        &self,
+        checker: &CheckerConfig,
+        modified: &[PathBuf],
+        diagnostics: Vec<Diagnostic>,
+    ) -> String {
+        let modified: Vec<PathBuf> = modified.iter().map(|p| normalize(p)).collect();
+        let mut relevant: Vec<Diagnostic> = diagnostics
+            .into_iter()
+            .filter(|d| modified.contains(&normalize(&d.path)))
+            .collect();
+        relevant.sort_by(|a, b| {
+            (&a.path, a.line, a.column, a.severity).cmp(&(&b.path, b.line, b.column, b.severity))
+        });
+        relevant.dedup();
+
+        let files: Vec<String> = modified
+            .iter()
+            .map(|p| self.relative(p).display().to_string())
+            .collect();
+        if relevant.is_empty() {
+            return format!(
+                "`{}`: no problems reported in {}",
+                checker.command,
+                files.join(", ")
+            );
+        }
+
+        let errors = relevant.iter().filter(|d| d.severity == Severity::Error).count();
+        let mut lines = vec![format!(
+            "`{}`: {} error(s), {} other diagnostic(s) in {}",
+            checker.command,
+            errors,
+            relevant.len() - errors,
+            files.join(", ")
+        )];
+        for d in relevant.iter().take(self.config.max_diagnostics) {
+            let location = match d.column {
+                Some(col) => format!("{}:{}:{}", self.relative(&d.path).display(), d.line, col),
+                None => format!("{}:{}", self.relative(&d.path).display(), d.line),
+            };
+            lines.push(format!("- {}: {}: {}", location, d.severity.as_str(), d.message));
+        }
+        if relevant.len() > self.config.max_diagnostics {
+            lines.push(format!(
+                "- ... and {} more",
+                relevant.len() - self.config.max_diagnostics
+            ));
+        }
+        lines.join("\n")
+    }
+
+    fn relative(&self, path: &Path) -> PathBuf {
+        let path = normalize(path);
+        path.strip_prefix(normalize(&self.working_dir))
+            .map(Path::to_path_buf)
+            .unwrap_or(path)
+    }
+}
+
+/// Canonical form used to compare checker paths with tool paths
+fn normalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,127 @@
+//! Parsers turning checker output into diagnostics
+
+use regex::Regex;
+use serde_json::Value;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use super::{Diagnostic, Severity};
+
+/// Parse rustc JSON diagnostics, either bare (`rustc --error-format=json`) or wrapped in
+/// cargo's `compiler-message` records (`cargo check --message-format=json`)
+pub fn parse_rustc_json(output: &str, working_dir: &Path) -> Vec<Diagnostic> {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line.trim()).ok())
+        .filter_map(|record| {
+            let message = match record.get("reason").and_then(Value::as_str) {
+                Some("compiler-message") => record.get("message")?.clone(),
+                Some(_) => return None,
+                None => record,
+            };
+
+            let severity = match message.get("level").and_then(Value::as_str)? {
+                "error" | "error: internal compiler error" => Severity::Error,
+                "warning" => Severity::Warning,
+                "note" | "help" => Severity::Note,
+                _ => return None,
+            };
+            // Summary lines like "aborting due to 2 previous errors" have no spans
+            let span = message
+                .get("spans")?
+                .as_array()?
+                .iter()
+                .find(|span| span.get("is_primary").and_then(Value::as_bool) == Some(true))?;
+
+            Some(Diagnostic {
+                path: working_dir.join(span.get("file_name")?.as_str()?),
+                line: span.get("line_start")?.as_u64()? as usize,
+                column: span.get("column_start").and_then(Value::as_u64).map(|c| c as usize),
+                severity,
+                message: message.get("message")?.as_str()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Parse `file:line[:col]: [severity:] message` lines (gcc, eslint unix, ruff concise, mypy, ...)
+pub fn parse_generic(output: &str, working_dir: &Path) -> Vec<Diagnostic> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern = PATTERN.get_or_init(|| {
+        Regex::new(
+            r"^(?P<file>[^:\s][^:]*):(?P<line>\d+):(?:(?P<col>\d+):)?\s*(?:(?P<sev>error|warning|note|info|hint)\s*:?\s*)?(?P<msg>\S.*)$",
+        )
+        .expect("valid diagnostic pattern")
+    });
+
+    output
+        .lines()
+        .filter_map(|line| {
+            let caps = pattern.captures(line.trim_end())?;
+            let severity = match caps.name("sev").map(|m| m.as_str()) {
+                Some("warning") => Severity::Warning,
+                Some("note") | Some("info") | Some("hint") => Severity::Note,
+                _ => Severity::Error,
+            };
+            Some(Diagnostic {
+                path: working_dir.join(&caps["file"]),
+                line: caps["line"].parse().ok()?,
+                column: caps.name("col").and_then(|m| m.as_str().parse().ok()),
+                severity,
+                message: caps["msg"].trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_json() {
+        let output = r#"{"reason":"compiler-artifact","target":{"name":"dep"}}
+{"reason":"compiler-message","message":{"message":"cannot find value `x` in this scope","level":"error","spans":[{"file_name":"src/lib.rs","line_start":3,"column_start":5,"is_primary":true}]}}
+{"reason":"compiler-message","message":{"message":"unused variable: `y`","level":"warning","spans":[{"file_name":"src/other.rs","line_start":1,"column_start":9,"is_primary":false},{"file_name":"src/main.rs","line_start":7,"column_start":9,"is_primary":true}]}}
+{"reason":"compiler-message","message":{"message":"aborting due to 1 previous error","level":"error","spans":[]}}
+{"reason":"build-finished","success":false}"#;
+
+        let diagnostics = parse_rustc_json(output, Path::new("/work"));
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic {
+                    path: "/work/src/lib.rs".into(),
+                    line: 3,
+                    column: Some(5),
+                    severity: Severity::Error,
+                    message: "cannot find value `x` in this scope".to_string(),
+                },
+                Diagnostic {
+                    path: "/work/src/main.rs".into(),
+                    line: 7,
+                    column: Some(9),
+                    severity: Severity::Warning,
+                    message: "unused variable: `y`".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_generic() {
+        let output = "app.py:1:8: F401 [*] `os` imported but unused\n\
+                      src/a.c:10: warning: implicit declaration\n\
+                      Found 2 errors.\n";
+
+        let diagnostics = parse_generic(output, Path::new("/work"));
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].path, Path::new("/work/app.py"));
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, Some(8)));
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].message, "F401 [*] `os` imported but unused");
+        assert_eq!(diagnostics[1].column, None);
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+        assert_eq!(diagnostics[1].message, "implicit declaration");
+    }
+}
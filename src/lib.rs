@@ -5,10 +5,13 @@
 pub mod cli;
 pub mod agent;
 pub mod config;
+pub mod diagnostics;
 pub mod event;
 pub mod llm;
 pub mod logging;
+pub mod lsp;
 pub mod process;
+pub mod repo_map;
 pub mod search;
 pub mod subagent;
 pub mod tool;
@@ -1,5 +1,5 @@
 use crate::config::station::Station;
-use crate::llm::types::{Message, StreamChunk, ToolUse};
+use crate::llm::types::{Message, StreamChunk, ToolChoice, ToolUse};
 use anyhow::{Context, Result};
 use eventsource_stream::Eventsource;
 use futures::stream::StreamExt;
@@ -28,6 +28,20 @@ impl AnthropicClient {
         &self,
         messages: Vec<Message>,
         tools: Option<Vec<serde_json::Value>>,
+    ) -> Result<Pin<Box<dyn Stream<Item = StreamChunk> + Send>>> {
+        self.stream_chat_with_tool_choice(messages, tools, None).await
+    }
+
+    /// Create a streaming chat completion with an explicit tool choice.
+    ///
+    /// The API rejects conversations containing tool_use/tool_result blocks unless tools are
+    /// defined, so a "no more tool calls" request keeps the definitions and passes
+    /// `ToolChoice::None` instead of dropping them.
+    pub async fn stream_chat_with_tool_choice(
+        &self,
+        messages: Vec<Message>,
+        tools: Option<Vec<serde_json::Value>>,
+        tool_choice: Option<ToolChoice>,
     ) -> Result<Pin<Box<dyn Stream<Item = StreamChunk> + Send>>> {
         let api_base = self
             .station
@@ -42,6 +56,7 @@ impl AnthropicClient {
             model = %self.station.model,
             message_count = messages.len(),
             tool_count = tools.as_ref().map(|t| t.len()).unwrap_or(0),
+            tool_choice = ?tool_choice,
             "anthropic stream_chat request"
         );
 
@@ -52,6 +67,7 @@ impl AnthropicClient {
             temperature: self.station.temperature,
             stream: true,
             tools,
+            tool_choice,
         };
 
         let response = self
@@ -194,6 +210,8 @@ struct CreateMessageRequest {
     stream: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<serde_json::Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<ToolChoice>,
 }
 
 /// Content block start event (for tool_use)
@@ -92,6 +92,32 @@ impl Message {
             content: MessageContent::Blocks(blocks),
         }
     }
+
+    /// Append a text block (e.g. a system reminder after tool results)
+    pub fn append_text(&mut self, text: impl Into<String>) {
+        let block = ContentBlock::Text { text: text.into() };
+        match &mut self.content {
+            MessageContent::Text(existing) => {
+                let first = ContentBlock::Text {
+                    text: std::mem::take(existing),
+                };
+                self.content = MessageContent::Blocks(vec![first, block]);
+            }
+            MessageContent::Blocks(blocks) => blocks.push(block),
+        }
+    }
+}
+
+/// Controls how the model may use the provided tools
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ToolChoice {
+    /// The model decides whether to call tools (API default)
+    Auto,
+    /// The model must call at least one tool
+    Any,
+    /// The model must not call any tools
+    None,
 }
 
 /// A chunk of streamed response
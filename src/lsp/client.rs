@@ -0,0 +1,399 @@
+//! JSON-RPC client for one language server process
+
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncRead, AsyncWrite, BufReader};
+use tokio::sync::{oneshot, Mutex, Notify};
+use tokio::task::JoinHandle;
+use url::Url;
+
+use super::transport::{read_message, write_message};
+use super::LspError;
+use crate::process::EnvFilter;
+
+/// How long to wait for an ordinary request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Servers may index the whole workspace before answering `initialize`
+const INITIALIZE_TIMEOUT: Duration = Duration::from_secs(120);
+
+type Writer = Box<dyn AsyncWrite + Send + Unpin>;
+type PendingReply = oneshot::Sender<Result<Value, (i64, String)>>;
+
+/// State shared with the task reading server messages
+#[derive(Default)]
+struct Shared {
+    pending: StdMutex<HashMap<i64, PendingReply>>,
+    /// Latest `textDocument/publishDiagnostics` per document
+    diagnostics: StdMutex<HashMap<Url, Vec<Value>>>,
+    diagnostics_published: Notify,
+}
+
+/// A document opened on the server, with the version and hash of the text last sent
+struct OpenDocument {
+    version: i32,
+    hash: u64,
+}
+
+/// Connection to a language server
+///
+/// Requests may be sent concurrently; replies are matched by id. Documents are synced with
+/// full-text `didOpen`/`didChange` notifications before each query.
+pub struct LspClient {
+    writer: Arc<Mutex<Writer>>,
+    shared: Arc<Shared>,
+    documents: Mutex<HashMap<Url, OpenDocument>>,
+    next_id: AtomicI64,
+    reader: JoinHandle<()>,
+    /// Server process, killed when the client is dropped
+    _child: Option<tokio::process::Child>,
+}
+
+impl LspClient {
+    /// Start `command` in `root`, with the environment `env_filter` lets through, and initialize it
+    pub async fn spawn(
+        command: &str,
+        args: &[String],
+        root: &Path,
+        env_filter: &EnvFilter,
+    ) -> Result<Self, LspError> {
+        let mut child = tokio::process::Command::new(command);
+        env_filter.apply(&mut child);
+        let mut child = child
+            .args(args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|source| LspError::Start {
+                command: command.to_string(),
+                source,
+            })?;
+
+        let stdin = child.stdin.take().ok_or(LspError::Closed)?;
+        let stdout = child.stdout.take().ok_or(LspError::Closed)?;
+        let mut client = Self::connect(stdout, stdin, root).await?;
+        client._child = Some(child);
+        Ok(client)
+    }
+
+    /// Initialize a server reachable through `reader`/`writer` (e.g. an in-process fake)
+    pub async fn connect<R, W>(reader: R, writer: W, root: &Path) -> Result<Self, LspError>
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let writer: Arc<Mutex<Writer>> = Arc::new(Mutex::new(Box::new(writer)));
+        let shared = Arc::new(Shared::default());
+        let reader = tokio::spawn(Self::read_loop(
+            BufReader::new(reader),
+            writer.clone(),
+            shared.clone(),
+        ));
+
+        let client = Self {
+            writer,
+            shared,
+            documents: Mutex::new(HashMap::new()),
+            next_id: AtomicI64::new(1),
+            reader,
+            _child: None,
+        };
+        client.initialize(root).await?;
+        Ok(client)
+    }
+
+    async fn initialize(&self, root: &Path) -> Result<(), LspError> {
+        let root_uri = path_to_uri(root)?;
+        let params = json!({
+            "processId": std::process::id(),
+            "rootUri": root_uri,
+            "workspaceFolders": [{ "uri": root_uri, "name": root.file_name().map(|n| n.to_string_lossy()).unwrap_or_default() }],
+            "capabilities": {
+                "textDocument": {
+                    "synchronization": { "didSave": false },
+                    "definition": { "linkSupport": true },
+                    "references": {},
+                    "hover": { "contentFormat": ["markdown", "plaintext"] },
+                    "documentSymbol": { "hierarchicalDocumentSymbolSupport": true },
+                    "publishDiagnostics": { "relatedInformation": false }
+                },
+                "workspace": {
+                    "symbol": {},
+                    "workspaceFolders": true,
+                    "configuration": true
+                }
+            }
+        });
+        self.request_with_timeout("initialize", params, INITIALIZE_TIMEOUT)
+            .await?;
+        self.notify("initialized", json!({})).await
+    }
+
+    /// Send a request and wait for its result
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value, LspError> {
+        self.request_with_timeout(method, params, REQUEST_TIMEOUT)
+            .await
+    }
+
+    async fn request_with_timeout(
+        &self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, LspError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.shared
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id, tx);
+
+        tracing::debug!(method, id, "lsp request");
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        if let Err(e) = self.send(&message).await {
+            self.forget(id);
+            return Err(e);
+        }
+
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(Ok(result))) => Ok(result),
+            Ok(Ok(Err((code, message)))) => Err(LspError::Server {
+                method: method.to_string(),
+                code,
+                message,
+            }),
+            Ok(Err(_)) => Err(LspError::Closed),
+            Err(_) => {
+                self.forget(id);
+                Err(LspError::Timeout(method.to_string()))
+            }
+        }
+    }
+
+    /// Send a notification
+    pub async fn notify(&self, method: &str, params: Value) -> Result<(), LspError> {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+            .await
+    }
+
+    /// Make the server's copy of `path` match `text`, opening it if needed
+    pub async fn sync_document(
+        &self,
+        path: &Path,
+        language_id: &str,
+        text: &str,
+    ) -> Result<Url, LspError> {
+        let uri = path_to_uri(path)?;
+        let hash = text_hash(text);
+
+        let mut documents = self.documents.lock().await;
+        match documents.get_mut(&uri) {
+            Some(doc) if doc.hash == hash => {}
+            Some(doc) => {
+                doc.version += 1;
+                doc.hash = hash;
+                self.clear_diagnostics(&uri);
+                self.notify(
+                    "textDocument/didChange",
+                    json!({
+                        "textDocument": { "uri": uri, "version": doc.version },
+                        "contentChanges": [{ "text": text }]
+                    }),
+                )
+                .await?;
+            }
+            None => {
+                self.clear_diagnostics(&uri);
+                self.notify(
+                    "textDocument/didOpen",
+                    json!({
+                        "textDocument": {
+                            "uri": uri,
+                            "languageId": language_id,
+                            "version": 1,
+                            "text": text
+                        }
+                    }),
+                )
+                .await?;
+                documents.insert(uri.clone(), OpenDocument { version: 1, hash });
+            }
+        }
+        Ok(uri)
+    }
+
+    /// Diagnostics last published for `uri`, waiting up to `wait` for the first publication
+    /// after the document was opened or changed
+    pub async fn diagnostics(&self, uri: &Url, wait: Duration) -> Option<Vec<Value>> {
+        let deadline = tokio::time::Instant::now() + wait;
+        loop {
+            let published = self.shared.diagnostics_published.notified();
+            tokio::pin!(published);
+            // Register before checking so a publication in between is not missed
+            published.as_mut().enable();
+
+            if let Some(diagnostics) = self
+                .shared
+                .diagnostics
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get(uri)
+            {
+                return Some(diagnostics.clone());
+            }
+            if tokio::time::timeout_at(deadline, published).await.is_err() {
+                return None;
+            }
+        }
+    }
+
+    /// Ask the server to shut down and exit
+    pub async fn shutdown(&self) {
+        if self
+            .request_with_timeout("shutdown", Value::Null, Duration::from_secs(5))
+            .await
+            .is_ok()
+        {
+            let _ = self.notify("exit", Value::Null).await;
+        }
+    }
+
+    async fn send(&self, message: &Value) -> Result<(), LspError> {
+        let mut writer = self.writer.lock().await;
+        write_message(&mut *writer, message)
+            .await
+            .map_err(|_| LspError::Closed)
+    }
+
+    fn forget(&self, id: i64) {
+        self.shared
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&id);
+    }
+
+    fn clear_diagnostics(&self, uri: &Url) {
+        self.shared
+            .diagnostics
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(uri);
+    }
+
+    /// Dispatch server messages until the connection closes
+    async fn read_loop<R: AsyncRead + Unpin>(
+        mut reader: BufReader<R>,
+        writer: Arc<Mutex<Writer>>,
+        shared: Arc<Shared>,
+    ) {
+        loop {
+            let message = match read_message(&mut reader).await {
+                Ok(Some(message)) => message,
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::warn!(error = %e, "language server sent an invalid message");
+                    break;
+                }
+            };
+
+            let method = message.get("method").and_then(Value::as_str);
+            let id = message.get("id").cloned();
+            match (method, id) {
+                // Reply to one of our requests
+                (None, Some(id)) => {
+                    let Some(id) = id.as_i64() else { continue };
+                    let reply = shared
+                        .pending
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .remove(&id);
+                    if let Some(reply) = reply {
+                        let result = match message.get("error") {
+                            Some(error) => Err((
+                                error.get("code").and_then(Value::as_i64).unwrap_or(0),
+                                error
+                                    .get("message")
+                                    .and_then(Value::as_str)
+                                    .unwrap_or("unknown error")
+                                    .to_string(),
+                            )),
+                            None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                        };
+                        let _ = reply.send(result);
+                    }
+                }
+                // Request from the server: answer with defaults so it doesn't stall
+                (Some(method), Some(id)) => {
+                    let result = match method {
+                        "workspace/configuration" => {
+                            let items = message["params"]["items"].as_array().map_or(0, Vec::len);
+                            Value::Array(vec![Value::Null; items])
+                        }
+                        _ => Value::Null,
+                    };
+                    let reply = json!({ "jsonrpc": "2.0", "id": id, "result": result });
+                    let mut writer = writer.lock().await;
+                    if write_message(&mut *writer, &reply).await.is_err() {
+                        break;
+                    }
+                }
+                (Some("textDocument/publishDiagnostics"), None) => {
+                    let params = &message["params"];
+                    let uri = params["uri"].as_str().and_then(|uri| Url::parse(uri).ok());
+                    if let Some(uri) = uri {
+                        let diagnostics = params["diagnostics"].as_array().cloned().unwrap_or_default();
+                        shared
+                            .diagnostics
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .insert(uri, diagnostics);
+                        shared.diagnostics_published.notify_waiters();
+                    }
+                }
+                // Progress, log and other notifications are not needed
+                _ => {}
+            }
+        }
+
+        // Fail outstanding requests instead of leaving them to time out
+        shared
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
+
+impl Drop for LspClient {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// `file://` URI for an absolute path
+pub fn path_to_uri(path: &Path) -> Result<Url, LspError> {
+    Url::from_file_path(path).map_err(|_| LspError::InvalidPath(path.to_path_buf()))
+}
+
+/// Local path for a `file://` URI
+pub fn uri_to_path(uri: &str) -> Option<std::path::PathBuf> {
+    Url::parse(uri).ok()?.to_file_path().ok()
+}
+
+fn text_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
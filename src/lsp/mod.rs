@@ -0,0 +1,213 @@
+//! Language-server integration: start servers per language and query them for code intelligence
+//!
+//! Servers are started lazily in the working directory the first time a file of their language
+//! is queried, and kept running for the session. A server starts outside the lock on the
+//! running servers, so queries to other servers go on while one is still initializing.
+
+pub mod client;
+pub mod transport;
+
+pub use client::LspClient;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tokio::sync::{Mutex, OnceCell};
+
+use crate::config::station::LspServerSettings;
+use crate::process::EnvFilter;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LspError {
+    #[error("Failed to start language server '{command}': {source}")]
+    Start {
+        command: String,
+        source: std::io::Error,
+    },
+
+    #[error("No language server configured for {0}")]
+    NoServer(PathBuf),
+
+    #[error("Language server returned an error for {method}: {message} (code {code})")]
+    Server {
+        method: String,
+        code: i64,
+        message: String,
+    },
+
+    #[error("Language server did not answer {0} in time")]
+    Timeout(String),
+
+    #[error("Language server connection closed")]
+    Closed,
+
+    #[error("Path cannot be sent to a language server: {0}")]
+    InvalidPath(PathBuf),
+
+    #[error(
+        "Language servers are not started in untrusted projects: they run the project's build \
+         scripts. Ask the user to trust the project with /trust."
+    )]
+    Untrusted,
+}
+
+/// Servers used when the config has no `[lsp.<language>]` section for a language
+pub fn default_servers() -> HashMap<String, LspServerSettings> {
+    let server = |command: &str, args: &[&str], extensions: &[&str]| LspServerSettings {
+        command: command.to_string(),
+        args: args.iter().map(|s| s.to_string()).collect(),
+        extensions: extensions.iter().map(|s| s.to_string()).collect(),
+        language_id: None,
+    };
+
+    HashMap::from([
+        ("rust".to_string(), server("rust-analyzer", &[], &["rs"])),
+        (
+            "python".to_string(),
+            server("pyright-langserver", &["--stdio"], &["py", "pyi"]),
+        ),
+        (
+            "typescript".to_string(),
+            server(
+                "typescript-language-server",
+                &["--stdio"],
+                &["ts", "tsx", "js", "jsx", "mjs", "cjs"],
+            ),
+        ),
+        ("go".to_string(), server("gopls", &[], &["go"])),
+    ])
+}
+
+/// A running server and the `languageId` to open a given file with
+pub struct ServerHandle {
+    pub client: Arc<LspClient>,
+    pub language_id: String,
+}
+
+/// A server for one language and root; set once it has started and initialized
+type ClientSlot = Arc<OnceCell<Arc<LspClient>>>;
+
+/// Starts and caches one client per language and project root
+pub struct LspManager {
+    servers: HashMap<String, LspServerSettings>,
+    clients: Mutex<HashMap<(String, PathBuf), ClientSlot>>,
+}
+
+impl LspManager {
+    /// Use the default servers, with `overrides` replacing or adding languages
+    ///
+    /// An override without `extensions` keeps the default extensions for that language.
+    pub fn new(overrides: HashMap<String, LspServerSettings>) -> Self {
+        let mut servers = default_servers();
+        for (language, mut settings) in overrides {
+            if settings.extensions.is_empty() {
+                if let Some(default) = servers.get(&language) {
+                    settings.extensions = default.extensions.clone();
+                }
+            }
+            servers.insert(language, settings);
+        }
+        Self {
+            servers,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Language configured for `path`'s extension
+    pub fn language_for(&self, path: &Path) -> Option<&str> {
+        let extension = path.extension()?.to_str()?;
+        self.servers
+            .iter()
+            .filter(|(_, settings)| settings.extensions.iter().any(|e| e == extension))
+            .map(|(language, _)| language.as_str())
+            // Deterministic choice if two languages claim an extension
+            .min()
+    }
+
+    /// Server for `path`, started in `root` with the environment `env_filter` lets through if
+    /// it isn't running yet
+    pub async fn server_for(
+        &self,
+        path: &Path,
+        root: &Path,
+        env_filter: &EnvFilter,
+    ) -> Result<ServerHandle, LspError> {
+        let language = self
+            .language_for(path)
+            .ok_or_else(|| LspError::NoServer(path.to_path_buf()))?;
+        let client = self.client(language, root, env_filter).await?;
+
+        let settings = &self.servers[language];
+        let language_id = settings
+            .language_id
+            .clone()
+            .unwrap_or_else(|| default_language_id(language, path));
+        Ok(ServerHandle {
+            client,
+            language_id,
+        })
+    }
+
+    /// Servers already running in `root`, for queries not tied to one file (servers still
+    /// starting are left out)
+    pub async fn running(&self, root: &Path) -> Vec<Arc<LspClient>> {
+        let clients = self.clients.lock().await;
+        let mut running: Vec<(&String, &Arc<LspClient>)> = clients
+            .iter()
+            .filter(|((_, r), _)| r == root)
+            .filter_map(|((language, _), slot)| Some((language, slot.get()?)))
+            .collect();
+        running.sort_by_key(|(language, _)| *language);
+        running.into_iter().map(|(_, client)| client.clone()).collect()
+    }
+
+    /// Register an already-connected client for `language` in `root` (used by tests)
+    pub async fn insert_client(&self, language: &str, root: &Path, client: LspClient) {
+        let slot = Arc::new(OnceCell::new_with(Some(Arc::new(client))));
+        self.clients
+            .lock()
+            .await
+            .insert((language.to_string(), root.to_path_buf()), slot);
+    }
+
+    async fn client(
+        &self,
+        language: &str,
+        root: &Path,
+        env_filter: &EnvFilter,
+    ) -> Result<Arc<LspClient>, LspError> {
+        // Hold the map lock only to find the slot; concurrent callers for the same server wait
+        // on the slot, and a failed start leaves it empty for the next call to retry
+        let slot = self
+            .clients
+            .lock()
+            .await
+            .entry((language.to_string(), root.to_path_buf()))
+            .or_default()
+            .clone();
+
+        let client = slot
+            .get_or_try_init(|| async {
+                let settings = &self.servers[language];
+                tracing::info!(language, command = %settings.command, root = %root.display(), "starting language server");
+                LspClient::spawn(&settings.command, &settings.args, root, env_filter)
+                    .await
+                    .map(Arc::new)
+            })
+            .await?;
+        Ok(client.clone())
+    }
+}
+
+/// `languageId` for a file when the config doesn't set one
+fn default_language_id(language: &str, path: &Path) -> String {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    match extension {
+        "tsx" => "typescriptreact",
+        "jsx" => "javascriptreact",
+        "js" | "mjs" | "cjs" => "javascript",
+        _ => language,
+    }
+    .to_string()
+}
@@ -1,10 +1,24 @@
-use std::collections::VecDeque;
+use super::env::EnvFilter;
+use super::group::{self, OutputCap, ProcessGroup, ResourceLimits};
+use super::notification::{ShellNotification, ShellNotificationKind, ShellNotifications};
+use super::output_log::{LogSlice, OutputLog, Stream, StreamFilter};
+use super::pty::{self, PtyMaster};
+use super::sandbox::Sandbox;
+use regex::Regex;
+use std::collections::HashMap;
 use std::process::Stdio;
 use std::sync::Arc;
-use std::time::SystemTime;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, Command};
-use tokio::sync::Mutex;
+use tokio::sync::{oneshot, Mutex};
+use tokio::task::JoinHandle;
+
+/// A terminal line without a newline is stored as a line once it gets this long
+const MAX_PENDING_LINE_BYTES: usize = 64 * 1024;
+
+/// How long to wait for remaining output after the process exits
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
 
 /// Status of a background shell
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -14,14 +28,49 @@ pub enum ShellStatus {
     Failed { error: String },
 }
 
+/// Optional settings applied when spawning a background shell
+#[derive(Debug, Clone, Default)]
+pub struct ShellOptions {
+    /// Session or subagent that launched the shell
+    pub owner: Option<String>,
+    /// Report output lines matching this pattern to the owner
+    pub watch: Option<Regex>,
+    /// Run the shell in this sandbox, with `writable_roots` left writable
+    pub sandbox: Option<Arc<Sandbox>>,
+    pub writable_roots: Vec<std::path::PathBuf>,
+    /// Run under a pseudo-terminal: output is merged into stdout with escape codes stripped,
+    /// and input can be sent with `send_input`
+    pub pty: bool,
+    /// Variables added to the shell's environment
+    pub env: HashMap<String, String>,
+    /// Text written to the shell's input (then closed, unless it has a terminal)
+    pub stdin: Option<String>,
+    /// Which of this process's variables the shell inherits
+    pub env_filter: Arc<EnvFilter>,
+    /// CPU, memory and output limits
+    pub limits: ResourceLimits,
+}
+
 /// A background shell process with captured output
+///
+/// A waiter task owns the child process and records its exit status as soon as it exits,
+/// queueing notifications for the owner (if any) on exit and on watched output.
 pub struct BackgroundShell {
     pub id: String,
-    process: Option<Child>,
-    stdout_lines: Arc<Mutex<VecDeque<String>>>,
-    stderr_lines: Arc<Mutex<VecDeque<String>>>,
+    command: String,
+    /// Signals the waiter task to kill the process (dropping it kills too)
+    kill_tx: Option<oneshot::Sender<()>>,
+    waiter: Option<JoinHandle<()>>,
+    /// stdout and stderr lines in the order they were read
+    log: Arc<Mutex<OutputLog>>,
+    /// Terminal output after the last newline (e.g. a prompt waiting for input); pty only
+    pending_line: Arc<Mutex<String>>,
+    /// Where input goes, for shells started with a pty
+    pty: Option<PtyMaster>,
     status: Arc<Mutex<ShellStatus>>,
     started_at: SystemTime,
+    /// Session or subagent that launched the shell (None for untagged shells)
+    owner: Option<String>,
 }
 
 impl BackgroundShell {
@@ -30,152 +79,222 @@ impl BackgroundShell {
         id: String,
         command: String,
         working_dir: std::path::PathBuf,
+    ) -> anyhow::Result<Self> {
+        Self::spawn_with_options(id, command, working_dir, ShellOptions::default(), None).await
+    }
+
+    /// Spawn a background shell with an owner and/or watch pattern
+    ///
+    /// Notifications are queued in `notifications` only when the shell has an owner.
+    pub async fn spawn_with_options(
+        id: String,
+        command: String,
+        working_dir: std::path::PathBuf,
+        options: ShellOptions,
+        notifications: Option<ShellNotifications>,
     ) -> anyhow::Result<Self> {
         tracing::debug!(
             id = %id,
             command = %command,
             working_dir = %working_dir.display(),
+            watch = ?options.watch.as_ref().map(Regex::as_str),
             "spawning background shell"
         );
 
-        // Spawn the command with pipes
-        let mut child = Command::new("bash")
-            .arg("-c")
-            .arg(&command)
-            .current_dir(&working_dir)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .stdin(Stdio::null())
-            .kill_on_drop(true)
-            .spawn()?;
-
-        // Take stdout/stderr handles
-        let stdout = child.stdout.take().expect("stdout not captured");
-        let stderr = child.stderr.take().expect("stderr not captured");
+        let mut cmd = match &options.sandbox {
+            Some(sandbox) => sandbox.command("bash", &command, &working_dir, &options.writable_roots),
+            None => {
+                let mut cmd = Command::new("bash");
+                cmd.arg("-c").arg(&command);
+                cmd
+            }
+        };
+        cmd.current_dir(&working_dir).kill_on_drop(true);
+        options.env_filter.apply(&mut cmd);
+        options.limits.apply(&mut cmd);
+        cmd.envs(&options.env);
 
         // Create shared buffers
-        let stdout_lines = Arc::new(Mutex::new(VecDeque::new()));
-        let stderr_lines = Arc::new(Mutex::new(VecDeque::new()));
+        let log = Arc::new(Mutex::new(OutputLog::default()));
+        let pending_line = Arc::new(Mutex::new(String::new()));
         let status = Arc::new(Mutex::new(ShellStatus::Running));
 
-        // Spawn stdout reader task
-        let stdout_buf = stdout_lines.clone();
-        let max_lines = 10000; // Keep last 10k lines
-        tokio::spawn(async move {
-            let reader = BufReader::new(stdout);
-            let mut lines = reader.lines();
-            while let Ok(Some(line)) = lines.next_line().await {
-                let mut buf = stdout_buf.lock().await;
-                buf.push_back(line);
-                if buf.len() > max_lines {
-                    buf.pop_front();
-                }
+        // Only owned shells have someone to notify
+        let notifier = match (&options.owner, notifications) {
+            (Some(owner), Some(queue)) => Some(Notifier {
+                queue,
+                shell_id: id.clone(),
+                command: command.clone(),
+                owner: owner.clone(),
+            }),
+            _ => None,
+        };
+        let watch = options.watch.clone().zip(notifier.clone());
+
+        // Spawn the command with pipes (or a terminal) and the output reader tasks
+        let (child, group, pty, readers) = if options.pty {
+            // The command leads a new session, and so its own process group
+            let (child, master) = pty::spawn(&mut cmd)?;
+            let group = ProcessGroup::of(&child);
+            let cap = Arc::new(OutputCap::new(options.limits.output_bytes, &group));
+            if let Some(input) = options.stdin.clone() {
+                let master = master.clone();
+                tokio::spawn(async move {
+                    let _ = master.write_all(input.as_bytes()).await;
+                });
             }
-        });
+            let reader = tokio::spawn(read_terminal(
+                master.clone(),
+                log.clone(),
+                pending_line.clone(),
+                watch,
+                cap,
+            ));
+            (child, group, Some(master), vec![reader])
+        } else {
+            // In a group of its own, so killing the shell also stops whatever it started
+            group::new_group(&mut cmd);
+            let mut child = cmd
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .stdin(if options.stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+                .spawn()?;
+            let group = ProcessGroup::of(&child);
+            let cap = Arc::new(OutputCap::new(options.limits.output_bytes, &group));
 
-        // Spawn stderr reader task
-        let stderr_buf = stderr_lines.clone();
-        tokio::spawn(async move {
-            let reader = BufReader::new(stderr);
-            let mut lines = reader.lines();
-            while let Ok(Some(line)) = lines.next_line().await {
-                let mut buf = stderr_buf.lock().await;
-                buf.push_back(line);
-                if buf.len() > max_lines {
-                    buf.pop_front();
-                }
+            // Closing stdin once the input is written lets the command see end of input
+            if let (Some(input), Some(mut pipe)) = (options.stdin.clone(), child.stdin.take()) {
+                tokio::spawn(async move {
+                    let _ = pipe.write_all(input.as_bytes()).await;
+                });
             }
-        });
+
+            // Take stdout/stderr handles
+            let stdout = child.stdout.take().expect("stdout not captured");
+            let stderr = child.stderr.take().expect("stderr not captured");
+
+            let stdout_reader = tokio::spawn(read_lines(
+                stdout,
+                log.clone(),
+                Stream::Stdout,
+                watch.clone(),
+                cap.clone(),
+            ));
+            let stderr_reader = tokio::spawn(read_lines(
+                stderr,
+                log.clone(),
+                Stream::Stderr,
+                watch,
+                cap,
+            ));
+            (child, group, None, vec![stdout_reader, stderr_reader])
+        };
+
+        // Spawn the waiter task: records the exit status as soon as the process exits
+        let (kill_tx, kill_rx) = oneshot::channel();
+        let waiter = tokio::spawn(wait_for_exit(
+            child,
+            group,
+            kill_rx,
+            status.clone(),
+            readers,
+            notifier,
+        ));
 
         Ok(Self {
             id,
-            process: Some(child),
-            stdout_lines,
-            stderr_lines,
+            command,
+            kill_tx: Some(kill_tx),
+            waiter: Some(waiter),
+            log,
+            pending_line,
+            pty,
             status,
             started_at: SystemTime::now(),
+            owner: options.owner,
         })
     }
 
+    /// Get the owner tag, if any
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
     /// Get the shell ID
     pub fn id(&self) -> &str {
         &self.id
     }
 
+    /// Get the command line the shell runs
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
     /// Get current status
     pub async fn status(&self) -> ShellStatus {
         self.status.lock().await.clone()
     }
 
-    /// Get stdout lines (all available)
+    /// Get stdout lines (all still in the log)
     pub async fn stdout_lines(&self) -> Vec<String> {
-        self.stdout_lines.lock().await.iter().cloned().collect()
+        self.log.lock().await.lines(Stream::Stdout)
     }
 
-    /// Get stderr lines (all available)
+    /// Get stderr lines (all still in the log)
     pub async fn stderr_lines(&self) -> Vec<String> {
-        self.stderr_lines.lock().await.iter().cloned().collect()
+        self.log.lock().await.lines(Stream::Stderr)
     }
 
-    /// Get new stdout lines since last offset
-    pub async fn stdout_since(&self, offset: usize) -> Vec<String> {
-        let lines = self.stdout_lines.lock().await;
-        if offset >= lines.len() {
-            vec![]
-        } else {
-            lines.iter().skip(offset).cloned().collect()
-        }
+    /// Get the lines of `filter`ed streams from sequence number `seq` on
+    pub async fn output_since(&self, seq: u64, filter: StreamFilter) -> LogSlice {
+        self.log.lock().await.since(seq, filter)
     }
 
-    /// Get new stderr lines since last offset
-    pub async fn stderr_since(&self, offset: usize) -> Vec<String> {
-        let lines = self.stderr_lines.lock().await;
-        if offset >= lines.len() {
-            vec![]
-        } else {
-            lines.iter().skip(offset).cloned().collect()
+    /// Sequence number the next output line will get
+    pub async fn next_seq(&self) -> u64 {
+        self.log.lock().await.next_seq()
+    }
+
+    /// Terminal output after the last complete line, such as a prompt (empty without a pty)
+    pub async fn pending_line(&self) -> String {
+        self.pending_line.lock().await.clone()
+    }
+
+    /// Whether the shell runs under a pseudo-terminal
+    pub fn has_pty(&self) -> bool {
+        self.pty.is_some()
+    }
+
+    /// Type `data` into the shell's terminal
+    pub async fn send_input(&self, data: &[u8]) -> anyhow::Result<()> {
+        let Some(pty) = &self.pty else {
+            anyhow::bail!("shell '{}' was not started with pty=true, so it takes no input", self.id);
+        };
+        if !matches!(*self.status.lock().await, ShellStatus::Running) {
+            anyhow::bail!("shell '{}' has already exited", self.id);
         }
+        pty.write_all(data).await?;
+        Ok(())
     }
 
-    /// Get total line counts
+    /// Get total line counts (including lines evicted from the log)
     pub async fn line_counts(&self) -> (usize, usize) {
-        let stdout_count = self.stdout_lines.lock().await.len();
-        let stderr_count = self.stderr_lines.lock().await.len();
-        (stdout_count, stderr_count)
+        self.log.lock().await.totals()
     }
 
     /// Check if process has finished
     pub async fn check_finished(&mut self) -> bool {
-        if let Some(ref mut child) = self.process {
-            match child.try_wait() {
-                Ok(Some(exit_status)) => {
-                    let exit_code = exit_status.code();
-                    *self.status.lock().await = ShellStatus::Completed { exit_code };
-                    self.process = None;
-                    true
-                }
-                Ok(None) => false, // Still running
-                Err(e) => {
-                    *self.status.lock().await = ShellStatus::Failed {
-                        error: e.to_string(),
-                    };
-                    self.process = None;
-                    true
-                }
-            }
-        } else {
-            true // Already finished
-        }
+        !matches!(*self.status.lock().await, ShellStatus::Running)
     }
 
-    /// Kill the background process
+    /// Kill the background process and wait for the waiter task to record it
     pub async fn kill(&mut self) -> anyhow::Result<()> {
-        if let Some(ref mut child) = self.process {
-            child.kill().await?;
-            *self.status.lock().await = ShellStatus::Completed {
-                exit_code: Some(137), // SIGKILL
-            };
-            self.process = None;
+        if let Some(kill_tx) = self.kill_tx.take() {
+            // Fails only if the process already exited, which is fine
+            let _ = kill_tx.send(());
+        }
+        if let Some(waiter) = self.waiter.take() {
+            waiter.await?;
         }
         Ok(())
     }
@@ -196,9 +315,159 @@ impl BackgroundShell {
 
 impl Drop for BackgroundShell {
     fn drop(&mut self) {
-        // Best effort kill on drop
-        if let Some(ref mut child) = self.process {
-            let _ = child.start_kill();
+        // Dropping the kill sender makes the waiter task kill the process
+        self.kill_tx.take();
+    }
+}
+
+/// Where a shell sends its notifications
+#[derive(Clone)]
+struct Notifier {
+    queue: ShellNotifications,
+    shell_id: String,
+    command: String,
+    owner: String,
+}
+
+impl Notifier {
+    fn notify(&self, kind: ShellNotificationKind) {
+        self.queue.push(ShellNotification {
+            shell_id: self.shell_id.clone(),
+            command: self.command.clone(),
+            owner: self.owner.clone(),
+            kind,
+        });
+    }
+}
+
+/// Read lines from a pipe into the log, reporting lines that match the watch pattern.
+/// Stops (noting why in the log) once the shell has printed more than `cap` allows.
+async fn read_lines<R>(
+    pipe: R,
+    log: Arc<Mutex<OutputLog>>,
+    stream: Stream,
+    watch: Option<(Regex, Notifier)>,
+    cap: Arc<OutputCap>,
+) where
+    R: AsyncRead + Unpin,
+{
+    let mut lines = BufReader::new(pipe).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if !cap.add(line.len() + 1) {
+            if let Some(note) = cap.note() {
+                log.lock().await.push(stream, note);
+            }
+            break;
+        }
+        if let Some((pattern, notifier)) = &watch {
+            if pattern.is_match(&line) {
+                notifier.notify(ShellNotificationKind::Matched {
+                    pattern: pattern.as_str().to_string(),
+                    stream: stream.as_str(),
+                    line: line.clone(),
+                });
+            }
+        }
+        log.lock().await.push(stream, line);
+    }
+}
+
+/// Read a pseudo-terminal's output into the log as plain stdout lines; output after the last
+/// newline is kept in `pending` so prompts are visible before they are answered
+async fn read_terminal(
+    mut master: PtyMaster,
+    log: Arc<Mutex<OutputLog>>,
+    pending: Arc<Mutex<String>>,
+    watch: Option<(Regex, Notifier)>,
+    cap: Arc<OutputCap>,
+) {
+    let mut partial = Vec::new();
+    let mut chunk = [0u8; 8192];
+    loop {
+        let n = match master.read(&mut chunk).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        if !cap.add(n) {
+            if let Some(note) = cap.note() {
+                log.lock().await.push(Stream::Stdout, note);
+            }
+            break;
+        }
+        partial.extend_from_slice(&chunk[..n]);
+
+        let mut lines = Vec::new();
+        while let Some(end) = partial.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = partial.drain(..=end).collect();
+            lines.push(pty::strip_terminal_codes(&String::from_utf8_lossy(&line[..end])));
+        }
+        if partial.len() > MAX_PENDING_LINE_BYTES {
+            lines.push(pty::strip_terminal_codes(&String::from_utf8_lossy(&partial)));
+            partial.clear();
+        }
+
+        for line in lines {
+            if let Some((pattern, notifier)) = &watch {
+                if pattern.is_match(&line) {
+                    notifier.notify(ShellNotificationKind::Matched {
+                        pattern: pattern.as_str().to_string(),
+                        stream: "stdout",
+                        line: line.clone(),
+                    });
+                }
+            }
+            log.lock().await.push(Stream::Stdout, line);
+        }
+        *pending.lock().await = pty::strip_terminal_codes(&String::from_utf8_lossy(&partial));
+    }
+
+    if !partial.is_empty() {
+        let line = pty::strip_terminal_codes(&String::from_utf8_lossy(&partial));
+        pending.lock().await.clear();
+        log.lock().await.push(Stream::Stdout, line);
+    }
+}
+
+/// Own the child (and its process group) until it exits or is killed, then record its status.
+/// If this task is dropped first (the runtime shuts down), the group is killed with it.
+async fn wait_for_exit(
+    mut child: Child,
+    mut group: ProcessGroup,
+    kill_rx: oneshot::Receiver<()>,
+    status: Arc<Mutex<ShellStatus>>,
+    readers: Vec<JoinHandle<()>>,
+    notifier: Option<Notifier>,
+) {
+    tokio::select! {
+        result = child.wait() => {
+            // Anything the command left running may keep going
+            group.release();
+
+            // Let the readers drain the pipes so the output is complete when the owner looks.
+            // A grandchild holding the pipes open must not delay the status forever.
+            let drain = futures::future::join_all(readers);
+            let _ = tokio::time::timeout(OUTPUT_DRAIN_TIMEOUT, drain).await;
+
+            let new_status = match result {
+                Ok(exit_status) => ShellStatus::Completed {
+                    exit_code: exit_status.code(),
+                },
+                Err(e) => ShellStatus::Failed {
+                    error: e.to_string(),
+                },
+            };
+            *status.lock().await = new_status.clone();
+
+            if let (Some(notifier), ShellStatus::Completed { exit_code }) = (notifier, new_status) {
+                notifier.notify(ShellNotificationKind::Exited { exit_code });
+            }
+        }
+        // Explicit kill, or the shell handle was dropped
+        _ = kill_rx => {
+            group.terminate(&mut child).await;
+            *status.lock().await = ShellStatus::Completed {
+                exit_code: Some(137), // SIGKILL
+            };
         }
     }
 }
@@ -0,0 +1,104 @@
+//! Which of the agent's environment variables shell commands inherit
+//!
+//! The agent runs with credentials in its environment (`ANTHROPIC_API_KEY`, ...) that commands
+//! have no business seeing. `[bash] env_allow` and `env_deny` name the variables passed on;
+//! by default everything except secret-looking names is.
+
+use tokio::process::Command;
+
+use crate::config::station::BashSettings;
+
+/// Filter for the environment inherited by shell commands
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvFilter {
+    allow: Vec<String>,
+    deny: Vec<String>,
+}
+
+impl Default for EnvFilter {
+    fn default() -> Self {
+        Self::new(&BashSettings::default())
+    }
+}
+
+impl EnvFilter {
+    pub fn new(settings: &BashSettings) -> Self {
+        Self {
+            allow: settings.env_allow.clone(),
+            deny: settings.env_deny.clone(),
+        }
+    }
+
+    /// Whether a command may inherit the variable `name`
+    pub fn allows(&self, name: &str) -> bool {
+        (self.allow.is_empty() || self.allow.iter().any(|p| wildcard_match(p, name)))
+            && !self.deny.iter().any(|p| wildcard_match(p, name))
+    }
+
+    /// Remove the variables `command` may not inherit from its environment
+    pub fn apply(&self, command: &mut Command) {
+        for (name, _) in std::env::vars_os() {
+            if !self.allows(&name.to_string_lossy()) {
+                command.env_remove(&name);
+            }
+        }
+    }
+}
+
+/// `pattern` matches all of `name`, with `*` matching any run of characters
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*`: the whole name must match
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("HOME", "HOME"));
+        assert!(!wildcard_match("HOME", "HOMEDIR"));
+        assert!(wildcard_match("*_API_KEY", "ANTHROPIC_API_KEY"));
+        assert!(!wildcard_match("*_API_KEY", "API_KEY"));
+        assert!(wildcard_match("CARGO_*", "CARGO_HOME"));
+        assert!(wildcard_match("*TOKEN*", "GH_TOKEN_2"));
+        assert!(wildcard_match("A*B*C", "AxxBxxC"));
+        assert!(!wildcard_match("A*B*C", "AxxCxxB"));
+        assert!(wildcard_match("*", "ANYTHING"));
+    }
+
+    #[test]
+    fn test_default_filter_scrubs_secrets() {
+        let filter = EnvFilter::default();
+        assert!(!filter.allows("ANTHROPIC_API_KEY"));
+        assert!(!filter.allows("AWS_SECRET_ACCESS_KEY"));
+        assert!(filter.allows("PATH"));
+        assert!(filter.allows("HOME"));
+
+        let filter = EnvFilter::new(&BashSettings {
+            env_allow: vec!["PATH".to_string(), "MY_*".to_string()],
+            env_deny: vec!["MY_PASSWORD".to_string()],
+            ..Default::default()
+        });
+        assert!(filter.allows("PATH"));
+        assert!(filter.allows("MY_VAR"));
+        assert!(!filter.allows("MY_PASSWORD"));
+        assert!(!filter.allows("HOME"));
+    }
+}
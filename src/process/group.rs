@@ -0,0 +1,225 @@
+//! Process groups and resource limits for shell commands
+//!
+//! Each command runs as the leader of its own process group, so stopping it also stops what it
+//! started (test servers, build workers) instead of orphaning them. [`ProcessGroup::terminate`]
+//! sends SIGTERM to the whole group and SIGKILL after a grace period; a group dropped while its
+//! command still runs is killed outright. [`ResourceLimits`] (`[bash]` `cpu_seconds`,
+//! `memory_mb`, `max_output_bytes`) caps what each command may use.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use tokio::process::{Child, Command};
+
+use crate::config::station::BashSettings;
+
+/// How long a group has to exit after SIGTERM before it gets SIGKILL
+pub const KILL_GRACE: Duration = Duration::from_secs(2);
+
+/// Limits on what a shell command may use (`None` = unlimited)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// CPU time of each process
+    pub cpu_seconds: Option<u64>,
+    /// Address space of each process
+    pub memory_bytes: Option<u64>,
+    /// Output (stdout and stderr together) of the command; it is killed once it prints more
+    pub output_bytes: Option<u64>,
+}
+
+impl ResourceLimits {
+    pub fn new(settings: &BashSettings) -> Self {
+        Self {
+            cpu_seconds: settings.cpu_seconds,
+            memory_bytes: settings.memory_mb.map(|mb| mb.saturating_mul(1024 * 1024)),
+            output_bytes: settings.max_output_bytes,
+        }
+    }
+
+    /// Limit the CPU time and memory of `command` and everything it starts (the output limit
+    /// is applied by whoever reads the output, see [`OutputCap`])
+    pub fn apply(&self, command: &mut Command) {
+        if self.cpu_seconds.is_none() && self.memory_bytes.is_none() {
+            return;
+        }
+        imp::set_rlimits(command, self.cpu_seconds, self.memory_bytes);
+    }
+}
+
+/// Start `command` as the leader of a new process group. Not for pty commands: they get a
+/// session (and so a group) of their own.
+pub fn new_group(command: &mut Command) {
+    #[cfg(unix)]
+    command.process_group(0);
+}
+
+/// The process group led by a spawned command; killed on drop unless released
+#[derive(Debug)]
+pub struct ProcessGroup {
+    id: Option<u32>,
+}
+
+impl ProcessGroup {
+    /// The group of `child`, which must have been started with [`new_group`] (or as a session
+    /// leader)
+    pub fn of(child: &Child) -> Self {
+        Self { id: child.id() }
+    }
+
+    /// The group ID (the leader's PID), until the group is released or terminated
+    pub fn id(&self) -> Option<u32> {
+        self.id
+    }
+
+    /// Kill every process in the group now
+    pub fn kill(&self) {
+        if let Some(id) = self.id {
+            imp::signal_group(id, imp::Signal::Kill);
+        }
+    }
+
+    /// Ask the group to exit (SIGTERM), give it [`KILL_GRACE`] to do so, then kill whatever is
+    /// left, and reap the leader
+    pub async fn terminate(&mut self, child: &mut Child) {
+        if let Some(id) = self.id.take() {
+            imp::signal_group(id, imp::Signal::Term);
+            let _ = tokio::time::timeout(KILL_GRACE, child.wait()).await;
+            imp::signal_group(id, imp::Signal::Kill);
+        }
+        let _ = child.kill().await;
+    }
+
+    /// The command exited by itself: leave anything it left running alone
+    pub fn release(&mut self) {
+        self.id = None;
+    }
+}
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+/// Count of the bytes a command has printed, shared by its output readers. Passing the limit
+/// kills the command's group.
+#[derive(Debug)]
+pub struct OutputCap {
+    limit: Option<u64>,
+    group: Option<u32>,
+    used: AtomicU64,
+    exceeded: AtomicBool,
+}
+
+impl OutputCap {
+    pub fn new(limit: Option<u64>, group: &ProcessGroup) -> Self {
+        Self {
+            limit,
+            group: group.id(),
+            used: AtomicU64::new(0),
+            exceeded: AtomicBool::new(false),
+        }
+    }
+
+    /// Count `bytes` more output. Returns `false` (once the group has been killed) when the
+    /// output goes over the limit; readers should stop there.
+    pub fn add(&self, bytes: usize) -> bool {
+        let Some(limit) = self.limit else {
+            return true;
+        };
+        let used = self.used.fetch_add(bytes as u64, Ordering::Relaxed) + bytes as u64;
+        if used <= limit {
+            return true;
+        }
+        if !self.exceeded.swap(true, Ordering::Relaxed) {
+            tracing::debug!(limit, "command output limit exceeded; killing its process group");
+            if let Some(id) = self.group {
+                imp::signal_group(id, imp::Signal::Kill);
+            }
+        }
+        false
+    }
+
+    /// Whether the command was stopped for printing too much
+    pub fn exceeded(&self) -> bool {
+        self.exceeded.load(Ordering::Relaxed)
+    }
+
+    /// Note for a result whose command was stopped, if it was
+    pub fn note(&self) -> Option<String> {
+        self.exceeded().then(|| {
+            format!(
+                "(The command printed more than {} bytes and was stopped.)",
+                self.limit.unwrap_or_default()
+            )
+        })
+    }
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::io;
+
+    use tokio::process::Command;
+
+    pub enum Signal {
+        Term,
+        Kill,
+    }
+
+    pub fn signal_group(id: u32, signal: Signal) {
+        let signal = match signal {
+            Signal::Term => libc::SIGTERM,
+            Signal::Kill => libc::SIGKILL,
+        };
+        // SAFETY: plain syscall; a group that no longer exists is reported as ESRCH
+        unsafe {
+            libc::kill(-(id as libc::pid_t), signal);
+        }
+    }
+
+    pub fn set_rlimits(command: &mut Command, cpu_seconds: Option<u64>, memory_bytes: Option<u64>) {
+        // SAFETY: only async-signal-safe calls between fork and exec
+        unsafe {
+            command.pre_exec(move || {
+                if let Some(seconds) = cpu_seconds {
+                    check(libc::setrlimit(libc::RLIMIT_CPU, &rlimit(seconds)))?;
+                }
+                if let Some(bytes) = memory_bytes {
+                    check(libc::setrlimit(libc::RLIMIT_AS, &rlimit(bytes)))?;
+                }
+                Ok(())
+            });
+        }
+    }
+
+    fn rlimit(value: u64) -> libc::rlimit {
+        libc::rlimit {
+            rlim_cur: value as libc::rlim_t,
+            rlim_max: value as libc::rlim_t,
+        }
+    }
+
+    fn check(result: libc::c_int) -> io::Result<()> {
+        if result == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use tokio::process::Command;
+
+    pub enum Signal {
+        Term,
+        Kill,
+    }
+
+    pub fn signal_group(_id: u32, _signal: Signal) {}
+
+    pub fn set_rlimits(_command: &mut Command, _cpu_seconds: Option<u64>, _memory_bytes: Option<u64>) {
+        tracing::warn!("resource limits are only supported on Unix");
+    }
+}
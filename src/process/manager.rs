@@ -1,4 +1,6 @@
-use super::background_shell::{BackgroundShell, ShellStatus};
+use super::background_shell::{BackgroundShell, ShellOptions, ShellStatus};
+use super::notification::{ShellNotification, ShellNotifications};
+use super::output_log::{LogSlice, StreamFilter};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -7,6 +9,8 @@ use tokio::sync::Mutex;
 #[derive(Clone)]
 pub struct BackgroundShellManager {
     shells: Arc<Mutex<HashMap<String, BackgroundShell>>>,
+    /// Exit and watch-match notifications waiting for their owners
+    notifications: ShellNotifications,
 }
 
 impl BackgroundShellManager {
@@ -14,6 +18,7 @@ impl BackgroundShellManager {
     pub fn new() -> Self {
         Self {
             shells: Arc::new(Mutex::new(HashMap::new())),
+            notifications: ShellNotifications::new(),
         }
     }
 
@@ -33,6 +38,59 @@ impl BackgroundShellManager {
         Ok(shell_id)
     }
 
+    /// Spawn a new background shell tagged with `owner` (a session or subagent ID)
+    pub async fn spawn_owned(
+        &self,
+        id: String,
+        command: String,
+        working_dir: std::path::PathBuf,
+        owner: &str,
+    ) -> anyhow::Result<String> {
+        let options = ShellOptions {
+            owner: Some(owner.to_string()),
+            ..Default::default()
+        };
+        self.spawn_with_options(id, command, working_dir, options).await
+    }
+
+    /// Spawn a new background shell with an owner and/or watch pattern
+    ///
+    /// Owned shells queue a notification when they exit and for each output line matching
+    /// `options.watch`; owners collect them with `take_notifications`.
+    pub async fn spawn_with_options(
+        &self,
+        id: String,
+        command: String,
+        working_dir: std::path::PathBuf,
+        options: ShellOptions,
+    ) -> anyhow::Result<String> {
+        let owner = options.owner.clone();
+        let shell = BackgroundShell::spawn_with_options(
+            id,
+            command,
+            working_dir,
+            options,
+            Some(self.notifications.clone()),
+        )
+        .await?;
+
+        let shell_id = shell.id().to_string();
+        self.shells.lock().await.insert(shell_id.clone(), shell);
+
+        tracing::info!(shell_id = %shell_id, owner = ?owner, "background shell registered");
+        Ok(shell_id)
+    }
+
+    /// Check whether `owner` has shell notifications waiting
+    pub fn has_notifications(&self, owner: &str) -> bool {
+        self.notifications.has_pending(owner)
+    }
+
+    /// Remove and return the pending shell notifications for `owner`
+    pub fn take_notifications(&self, owner: &str) -> Vec<ShellNotification> {
+        self.notifications.take(owner)
+    }
+
     /// Check if a shell exists
     pub async fn exists(&self, id: &str) -> bool {
         self.shells.lock().await.contains_key(id)
@@ -48,21 +106,26 @@ impl BackgroundShellManager {
         }
     }
 
-    /// Get stdout lines since offset
-    pub async fn get_stdout_since(&self, id: &str, offset: usize) -> Option<Vec<String>> {
+    /// Get output lines of the `filter`ed streams from sequence number `seq` on
+    pub async fn get_output_since(
+        &self,
+        id: &str,
+        seq: u64,
+        filter: StreamFilter,
+    ) -> Option<LogSlice> {
         let shells = self.shells.lock().await;
         if let Some(shell) = shells.get(id) {
-            Some(shell.stdout_since(offset).await)
+            Some(shell.output_since(seq, filter).await)
         } else {
             None
         }
     }
 
-    /// Get stderr lines since offset
-    pub async fn get_stderr_since(&self, id: &str, offset: usize) -> Option<Vec<String>> {
+    /// Get the sequence number the shell's next output line will get
+    pub async fn get_next_seq(&self, id: &str) -> Option<u64> {
         let shells = self.shells.lock().await;
         if let Some(shell) = shells.get(id) {
-            Some(shell.stderr_since(offset).await)
+            Some(shell.next_seq().await)
         } else {
             None
         }
@@ -88,6 +151,25 @@ impl BackgroundShellManager {
         }
     }
 
+    /// Get the unfinished last line of a pty shell's output (e.g. a prompt)
+    pub async fn get_pending_line(&self, id: &str) -> Option<String> {
+        let shells = self.shells.lock().await;
+        if let Some(shell) = shells.get(id) {
+            Some(shell.pending_line().await)
+        } else {
+            None
+        }
+    }
+
+    /// Send input to a shell started with a pty
+    pub async fn send_input(&self, id: &str, data: &[u8]) -> anyhow::Result<()> {
+        let shells = self.shells.lock().await;
+        match shells.get(id) {
+            Some(shell) => shell.send_input(data).await,
+            None => anyhow::bail!("Background shell '{}' not found", id),
+        }
+    }
+
     /// Get line counts for a shell
     pub async fn get_line_counts(&self, id: &str) -> Option<(usize, usize)> {
         let shells = self.shells.lock().await;
@@ -122,6 +204,47 @@ impl BackgroundShellManager {
         self.shells.lock().await.keys().cloned().collect()
     }
 
+    /// List the IDs of shells owned by `owner`
+    pub async fn list_ids_owned_by(&self, owner: &str) -> Vec<String> {
+        self.shells
+            .lock()
+            .await
+            .values()
+            .filter(|shell| shell.owner() == Some(owner))
+            .map(|shell| shell.id().to_string())
+            .collect()
+    }
+
+    /// Kill every still-running shell owned by `owner`
+    ///
+    /// Shells stay registered so their output can still be read. Pending notifications for
+    /// `owner` are discarded, since nobody is left to receive them. Returns the IDs that were
+    /// actually running (and are now killed).
+    pub async fn kill_owned_by(&self, owner: &str) -> Vec<String> {
+        let mut shells = self.shells.lock().await;
+        let mut killed = Vec::new();
+
+        for shell in shells.values_mut() {
+            if shell.owner() != Some(owner) || shell.check_finished().await {
+                continue;
+            }
+            match shell.kill().await {
+                Ok(()) => killed.push(shell.id().to_string()),
+                Err(e) => {
+                    tracing::warn!(shell_id = %shell.id(), error = %e, "failed to kill owned shell")
+                }
+            }
+        }
+
+        self.notifications.take(owner);
+
+        if !killed.is_empty() {
+            tracing::info!(owner = %owner, killed = killed.len(), "killed leftover owned shells");
+        }
+        killed.sort();
+        killed
+    }
+
     /// Get shell count
     pub async fn count(&self) -> usize {
         self.shells.lock().await.len()
@@ -150,26 +273,61 @@ impl BackgroundShellManager {
         count
     }
 
-    /// Get summary of all shells
+    /// Get summary of all shells, oldest first
+    ///
+    /// Reaps exited processes first so statuses and exit codes are current.
     pub async fn summary(&self) -> Vec<ShellSummary> {
-        let shells = self.shells.lock().await;
+        let mut shells = self.shells.lock().await;
         let mut summaries = Vec::new();
 
-        for shell in shells.values() {
+        for shell in shells.values_mut() {
+            shell.check_finished().await;
             let status = shell.status().await;
             let (stdout_lines, stderr_lines) = shell.line_counts().await;
+            let exit_code = match status {
+                ShellStatus::Completed { exit_code } => exit_code,
+                _ => None,
+            };
 
             summaries.push(ShellSummary {
                 id: shell.id().to_string(),
+                command: shell.command().to_string(),
                 status,
+                exit_code,
                 stdout_lines,
                 stderr_lines,
                 uptime_secs: shell.uptime_secs(),
+                started_at: shell.started_at(),
+                owner: shell.owner().map(str::to_string),
             });
         }
 
+        summaries.sort_by_key(|s| s.started_at);
         summaries
     }
+
+    /// Kill every running shell (e.g. on application exit); returns how many were killed
+    pub async fn kill_all(&self) -> usize {
+        let mut shells = self.shells.lock().await;
+        let mut killed = 0;
+
+        for shell in shells.values_mut() {
+            if shell.check_finished().await {
+                continue;
+            }
+            match shell.kill().await {
+                Ok(()) => killed += 1,
+                Err(e) => {
+                    tracing::warn!(shell_id = %shell.id(), error = %e, "failed to kill shell")
+                }
+            }
+        }
+
+        if killed > 0 {
+            tracing::info!(killed, "killed all running background shells");
+        }
+        killed
+    }
 }
 
 impl Default for BackgroundShellManager {
@@ -182,8 +340,13 @@ impl Default for BackgroundShellManager {
 #[derive(Debug, Clone)]
 pub struct ShellSummary {
     pub id: String,
+    pub command: String,
     pub status: ShellStatus,
+    /// Exit code once the shell has completed (137 when killed)
+    pub exit_code: Option<i32>,
     pub stdout_lines: usize,
     pub stderr_lines: usize,
     pub uptime_secs: u64,
+    pub started_at: std::time::SystemTime,
+    pub owner: Option<String>,
 }
@@ -1,5 +1,18 @@
 pub mod background_shell;
+pub mod env;
+pub mod group;
 pub mod manager;
+pub mod notification;
+pub mod output_log;
+pub mod pty;
+pub mod sandbox;
+pub mod session;
 
-pub use background_shell::BackgroundShell;
+pub use background_shell::{BackgroundShell, ShellOptions};
+pub use env::EnvFilter;
+pub use group::ResourceLimits;
 pub use manager::BackgroundShellManager;
+pub use notification::{ShellNotification, ShellNotificationKind, ShellNotifications};
+pub use output_log::{LogLine, LogSlice, OutputLog, Stream, StreamFilter};
+pub use sandbox::Sandbox;
+pub use session::{SessionOutput, ShellSession};
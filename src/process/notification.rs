@@ -0,0 +1,244 @@
+use std::sync::{Arc, Mutex};
+
+/// Maximum matching lines queued and listed per shell (the rest are only counted)
+const MAX_MATCHES_PER_SHELL: usize = 5;
+
+/// Something a background shell did that its owner should hear about
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShellNotification {
+    pub shell_id: String,
+    pub command: String,
+    /// Session or subagent that launched the shell
+    pub owner: String,
+    pub kind: ShellNotificationKind,
+}
+
+/// What happened in the background shell
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShellNotificationKind {
+    /// The process exited on its own (not via kill)
+    Exited { exit_code: Option<i32> },
+    /// A line of output matched the shell's watch pattern
+    Matched {
+        pattern: String,
+        stream: &'static str,
+        line: String,
+    },
+    /// More lines matched after [`MAX_MATCHES_PER_SHELL`] were already waiting
+    MoreMatches { count: usize },
+}
+
+/// Queue of pending notifications, shared by all shells of a manager
+///
+/// Uses a synchronous lock so the UI can check for pending work without awaiting.
+#[derive(Debug, Clone, Default)]
+pub struct ShellNotifications {
+    pending: Arc<Mutex<Vec<ShellNotification>>>,
+}
+
+impl ShellNotifications {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a notification. Once a shell has [`MAX_MATCHES_PER_SHELL`] matches waiting,
+    /// further matches are only counted, so a chatty watch pattern cannot grow the queue.
+    pub fn push(&self, notification: ShellNotification) {
+        let mut pending = self.lock();
+        if matches!(notification.kind, ShellNotificationKind::Matched { .. }) {
+            let same_shell = |n: &&mut ShellNotification| {
+                n.owner == notification.owner && n.shell_id == notification.shell_id
+            };
+            let waiting = pending
+                .iter_mut()
+                .filter(same_shell)
+                .filter(|n| matches!(n.kind, ShellNotificationKind::Matched { .. }))
+                .count();
+            if waiting >= MAX_MATCHES_PER_SHELL {
+                let more = pending.iter_mut().filter(same_shell).find_map(|n| match &mut n.kind {
+                    ShellNotificationKind::MoreMatches { count } => Some(count),
+                    _ => None,
+                });
+                match more {
+                    Some(count) => *count += 1,
+                    None => pending.push(ShellNotification {
+                        kind: ShellNotificationKind::MoreMatches { count: 1 },
+                        ..notification
+                    }),
+                }
+                return;
+            }
+        }
+
+        tracing::debug!(
+            shell_id = %notification.shell_id,
+            owner = %notification.owner,
+            kind = ?notification.kind,
+            "queued background shell notification"
+        );
+        pending.push(notification);
+    }
+
+    /// Check whether `owner` has notifications waiting
+    pub fn has_pending(&self, owner: &str) -> bool {
+        self.lock().iter().any(|n| n.owner == owner)
+    }
+
+    /// Remove and return all notifications for `owner`, in arrival order
+    pub fn take(&self, owner: &str) -> Vec<ShellNotification> {
+        let mut pending = self.lock();
+        let (taken, rest) = pending.drain(..).partition(|n| n.owner == owner);
+        *pending = rest;
+        taken
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<ShellNotification>> {
+        // A panic while holding the lock cannot leave the Vec inconsistent
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Render notifications as a `<system-reminder>` block for the model
+pub fn format_reminder(notifications: &[ShellNotification]) -> String {
+    let mut lines = vec!["Background shell updates:".to_string()];
+
+    // Group by shell, keeping the order in which shells first reported
+    let mut shell_ids: Vec<&str> = Vec::new();
+    for n in notifications {
+        if !shell_ids.contains(&n.shell_id.as_str()) {
+            shell_ids.push(&n.shell_id);
+        }
+    }
+
+    for shell_id in shell_ids {
+        let for_shell: Vec<&ShellNotification> = notifications
+            .iter()
+            .filter(|n| n.shell_id == shell_id)
+            .collect();
+        let command = &for_shell[0].command;
+
+        let matches: Vec<(&str, &str, &str)> = for_shell
+            .iter()
+            .filter_map(|n| match &n.kind {
+                ShellNotificationKind::Matched {
+                    pattern,
+                    stream,
+                    line,
+                } => Some((pattern.as_str(), *stream, line.as_str())),
+                _ => None,
+            })
+            .collect();
+
+        let more: usize = for_shell
+            .iter()
+            .map(|n| match n.kind {
+                ShellNotificationKind::MoreMatches { count } => count,
+                _ => 0,
+            })
+            .sum::<usize>()
+            + matches.len().saturating_sub(MAX_MATCHES_PER_SHELL);
+
+        if let Some((pattern, _, _)) = matches.first() {
+            lines.push(format!(
+                "- {} (`{}`) printed output matching /{}/:",
+                shell_id, command, pattern
+            ));
+            for (_, stream, line) in matches.iter().take(MAX_MATCHES_PER_SHELL) {
+                lines.push(format!("    [{}] {}", stream, line));
+            }
+            if more > 0 {
+                lines.push(format!("    ... and {} more matching lines", more));
+            }
+        }
+
+        for n in &for_shell {
+            if let ShellNotificationKind::Exited { exit_code } = n.kind {
+                let status = match exit_code {
+                    Some(code) => format!("exited with code {}", code),
+                    None => "was terminated by a signal".to_string(),
+                };
+                lines.push(format!("- {} (`{}`) {}.", shell_id, command, status));
+            }
+        }
+    }
+
+    lines.push("Use bash_output to read the full output.".to_string());
+    format!("<system-reminder>\n{}\n</system-reminder>", lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exited(shell_id: &str, owner: &str, exit_code: Option<i32>) -> ShellNotification {
+        ShellNotification {
+            shell_id: shell_id.to_string(),
+            command: "cargo build".to_string(),
+            owner: owner.to_string(),
+            kind: ShellNotificationKind::Exited { exit_code },
+        }
+    }
+
+    fn matched(shell_id: &str, line: &str) -> ShellNotification {
+        ShellNotification {
+            shell_id: shell_id.to_string(),
+            command: "npm run dev".to_string(),
+            owner: "session_1".to_string(),
+            kind: ShellNotificationKind::Matched {
+                pattern: "ready".to_string(),
+                stream: "stdout",
+                line: line.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_take_only_returns_owner_notifications() {
+        let queue = ShellNotifications::new();
+        queue.push(exited("shell_a", "session_1", Some(0)));
+        queue.push(exited("shell_b", "subagent_1", Some(1)));
+
+        assert!(queue.has_pending("session_1"));
+        assert_eq!(queue.take("session_1"), vec![exited("shell_a", "session_1", Some(0))]);
+        assert!(!queue.has_pending("session_1"));
+        assert!(queue.has_pending("subagent_1"));
+    }
+
+    #[test]
+    fn test_format_reminder() {
+        let mut notes = vec![exited("shell_a", "session_1", Some(2))];
+        for i in 0..7 {
+            notes.push(matched("shell_b", &format!("ready {}", i)));
+        }
+
+        let reminder = format_reminder(&notes);
+        assert!(reminder.starts_with("<system-reminder>\n"));
+        assert!(reminder.ends_with("</system-reminder>"));
+        assert!(reminder.contains("- shell_a (`cargo build`) exited with code 2."));
+        assert!(reminder.contains("- shell_b (`npm run dev`) printed output matching /ready/:"));
+        assert!(reminder.contains("    [stdout] ready 4"));
+        assert!(!reminder.contains("ready 5"));
+        assert!(reminder.contains("... and 2 more matching lines"));
+    }
+
+    #[test]
+    fn test_repeated_matches_are_counted_not_queued() {
+        let queue = ShellNotifications::new();
+        for i in 0..100 {
+            queue.push(matched("shell_b", &format!("ready {}", i)));
+        }
+        queue.push(exited("shell_b", "session_1", Some(0)));
+
+        let notes = queue.take("session_1");
+        assert_eq!(notes.len(), MAX_MATCHES_PER_SHELL + 2);
+        assert!(notes.contains(&ShellNotification {
+            kind: ShellNotificationKind::MoreMatches { count: 95 },
+            ..matched("shell_b", "")
+        }));
+
+        let reminder = format_reminder(&notes);
+        assert!(reminder.contains("    [stdout] ready 4"));
+        assert!(reminder.contains("... and 95 more matching lines"));
+        assert!(reminder.contains("exited with code 0"));
+    }
+}
@@ -0,0 +1,243 @@
+//! Captured output of a background shell
+//!
+//! stdout and stderr lines go into one log in the order they were read, each with a sequence
+//! number and the time since the shell started. Sequence numbers never change, so a reader
+//! can resume from the last one it saw even after older lines have been evicted to keep the
+//! log within its byte budget; the lines it missed are counted instead of silently skipped.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+/// Bytes of output text kept per shell (older lines are evicted)
+pub const MAX_LOG_BYTES: usize = 4 * 1024 * 1024;
+
+/// Stream a line was printed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+impl Stream {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stream::Stdout => "stdout",
+            Stream::Stderr => "stderr",
+        }
+    }
+}
+
+/// Which streams to read from the log
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamFilter {
+    Stdout,
+    Stderr,
+    #[default]
+    Combined,
+}
+
+impl StreamFilter {
+    pub fn includes(&self, stream: Stream) -> bool {
+        match self {
+            StreamFilter::Stdout => stream == Stream::Stdout,
+            StreamFilter::Stderr => stream == Stream::Stderr,
+            StreamFilter::Combined => true,
+        }
+    }
+}
+
+/// One line of output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogLine {
+    pub seq: u64,
+    pub stream: Stream,
+    /// When the line was read, relative to the start of the shell
+    pub at: Duration,
+    pub text: String,
+}
+
+/// Lines read from the log with [`OutputLog::since`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogSlice {
+    pub lines: Vec<LogLine>,
+    /// Lines (of any stream) at or after the requested sequence number that were evicted
+    /// before they could be read
+    pub missed: u64,
+    /// Sequence number to pass next time to get only newer lines
+    pub next_seq: u64,
+}
+
+/// Interleaved, byte-bounded log of a shell's output
+#[derive(Debug)]
+pub struct OutputLog {
+    lines: VecDeque<LogLine>,
+    started: Instant,
+    max_bytes: usize,
+    /// Text bytes currently held
+    bytes: usize,
+    next_seq: u64,
+    stdout_total: usize,
+    stderr_total: usize,
+}
+
+impl Default for OutputLog {
+    fn default() -> Self {
+        Self::new(MAX_LOG_BYTES)
+    }
+}
+
+impl OutputLog {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            started: Instant::now(),
+            max_bytes,
+            bytes: 0,
+            next_seq: 0,
+            stdout_total: 0,
+            stderr_total: 0,
+        }
+    }
+
+    /// Append a line, evicting the oldest lines if the log gets too big; returns its
+    /// sequence number. A single line longer than the whole budget is cut to fit.
+    pub fn push(&mut self, stream: Stream, mut text: String) -> u64 {
+        if text.len() > self.max_bytes {
+            let mut end = self.max_bytes;
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            text.truncate(end);
+        }
+
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        match stream {
+            Stream::Stdout => self.stdout_total += 1,
+            Stream::Stderr => self.stderr_total += 1,
+        }
+        self.bytes += text.len();
+        self.lines.push_back(LogLine {
+            seq,
+            stream,
+            at: self.started.elapsed(),
+            text,
+        });
+
+        while self.bytes > self.max_bytes {
+            let Some(evicted) = self.lines.pop_front() else {
+                break;
+            };
+            self.bytes -= evicted.text.len();
+        }
+        seq
+    }
+
+    /// Lines of the `filter`ed streams with a sequence number of at least `seq`
+    pub fn since(&self, seq: u64, filter: StreamFilter) -> LogSlice {
+        let first = self.first_seq();
+        let skip = seq.saturating_sub(first).min(self.lines.len() as u64) as usize;
+        LogSlice {
+            lines: self
+                .lines
+                .iter()
+                .skip(skip)
+                .filter(|line| filter.includes(line.stream))
+                .cloned()
+                .collect(),
+            missed: first.saturating_sub(seq).min(self.evicted()),
+            next_seq: self.next_seq,
+        }
+    }
+
+    /// Text of the retained lines of `stream`
+    pub fn lines(&self, stream: Stream) -> Vec<String> {
+        self.lines
+            .iter()
+            .filter(|line| line.stream == stream)
+            .map(|line| line.text.clone())
+            .collect()
+    }
+
+    /// Lines ever printed on stdout and stderr, evicted or not
+    pub fn totals(&self) -> (usize, usize) {
+        (self.stdout_total, self.stderr_total)
+    }
+
+    /// Sequence number the next line will get
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// Sequence number of the oldest retained line
+    pub fn first_seq(&self) -> u64 {
+        self.next_seq - self.lines.len() as u64
+    }
+
+    /// Lines evicted so far
+    pub fn evicted(&self) -> u64 {
+        self.first_seq()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines_keep_their_order_across_streams() {
+        let mut log = OutputLog::default();
+        log.push(Stream::Stdout, "one".to_string());
+        log.push(Stream::Stderr, "two".to_string());
+        log.push(Stream::Stdout, "three".to_string());
+
+        let all = log.since(0, StreamFilter::Combined);
+        let texts: Vec<_> = all.lines.iter().map(|l| (l.seq, l.text.as_str())).collect();
+        assert_eq!(texts, vec![(0, "one"), (1, "two"), (2, "three")]);
+        assert_eq!(all.next_seq, 3);
+        assert_eq!(all.missed, 0);
+
+        let stderr = log.since(0, StreamFilter::Stderr);
+        assert_eq!(stderr.lines.len(), 1);
+        assert_eq!(stderr.lines[0].seq, 1);
+        assert_eq!(log.since(2, StreamFilter::Stdout).lines[0].text, "three");
+        assert!(log.since(3, StreamFilter::Combined).lines.is_empty());
+        assert_eq!(log.totals(), (2, 1));
+    }
+
+    #[test]
+    fn test_eviction_keeps_sequence_numbers_and_reports_missed_lines() {
+        let mut log = OutputLog::new(10);
+        for i in 0..5 {
+            log.push(Stream::Stdout, format!("line{}", i));
+        }
+        // 5 bytes each: only the last two fit
+        assert_eq!(log.first_seq(), 3);
+        assert_eq!(log.evicted(), 3);
+        assert_eq!(log.lines(Stream::Stdout), vec!["line3", "line4"]);
+
+        let slice = log.since(1, StreamFilter::Combined);
+        assert_eq!(slice.missed, 2);
+        assert_eq!(slice.lines[0].seq, 3);
+        assert_eq!(slice.lines[0].text, "line3");
+
+        let slice = log.since(4, StreamFilter::Combined);
+        assert_eq!(slice.missed, 0);
+        assert_eq!(slice.lines.len(), 1);
+        assert_eq!(slice.lines[0].text, "line4");
+        assert_eq!(log.totals(), (5, 0));
+    }
+
+    #[test]
+    fn test_oversized_line_is_cut_to_the_budget() {
+        let mut log = OutputLog::new(4);
+        log.push(Stream::Stdout, "ab".to_string());
+        log.push(Stream::Stderr, "ééé".to_string());
+        assert_eq!(log.lines(Stream::Stderr), vec!["éé"]);
+        assert!(log.lines(Stream::Stdout).is_empty());
+        assert_eq!(log.next_seq(), 2);
+    }
+}
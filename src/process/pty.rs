@@ -0,0 +1,275 @@
+//! Running commands under a pseudo-terminal
+//!
+//! Some programs behave differently without a terminal: progress bars disappear, test runners
+//! buffer all output, prompts are skipped. [`spawn`] gives a command a fixed-size terminal as
+//! its stdin, stdout and stderr (so the two output streams are merged), and
+//! [`strip_terminal_codes`] turns what it prints back into plain text.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::process::{Child, Command};
+
+/// Terminal size reported to commands
+pub const PTY_ROWS: u16 = 40;
+pub const PTY_COLS: u16 = 120;
+
+/// Spawn `command` with a new terminal as its controlling terminal and stdio.
+/// Returns the child and the terminal's master side (its output, and where input goes).
+pub fn spawn(command: &mut Command) -> io::Result<(Child, PtyMaster)> {
+    imp::spawn(command)
+}
+
+/// The controlling side of a command's terminal; clones share it
+#[derive(Clone)]
+pub struct PtyMaster {
+    inner: imp::Master,
+}
+
+impl PtyMaster {
+    /// Send input to the command as if typed
+    pub async fn write_all(&self, data: &[u8]) -> io::Result<()> {
+        self.inner.write_all(data).await
+    }
+}
+
+impl AsyncRead for PtyMaster {
+    /// Reads end (`Ok(0)`) once every process holding the terminal has exited
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        self.inner.poll_read(cx, buf)
+    }
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::process::Stdio;
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+
+    use tokio::io::unix::AsyncFd;
+    use tokio::io::ReadBuf;
+    use tokio::process::{Child, Command};
+
+    use super::{PtyMaster, PTY_COLS, PTY_ROWS};
+
+    #[derive(Clone)]
+    pub struct Master(Arc<AsyncFd<OwnedFd>>);
+
+    pub fn spawn(command: &mut Command) -> io::Result<(Child, PtyMaster)> {
+        let (master, slave) = open()?;
+
+        command
+            .stdin(Stdio::from(slave.try_clone()?))
+            .stdout(Stdio::from(slave.try_clone()?))
+            .stderr(Stdio::from(slave))
+            .env("TERM", "xterm-256color")
+            .env("COLUMNS", PTY_COLS.to_string())
+            .env("LINES", PTY_ROWS.to_string());
+        // SAFETY: only async-signal-safe calls between fork and exec
+        unsafe {
+            command.pre_exec(|| {
+                // A new session whose controlling terminal is the pty (stdin by now)
+                if libc::setsid() == -1 || libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let child = command.spawn()?;
+        // `command` keeps its stdio until replaced; closing our copies of the slave side lets
+        // reads end once the command (and its children) exit
+        command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+
+        Ok((
+            child,
+            PtyMaster {
+                inner: Master(Arc::new(AsyncFd::new(master)?)),
+            },
+        ))
+    }
+
+    fn open() -> io::Result<(OwnedFd, OwnedFd)> {
+        let mut master = -1;
+        let mut slave = -1;
+        let size = libc::winsize {
+            ws_row: PTY_ROWS,
+            ws_col: PTY_COLS,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        // SAFETY: valid out-pointers; the returned descriptors are owned from here on
+        unsafe {
+            if libc::openpty(&mut master, &mut slave, std::ptr::null_mut(), std::ptr::null(), &size) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            let (master, slave) = (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave));
+            for fd in [&master, &slave] {
+                libc::fcntl(fd.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC);
+            }
+            let flags = libc::fcntl(master.as_raw_fd(), libc::F_GETFL);
+            if libc::fcntl(master.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok((master, slave))
+        }
+    }
+
+    impl Master {
+        pub fn poll_read(&self, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+            loop {
+                let mut guard = std::task::ready!(self.0.poll_read_ready(cx))?;
+                let unfilled = buf.initialize_unfilled();
+                let result = guard.try_io(|fd| {
+                    // SAFETY: reads into the initialized, unfilled part of `buf`
+                    let n = unsafe { libc::read(fd.as_raw_fd(), unfilled.as_mut_ptr().cast(), unfilled.len()) };
+                    if n < 0 {
+                        Err(io::Error::last_os_error())
+                    } else {
+                        Ok(n as usize)
+                    }
+                });
+                match result {
+                    Ok(Ok(n)) => {
+                        buf.advance(n);
+                        return Poll::Ready(Ok(()));
+                    }
+                    // Linux reports EIO once the slave side is closed for good
+                    Ok(Err(e)) if e.raw_os_error() == Some(libc::EIO) => return Poll::Ready(Ok(())),
+                    Ok(Err(e)) => return Poll::Ready(Err(e)),
+                    Err(_would_block) => continue,
+                }
+            }
+        }
+
+        pub async fn write_all(&self, mut data: &[u8]) -> io::Result<()> {
+            while !data.is_empty() {
+                let mut guard = self.0.writable().await?;
+                let result = guard.try_io(|fd| {
+                    // SAFETY: writes from a valid slice
+                    let n = unsafe { libc::write(fd.as_raw_fd(), data.as_ptr().cast(), data.len()) };
+                    if n < 0 {
+                        Err(io::Error::last_os_error())
+                    } else {
+                        Ok(n as usize)
+                    }
+                });
+                if let Ok(written) = result {
+                    data = &data[written?..];
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use std::io;
+    use std::task::{Context, Poll};
+
+    use tokio::io::ReadBuf;
+    use tokio::process::{Child, Command};
+
+    use super::PtyMaster;
+
+    #[derive(Clone)]
+    pub struct Master;
+
+    pub fn spawn(_command: &mut Command) -> io::Result<(Child, PtyMaster)> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "pseudo-terminals are only supported on Unix",
+        ))
+    }
+
+    impl Master {
+        pub fn poll_read(&self, _cx: &mut Context<'_>, _buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        pub async fn write_all(&self, _data: &[u8]) -> io::Result<()> {
+            Err(io::ErrorKind::Unsupported.into())
+        }
+    }
+}
+
+/// Plain text of terminal output: escape sequences (colors, cursor movement, titles) are
+/// removed, `\r\n` becomes `\n`, and text overwritten after a carriage return or backspace is
+/// dropped, so a progress bar leaves only its last state
+pub fn strip_terminal_codes(raw: &str) -> String {
+    let mut output = String::with_capacity(raw.len());
+    // Start of the current line in `output`, where a carriage return goes back to
+    let mut line_start = 0;
+    // After a carriage return, the next character starts overwriting the line
+    let mut returned = false;
+    let mut chars = raw.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                // CSI: parameters, then a final byte in @..~
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // OSC, DCS, ...: up to BEL or ESC \
+                Some(']' | 'P' | 'X' | '^' | '_') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                // Character set selection takes one more character
+                Some('(' | ')' | '*' | '+' | '#' | '%') => {
+                    chars.next();
+                }
+                _ => {}
+            },
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\r' => returned = true,
+            '\n' => {
+                output.push('\n');
+                line_start = output.len();
+                returned = false;
+            }
+            '\x08' => {
+                if output.len() > line_start {
+                    output.pop();
+                }
+            }
+            '\t' => output.push('\t'),
+            c if c.is_control() => {}
+            c => {
+                if std::mem::take(&mut returned) {
+                    output.truncate(line_start);
+                }
+                output.push(c);
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_terminal_codes() {
+        assert_eq!(
+            strip_terminal_codes("\x1b[1;32mok\x1b[0m\r\n\x1b]0;title\x07done\r\n"),
+            "ok\ndone\n"
+        );
+        assert_eq!(strip_terminal_codes(" 10%\r 50%\r100%\nnext"), "100%\nnext");
+        assert_eq!(strip_terminal_codes("Continue? [y/n] \r"), "Continue? [y/n] ");
+        assert_eq!(strip_terminal_codes("ab\x08c\x1b(B\x1b[?25l"), "ac");
+    }
+}
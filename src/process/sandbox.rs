@@ -0,0 +1,437 @@
+//! Optional sandbox for shell commands (Linux)
+//!
+//! Commands run in new user/mount (and, unless allowed, network) namespaces where the whole
+//! filesystem is read-only except the writable roots the caller passes in — the working
+//! directory and temp directories, as for the file tools. Set up with bubblewrap when it is
+//! installed, otherwise by the agent itself: it enters the namespaces before exec, a small
+//! mount script makes the filesystem read-only, and util-linux `setpriv` runs the command.
+//!
+//! Either way the command runs without capabilities, so it cannot undo the read-only mounts.
+//! The `unshare` backend also installs a seccomp filter (see `imp::seccomp_filter`) that
+//! refuses `mount` calls making a mount writable, `umount2`, `unshare`, `setns` and `clone`
+//! into new namespaces. The sandbox keeps commands from writing outside the allowed roots and
+//! from reaching the network; it is not meant to contain code that attacks the kernel.
+
+use std::path::{Path, PathBuf};
+
+use tokio::process::Command;
+use tokio::sync::OnceCell;
+
+use crate::config::station::{SandboxBackend, SandboxSettings};
+
+/// Exit code of the unshare setup script when the sandbox cannot be set up
+const SETUP_FAILED: i32 = 125;
+
+/// Runs inside the new namespaces: bind the writable roots onto themselves, make every other
+/// mount read-only, then exec the shell without any capabilities. Arguments: shell, command,
+/// working dir, roots...
+const UNSHARE_SCRIPT: &str = r#"shell=$1; cmd=$2; wd=$3; shift 3
+for root in "$@"; do
+  mount --rbind "$root" "$root" || { echo "ok-sandbox: cannot bind $root" >&2; exit 125; }
+done
+while read -r _ target _ opts _; do
+  target=$(printf '%b' "$target")
+  case "$target" in /dev|/dev/*|/proc|/proc/*) continue ;; esac
+  keep=0
+  for root in "$@"; do
+    case "$target" in "$root"|"$root"/*) keep=1 ;; esac
+  done
+  [ "$keep" = 1 ] && continue
+  flags=$(printf '%s' "$opts" | tr ',' '\n' | grep -xE 'nosuid|nodev|noexec|noatime|nodiratime|relatime' | paste -sd, -)
+  mount -o "remount,bind,ro${flags:+,$flags}" "$target" 2>/dev/null \
+    || { echo "ok-sandbox: cannot make $target read-only" >&2; exit 125; }
+done < /proc/self/mounts
+cd "$wd" || exit 125
+command -v setpriv >/dev/null || { echo "ok-sandbox: setpriv (util-linux) is not installed" >&2; exit 125; }
+exec setpriv --bounding-set=-all --inh-caps=-all --ambient-caps=-all --no-new-privs -- "$shell" -c "$cmd"
+"#;
+
+/// stderr lines that mean the sandbox stopped a write
+const WRITE_DENIED: &[&str] = &["Read-only file system"];
+
+/// stderr lines that mean the sandbox stopped network access
+const NETWORK_DENIED: &[&str] = &[
+    "Network is unreachable",
+    "Could not resolve host",
+    "Temporary failure in name resolution",
+];
+
+#[derive(Debug, thiserror::Error)]
+pub enum SandboxError {
+    #[error("Sandbox is enabled but cannot be set up with {backend}: {reason}. Install bubblewrap or allow unprivileged user namespaces, or disable [sandbox] in config.toml.")]
+    Unavailable {
+        backend: &'static str,
+        reason: String,
+    },
+
+    #[error("Command was blocked by the sandbox: {reason}\n\n{output}")]
+    Violation { reason: String, output: String },
+}
+
+/// A configured sandbox; cheap to share between tool calls and background shells
+#[derive(Debug)]
+pub struct Sandbox {
+    backend: SandboxBackend,
+    network: bool,
+    /// Result of the first setup attempt, kept for the session
+    probe: OnceCell<Result<(), String>>,
+}
+
+impl Sandbox {
+    /// Resolve the backend (`auto` picks bubblewrap when `bwrap` is on PATH)
+    pub fn new(settings: &SandboxSettings) -> Self {
+        let backend = match settings.backend {
+            SandboxBackend::Auto if find_in_path("bwrap").is_some() => SandboxBackend::Bubblewrap,
+            SandboxBackend::Auto => SandboxBackend::Unshare,
+            backend => backend,
+        };
+        Self {
+            backend,
+            network: settings.network,
+            probe: OnceCell::new(),
+        }
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        match self.backend {
+            SandboxBackend::Bubblewrap => "bubblewrap",
+            _ => "unshare",
+        }
+    }
+
+    /// Make sure the sandbox can be set up here (tried once, then remembered)
+    pub async fn ensure_available(&self) -> Result<(), SandboxError> {
+        let probe = self
+            .probe
+            .get_or_init(|| async {
+                if !cfg!(target_os = "linux") {
+                    return Err("sandboxing is only supported on Linux".to_string());
+                }
+                let output = self
+                    .command("sh", "true", Path::new("/"), &[std::env::temp_dir()])
+                    .output()
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if output.status.success() {
+                    Ok(())
+                } else {
+                    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+                    Err(if stderr.is_empty() {
+                        format!("setup exited with {}", output.status)
+                    } else {
+                        stderr
+                    })
+                }
+            })
+            .await;
+
+        probe.clone().map_err(|reason| SandboxError::Unavailable {
+            backend: self.backend_name(),
+            reason,
+        })
+    }
+
+    /// `shell -c command` in `working_dir`, inside the sandbox; only `writable` stays writable
+    pub fn command(&self, shell: &str, command: &str, working_dir: &Path, writable: &[PathBuf]) -> Command {
+        // Binding a root that doesn't exist would fail the whole setup
+        let writable = writable.iter().filter(|root| root.is_dir());
+
+        match self.backend {
+            SandboxBackend::Bubblewrap => {
+                let mut cmd = Command::new("bwrap");
+                cmd.args(["--ro-bind", "/", "/", "--dev", "/dev", "--proc", "/proc"])
+                    .args(["--unshare-user", "--unshare-pid", "--die-with-parent"])
+                    .args(["--cap-drop", "ALL"]);
+                if !self.network {
+                    cmd.arg("--unshare-net");
+                }
+                for root in writable {
+                    cmd.arg("--bind").arg(root).arg(root);
+                }
+                cmd.arg("--chdir")
+                    .arg(working_dir)
+                    .args(["--", shell, "-c", command]);
+                cmd
+            }
+            _ => {
+                let mut cmd = Command::new("sh");
+                cmd.args(["-c", UNSHARE_SCRIPT, "ok-sandbox", shell, command])
+                    .arg(working_dir)
+                    .args(writable);
+                imp::enter_namespaces(&mut cmd, self.network);
+                cmd
+            }
+        }
+    }
+
+    /// Whether a finished command ran into the sandbox; `output` is returned in the error.
+    ///
+    /// Only a failed command is a violation. A command that succeeded although something it ran
+    /// was denied (e.g. `cmd || true`, or a tool that falls back) keeps its result, and the
+    /// returned warning is added to it.
+    pub fn check_output(
+        &self,
+        exit_code: Option<i32>,
+        stderr: &str,
+        output: &str,
+    ) -> Result<Option<String>, SandboxError> {
+        let matching = |patterns: &[&str]| {
+            stderr
+                .lines()
+                .filter(|line| patterns.iter().any(|p| line.contains(p)))
+                .map(str::trim)
+                .collect::<Vec<_>>()
+        };
+
+        let reason = if exit_code == Some(SETUP_FAILED) && stderr.contains("ok-sandbox:") {
+            return Err(SandboxError::Unavailable {
+                backend: self.backend_name(),
+                reason: stderr.trim().to_string(),
+            });
+        } else if let Some(line) = matching(WRITE_DENIED).first() {
+            format!(
+                "writes are only allowed under the working directory and temp directories ({})",
+                line
+            )
+        } else if let Some(line) = matching(NETWORK_DENIED).first().filter(|_| !self.network) {
+            format!("network access is disabled ({})", line)
+        } else {
+            return Ok(None);
+        };
+
+        if exit_code == Some(0) {
+            return Ok(Some(format!("Part of the command was blocked by the sandbox: {}", reason)));
+        }
+        Err(SandboxError::Violation {
+            reason,
+            output: output.to_string(),
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use std::ffi::CStr;
+    use std::io;
+
+    use tokio::process::Command;
+
+    /// `AUDIT_ARCH_*` of the system calls the filter expects; others are refused
+    #[cfg(target_arch = "x86_64")]
+    const AUDIT_ARCH: u32 = 0xc000_003e;
+    #[cfg(target_arch = "aarch64")]
+    const AUDIT_ARCH: u32 = 0xc000_00b7;
+
+    /// Offsets into `struct seccomp_data`: system call number, architecture, and the low word
+    /// of argument `i`
+    const NR: u32 = 0;
+    const ARCH: u32 = 4;
+    const fn arg(i: u32) -> u32 {
+        16 + 8 * i
+    }
+
+    /// `clone` flags that create namespaces
+    const NAMESPACE_FLAGS: libc::c_int = libc::CLONE_NEWUSER
+        | libc::CLONE_NEWNS
+        | libc::CLONE_NEWNET
+        | libc::CLONE_NEWPID
+        | libc::CLONE_NEWUTS
+        | libc::CLONE_NEWIPC
+        | libc::CLONE_NEWCGROUP;
+
+    /// Make `command` enter new user and mount (and, without `network`, network) namespaces as
+    /// root mapped to the current user, with [`seccomp_filter`] installed
+    pub fn enter_namespaces(command: &mut Command, network: bool) {
+        // SAFETY: getuid/getgid cannot fail
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        let uid_map = format!("0 {} 1\n", uid);
+        let gid_map = format!("0 {} 1\n", gid);
+        let filter = seccomp_filter();
+
+        let mut flags = libc::CLONE_NEWUSER | libc::CLONE_NEWNS;
+        if !network {
+            flags |= libc::CLONE_NEWNET;
+        }
+
+        // SAFETY: only async-signal-safe calls between fork and exec; everything they use is
+        // allocated before the fork
+        unsafe {
+            command.pre_exec(move || {
+                check(libc::unshare(flags))?;
+                write_file(c"/proc/self/setgroups", b"deny")?;
+                write_file(c"/proc/self/uid_map", uid_map.as_bytes())?;
+                write_file(c"/proc/self/gid_map", gid_map.as_bytes())?;
+                // Keep the script's mounts from propagating out of the namespace
+                check(libc::mount(
+                    std::ptr::null(),
+                    c"/".as_ptr(),
+                    std::ptr::null(),
+                    libc::MS_REC | libc::MS_PRIVATE,
+                    std::ptr::null(),
+                ))?;
+
+                let program = libc::sock_fprog {
+                    len: filter.len() as libc::c_ushort,
+                    filter: filter.as_ptr() as *mut libc::sock_filter,
+                };
+                check(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0))?;
+                check(libc::prctl(
+                    libc::PR_SET_SECCOMP,
+                    libc::SECCOMP_MODE_FILTER,
+                    &program as *const libc::sock_fprog,
+                ))?;
+                Ok(())
+            });
+        }
+    }
+
+    /// The system calls a sandboxed command could use to undo the sandbox's mounts
+    ///
+    /// The mount script still needs `mount` for bind mounts and read-only remounts, so only
+    /// remounts without `MS_RDONLY` are refused. The newer mount API and `clone3` (whose flags
+    /// a filter cannot read) report ENOSYS, so callers fall back to `mount` and `clone`.
+    pub(super) fn seccomp_filter() -> Vec<libc::sock_filter> {
+        let eperm = libc::SECCOMP_RET_ERRNO | libc::EPERM as u32;
+        let enosys = libc::SECCOMP_RET_ERRNO | libc::ENOSYS as u32;
+        let refused = [
+            (libc::SYS_unshare, eperm),
+            (libc::SYS_setns, eperm),
+            (libc::SYS_umount2, eperm),
+            (libc::SYS_pivot_root, eperm),
+            (libc::SYS_clone3, enosys),
+            (libc::SYS_open_tree, enosys),
+            (libc::SYS_move_mount, enosys),
+            (libc::SYS_fsopen, enosys),
+            (libc::SYS_fsconfig, enosys),
+            (libc::SYS_fsmount, enosys),
+            (libc::SYS_fspick, enosys),
+            (libc::SYS_mount_setattr, enosys),
+        ];
+
+        let mut filter = vec![load(ARCH), jump(libc::BPF_JEQ, AUDIT_ARCH, 1, 0), ret(eperm), load(NR)];
+        // x32 system calls share the architecture and set this bit
+        #[cfg(target_arch = "x86_64")]
+        filter.extend([jump(libc::BPF_JGE, 0x4000_0000, 0, 1), ret(eperm)]);
+        for (nr, action) in refused {
+            filter.extend([jump(libc::BPF_JEQ, nr as u32, 0, 1), ret(action)]);
+        }
+        filter.extend([
+            // mount: bind mounts, new mounts and read-only remounts only
+            jump(libc::BPF_JEQ, libc::SYS_mount as u32, 0, 5),
+            load(arg(3)),
+            jump(libc::BPF_JSET, libc::MS_REMOUNT as u32, 0, 2),
+            jump(libc::BPF_JSET, libc::MS_RDONLY as u32, 1, 0),
+            ret(eperm),
+            ret(libc::SECCOMP_RET_ALLOW),
+            // clone: no new namespaces
+            jump(libc::BPF_JEQ, libc::SYS_clone as u32, 0, 3),
+            load(arg(0)),
+            jump(libc::BPF_JSET, NAMESPACE_FLAGS as u32, 0, 1),
+            ret(eperm),
+            ret(libc::SECCOMP_RET_ALLOW),
+        ]);
+        filter
+    }
+
+    fn load(offset: u32) -> libc::sock_filter {
+        statement(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, offset)
+    }
+
+    fn ret(action: u32) -> libc::sock_filter {
+        statement(libc::BPF_RET | libc::BPF_K, action)
+    }
+
+    fn statement(code: u32, k: u32) -> libc::sock_filter {
+        libc::sock_filter {
+            code: code as u16,
+            jt: 0,
+            jf: 0,
+            k,
+        }
+    }
+
+    fn jump(condition: u32, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+        libc::sock_filter {
+            code: (libc::BPF_JMP | condition | libc::BPF_K) as u16,
+            jt,
+            jf,
+            k,
+        }
+    }
+
+    fn write_file(path: &CStr, contents: &[u8]) -> io::Result<()> {
+        // SAFETY: plain system calls on a descriptor owned by this function
+        unsafe {
+            let fd = libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+            check(fd)?;
+            let written = libc::write(fd, contents.as_ptr().cast(), contents.len());
+            libc::close(fd);
+            if written != contents.len() as isize {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    fn check(result: libc::c_int) -> io::Result<()> {
+        if result == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use tokio::process::Command;
+
+    /// Sandboxing is Linux-only; `ensure_available` fails before any command is run
+    pub fn enter_namespaces(_command: &mut Command, _network: bool) {}
+}
+
+fn find_in_path(program: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_output_reports_violations() {
+        let sandbox = Sandbox::new(&SandboxSettings {
+            enabled: true,
+            network: false,
+            backend: SandboxBackend::Unshare,
+        });
+
+        assert_eq!(sandbox.check_output(Some(0), "", "done").unwrap(), None);
+        assert_eq!(sandbox.check_output(Some(1), "error: no such file", "").unwrap(), None);
+
+        // A command that succeeded anyway only gets a warning
+        let warning = sandbox
+            .check_output(Some(0), "touch: cannot touch '/etc/x': Read-only file system\n", "out")
+            .unwrap()
+            .unwrap();
+        assert!(warning.contains("writes are only allowed") && warning.contains("/etc/x"));
+
+        let err = sandbox
+            .check_output(Some(1), "touch: cannot touch '/etc/x': Read-only file system\n", "out")
+            .unwrap_err();
+        assert!(matches!(&err, SandboxError::Violation { reason, output }
+            if reason.contains("/etc/x") && output == "out"));
+
+        let err = sandbox
+            .check_output(Some(6), "curl: (6) Could not resolve host: example.com", "")
+            .unwrap_err();
+        assert!(err.to_string().contains("network access is disabled"));
+
+        let err = sandbox
+            .check_output(Some(SETUP_FAILED), "ok-sandbox: cannot make /sys read-only", "")
+            .unwrap_err();
+        assert!(matches!(err, SandboxError::Unavailable { backend: "unshare", .. }));
+    }
+}
@@ -0,0 +1,361 @@
+//! Persistent shell session: one long-lived bash process per agent session
+//!
+//! Commands are written to the shell's stdin followed by a sentinel line that reports the exit
+//! status, a checksum of the exported environment and `$PWD`, so `cd`, `export` and
+//! `source venv/bin/activate` carry over to the next command. Each command reads stdin from
+//! `/dev/null` so it can't swallow the protocol.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+
+use super::env::EnvFilter;
+use super::group::{self, OutputCap, ProcessGroup, ResourceLimits};
+use super::sandbox::Sandbox;
+
+/// How long a new shell may take to answer its first (empty) command
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    #[error("Failed to start the shell session: {0}")]
+    Start(std::io::Error),
+
+    #[error("Shell session I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The command outlived its timeout; the shell was killed and the session reset
+    #[error("Command timed out; the shell session was reset")]
+    Timeout,
+}
+
+/// Result of one command in the session
+#[derive(Debug, Clone)]
+pub struct SessionOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: Option<i32>,
+    /// The shell's directory after the command
+    pub cwd: PathBuf,
+    /// Whether the command changed exported variables
+    pub env_changed: bool,
+    /// The command exited the shell; the next one starts a fresh session
+    pub shell_exited: bool,
+    /// Why the command was stopped before it finished (taking the shell with it), if it was
+    pub stopped: Option<String>,
+}
+
+/// Callback for output lines of a running command: `(stream, line)`
+pub type OnLine<'a> = dyn Fn(&'static str, &str) + Send + Sync + 'a;
+
+/// Where a shell stands between commands
+#[derive(Debug, Clone)]
+struct ShellState {
+    cwd: PathBuf,
+    env_checksum: String,
+}
+
+struct RunningShell {
+    child: Child,
+    /// The shell and every command it runs; killed if the shell is dropped
+    group: ProcessGroup,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    stderr: BufReader<ChildStderr>,
+    state: ShellState,
+}
+
+/// A bash process kept alive across `bash` tool calls (started on first use)
+pub struct ShellSession {
+    shell: Mutex<Option<RunningShell>>,
+    /// Marks the end of a command's output; unique per session
+    sentinel: String,
+    /// Which of this process's variables the shell inherits
+    env_filter: Arc<EnvFilter>,
+    limits: ResourceLimits,
+}
+
+impl Default for ShellSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for ShellSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShellSession").finish_non_exhaustive()
+    }
+}
+
+impl ShellSession {
+    pub fn new() -> Self {
+        Self {
+            shell: Mutex::new(None),
+            sentinel: format!("__OK_SHELL_DONE_{}__", uuid::Uuid::new_v4().simple()),
+            env_filter: Arc::default(),
+            limits: ResourceLimits::default(),
+        }
+    }
+
+    /// Apply `limits` to the shell and each command run in it
+    pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Start shells with only the variables `filter` allows
+    pub fn with_env_filter(mut self, filter: Arc<EnvFilter>) -> Self {
+        self.env_filter = filter;
+        self
+    }
+
+    /// The shell's current directory, if it is running
+    pub async fn cwd(&self) -> Option<PathBuf> {
+        self.shell.lock().await.as_ref().map(|shell| shell.state.cwd.clone())
+    }
+
+    /// Stop the shell (and anything it left running); the next command starts a fresh one in
+    /// the working directory
+    pub async fn reset(&self) {
+        if let Some(mut shell) = self.shell.lock().await.take() {
+            shell.group.terminate(&mut shell.child).await;
+        }
+    }
+
+    /// Run `command` in the session, starting the shell in `working_dir` if needed.
+    /// `sandbox` and `writable_roots` apply when a new shell is started. `on_line` gets each
+    /// output line (with its stream, "stdout" or "stderr") as it is printed.
+    pub async fn run(
+        &self,
+        command: &str,
+        timeout: Duration,
+        working_dir: &Path,
+        sandbox: Option<&Sandbox>,
+        writable_roots: &[PathBuf],
+        on_line: &OnLine<'_>,
+    ) -> Result<SessionOutput, SessionError> {
+        let mut guard = self.shell.lock().await;
+
+        // Taken out while the command runs: if this future is dropped midway, the shell is
+        // dropped (and killed) with it rather than left with unread output
+        let mut shell = match guard.take() {
+            Some(shell) => shell,
+            None => self.start(working_dir, sandbox, writable_roots).await?,
+        };
+
+        let cap = OutputCap::new(self.limits.output_bytes, &shell.group);
+        let result = tokio::time::timeout(timeout, self.exchange(&mut shell, command, &cap, on_line)).await;
+        let Ok(result) = result else {
+            shell.group.terminate(&mut shell.child).await;
+            return Err(SessionError::Timeout);
+        };
+        let (stdout, stderr, status) = result?;
+
+        let Some((exit_code, state)) = status else {
+            // The command exited the shell (`exit`, `set -e` and a failure, ...), or was
+            // stopped for printing too much; stop whatever it left behind too
+            let exit_code = shell.child.wait().await.ok().and_then(|s| s.code());
+            shell.group.kill();
+            return Ok(SessionOutput {
+                stdout,
+                stderr,
+                exit_code,
+                cwd: shell.state.cwd,
+                env_changed: false,
+                shell_exited: true,
+                stopped: cap.note(),
+            });
+        };
+
+        let output = SessionOutput {
+            stdout,
+            stderr,
+            exit_code: Some(exit_code),
+            cwd: state.cwd.clone(),
+            env_changed: state.env_checksum != shell.state.env_checksum,
+            shell_exited: false,
+            stopped: None,
+        };
+        shell.state = state;
+        *guard = Some(shell);
+        Ok(output)
+    }
+
+    async fn start(
+        &self,
+        working_dir: &Path,
+        sandbox: Option<&Sandbox>,
+        writable_roots: &[PathBuf],
+    ) -> Result<RunningShell, SessionError> {
+        const SHELL: &str = "exec bash --noprofile --norc";
+        let mut command = match sandbox {
+            Some(sandbox) => sandbox.command("bash", SHELL, working_dir, writable_roots),
+            None => {
+                let mut command = Command::new("bash");
+                command.args(["--noprofile", "--norc"]);
+                command
+            }
+        };
+        self.env_filter.apply(&mut command);
+        self.limits.apply(&mut command);
+        group::new_group(&mut command);
+        let mut child = command
+            .current_dir(working_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(SessionError::Start)?;
+
+        let mut shell = RunningShell {
+            group: ProcessGroup::of(&child),
+            stdin: child.stdin.take().expect("stdin not captured"),
+            stdout: BufReader::new(child.stdout.take().expect("stdout not captured")),
+            stderr: BufReader::new(child.stderr.take().expect("stderr not captured")),
+            child,
+            state: ShellState {
+                cwd: working_dir.to_path_buf(),
+                env_checksum: String::new(),
+            },
+        };
+
+        // An empty command records the starting state (and shows the shell is up)
+        let cap = OutputCap::new(None, &shell.group);
+        let started = tokio::time::timeout(STARTUP_TIMEOUT, self.exchange(&mut shell, "true", &cap, &|_, _| {})).await;
+        match started {
+            Ok(Ok((_, _, Some((_, state))))) => {
+                tracing::debug!(cwd = %state.cwd.display(), "shell session started");
+                shell.state = state;
+                Ok(shell)
+            }
+            Ok(Err(e)) => Err(SessionError::Start(e)),
+            Ok(Ok((_, stderr, None))) => Err(SessionError::Start(std::io::Error::other(format!(
+                "the shell exited: {}",
+                stderr.trim()
+            )))),
+            Err(_) => Err(SessionError::Start(std::io::Error::other(
+                "the shell did not respond",
+            ))),
+        }
+    }
+
+    /// Send one command and read both streams up to the sentinel.
+    /// Returns `None` as status if the shell exited instead.
+    #[allow(clippy::type_complexity)]
+    async fn exchange(
+        &self,
+        shell: &mut RunningShell,
+        command: &str,
+        cap: &OutputCap,
+        on_line: &OnLine<'_>,
+    ) -> std::io::Result<(String, String, Option<(i32, ShellState)>)> {
+        let script = format!(
+            "eval '{}' < /dev/null\n\
+             __ok_status=$?\n\
+             __ok_env=$(export -p | grep -v -e '^declare -x PWD=' -e '^declare -x OLDPWD=' | cksum)\n\
+             printf '%s %d %s %s\\n' '{sentinel}' \"$__ok_status\" \"${{__ok_env// /-}}\" \"$PWD\"\n\
+             printf '%s\\n' '{sentinel}' >&2\n",
+            command.replace('\'', r"'\''"),
+            sentinel = self.sentinel,
+        );
+        shell.stdin.write_all(script.as_bytes()).await?;
+        shell.stdin.flush().await?;
+
+        let (stdout, stderr) = tokio::join!(
+            read_until_sentinel(&mut shell.stdout, &self.sentinel, "stdout", cap, on_line),
+            read_until_sentinel(&mut shell.stderr, &self.sentinel, "stderr", cap, on_line)
+        );
+        let (stdout, status_line) = stdout?;
+        let (stderr, _) = stderr?;
+
+        let status = status_line.and_then(|line| {
+            let mut fields = line.trim_end_matches('\n').splitn(3, ' ');
+            let exit_code = fields.next()?.parse().ok()?;
+            let env_checksum = fields.next()?.to_string();
+            let cwd = PathBuf::from(fields.next()?);
+            Some((exit_code, ShellState { cwd, env_checksum }))
+        });
+        Ok((stdout, stderr, status))
+    }
+}
+
+/// Read output up to the sentinel, passing complete lines to `on_line`; returns the output and
+/// what followed the sentinel on its line (`None` at end of stream, or once `cap` is exceeded,
+/// which kills the shell)
+async fn read_until_sentinel<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    sentinel: &str,
+    stream: &'static str,
+    cap: &OutputCap,
+    on_line: &OnLine<'_>,
+) -> std::io::Result<(String, Option<String>)> {
+    let mut output = Vec::new();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let n = reader.read_until(b'\n', &mut line).await?;
+        if n == 0 {
+            return Ok((String::from_utf8_lossy(&output).into_owned(), None));
+        }
+        if !cap.add(n) {
+            // The other stream ends too now that the shell is gone
+            return Ok((String::from_utf8_lossy(&output).into_owned(), None));
+        }
+        let text = String::from_utf8_lossy(&line);
+        // Output without a trailing newline puts the sentinel mid-line
+        if let Some(pos) = text.find(sentinel) {
+            output.extend_from_slice(&line[..pos]);
+            let rest = text[pos + sentinel.len()..].trim_start().to_string();
+            return Ok((String::from_utf8_lossy(&output).into_owned(), Some(rest)));
+        }
+        on_line(stream, text.trim_end_matches(['\n', '\r']));
+        output.extend_from_slice(&line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_session_keeps_cwd_and_environment() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir(root.join("sub")).unwrap();
+        let session = ShellSession::new();
+        let timeout = Duration::from_secs(10);
+        let run = |command: &'static str| {
+            let session = &session;
+            let root = root.clone();
+            async move { session.run(command, timeout, &root, None, &[], &|_, _| {}).await.unwrap() }
+        };
+
+        let output = run("cd sub && export GREETING='it'\\''s me'").await;
+        assert_eq!(output.cwd, root.join("sub"));
+        assert!(output.env_changed);
+
+        let output = run("pwd; echo \"$GREETING\"; printf partial; echo oops >&2; false").await;
+        assert_eq!(output.stdout, format!("{}\nit's me\npartial", root.join("sub").display()));
+        assert_eq!(output.stderr, "oops\n");
+        assert_eq!(output.exit_code, Some(1));
+        assert!(!output.env_changed);
+
+        // Commands can't read the protocol from stdin
+        let output = run("cat; echo after").await;
+        assert_eq!(output.stdout, "after\n");
+
+        let output = run("exit 3").await;
+        assert!(output.shell_exited);
+        assert_eq!(output.exit_code, Some(3));
+
+        // A new shell starts over in the working directory
+        let output = run("pwd").await;
+        assert_eq!(output.stdout.trim(), root.display().to_string());
+    }
+}
@@ -0,0 +1,279 @@
+//! Repository map: a compact tree of the project's files with their most referenced symbols
+//!
+//! Given to the model at session start so it doesn't need a dozen glob/grep calls to orient
+//! itself. Symbols come from `code_outline`'s parser and are ranked by how often their name is
+//! used across the project.
+//!
+//! There is no system prompt to put the map in (requests carry only the conversation), so it is
+//! appended to the first user message. That message is never rewritten, so the map stays in the
+//! same place at the start of every request. Refreshed maps are appended to the latest message
+//! instead of replacing the first one, which would change every request's opening message.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::tool::outline::{self, OutlineItem};
+
+/// Rough size of a token, used to keep the map within its budget
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Files considered at most, whatever the budget; shallower files are kept first
+const MAX_FILES: usize = 10_000;
+
+/// Larger files are listed but not parsed (usually generated code)
+const MAX_PARSE_BYTES: u64 = 256 * 1024;
+
+/// A rendered repository map
+#[derive(Debug, Clone)]
+pub struct RepoMap {
+    pub text: String,
+    /// Files found in the working directory
+    pub total_files: usize,
+    pub shown_files: usize,
+    pub shown_symbols: usize,
+}
+
+impl RepoMap {
+    /// The map as a reminder for the model
+    pub fn reminder(&self, heading: &str) -> String {
+        format!("<system-reminder>\n{}\n\n{}</system-reminder>", heading, self.text)
+    }
+}
+
+/// A symbol that may be shown, with its ranking score
+struct Candidate {
+    file: usize,
+    item: OutlineItem,
+    score: f64,
+}
+
+/// Build the map of `root`, keeping it to about `token_budget` tokens (blocking)
+pub fn build(root: &Path, token_budget: usize) -> RepoMap {
+    // 1. Walk the project like the glob tool does
+    let mut builder = ignore::WalkBuilder::new(root);
+    builder
+        .hidden(true)
+        .git_ignore(true)
+        .git_global(true)
+        .git_exclude(true);
+
+    let all_files: Vec<PathBuf> = builder
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .filter_map(|entry| entry.path().strip_prefix(root).ok().map(Path::to_path_buf))
+        .collect();
+    let total_files = all_files.len();
+    let files = keep_files(all_files, MAX_FILES);
+
+    // 2. Outline supported files and count identifier uses across them
+    let identifier = regex::Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").expect("valid regex");
+    let mut uses: HashMap<String, usize> = HashMap::new();
+    let mut definitions: HashMap<String, usize> = HashMap::new();
+    let mut outlined: Vec<(usize, Vec<OutlineItem>)> = Vec::new();
+    for (idx, file) in files.iter().enumerate() {
+        let path = root.join(file);
+        if !outline::is_supported(&path)
+            || std::fs::metadata(&path).map_or(true, |m| m.len() > MAX_PARSE_BYTES)
+        {
+            continue;
+        }
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok(outline) = outline::outline(&path, &text) else {
+            continue;
+        };
+
+        for m in identifier.find_iter(&text) {
+            *uses.entry(m.as_str().to_string()).or_default() += 1;
+        }
+        // impl blocks repeat their type's name; their methods are listed instead
+        let items: Vec<OutlineItem> = outline
+            .items
+            .into_iter()
+            .filter(|item| item.depth <= 1 && item.kind != "impl")
+            .collect();
+        for item in &items {
+            *definitions.entry(item.name.clone()).or_default() += 1;
+        }
+        outlined.push((idx, items));
+    }
+
+    // 3. Rank symbols by references, shared between same-named definitions
+    let mut candidates: Vec<Candidate> = outlined
+        .into_iter()
+        .flat_map(|(file, items)| items.into_iter().map(move |item| (file, item)))
+        .map(|(file, item)| {
+            let defined = definitions[&item.name];
+            let used = uses.get(&item.name).copied().unwrap_or(0);
+            let score = used.saturating_sub(defined) as f64 / defined as f64;
+            Candidate { file, item, score }
+        })
+        .collect();
+    candidates.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.file.cmp(&b.file))
+            .then_with(|| a.item.line.cmp(&b.item.line))
+    });
+
+    // 4. Take symbols, then bare files, while the budget lasts
+    let budget = token_budget * CHARS_PER_TOKEN;
+    let mut used = 0;
+    let mut shown: BTreeMap<usize, Vec<OutlineItem>> = BTreeMap::new();
+    for candidate in candidates {
+        let mut cost = symbol_line(&candidate.item).len() + 6;
+        if !shown.contains_key(&candidate.file) {
+            cost += file_cost(&files[candidate.file]);
+        }
+        if used + cost > budget {
+            break;
+        }
+        used += cost;
+        shown.entry(candidate.file).or_default().push(candidate.item);
+    }
+    for (idx, file) in files.iter().enumerate() {
+        if shown.contains_key(&idx) {
+            continue;
+        }
+        let cost = file_cost(file);
+        if used + cost > budget {
+            break;
+        }
+        used += cost;
+        shown.insert(idx, Vec::new());
+    }
+
+    // 5. Render as an indented tree
+    let shown_symbols = shown.values().map(Vec::len).sum();
+    let mut text = String::new();
+    let mut open_dirs: Vec<String> = Vec::new();
+    for (idx, items) in &mut shown {
+        let file = &files[*idx];
+        let dirs: Vec<String> = file
+            .parent()
+            .into_iter()
+            .flat_map(|p| p.components())
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        let common = open_dirs.iter().zip(&dirs).take_while(|(a, b)| a == b).count();
+        for (depth, dir) in dirs.iter().enumerate().skip(common) {
+            text.push_str(&format!("{}{}/\n", "  ".repeat(depth), dir));
+        }
+        open_dirs = dirs;
+
+        let indent = "  ".repeat(open_dirs.len());
+        let name = file.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        text.push_str(&format!("{}{}\n", indent, name));
+        items.sort_by_key(|item| item.line);
+        for item in items.iter() {
+            text.push_str(&format!("{}  {}\n", indent, symbol_line(item)));
+        }
+    }
+
+    let hidden_files = total_files - shown.len();
+    if hidden_files > 0 {
+        text.push_str(&format!(
+            "({} more files not shown; use glob/grep/code_outline to explore)\n",
+            hidden_files
+        ));
+    }
+
+    RepoMap {
+        text,
+        total_files,
+        shown_files: shown.len(),
+        shown_symbols,
+    }
+}
+
+/// Build the map on a blocking thread
+pub async fn build_async(root: PathBuf, token_budget: usize) -> RepoMap {
+    tokio::task::spawn_blocking(move || build(&root, token_budget))
+        .await
+        .unwrap_or_else(|e| RepoMap {
+            text: format!("(Failed to build repository map: {})\n", e),
+            total_files: 0,
+            shown_files: 0,
+            shown_symbols: 0,
+        })
+}
+
+/// At most `max` of `files`, in path order. The walk order depends on the file system, so
+/// files are ranked by depth and path before cutting: top-level files and directories are kept
+/// and the deepest trees lose files first.
+fn keep_files(mut files: Vec<PathBuf>, max: usize) -> Vec<PathBuf> {
+    if files.len() > max {
+        files.sort_by(|a, b| {
+            a.components()
+                .count()
+                .cmp(&b.components().count())
+                .then_with(|| a.cmp(b))
+        });
+        files.truncate(max);
+    }
+    files.sort();
+    files
+}
+
+fn symbol_line(item: &OutlineItem) -> String {
+    format!("{}: {} {}", item.line, item.kind, item.qualified_name)
+}
+
+/// Characters a file adds to the tree (its name plus, at worst, its directories)
+fn file_cost(file: &Path) -> usize {
+    file.as_os_str().len() + 3 * file.components().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranks_referenced_symbols_first() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(
+            dir.path().join("src/lib.rs"),
+            "pub fn popular() {}\npub fn lonely() {}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("src/main.rs"),
+            "fn main() { popular(); popular(); }\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("README.md"), "# Demo\n").unwrap();
+
+        let map = build(dir.path(), 1000);
+        assert_eq!(map.total_files, 3);
+        assert_eq!(map.shown_files, 3);
+        assert_eq!(
+            map.text,
+            "README.md\nsrc/\n  lib.rs\n    1: function popular\n    2: function lonely\n  main.rs\n    1: function main\n"
+        );
+
+        // A tiny budget keeps only the most referenced symbol
+        let map = build(dir.path(), 12);
+        assert_eq!(map.shown_symbols, 1);
+        assert!(map.text.contains("1: function popular"));
+        assert!(!map.text.contains("lonely"));
+        assert!(map.text.contains("more files not shown"));
+    }
+
+    #[test]
+    fn test_keep_files_prefers_shallow_paths() {
+        let files: Vec<PathBuf> = ["z/deep/a.rs", "b.rs", "z/c.rs", "a/b/c/d.rs", "README.md"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        let kept = keep_files(files.clone(), 3);
+        assert_eq!(kept, vec![PathBuf::from("README.md"), PathBuf::from("b.rs"), PathBuf::from("z/c.rs")]);
+
+        let all = keep_files(files, 10);
+        assert_eq!(all.len(), 5);
+        assert_eq!(all[0], PathBuf::from("README.md"));
+        assert_eq!(all[4], PathBuf::from("z/deep/a.rs"));
+    }
+}
@@ -38,6 +38,9 @@ impl std::fmt::Display for SubagentType {
     }
 }
 
+/// Upper bound for any subagent turn limit, whatever the config or Task call asks for
+pub const MAX_TURNS_CEILING: usize = 200;
+
 /// Configuration for a subagent including available tools and custom system prompt
 #[derive(Debug, Clone)]
 pub struct SubagentConfig {
@@ -45,6 +48,8 @@ pub struct SubagentConfig {
     pub description: String,
     pub available_tools: Vec<String>,
     pub system_prompt: Option<String>,
+    /// Maximum number of tool-calling turns before the subagent must summarize
+    pub max_turns: usize,
 }
 
 impl SubagentConfig {
@@ -56,6 +61,7 @@ impl SubagentConfig {
                 description: "General-purpose agent for complex multi-step tasks".to_string(),
                 available_tools: vec!["*".to_string()], // All tools
                 system_prompt: None, // Use default system prompt
+                max_turns: 50,
             },
             SubagentType::Explore => Self {
                 name: "Explore".to_string(),
@@ -76,6 +82,7 @@ impl SubagentConfig {
                      Focus on thorough exploration and clear explanations."
                         .to_string(),
                 ),
+                max_turns: 40,
             },
             SubagentType::Plan => Self {
                 name: "Plan".to_string(),
@@ -97,6 +104,7 @@ impl SubagentConfig {
                      Focus on thorough analysis and detailed planning."
                         .to_string(),
                 ),
+                max_turns: 40,
             },
             SubagentType::Bash => Self {
                 name: "Bash".to_string(),
@@ -111,10 +119,17 @@ impl SubagentConfig {
                      Focus on quick execution and clear reporting."
                         .to_string(),
                 ),
+                max_turns: 15,
             },
         }
     }
 
+    /// Override the turn limit (clamped to `1..=MAX_TURNS_CEILING`)
+    pub fn with_max_turns(mut self, max_turns: usize) -> Self {
+        self.max_turns = max_turns.clamp(1, MAX_TURNS_CEILING);
+        self
+    }
+
     /// Check if a tool is available for this subagent
     pub fn is_tool_available(&self, tool_name: &str) -> bool {
         self.available_tools.contains(&"*".to_string())
@@ -182,6 +197,21 @@ mod tests {
             .contains("implementation planning"));
     }
 
+    #[test]
+    fn test_default_turn_limits() {
+        assert_eq!(SubagentConfig::for_type(&SubagentType::Explore).max_turns, 40);
+        assert_eq!(SubagentConfig::for_type(&SubagentType::Plan).max_turns, 40);
+        assert_eq!(SubagentConfig::for_type(&SubagentType::Bash).max_turns, 15);
+    }
+
+    #[test]
+    fn test_with_max_turns_is_clamped() {
+        let config = SubagentConfig::for_type(&SubagentType::Explore);
+        assert_eq!(config.clone().with_max_turns(25).max_turns, 25);
+        assert_eq!(config.clone().with_max_turns(0).max_turns, 1);
+        assert_eq!(config.with_max_turns(10_000).max_turns, MAX_TURNS_CEILING);
+    }
+
     #[test]
     fn test_bash_specialist_tools() {
         let config = SubagentConfig::for_type(&SubagentType::Bash);
@@ -0,0 +1,136 @@
+use crate::tool::base::ToolResult;
+use serde_json::Value;
+
+/// Maximum length of the one-line summaries carried by subagent events
+const SUMMARY_MAX_CHARS: usize = 80;
+
+/// Progress event emitted by a running subagent
+///
+/// Events are tagged with the subagent that produced them so the parent can route them
+/// to the right Task call.
+#[derive(Debug, Clone)]
+pub struct SubagentEvent {
+    pub subagent_id: String,
+    pub subagent_type: String,
+    pub kind: SubagentEventKind,
+}
+
+/// What happened inside the subagent
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubagentEventKind {
+    /// The Task tool launched the subagent
+    Started { description: String },
+    /// Assistant text produced during a turn
+    Text(String),
+    /// The subagent is about to execute a tool
+    ToolUse {
+        tool_use_id: String,
+        tool_name: String,
+        input_summary: String,
+    },
+    /// A subagent tool call finished
+    ToolResult {
+        tool_use_id: String,
+        tool_name: String,
+        summary: String,
+        is_error: bool,
+    },
+    /// The subagent produced its final answer
+    Finished { turns: usize, incomplete: bool },
+}
+
+/// Short, human-readable description of a tool call's input (e.g. `'foo'` for grep)
+pub fn describe_tool_input(tool_name: &str, input: &Value) -> String {
+    let key = match tool_name {
+        "grep" | "glob" => "pattern",
+        "read" | "write" | "edit" | "multi_edit" | "git_blame" => "file_path",
+        "bash" => "command",
+        "bash_output" | "bash_input" | "kill_shell" => "shell_id",
+        "web_fetch" => "url",
+        "web_search" => "query",
+        _ => "",
+    };
+
+    let summary = match input.get(key).and_then(Value::as_str) {
+        Some(value) if tool_name == "grep" => format!("'{}'", value),
+        Some(value) => value.to_string(),
+        None => input.to_string(),
+    };
+
+    truncate_line(&summary)
+}
+
+/// One-line summary of a tool result (e.g. `12 matches`), preferring structured metadata
+pub fn summarize_tool_result(result: &ToolResult) -> String {
+    let metadata = &result.metadata;
+    let count = |key: &str| metadata.get(key).and_then(Value::as_u64);
+
+    if let Some(n) = count("total_matches") {
+        return format!("{} matches", n);
+    }
+    if let Some(n) = count("total_files") {
+        return format!("{} files", n);
+    }
+    if let Some(n) = count("lines_read") {
+        return format!("{} lines", n);
+    }
+    if let Some(code) = metadata.get("exit_code") {
+        return format!("exit {}", code);
+    }
+
+    let first_line = result
+        .output
+        .lines()
+        .find(|l| !l.trim().is_empty())
+        .unwrap_or("(no output)");
+    truncate_line(first_line)
+}
+
+/// First line of `text`, cut to `SUMMARY_MAX_CHARS` characters
+pub fn truncate_line(text: &str) -> String {
+    let line = text.lines().next().unwrap_or("").trim();
+    if line.chars().count() > SUMMARY_MAX_CHARS {
+        let cut: String = line.chars().take(SUMMARY_MAX_CHARS).collect();
+        format!("{}…", cut)
+    } else {
+        line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_describe_tool_input() {
+        assert_eq!(describe_tool_input("grep", &json!({"pattern": "foo"})), "'foo'");
+        assert_eq!(
+            describe_tool_input("read", &json!({"file_path": "src/main.rs"})),
+            "src/main.rs"
+        );
+        assert_eq!(
+            describe_tool_input("custom", &json!({"a": 1})),
+            r#"{"a":1}"#
+        );
+    }
+
+    #[test]
+    fn test_summarize_tool_result_prefers_metadata() {
+        let grep = ToolResult::new("grep: foo", "Found 12 matches in 3 files:")
+            .with_metadata("total_matches", json!(12));
+        assert_eq!(summarize_tool_result(&grep), "12 matches");
+
+        let plain = ToolResult::new("title", "\nfirst line\nsecond line");
+        assert_eq!(summarize_tool_result(&plain), "first line");
+    }
+
+    #[test]
+    fn test_truncate_line() {
+        let long = "x".repeat(200);
+        let truncated = truncate_line(&long);
+        assert!(truncated.ends_with('…'));
+        assert_eq!(truncated.chars().count(), SUMMARY_MAX_CHARS + 1);
+        assert_eq!(truncate_line("a\nb"), "a");
+    }
+}
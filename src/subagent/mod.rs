@@ -1,7 +1,9 @@
 pub mod config;
+pub mod event;
 pub mod manager;
 pub mod runner;
 
 pub use config::{SubagentConfig, SubagentType};
+pub use event::{SubagentEvent, SubagentEventKind};
 pub use manager::SubagentManager;
 pub use runner::{SubagentRunner, SubagentResult, SubagentError};
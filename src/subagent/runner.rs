@@ -1,12 +1,24 @@
+use crate::diagnostics::Diagnostics;
 use crate::llm::anthropic::AnthropicClient;
-use crate::llm::types::{ContentBlock, Message, StreamChunk};
-use crate::process::BackgroundShellManager;
+use crate::llm::types::{ContentBlock, Message, StreamChunk, ToolChoice, ToolUse};
+use crate::process::notification::format_reminder;
+use crate::process::{BackgroundShellManager, EnvFilter, ResourceLimits, Sandbox};
 use crate::subagent::config::SubagentConfig;
+use crate::subagent::event::{self, SubagentEvent, SubagentEventKind};
 use crate::tool::base::ToolContext;
+use crate::tool::file_state::FileStateTracker;
+use crate::tool::output_limit::OutputLimiter;
 use crate::tool::ToolRegistry;
-use futures::StreamExt;
+use futures::{Stream, StreamExt};
 use std::path::PathBuf;
 use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Final request sent once the turn limit is reached, so partial findings are not lost
+const TURN_LIMIT_SUMMARY_PROMPT: &str = "You have reached the maximum number of turns for this task \
+     and cannot call any more tools. Summarize what you have found so far: answer the task as \
+     completely as you can with the information already gathered, and list what remains \
+     unexplored or uncertain.";
 
 /// Result from a completed subagent execution
 #[derive(Debug, Clone)]
@@ -17,6 +29,10 @@ pub struct SubagentResult {
     pub turns: usize,
     /// Full conversation history
     pub conversation: Vec<Message>,
+    /// True when the turn limit was hit and `output` is a summary of partial findings
+    pub incomplete: bool,
+    /// Background shells the subagent left running, killed when it finished
+    pub terminated_shells: Vec<String>,
 }
 
 /// Errors that can occur during subagent execution
@@ -41,7 +57,14 @@ pub enum SubagentError {
 /// - Filtered tool access (based on SubagentConfig)
 /// - Custom system prompt (if specified)
 /// - Independent conversation history
-/// - Turn limit to prevent runaway execution
+/// - Turn limit to prevent runaway execution (`SubagentConfig::max_turns`)
+/// - Background shells that persist across its turns, tagged with its agent ID and
+///   killed when the task ends
+/// - Its own record of files read, so it must read a file before editing it
+/// - The parent's diagnostics checkers, run after it modifies files
+/// - The parent's shell sandbox, if any, filter for the environment of shell commands and
+///   resource limits
+/// - The parent's limits on tool result size
 pub struct SubagentRunner {
     agent_id: String,
     config: SubagentConfig,
@@ -49,6 +72,15 @@ pub struct SubagentRunner {
     working_dir: PathBuf,
     llm_client: AnthropicClient,
     conversation: Vec<Message>,
+    shell_manager: Arc<BackgroundShellManager>,
+    file_states: FileStateTracker,
+    diagnostics: Option<Arc<Diagnostics>>,
+    sandbox: Option<Arc<Sandbox>>,
+    output_limiter: Option<Arc<OutputLimiter>>,
+    env_filter: Arc<EnvFilter>,
+    resource_limits: ResourceLimits,
+    trusted_project: bool,
+    event_sink: Option<mpsc::UnboundedSender<SubagentEvent>>,
 }
 
 impl SubagentRunner {
@@ -67,6 +99,74 @@ impl SubagentRunner {
             working_dir,
             llm_client,
             conversation: Vec::new(),
+            shell_manager: Arc::new(BackgroundShellManager::new()),
+            file_states: FileStateTracker::new(),
+            diagnostics: None,
+            sandbox: None,
+            output_limiter: None,
+            env_filter: Arc::default(),
+            resource_limits: ResourceLimits::default(),
+            trusted_project: false,
+            event_sink: None,
+        }
+    }
+
+    /// Run background shells in `manager` (e.g. the parent's) instead of a private one
+    pub fn with_shell_manager(mut self, manager: Arc<BackgroundShellManager>) -> Self {
+        self.shell_manager = manager;
+        self
+    }
+
+    /// Run `diagnostics` on files the subagent modifies, reporting them before its next turn
+    pub fn with_diagnostics(mut self, diagnostics: Arc<Diagnostics>) -> Self {
+        self.diagnostics = Some(diagnostics);
+        self
+    }
+
+    /// Run the subagent's shell commands in `sandbox`
+    pub fn with_sandbox(mut self, sandbox: Arc<Sandbox>) -> Self {
+        self.sandbox = Some(sandbox);
+        self
+    }
+
+    /// Pass only the variables `filter` allows to the subagent's shell commands
+    pub fn with_env_filter(mut self, filter: Arc<EnvFilter>) -> Self {
+        self.env_filter = filter;
+        self
+    }
+
+    /// Apply `limits` to the subagent's shell commands
+    pub fn with_resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.resource_limits = limits;
+        self
+    }
+
+    /// Let the subagent's tools run the project's own code (language servers)
+    pub fn with_trusted_project(mut self, trusted: bool) -> Self {
+        self.trusted_project = trusted;
+        self
+    }
+
+    /// Cut long tool results with `limiter` before they reach the model
+    pub fn with_output_limiter(mut self, limiter: Arc<OutputLimiter>) -> Self {
+        self.output_limiter = Some(limiter);
+        self
+    }
+
+    /// Report tool calls, results and text to `sink` while the task runs
+    pub fn with_event_sink(mut self, sink: mpsc::UnboundedSender<SubagentEvent>) -> Self {
+        self.event_sink = Some(sink);
+        self
+    }
+
+    /// Send a progress event if anyone is listening (a dropped receiver is not an error)
+    fn emit(&self, kind: SubagentEventKind) {
+        if let Some(sink) = &self.event_sink {
+            let _ = sink.send(SubagentEvent {
+                subagent_id: self.agent_id.clone(),
+                subagent_type: self.config.name.clone(),
+                kind,
+            });
         }
     }
 
@@ -78,8 +178,27 @@ impl SubagentRunner {
     /// 3. Execute tools as needed (with filtered access)
     /// 4. Continue until task complete or max turns reached
     ///
-    /// Returns the final output and conversation history
+    /// Returns the final output and conversation history. Background shells the subagent
+    /// started and left running are killed afterwards and listed in the result.
     pub async fn run_task(&mut self, prompt: String) -> Result<SubagentResult, SubagentError> {
+        let result = self.run_conversation(prompt).await;
+        let terminated_shells = self.shell_manager.kill_owned_by(&self.agent_id).await;
+
+        if !terminated_shells.is_empty() {
+            tracing::info!(
+                agent_id = %self.agent_id,
+                shells = ?terminated_shells,
+                "terminated background shells left by subagent"
+            );
+        }
+
+        result.map(|r| SubagentResult {
+            terminated_shells,
+            ..r
+        })
+    }
+
+    async fn run_conversation(&mut self, prompt: String) -> Result<SubagentResult, SubagentError> {
         tracing::info!(
             agent_id = %self.agent_id,
             subagent_type = %self.config.name,
@@ -96,20 +215,20 @@ impl SubagentRunner {
 
         self.conversation.push(Message::user(initial_message));
 
+        let max_turns = self.config.max_turns;
         let mut turns = 0;
-        let max_turns = 10; // MVP: hard limit at 10 turns
 
         // Main conversation loop
         loop {
-            turns += 1;
-            if turns > max_turns {
+            if turns >= max_turns {
                 tracing::warn!(
                     agent_id = %self.agent_id,
-                    turns = turns,
-                    "subagent exceeded max turns"
+                    max_turns,
+                    "subagent reached max turns, requesting summary"
                 );
-                return Err(SubagentError::MaxTurnsExceeded(max_turns));
+                return self.summarize_after_turn_limit(turns).await;
             }
+            turns += 1;
 
             tracing::debug!(
                 agent_id = %self.agent_id,
@@ -117,6 +236,14 @@ impl SubagentRunner {
                 "starting subagent turn"
             );
 
+            // Report the subagent's own background shells that finished or matched
+            let notifications = self.shell_manager.take_notifications(&self.agent_id);
+            if !notifications.is_empty() {
+                if let Some(last) = self.conversation.last_mut() {
+                    last.append_text(format_reminder(&notifications));
+                }
+            }
+
             // Get tool definitions (filtered by subagent config)
             let tool_definitions = Some(self.tool_registry.list_tool_definitions());
 
@@ -127,28 +254,10 @@ impl SubagentRunner {
                 .await
                 .map_err(|e| SubagentError::LlmError(e.to_string()))?;
 
-            let mut assistant_text = String::new();
-            let mut tool_uses = Vec::new();
+            let (assistant_text, tool_uses) = self.collect_response(&mut stream).await?;
 
-            // Process streaming response
-            while let Some(chunk) = stream.next().await {
-                match chunk {
-                    StreamChunk::Text(text) => {
-                        assistant_text.push_str(&text);
-                    }
-                    StreamChunk::ToolUse(tool_use) => {
-                        tool_uses.push(tool_use);
-                    }
-                    StreamChunk::Done => break,
-                    StreamChunk::Error(err) => {
-                        tracing::error!(
-                            agent_id = %self.agent_id,
-                            error = %err,
-                            "subagent llm error"
-                        );
-                        return Err(SubagentError::LlmError(err));
-                    }
-                }
+            if !assistant_text.trim().is_empty() {
+                self.emit(SubagentEventKind::Text(assistant_text.clone()));
             }
 
             tracing::debug!(
@@ -189,10 +298,17 @@ impl SubagentRunner {
                     "subagent task completed successfully"
                 );
 
+                self.emit(SubagentEventKind::Finished {
+                    turns,
+                    incomplete: false,
+                });
+
                 return Ok(SubagentResult {
                     output: assistant_text,
                     turns,
                     conversation: self.conversation.clone(),
+                    incomplete: false,
+                    terminated_shells: Vec::new(),
                 });
             }
 
@@ -205,6 +321,12 @@ impl SubagentRunner {
                     "executing subagent tool"
                 );
 
+                self.emit(SubagentEventKind::ToolUse {
+                    tool_use_id: tool_use.id.clone(),
+                    tool_name: tool_use.name.clone(),
+                    input_summary: event::describe_tool_input(&tool_use.name, &tool_use.input),
+                });
+
                 // Check if tool is available in filtered registry
                 let tool = match self.tool_registry.get(&tool_use.name) {
                     Some(t) => t.clone(),
@@ -217,6 +339,13 @@ impl SubagentRunner {
                             "tool not available in filtered registry"
                         );
 
+                        self.emit(SubagentEventKind::ToolResult {
+                            tool_use_id: tool_use.id.clone(),
+                            tool_name: tool_use.name.clone(),
+                            summary: error_msg.clone(),
+                            is_error: true,
+                        });
+
                         self.conversation
                             .push(Message::user_with_tool_result_detailed(
                                 tool_use.id,
@@ -227,28 +356,43 @@ impl SubagentRunner {
                     }
                 };
 
-                // Create tool context for subagent
-                let ctx = ToolContext {
-                    session_id: self.agent_id.clone(),
-                    message_id: tool_use.id.clone(),
-                    agent: self.config.name.clone(),
-                    working_dir: self.working_dir.clone(),
-                    shell_manager: Arc::new(BackgroundShellManager::new()),
-                };
+                // Create tool context for subagent (its agent ID tags any background shells)
+                let mut ctx = ToolContext::new(
+                    self.agent_id.clone(),
+                    tool_use.id.clone(),
+                    self.config.name.clone(),
+                    self.working_dir.clone(),
+                    self.shell_manager.clone(),
+                )
+                .with_file_states(self.file_states.clone())
+                .with_env_filter(self.env_filter.clone())
+                .with_resource_limits(self.resource_limits)
+                .with_trusted_project(self.trusted_project);
+                if let Some(sandbox) = &self.sandbox {
+                    ctx = ctx.with_sandbox(sandbox.clone());
+                }
+                if let Some(limiter) = &self.output_limiter {
+                    ctx = ctx.with_output_limiter(limiter.clone());
+                }
 
                 // Execute tool
                 let result = tool.execute(tool_use.input, &ctx).await;
 
-                let (result_content, is_error) = match result {
+                let (result_content, is_error, summary) = match result {
                     Ok(tr) => {
-                        let formatted = format!("Tool: {}\nOutput:\n{}", tr.title, tr.output);
+                        let output = match &self.output_limiter {
+                            Some(limiter) => limiter.limit(&tool_use.name, &tool_use.id, tr.output.clone()),
+                            None => tr.output.clone(),
+                        };
+                        let formatted = format!("Tool: {}\nOutput:\n{}", tr.title, output);
                         tracing::debug!(
                             agent_id = %self.agent_id,
                             tool_name = %tool_use.name,
                             output_len = tr.output.len(),
                             "tool executed successfully"
                         );
-                        (formatted, false)
+                        let summary = event::summarize_tool_result(&tr);
+                        (formatted, false, summary)
                     }
                     Err(e) => {
                         let error_msg = format!("Tool error: {}", e);
@@ -258,10 +402,18 @@ impl SubagentRunner {
                             error = %e,
                             "tool execution failed"
                         );
-                        (error_msg, true)
+                        let summary = event::truncate_line(&e.to_string());
+                        (error_msg, true, summary)
                     }
                 };
 
+                self.emit(SubagentEventKind::ToolResult {
+                    tool_use_id: tool_use.id.clone(),
+                    tool_name: tool_use.name.clone(),
+                    summary,
+                    is_error,
+                });
+
                 // Add tool result to conversation
                 self.conversation
                     .push(Message::user_with_tool_result_detailed(
@@ -271,9 +423,112 @@ impl SubagentRunner {
                     ));
             }
 
+            let modified = self.file_states.take_modified();
+            if let Some(diagnostics) = self.diagnostics.as_ref().filter(|_| !modified.is_empty()) {
+                if let Some(report) = diagnostics.check(&modified).await {
+                    if let Some(last) = self.conversation.last_mut() {
+                        last.append_text(report);
+                    }
+                }
+            }
+
             // Continue to next turn
         }
     }
+
+    /// Ask the subagent for a final, tool-less summary once `max_turns` is used up.
+    ///
+    /// The returned result is flagged `incomplete` so the parent knows the task was cut short.
+    async fn summarize_after_turn_limit(
+        &mut self,
+        turns: usize,
+    ) -> Result<SubagentResult, SubagentError> {
+        self.conversation.push(Message::user(TURN_LIMIT_SUMMARY_PROMPT));
+
+        // Tool definitions must still be sent because the history contains tool blocks.
+        let tool_definitions = Some(self.tool_registry.list_tool_definitions());
+
+        let mut stream = self
+            .llm_client
+            .stream_chat_with_tool_choice(
+                self.conversation.clone(),
+                tool_definitions,
+                Some(ToolChoice::None),
+            )
+            .await
+            .map_err(|e| SubagentError::LlmError(e.to_string()))?;
+
+        let (summary, ignored_tool_uses) = self.collect_response(&mut stream).await?;
+
+        if !ignored_tool_uses.is_empty() {
+            tracing::warn!(
+                agent_id = %self.agent_id,
+                tool_count = ignored_tool_uses.len(),
+                "ignoring tool calls in turn-limit summary"
+            );
+        }
+
+        if summary.trim().is_empty() {
+            return Err(SubagentError::MaxTurnsExceeded(self.config.max_turns));
+        }
+
+        self.conversation.push(Message::assistant(summary.clone()));
+
+        tracing::info!(
+            agent_id = %self.agent_id,
+            turns,
+            output_len = summary.len(),
+            "subagent task summarized after turn limit"
+        );
+
+        self.emit(SubagentEventKind::Text(summary.clone()));
+        self.emit(SubagentEventKind::Finished {
+            turns,
+            incomplete: true,
+        });
+
+        Ok(SubagentResult {
+            output: summary,
+            turns,
+            conversation: self.conversation.clone(),
+            incomplete: true,
+            terminated_shells: Vec::new(),
+        })
+    }
+
+    /// Drain one streamed LLM response into its text and requested tool calls
+    async fn collect_response<S>(
+        &self,
+        stream: &mut S,
+    ) -> Result<(String, Vec<ToolUse>), SubagentError>
+    where
+        S: Stream<Item = StreamChunk> + Unpin,
+    {
+        let mut text = String::new();
+        let mut tool_uses = Vec::new();
+
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                StreamChunk::Text(delta) => {
+                    text.push_str(&delta);
+                }
+                StreamChunk::ToolUse(tool_use) => {
+                    tool_uses.push(tool_use);
+                }
+                StreamChunk::Done => break,
+                StreamChunk::Error(err) => {
+                    tracing::error!(
+                        agent_id = %self.agent_id,
+                        error = %err,
+                        "subagent llm error"
+                    );
+                    return Err(SubagentError::LlmError(err));
+                }
+            }
+        }
+
+        Ok((text, tool_uses))
+    }
 }
 
 #[cfg(test)]
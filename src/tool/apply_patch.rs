@@ -0,0 +1,793 @@
+use super::base::{Tool, ToolContext, ToolError, ToolResult};
+use super::edit::EditTool;
+use super::text_file::{self, TextFormat};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Maximum context lines dropped from each end of a hunk when it doesn't match as written
+const MAX_FUZZ: usize = 2;
+
+/// ApplyPatch tool - applies a unified diff to one or more files, all or nothing
+pub struct ApplyPatchTool;
+
+#[derive(Debug, Deserialize)]
+struct ApplyPatchParams {
+    patch: String,
+}
+
+/// A line inside a hunk
+#[derive(Debug, Clone, PartialEq)]
+enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+/// A single `@@ -a,b +c,d @@` section
+#[derive(Debug, Clone, PartialEq)]
+struct Hunk {
+    /// Line number from the header (1-based; 0 when inserting at the top)
+    old_start: usize,
+    lines: Vec<HunkLine>,
+    /// The new side ends without a trailing newline (`\ No newline at end of file`)
+    new_missing_newline: bool,
+    /// The old side ended without a trailing newline
+    old_missing_newline: bool,
+}
+
+/// The changes for one file; a `None` path is `/dev/null`
+#[derive(Debug, Clone, PartialEq)]
+struct FilePatch {
+    old_path: Option<String>,
+    new_path: Option<String>,
+    hunks: Vec<Hunk>,
+}
+
+/// How loosely lines are compared when locating a hunk
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LineMatch {
+    Exact,
+    IgnoreTrailingWhitespace,
+    IgnoreWhitespace,
+}
+
+impl LineMatch {
+    fn eq(self, a: &str, b: &str) -> bool {
+        match self {
+            LineMatch::Exact => a == b,
+            LineMatch::IgnoreTrailingWhitespace => a.trim_end() == b.trim_end(),
+            LineMatch::IgnoreWhitespace => a.trim() == b.trim(),
+        }
+    }
+}
+
+/// Where and how loosely a hunk was applied
+#[derive(Debug, Clone, PartialEq)]
+struct AppliedHunk {
+    /// 1-based line in the original file where the hunk's old lines start
+    line: usize,
+    /// Distance from the line given in the hunk header
+    offset: isize,
+    /// Context lines ignored at each end
+    fuzz: usize,
+    whitespace: LineMatch,
+}
+
+/// Parse unified diff text (as produced by `diff -u` or `git diff`) into per-file patches
+fn parse_patch(text: &str) -> Result<Vec<FilePatch>, String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut patches = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        // Skip anything outside file sections (`diff --git`, `index`, mode lines, prose)
+        if !is_file_header(&lines, i) {
+            if lines[i].starts_with("@@") {
+                return Err(format!("hunk at line {} has no file header", i + 1));
+            }
+            i += 1;
+            continue;
+        }
+
+        let (old_path, new_path) = strip_git_prefixes(
+            parse_header_path(&lines[i][4..]),
+            parse_header_path(&lines[i + 1][4..]),
+        );
+        if old_path.is_none() && new_path.is_none() {
+            return Err(format!("file header at line {} has no path", i + 1));
+        }
+        i += 2;
+
+        let mut hunks = Vec::new();
+        while i < lines.len() && lines[i].starts_with("@@") {
+            let (old_start, mut old_left, mut new_left) = parse_hunk_header(lines[i])
+                .ok_or_else(|| format!("malformed hunk header at line {}: {}", i + 1, lines[i]))?;
+            i += 1;
+
+            let mut hunk = Hunk {
+                old_start,
+                lines: Vec::new(),
+                new_missing_newline: false,
+                old_missing_newline: false,
+            };
+            // The header's line counts say where the hunk ends, so removed `-- ...` and added
+            // `++ ...` lines are not taken for a file header. Counts in hand-written patches are
+            // often too small or too large, so the hunk also runs on past them until the next
+            // header, and stops early at a header followed by a hunk.
+            let mut trailing_blank = 0;
+            while i < lines.len() {
+                let line = lines[i];
+                let counted = old_left > 0 || new_left > 0;
+                let next_is_header = if counted {
+                    is_file_header(&lines, i) && lines.get(i + 2).is_some_and(|l| l.starts_with("@@"))
+                } else {
+                    is_file_header(&lines, i)
+                };
+                if line.starts_with("@@") || line.starts_with("diff ") || next_is_header {
+                    break;
+                }
+                if let Some(rest) = line.strip_prefix(' ') {
+                    hunk.lines.push(HunkLine::Context(rest.to_string()));
+                    old_left = old_left.saturating_sub(1);
+                    new_left = new_left.saturating_sub(1);
+                    trailing_blank = 0;
+                } else if let Some(rest) = line.strip_prefix('+') {
+                    hunk.lines.push(HunkLine::Add(rest.to_string()));
+                    new_left = new_left.saturating_sub(1);
+                    trailing_blank = 0;
+                } else if let Some(rest) = line.strip_prefix('-') {
+                    hunk.lines.push(HunkLine::Remove(rest.to_string()));
+                    old_left = old_left.saturating_sub(1);
+                    trailing_blank = 0;
+                } else if line.starts_with('\\') {
+                    match hunk.lines.last() {
+                        Some(HunkLine::Remove(_)) => hunk.old_missing_newline = true,
+                        Some(HunkLine::Add(_)) => hunk.new_missing_newline = true,
+                        Some(HunkLine::Context(_)) => {
+                            hunk.old_missing_newline = true;
+                            hunk.new_missing_newline = true;
+                        }
+                        None => {}
+                    }
+                } else if line.is_empty() {
+                    // Editors strip the space from blank context lines
+                    hunk.lines.push(HunkLine::Context(String::new()));
+                    if counted {
+                        old_left = old_left.saturating_sub(1);
+                        new_left = new_left.saturating_sub(1);
+                    } else {
+                        trailing_blank += 1;
+                    }
+                } else {
+                    break;
+                }
+                i += 1;
+            }
+            // Blank lines separating sections are not part of the hunk
+            hunk.lines.truncate(hunk.lines.len() - trailing_blank);
+
+            if hunk.lines.is_empty() {
+                return Err(format!("empty hunk before line {}", i + 1));
+            }
+            hunks.push(hunk);
+        }
+
+        if hunks.is_empty() {
+            return Err(format!(
+                "no hunks for {}",
+                new_path.as_deref().or(old_path.as_deref()).unwrap_or_default()
+            ));
+        }
+        patches.push(FilePatch {
+            old_path,
+            new_path,
+            hunks,
+        });
+    }
+
+    if patches.is_empty() {
+        return Err("no file headers (`--- a/path` / `+++ b/path`) found".to_string());
+    }
+    Ok(patches)
+}
+
+/// Whether `lines[i]` starts a `--- old` / `+++ new` file header
+fn is_file_header(lines: &[&str], i: usize) -> bool {
+    lines[i].starts_with("--- ") && lines.get(i + 1).is_some_and(|l| l.starts_with("+++ "))
+}
+
+/// Path from a `---`/`+++` header, without a trailing timestamp; None for `/dev/null`
+fn parse_header_path(rest: &str) -> Option<String> {
+    let path = rest.split('\t').next().unwrap_or("").trim();
+    if path == "/dev/null" || path.is_empty() {
+        None
+    } else {
+        Some(path.to_string())
+    }
+}
+
+/// Drop git's `a/` and `b/` prefixes when the headers use them
+fn strip_git_prefixes(old: Option<String>, new: Option<String>) -> (Option<String>, Option<String>) {
+    let has = |path: &Option<String>, prefix: &str| path.as_ref().map(|p| p.starts_with(prefix));
+    let git_style = match (has(&old, "a/"), has(&new, "b/")) {
+        (Some(a), Some(b)) => a && b,
+        (Some(a), None) => a,
+        (None, Some(b)) => b,
+        (None, None) => false,
+    };
+    if !git_style {
+        return (old, new);
+    }
+    let strip = |path: Option<String>| path.map(|p| p[2..].to_string());
+    (strip(old), strip(new))
+}
+
+/// Old start line and old and new line counts from `@@ -start[,count] +start[,count] @@`
+fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize)> {
+    let mut ranges = line.strip_prefix("@@ -")?.split_whitespace();
+    let range = |range: &str| -> Option<(usize, usize)> {
+        let mut parts = range.split(',');
+        let start = parts.next()?.parse().ok()?;
+        let count = parts.next().map_or(Some(1), |c| c.parse().ok())?;
+        Some((start, count))
+    };
+    let (old_start, old_count) = range(ranges.next()?)?;
+    // A missing or malformed new range counts as unknown, not as an error
+    let new_count = ranges
+        .next()
+        .and_then(|r| r.strip_prefix('+'))
+        .and_then(range)
+        .map_or(0, |(_, count)| count);
+    Some((old_start, old_count, new_count))
+}
+
+/// Apply `hunks` in order to LF-normalized `content`, returning the new content and where each
+/// hunk landed
+fn apply_hunks(content: &str, hunks: &[Hunk]) -> Result<(String, Vec<AppliedHunk>), String> {
+    let lines: Vec<&str> = content.lines().collect();
+
+    let mut output: Vec<String> = Vec::with_capacity(lines.len());
+    let mut applied = Vec::new();
+    let mut cursor = 0;
+    let mut drift: isize = 0;
+
+    for (idx, hunk) in hunks.iter().enumerate() {
+        let (pos, fuzz, whitespace, hunk_lines) = locate_hunk(&lines, hunk, cursor, drift)
+            .ok_or_else(|| describe_mismatch(idx, hunk))?;
+
+        output.extend(lines[cursor..pos].iter().map(|l| l.to_string()));
+        let mut file_idx = pos;
+        for line in hunk_lines {
+            match line {
+                // Keep the file's own text for context matched loosely
+                HunkLine::Context(_) => {
+                    output.push(lines[file_idx].to_string());
+                    file_idx += 1;
+                }
+                HunkLine::Remove(_) => file_idx += 1,
+                HunkLine::Add(text) => output.push(text.clone()),
+            }
+        }
+
+        let expected = expected_position(hunk) as isize + fuzz as isize;
+        let offset = pos as isize - expected;
+        drift = offset;
+        applied.push(AppliedHunk {
+            line: pos + 1,
+            offset,
+            fuzz,
+            whitespace,
+        });
+        cursor = file_idx;
+    }
+    output.extend(lines[cursor..].iter().map(|l| l.to_string()));
+
+    let mut trailing_newline = content.is_empty() || content.ends_with('\n');
+    if hunks.iter().any(|h| h.new_missing_newline) {
+        trailing_newline = false;
+    } else if hunks.iter().any(|h| h.old_missing_newline) {
+        trailing_newline = true;
+    }
+
+    let mut result = output.join("\n");
+    if trailing_newline && !output.is_empty() {
+        result.push('\n');
+    }
+    Ok((result, applied))
+}
+
+/// 0-based index where the hunk's old lines start according to its header
+fn expected_position(hunk: &Hunk) -> usize {
+    let has_old_lines = hunk.lines.iter().any(|l| !matches!(l, HunkLine::Add(_)));
+    if has_old_lines {
+        hunk.old_start.saturating_sub(1)
+    } else {
+        // `-N,0` means "insert after line N"
+        hunk.old_start
+    }
+}
+
+/// Find where the hunk applies at or after `min_start`, trying exact matches nearest the
+/// expected line first, then looser whitespace, then fewer context lines
+fn locate_hunk<'a>(
+    lines: &[&str],
+    hunk: &'a Hunk,
+    min_start: usize,
+    drift: isize,
+) -> Option<(usize, usize, LineMatch, &'a [HunkLine])> {
+    let leading_context = hunk
+        .lines
+        .iter()
+        .take_while(|l| matches!(l, HunkLine::Context(_)))
+        .count();
+    let trailing_context = hunk
+        .lines
+        .iter()
+        .rev()
+        .take_while(|l| matches!(l, HunkLine::Context(_)))
+        .count();
+
+    for fuzz in 0..=MAX_FUZZ {
+        let (front, back) = (fuzz.min(leading_context), fuzz.min(trailing_context));
+        if fuzz > 0 && front < fuzz && back < fuzz {
+            // No more context to drop
+            break;
+        }
+        let hunk_lines = &hunk.lines[front..hunk.lines.len() - back.min(hunk.lines.len() - front)];
+        let old: Vec<&str> = hunk_lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(s) | HunkLine::Remove(s) => Some(s.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect();
+
+        let expected = (expected_position(hunk) + front) as isize + drift;
+        if old.is_empty() {
+            let pos = expected.clamp(min_start as isize, lines.len() as isize) as usize;
+            return Some((pos, front, LineMatch::Exact, hunk_lines));
+        }
+        if old.len() > lines.len().saturating_sub(min_start) {
+            continue;
+        }
+
+        // Candidate start positions, nearest to the expected line first
+        let last = lines.len() - old.len();
+        let mut candidates: Vec<usize> = (min_start..=last).collect();
+        candidates.sort_by_key(|&pos| (pos as isize - expected).abs());
+
+        for whitespace in [
+            LineMatch::Exact,
+            LineMatch::IgnoreTrailingWhitespace,
+            LineMatch::IgnoreWhitespace,
+        ] {
+            let found = candidates.iter().copied().find(|&pos| {
+                old.iter()
+                    .zip(&lines[pos..pos + old.len()])
+                    .all(|(want, have)| whitespace.eq(want, have))
+            });
+            if let Some(pos) = found {
+                return Some((pos, front, whitespace, hunk_lines));
+            }
+        }
+    }
+    None
+}
+
+fn describe_mismatch(idx: usize, hunk: &Hunk) -> String {
+    let first_old = hunk.lines.iter().find_map(|l| match l {
+        HunkLine::Context(s) | HunkLine::Remove(s) => Some(s.as_str()),
+        HunkLine::Add(_) => None,
+    });
+    format!(
+        "hunk {} (@@ -{} @@) does not match the current contents{}. Re-read the file and regenerate the patch.",
+        idx + 1,
+        hunk.old_start,
+        first_old
+            .map(|l| format!(" (expected a line like {:?})", l))
+            .unwrap_or_default()
+    )
+}
+
+/// A file's content before the patch and after the hunks applied so far (None = absent)
+struct StagedFile {
+    original: Option<String>,
+    current: Option<String>,
+    /// Bytes on disk before the patch, restored if a later write fails
+    original_bytes: Option<Vec<u8>>,
+    /// How the file is stored; new files are UTF-8 with LF line endings
+    format: TextFormat,
+}
+
+#[async_trait::async_trait]
+impl Tool for ApplyPatchTool {
+    fn id(&self) -> &str {
+        "apply_patch"
+    }
+
+    fn description(&self) -> &str {
+        "Apply a unified diff (as produced by `diff -u` or `git diff`) to one or more files. \
+         Supports modifying, creating (--- /dev/null) and deleting (+++ /dev/null) files. \
+         Hunks are matched against the current contents, tolerating shifted line numbers, \
+         whitespace differences and up to 2 lines of mismatched context. \
+         Files that are modified or deleted must have been read with the read tool first, and \
+         not changed since. Either every file is changed or none is."
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "patch": {
+                    "type": "string",
+                    "description": "Unified diff text with `--- a/path` / `+++ b/path` headers and `@@` hunks. Paths are relative to the working directory."
+                }
+            },
+            "required": ["patch"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let params: ApplyPatchParams = serde_json::from_value(params)
+            .map_err(|e| ToolError::InvalidParams(e.to_string()))?;
+
+        tracing::debug!(
+            working_dir = %ctx.working_dir.display(),
+            bytes = params.patch.len(),
+            "tool apply_patch start"
+        );
+
+        // 1. Parse the patch and resolve every path before touching anything
+        let patches = parse_patch(&params.patch)
+            .map_err(|e| ToolError::InvalidParams(format!("Invalid patch: {}", e)))?;
+
+        let resolve = |path: &Option<String>| -> Result<Option<PathBuf>, ToolError> {
+            path.as_ref()
+                .map(|p| ctx.resolve_path(&PathBuf::from(p)))
+                .transpose()
+        };
+
+        // 2. Apply every hunk in memory, in patch order
+        let mut staged: HashMap<PathBuf, StagedFile> = HashMap::new();
+        let mut order: Vec<PathBuf> = Vec::new();
+        let mut summary = Vec::new();
+        let mut file_results = Vec::new();
+
+        for patch in &patches {
+            let old_path = resolve(&patch.old_path)?;
+            let new_path = resolve(&patch.new_path)?;
+
+            let source = match &old_path {
+                Some(path) => {
+                    let content = current_content(&mut staged, &mut order, path).await?;
+                    let content = content.ok_or_else(|| ToolError::FileNotFound(path.clone()))?;
+                    // Files on disk must have been read in their current state, like for edit
+                    let file = &staged[path];
+                    if let Some(bytes) = &file.original_bytes {
+                        ctx.file_states.check(path, bytes)?;
+                        file.format.check_editable(path)?;
+                    }
+                    Some(content)
+                }
+                None => None,
+            };
+            if let (None, Some(path)) = (&old_path, &new_path) {
+                if current_content(&mut staged, &mut order, path).await?.is_some() {
+                    return Err(ToolError::PatchFailed {
+                        path: path.clone(),
+                        reason: "the patch creates this file but it already exists".to_string(),
+                    });
+                }
+            }
+
+            let target = new_path.clone().or_else(|| old_path.clone()).unwrap_or_default();
+            let (patched, applied) = apply_hunks(source.as_deref().unwrap_or(""), &patch.hunks)
+                .map_err(|reason| ToolError::PatchFailed {
+                    path: target.clone(),
+                    reason,
+                })?;
+
+            let action = match (&old_path, &new_path) {
+                (None, Some(_)) => "created",
+                (Some(_), None) => "deleted",
+                (Some(old), Some(new)) if old != new => "renamed",
+                _ => "modified",
+            };
+            match (&old_path, &new_path) {
+                (Some(old), Some(new)) if old != new => {
+                    if current_content(&mut staged, &mut order, new).await?.is_some() {
+                        return Err(ToolError::PatchFailed {
+                            path: new.clone(),
+                            reason: "the patch renames a file onto this path but it already exists"
+                                .to_string(),
+                        });
+                    }
+                    let format = staged[old].format;
+                    set_current(&mut staged, old, None);
+                    set_current(&mut staged, new, Some(patched));
+                    // The renamed file keeps its encoding and line endings
+                    if let Some(file) = staged.get_mut(new) {
+                        file.format = format;
+                    }
+                }
+                (_, Some(new)) => set_current(&mut staged, new, Some(patched)),
+                (Some(old), None) => set_current(&mut staged, old, None),
+                (None, None) => unreachable!("parser rejects headers without paths"),
+            }
+
+            let mut line = match (&old_path, &new_path) {
+                (Some(old), Some(new)) if old != new => {
+                    format!("{} {} -> {}", action, old.display(), new.display())
+                }
+                _ => format!("{} {}", action, target.display()),
+            };
+            line.push_str(&format!(" ({} hunk(s))", applied.len()));
+            for (idx, hunk) in applied.iter().enumerate() {
+                let mut notes = Vec::new();
+                if hunk.offset != 0 {
+                    notes.push(format!("offset {:+} lines", hunk.offset));
+                }
+                if hunk.fuzz > 0 {
+                    notes.push(format!("fuzz {}", hunk.fuzz));
+                }
+                if hunk.whitespace != LineMatch::Exact {
+                    notes.push("ignoring whitespace".to_string());
+                }
+                if !notes.is_empty() {
+                    line.push_str(&format!(
+                        "\n    hunk {} applied at line {} ({})",
+                        idx + 1,
+                        hunk.line,
+                        notes.join(", ")
+                    ));
+                }
+            }
+            summary.push(line);
+            file_results.push(json!({
+                "path": target.to_string_lossy(),
+                "action": action,
+                "hunks": applied.len(),
+            }));
+        }
+
+        // 3. Encode every changed file in its format, then write the results, restoring earlier
+        //    files if a later write fails
+        let changed: Vec<&PathBuf> = order
+            .iter()
+            .filter(|path| staged[*path].current != staged[*path].original)
+            .collect();
+        let mut new_bytes: HashMap<&PathBuf, Option<Vec<u8>>> = HashMap::new();
+        for path in &changed {
+            let file = &staged[*path];
+            let bytes = file
+                .current
+                .as_deref()
+                .map(|content| file.format.encode(content))
+                .transpose()?;
+            new_bytes.insert(path, bytes);
+        }
+        let mut written: Vec<&PathBuf> = Vec::new();
+        for path in &changed {
+            if let Err(e) = commit_file(path, new_bytes[path].as_deref()).await {
+                for done in written.iter().rev() {
+                    if let Err(restore) = commit_file(done, staged[*done].original_bytes.as_deref()).await {
+                        tracing::warn!(path = %done.display(), error = %restore, "failed to roll back patched file");
+                    }
+                }
+                return Err(ToolError::Other(e.context(format!(
+                    "failed to write {}; no files were changed",
+                    path.display()
+                ))));
+            }
+            written.push(path);
+        }
+
+        // 4. Report per-file results and the diff as applied
+        let mut diff = String::new();
+        for path in &changed {
+            let file = &staged[*path];
+            if let Some(bytes) = &new_bytes[path] {
+                ctx.file_states.record_write(path, bytes);
+            }
+            diff.push_str(&EditTool::generate_diff(
+                path,
+                file.original.as_deref().unwrap_or(""),
+                file.current.as_deref().unwrap_or(""),
+            ));
+        }
+
+        let mut output = format!("Successfully applied patch to {} file(s):\n", patches.len());
+        for line in &summary {
+            output.push_str(&format!("  {}\n", line));
+        }
+        output.push('\n');
+        output.push_str(&diff);
+
+        tracing::debug!(
+            files = patches.len(),
+            files_written = changed.len(),
+            "tool apply_patch done"
+        );
+
+        Ok(ToolResult::new(
+            format!("Applied patch to {} file(s)", patches.len()),
+            output,
+        )
+        .with_metadata("files", json!(file_results))
+        .with_metadata("files_changed", json!(changed.len())))
+    }
+}
+
+/// Content of `path` as staged so far, loading it from disk the first time
+async fn current_content(
+    staged: &mut HashMap<PathBuf, StagedFile>,
+    order: &mut Vec<PathBuf>,
+    path: &PathBuf,
+) -> Result<Option<String>, ToolError> {
+    if !staged.contains_key(path) {
+        let (original, original_bytes, format) = if path.exists() {
+            let bytes = tokio::fs::read(path)
+                .await
+                .map_err(|e| ToolError::Other(e.into()))?;
+            let Some((content, format)) = text_file::decode(&bytes) else {
+                return Err(ToolError::BinaryFile(path.clone()));
+            };
+            (Some(content), Some(bytes), format)
+        } else {
+            (None, None, TextFormat::default())
+        };
+        staged.insert(
+            path.clone(),
+            StagedFile {
+                current: original.clone(),
+                original,
+                original_bytes,
+                format,
+            },
+        );
+        order.push(path.clone());
+    }
+    Ok(staged[path].current.clone())
+}
+
+fn set_current(staged: &mut HashMap<PathBuf, StagedFile>, path: &PathBuf, content: Option<String>) {
+    if let Some(file) = staged.get_mut(path) {
+        file.current = content;
+    }
+}
+
+/// Write `content` to `path`, or remove the file when `content` is None
+async fn commit_file(path: &PathBuf, content: Option<&[u8]>) -> anyhow::Result<()> {
+    match content {
+        Some(content) => {
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(path, content).await?;
+        }
+        None => {
+            if path.exists() {
+                tokio::fs::remove_file(path).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(content: &str, patch: &str) -> Result<String, String> {
+        let patches = parse_patch(patch)?;
+        apply_hunks(content, &patches[0].hunks).map(|(out, _)| out)
+    }
+
+    #[test]
+    fn test_parse_git_style_patch() {
+        let patch = "diff --git a/src/a.rs b/src/a.rs\n\
+                     index 123..456 100644\n\
+                     --- a/src/a.rs\n\
+                     +++ b/src/a.rs\n\
+                     @@ -1,2 +1,2 @@\n \
+                     keep\n\
+                     -old\n\
+                     +new\n\
+                     --- /dev/null\n\
+                     +++ b/new.txt\t2024-01-01 00:00:00\n\
+                     @@ -0,0 +1 @@\n\
+                     +hello\n";
+        let patches = parse_patch(patch).unwrap();
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[0].old_path.as_deref(), Some("src/a.rs"));
+        assert_eq!(patches[0].new_path.as_deref(), Some("src/a.rs"));
+        assert_eq!(
+            patches[0].hunks[0].lines,
+            vec![
+                HunkLine::Context("keep".into()),
+                HunkLine::Remove("old".into()),
+                HunkLine::Add("new".into()),
+            ]
+        );
+        assert_eq!(patches[1].old_path, None);
+        assert_eq!(patches[1].new_path.as_deref(), Some("new.txt"));
+    }
+
+    #[test]
+    fn test_parse_rejects_garbage() {
+        assert!(parse_patch("just some text").is_err());
+        assert!(parse_patch("@@ -1 +1 @@\n-a\n+b\n").is_err());
+        assert!(parse_patch("--- a/x\n+++ b/x\n").is_err());
+    }
+
+    #[test]
+    fn test_apply_with_offset_and_whitespace() {
+        let content = "header\nextra\nfn main() {\n    run();   \n}\n";
+        // Line numbers are off by one and the context lost its trailing spaces
+        let patch = "--- a/m.rs\n+++ b/m.rs\n@@ -2,3 +2,3 @@\n fn main() {\n-    run();\n+    run_all();\n }\n";
+        assert_eq!(
+            apply(content, patch).unwrap(),
+            "header\nextra\nfn main() {\n    run_all();\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_with_fuzz() {
+        let content = "a\nb\nc\nd\ne\n";
+        // The first context line no longer matches
+        let patch = "--- a/f\n+++ b/f\n@@ -1,4 +1,4 @@\n x\n b\n-c\n+C\n d\n";
+        assert_eq!(apply(content, patch).unwrap(), "a\nb\nC\nd\ne\n");
+    }
+
+    #[test]
+    fn test_apply_fails_on_mismatch() {
+        let patch = "--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n one\n-two\n+TWO\n";
+        let err = apply("alpha\nbeta\n", patch).unwrap_err();
+        assert!(err.contains("hunk 1"));
+    }
+
+    #[test]
+    fn test_apply_handles_missing_newline() {
+        let patch = "--- a/f\n+++ b/f\n@@ -1 +1 @@\n-old\n\\ No newline at end of file\n+new\n\\ No newline at end of file\n";
+        assert_eq!(apply("old", patch).unwrap(), "new");
+
+        let patch = "--- a/f\n+++ b/f\n@@ -1 +1 @@\n-old\n\\ No newline at end of file\n+new\n";
+        assert_eq!(apply("old", patch).unwrap(), "new\n");
+    }
+
+    #[test]
+    fn test_parse_uses_hunk_counts() {
+        // Removing an SQL comment and adding a `++` line look like a `--- `/`+++ ` header
+        let patch = "--- a/q.sql\n+++ b/q.sql\n@@ -1,2 +1,2 @@\n--- old note\n+++ counter\n select 1;\n\
+                     --- a/r.sql\n+++ b/r.sql\n@@ -1 +1 @@\n-a\n+b\n";
+        let patches = parse_patch(patch).unwrap();
+        assert_eq!(patches.len(), 2);
+        assert_eq!(
+            patches[0].hunks[0].lines,
+            vec![
+                HunkLine::Remove("-- old note".into()),
+                HunkLine::Add("++ counter".into()),
+                HunkLine::Context("select 1;".into()),
+            ]
+        );
+        assert_eq!(patches[1].new_path.as_deref(), Some("r.sql"));
+
+        // Counts that are too large stop at the next file's header
+        let patch = "--- a/f\n+++ b/f\n@@ -1,5 +1,5 @@\n-a\n+b\n--- a/g\n+++ b/g\n@@ -1 +1 @@\n-c\n+d\n";
+        let patches = parse_patch(patch).unwrap();
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[0].hunks[0].lines.len(), 2);
+    }
+
+    #[test]
+    fn test_apply_pure_insertion() {
+        let patch = "--- a/f\n+++ b/f\n@@ -1,0 +2 @@\n+inserted\n";
+        assert_eq!(apply("one\ntwo\n", patch).unwrap(), "one\ninserted\ntwo\n");
+    }
+}
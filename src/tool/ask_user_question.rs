@@ -230,13 +230,13 @@ mod tests {
     use std::sync::Arc;
 
     fn create_test_context() -> ToolContext {
-        ToolContext {
-            session_id: "test-session".to_string(),
-            message_id: "test-message".to_string(),
-            agent: "test-agent".to_string(),
-            working_dir: PathBuf::from("/tmp"),
-            shell_manager: Arc::new(crate::process::BackgroundShellManager::new()),
-        }
+        ToolContext::new(
+            "test-session",
+            "test-message",
+            "test-agent",
+            PathBuf::from("/tmp"),
+            Arc::new(crate::process::BackgroundShellManager::new()),
+        )
     }
 
     #[tokio::test]
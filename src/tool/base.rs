@@ -1,10 +1,14 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use crate::process::BackgroundShellManager;
+use super::file_state::FileStateTracker;
+use super::output_limit::OutputLimiter;
+use crate::diagnostics::Diagnostics;
+use crate::process::{BackgroundShellManager, EnvFilter, ResourceLimits, Sandbox, ShellSession};
+use crate::subagent::SubagentEvent;
 
 /// Tool execution context - provides environment information to tools
 #[derive(Clone)]
@@ -14,9 +18,49 @@ pub struct ToolContext {
     pub agent: String,
     pub working_dir: PathBuf,
     pub shell_manager: Arc<BackgroundShellManager>,
+    /// Sink for progress of subagents launched by this tool call (Task tool), if the caller listens
+    pub subagent_events: Option<tokio::sync::mpsc::UnboundedSender<SubagentEvent>>,
+    /// Files read in this session, consulted before edits (shared across tool calls)
+    pub file_states: FileStateTracker,
+    /// Project checkers run after file modifications, passed on to subagents
+    pub diagnostics: Option<Arc<Diagnostics>>,
+    /// Sandbox for shell commands (`[sandbox]`), if enabled
+    pub sandbox: Option<Arc<Sandbox>>,
+    /// Long-lived shell `bash` runs commands in (`[bash] persistent_session`), if enabled
+    pub shell_session: Option<Arc<ShellSession>>,
+    /// Sink for output of this tool call while it runs, if the caller shows it live
+    pub progress: Option<tokio::sync::mpsc::UnboundedSender<ToolProgress>>,
+    /// Size limits for tool results (`[tool_output]`), passed on to subagents
+    pub output_limiter: Option<Arc<OutputLimiter>>,
+    /// Which of the agent's environment variables shell commands inherit (`[bash] env_*`)
+    pub env_filter: Arc<EnvFilter>,
+    /// CPU, memory and output limits for shell commands (`[bash]`)
+    pub resource_limits: ResourceLimits,
+    /// Where to ask the user before running a risky command; without it such commands are refused
+    pub approvals: Option<tokio::sync::mpsc::UnboundedSender<ApprovalRequest>>,
+    /// Whether the user trusts the project's own code to run (`/trust`); language servers,
+    /// which run its build scripts, are only started if so
+    pub trusted_project: bool,
 }
 
-fn lexical_normalize_path(path: &std::path::Path) -> PathBuf {
+/// A line of output from a tool call that is still running
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolProgress {
+    /// "stdout" or "stderr"
+    pub stream: &'static str,
+    pub line: String,
+}
+
+/// A command waiting for the user to approve it; the answer goes back through `reply`
+#[derive(Debug)]
+pub struct ApprovalRequest {
+    pub command: String,
+    /// Why the command needs approval, one finding per line
+    pub reasons: String,
+    pub reply: tokio::sync::oneshot::Sender<bool>,
+}
+
+pub(crate) fn lexical_normalize_path(path: &std::path::Path) -> PathBuf {
     use std::path::Component;
 
     let mut prefix: Option<std::ffi::OsString> = None;
@@ -61,31 +105,37 @@ fn lexical_normalize_path(path: &std::path::Path) -> PathBuf {
     normalized
 }
 
-impl ToolContext {
-    fn allowed_roots(&self) -> Vec<PathBuf> {
-        let mut roots = Vec::new();
+/// Directories tools may touch for a session in `working_dir`: the directory itself and the
+/// temp roots (also what stays writable in the sandbox)
+pub fn allowed_roots(working_dir: &Path) -> Vec<PathBuf> {
+    let mut roots = Vec::new();
 
-        roots.push(lexical_normalize_path(&self.working_dir));
+    roots.push(lexical_normalize_path(working_dir));
 
-        // Allow system temp directory for ephemeral files (Linux/macOS).
-        let tmp = std::env::temp_dir();
-        roots.push(lexical_normalize_path(&tmp));
+    // Allow system temp directory for ephemeral files (Linux/macOS).
+    let tmp = std::env::temp_dir();
+    roots.push(lexical_normalize_path(&tmp));
 
-        #[cfg(unix)]
-        {
-            roots.push(PathBuf::from("/tmp"));
-            roots.push(PathBuf::from("/var/tmp"));
-        }
+    #[cfg(unix)]
+    {
+        roots.push(PathBuf::from("/tmp"));
+        roots.push(PathBuf::from("/var/tmp"));
+    }
 
-        #[cfg(target_os = "macos")]
-        {
-            roots.push(PathBuf::from("/private/tmp"));
-        }
+    #[cfg(target_os = "macos")]
+    {
+        roots.push(PathBuf::from("/private/tmp"));
+    }
+
+    // Deduplicate (best-effort).
+    roots.sort();
+    roots.dedup();
+    roots
+}
 
-        // Deduplicate (best-effort).
-        roots.sort();
-        roots.dedup();
-        roots
+impl ToolContext {
+    pub fn allowed_roots(&self) -> Vec<PathBuf> {
+        allowed_roots(&self.working_dir)
     }
 
     /// Resolve a user-supplied path against `working_dir`.
@@ -131,6 +181,17 @@ impl std::fmt::Debug for ToolContext {
             .field("agent", &self.agent)
             .field("working_dir", &self.working_dir)
             .field("shell_manager", &"<BackgroundShellManager>")
+            .field("subagent_events", &self.subagent_events.is_some())
+            .field("file_states", &"<FileStateTracker>")
+            .field("diagnostics", &self.diagnostics.is_some())
+            .field("sandbox", &self.sandbox.is_some())
+            .field("shell_session", &self.shell_session.is_some())
+            .field("progress", &self.progress.is_some())
+            .field("output_limiter", &self.output_limiter.is_some())
+            .field("env_filter", &self.env_filter)
+            .field("resource_limits", &self.resource_limits)
+            .field("approvals", &self.approvals.is_some())
+            .field("trusted_project", &self.trusted_project)
             .finish()
     }
 }
@@ -180,6 +241,9 @@ pub enum ToolError {
     #[error("Invalid parameters: {0}")]
     InvalidParams(String),
 
+    #[error("The user did not approve this command:\n{reasons}\n\nDon't try to run it another way; ask the user how to proceed.")]
+    ApprovalDenied { command: String, reasons: String },
+
     // Edit tool specific errors
     #[error("String not found in file: {0}")]
     OldStringNotFound(String),
@@ -190,6 +254,35 @@ pub enum ToolError {
     #[error("old_string and new_string must be different")]
     OldNewIdentical,
 
+    #[error("Edit {index} of {total} failed: {source}")]
+    EditFailed {
+        /// 1-based position of the failing edit
+        index: usize,
+        total: usize,
+        source: Box<ToolError>,
+    },
+
+    #[error("Patch does not apply to {path}: {reason}")]
+    PatchFailed { path: PathBuf, reason: String },
+
+    #[error("File has not been read yet: {0}. Read it first before modifying it.")]
+    FileNotRead(PathBuf),
+
+    #[error("File has been modified since it was last read: {0}. Read it again before modifying it.")]
+    FileModifiedSinceRead(PathBuf),
+
+    #[error("Cannot edit {path} without changing lines outside the edit: {reason}. Use write to replace the whole file, or bash to change it.")]
+    UnsupportedFormat { path: PathBuf, reason: String },
+
+    #[error(transparent)]
+    Lsp(#[from] crate::lsp::LspError),
+
+    #[error(transparent)]
+    Sandbox(#[from] crate::process::sandbox::SandboxError),
+
+    #[error(transparent)]
+    ShellSession(#[from] crate::process::session::SessionError),
+
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
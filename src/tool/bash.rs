@@ -1,20 +1,43 @@
 use super::base::{Tool, ToolContext, ToolError, ToolResult};
+use super::command_safety::{CommandSafety, Decision};
+use crate::process::group::{self, OutputCap, ProcessGroup};
+use crate::process::session::SessionError;
+use crate::process::{pty, ShellOptions};
 use serde::Deserialize;
 use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::time::Duration;
-use tokio::io::AsyncReadExt;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
 
 /// Bash tool - executes shell commands and returns output
 pub struct BashTool;
 
+/// What a foreground command printed
+struct CommandOutput {
+    stdout: String,
+    stderr: String,
+    exit_code: Option<i32>,
+    /// Why the command was stopped before it finished, if it was
+    stopped: Option<String>,
+}
+
 impl BashTool {
     /// Execute command in background mode
     async fn execute_background(
         &self,
         params: BashParams,
+        cwd: PathBuf,
         ctx: &ToolContext,
     ) -> Result<ToolResult, ToolError> {
+        let watch = params
+            .watch
+            .as_deref()
+            .map(regex::Regex::new)
+            .transpose()
+            .map_err(|e| ToolError::InvalidParams(format!("Invalid watch pattern: {}", e)))?;
+
         // Generate a unique shell ID
         let shell_id = format!("shell_{}", uuid::Uuid::new_v4());
 
@@ -24,9 +47,26 @@ impl BashTool {
             "spawning background shell"
         );
 
-        // Spawn the background shell
+        // Spawn the background shell, tagged with the calling session (or subagent) so that
+        // its exit and watch matches are reported back on the next turn
+        let options = ShellOptions {
+            owner: Some(ctx.session_id.clone()),
+            watch,
+            sandbox: ctx.sandbox.clone(),
+            writable_roots: ctx.allowed_roots(),
+            pty: params.pty,
+            env: params.env.clone(),
+            stdin: params.stdin.clone(),
+            env_filter: ctx.env_filter.clone(),
+            limits: ctx.resource_limits,
+        };
         ctx.shell_manager
-            .spawn(shell_id.clone(), params.command.clone(), ctx.working_dir.clone())
+            .spawn_with_options(
+                shell_id.clone(),
+                params.command.clone(),
+                cwd,
+                options,
+            )
             .await
             .map_err(|e| ToolError::Other(e))?;
 
@@ -40,15 +80,165 @@ impl BashTool {
         let output = format!(
             "Command started in background.\n\
              Shell ID: {}\n\
-             Use 'bash_output' tool to monitor output.\n\
+             You will be notified when it exits{}.\n\
+             Use 'bash_output' tool to monitor output.{}\n\
              Use 'kill_shell' tool to terminate.",
-            shell_id
+            shell_id,
+            match &params.watch {
+                Some(pattern) => format!(" or prints a line matching /{}/", pattern),
+                None => String::new(),
+            },
+            if params.pty {
+                "\nUse 'bash_input' tool to answer prompts or send keys."
+            } else {
+                ""
+            }
         );
 
         Ok(ToolResult::new(title, output)
             .with_metadata("shell_id", json!(shell_id))
             .with_metadata("command", json!(params.command))
-            .with_metadata("background", json!(true)))
+            .with_metadata("background", json!(true))
+            .with_metadata("watch", json!(params.watch))
+            .with_metadata("pty", json!(params.pty)))
+    }
+
+    /// Run a prepared command under a pseudo-terminal; stderr is merged into stdout.
+    /// Nobody types into the terminal, so pagers are replaced by `cat`.
+    async fn run_in_pty(
+        mut command: tokio::process::Command,
+        params: &BashParams,
+        timeout: Duration,
+        ctx: &ToolContext,
+    ) -> Result<CommandOutput, ToolError> {
+        command.env("PAGER", "cat").env("GIT_PAGER", "cat");
+        let (mut child, mut master) = pty::spawn(&mut command).map_err(|e| ToolError::Other(e.into()))?;
+        // The command leads a new session, and so its own process group
+        let mut group = ProcessGroup::of(&child);
+        let cap = OutputCap::new(ctx.resource_limits.output_bytes, &group);
+
+        let result = tokio::time::timeout(timeout, async {
+            let mut raw = Vec::new();
+            let mut chunk = [0u8; 8192];
+            let mut line_start = 0;
+            loop {
+                let n = master.read(&mut chunk).await?;
+                if n == 0 || !cap.add(n) {
+                    break;
+                }
+                raw.extend_from_slice(&chunk[..n]);
+                while let Some(end) = raw[line_start..].iter().position(|&b| b == b'\n') {
+                    let line = String::from_utf8_lossy(&raw[line_start..line_start + end]);
+                    ctx.report_progress("stdout", pty::strip_terminal_codes(&line));
+                    line_start += end + 1;
+                }
+            }
+            let exit_status = child.wait().await?;
+            Ok::<_, anyhow::Error>((raw, exit_status.code()))
+        })
+        .await;
+
+        match result {
+            Ok(Ok((raw, exit_code))) => {
+                group.release();
+                Ok(CommandOutput {
+                    stdout: pty::strip_terminal_codes(&String::from_utf8_lossy(&raw)),
+                    stderr: String::new(),
+                    exit_code,
+                    stopped: cap.note(),
+                })
+            }
+            Ok(Err(e)) => Err(ToolError::Other(e)),
+            Err(_) => {
+                group.terminate(&mut child).await;
+                Err(ToolError::Timeout(params.timeout))
+            }
+        }
+    }
+
+    /// Run the command in a fresh `sh` in `cwd` (outside any persistent session)
+    async fn run_once(
+        &self,
+        params: &BashParams,
+        timeout: Duration,
+        cwd: &Path,
+        ctx: &ToolContext,
+    ) -> Result<CommandOutput, ToolError> {
+        let mut command = match &ctx.sandbox {
+            Some(sandbox) => sandbox.command("sh", &params.command, cwd, &ctx.allowed_roots()),
+            None => {
+                let mut command = tokio::process::Command::new("sh");
+                command.arg("-c").arg(&params.command);
+                command
+            }
+        };
+        command.current_dir(cwd).kill_on_drop(true);
+        ctx.env_filter.apply(&mut command);
+        ctx.resource_limits.apply(&mut command);
+        command.envs(&params.env);
+
+        if params.pty {
+            return Self::run_in_pty(command, params, timeout, ctx).await;
+        }
+
+        // In a group of its own, so a timeout also stops whatever the command started
+        group::new_group(&mut command);
+        let mut child = command
+            .stdin(if params.stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| ToolError::Other(e.into()))?;
+        let mut group = ProcessGroup::of(&child);
+        let cap = OutputCap::new(ctx.resource_limits.output_bytes, &group);
+
+        // Write the input while the output is read (a large input could otherwise fill the
+        // pipe before the command reads it), then close it so the command sees end of input
+        let stdin = child.stdin.take().zip(params.stdin.as_deref());
+        let write_stdin = async move {
+            if let Some((mut pipe, input)) = stdin {
+                // A command that exits without reading its input is not an error
+                let _ = pipe.write_all(input.as_bytes()).await;
+            }
+        };
+
+        // Collect output with timeout
+        let result = tokio::time::timeout(timeout, async {
+            // Get stdout and stderr pipes
+            let mut stdout = child.stdout.take().expect("Failed to capture stdout");
+            let mut stderr = child.stderr.take().expect("Failed to capture stderr");
+
+            // Read both streams concurrently, passing lines on as they arrive
+            let (stdout_result, stderr_result, ()) = tokio::join!(
+                read_streaming(&mut stdout, "stdout", ctx, &cap),
+                read_streaming(&mut stderr, "stderr", ctx, &cap),
+                write_stdin
+            );
+
+            // Wait for process to exit
+            let exit_status = child.wait().await?;
+
+            Ok::<_, anyhow::Error>(CommandOutput {
+                stdout: stdout_result?,
+                stderr: stderr_result?,
+                exit_code: exit_status.code(),
+                stopped: cap.note(),
+            })
+        })
+        .await;
+
+        match result {
+            Ok(Ok(output)) => {
+                group.release();
+                Ok(output)
+            }
+            Ok(Err(e)) => Err(ToolError::Other(e)),
+            Err(_) => {
+                // Timeout - stop the command and everything it started
+                group.terminate(&mut child).await;
+                Err(ToolError::Timeout(params.timeout))
+            }
+        }
     }
 }
 
@@ -61,6 +251,24 @@ struct BashParams {
     description: String,
     #[serde(default)]
     run_in_background: bool,
+    /// Regex; background output lines matching it are reported to the agent
+    #[serde(default)]
+    watch: Option<String>,
+    /// Restart the persistent shell session before running the command
+    #[serde(default)]
+    reset: bool,
+    /// Run under a pseudo-terminal (output with escape codes stripped)
+    #[serde(default)]
+    pty: bool,
+    /// Variables added to the command's environment
+    #[serde(default)]
+    env: HashMap<String, String>,
+    /// Text written to the command's standard input
+    #[serde(default)]
+    stdin: Option<String>,
+    /// Directory to run in, relative to the working directory
+    #[serde(default)]
+    cwd: Option<PathBuf>,
 }
 
 fn default_timeout() -> u64 {
@@ -77,7 +285,16 @@ impl Tool for BashTool {
         "Execute shell commands and capture stdout/stderr. \
          Supports timeout control (default 1 minute). \
          Supports background execution mode for long-running commands. \
-         Returns exit code and combined output."
+         Returns exit code and combined output. \
+         Commands are checked before they run: searches outside the working directory and \
+         deletes of system or home directories are refused, and risky commands (force pushes, \
+         sudo, curl | sh, writes outside the working directory) only run if the user approves \
+         them when asked (subagents can't run them). \
+         When the persistent shell session is enabled, `cd` and `export` carry over to later \
+         commands; pass reset=true to start a fresh shell in the working directory. \
+         Set pty=true to run a command under a pseudo-terminal. \
+         env, stdin and cwd set extra environment variables, the command's input and the \
+         directory it runs in."
     }
 
     fn input_schema(&self) -> serde_json::Value {
@@ -102,6 +319,40 @@ impl Tool for BashTool {
                     "type": "boolean",
                     "description": "Run command in background and return shell_id immediately",
                     "default": false
+                },
+                "watch": {
+                    "type": "string",
+                    "description": "Background only: regex; output lines matching it are reported \
+                                    to you on your next turn (e.g. \"error|Finished\")"
+                },
+                "pty": {
+                    "type": "boolean",
+                    "description": "Run under a pseudo-terminal (120x40) for programs that behave \
+                                    differently without one (progress output, buffering, prompts). \
+                                    stderr is merged into stdout and color/escape codes are stripped. \
+                                    With run_in_background, use bash_input to answer prompts.",
+                    "default": false
+                },
+                "env": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "description": "Environment variables to set for this command only"
+                },
+                "stdin": {
+                    "type": "string",
+                    "description": "Text to pass to the command on standard input (not with pty=true)"
+                },
+                "cwd": {
+                    "type": "string",
+                    "description": "Directory to run the command in, relative to the working directory \
+                                    (default: the working directory, or where the persistent shell is)"
+                },
+                "reset": {
+                    "type": "boolean",
+                    "description": "Persistent shell session only: restart the shell (back in the working \
+                                    directory, with the original environment) before running the command. \
+                                    The command may be empty to only reset.",
+                    "default": false
                 }
             },
             "required": ["command"]
@@ -116,73 +367,151 @@ impl Tool for BashTool {
         let params: BashParams = serde_json::from_value(params)
             .map_err(|e| ToolError::InvalidParams(e.to_string()))?;
 
-        // Validate command for common issues
-        validate_command(&params.command, &ctx.working_dir)?;
+        if params.reset {
+            let Some(session) = &ctx.shell_session else {
+                return Err(ToolError::InvalidParams(
+                    "reset requires the persistent shell session ([bash] persistent_session = true)"
+                        .to_string(),
+                ));
+            };
+            session.reset().await;
+            if params.command.trim().is_empty() {
+                return Ok(ToolResult::new(
+                    "Reset shell session",
+                    format!("Shell session reset; commands run in {} again.", ctx.working_dir.display()),
+                )
+                .with_metadata("cwd", json!(ctx.working_dir))
+                .with_metadata("session", json!(true)));
+            }
+        }
+
+        if let Some(name) = params
+            .env
+            .keys()
+            .find(|name| name.is_empty() || name.contains(['=', '\0']))
+        {
+            return Err(ToolError::InvalidParams(format!(
+                "Invalid environment variable name '{}'",
+                name
+            )));
+        }
+        if params.stdin.is_some() && params.pty {
+            return Err(ToolError::InvalidParams(
+                "stdin can't be combined with pty=true; use bash_input to type into a background \
+                 shell started with pty=true"
+                    .to_string(),
+            ));
+        }
+
+        // A persistent shell runs the command wherever earlier commands left it
+        let cwd = match (&params.cwd, &ctx.shell_session) {
+            (Some(dir), _) => {
+                let dir = ctx.resolve_path(dir)?;
+                if !dir.is_dir() {
+                    return Err(ToolError::InvalidParams(format!(
+                        "cwd is not a directory: {}",
+                        dir.display()
+                    )));
+                }
+                dir
+            }
+            (None, Some(session)) => session.cwd().await.unwrap_or_else(|| ctx.working_dir.clone()),
+            (None, None) => ctx.working_dir.clone(),
+        };
+
+        // Check what the command would run: denied commands never run, risky ones only once the
+        // user has approved them
+        let assessment = CommandSafety::default().analyze_from(
+            &params.command,
+            &cwd,
+            &ctx.working_dir,
+            &ctx.allowed_roots(),
+        );
+        match assessment.decision {
+            Decision::Deny => return Err(ToolError::InvalidParams(assessment.reasons())),
+            Decision::Ask => {
+                let reasons = assessment.reasons();
+                if !ctx.request_approval(&params.command, &reasons).await {
+                    tracing::debug!(
+                        command = %crate::logging::redact_secrets(&params.command),
+                        "tool bash: command not approved"
+                    );
+                    return Err(ToolError::ApprovalDenied {
+                        command: params.command,
+                        reasons,
+                    });
+                }
+            }
+            Decision::Allow => {}
+        }
 
         tracing::debug!(
             working_dir = %ctx.working_dir.display(),
+            cwd = %cwd.display(),
             timeout_ms = params.timeout,
             description = %params.description,
             run_in_background = params.run_in_background,
+            env = ?params.env.keys().collect::<Vec<_>>(),
             command = %crate::logging::redact_secrets(&params.command),
             "tool bash start"
         );
 
+        if params.watch.is_some() && !params.run_in_background {
+            return Err(ToolError::InvalidParams(
+                "watch requires run_in_background=true".to_string(),
+            ));
+        }
+
+        // Fail early (and clearly) if the sandbox is enabled but can't be set up here
+        if let Some(sandbox) = &ctx.sandbox {
+            sandbox.ensure_available().await?;
+        }
+
         // Handle background execution mode
         if params.run_in_background {
-            return self.execute_background(params, ctx).await;
+            return self.execute_background(params, cwd, ctx).await;
         }
 
-        // 1. Create command process
-        let mut child = tokio::process::Command::new("sh")
-            .arg("-c")
-            .arg(&params.command)
-            .current_dir(&ctx.working_dir)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| ToolError::Other(e.into()))?;
-
-        // 2. Set timeout duration
         let timeout = Duration::from_millis(params.timeout);
-
-        // 3. Collect output with timeout
-        let result = tokio::time::timeout(timeout, async {
-            // Get stdout and stderr pipes
-            let mut stdout = child.stdout.take().expect("Failed to capture stdout");
-            let mut stderr = child.stderr.take().expect("Failed to capture stderr");
-
-            // Read both streams concurrently
-            let (stdout_result, stderr_result) = tokio::join!(
-                read_to_string(&mut stdout),
-                read_to_string(&mut stderr)
-            );
-
-            // Wait for process to exit
-            let exit_status = child.wait().await?;
-
-            Ok::<_, anyhow::Error>((
-                stdout_result?,
-                stderr_result?,
-                exit_status.code(),
-            ))
-        })
-        .await;
-
-        // 4. Handle timeout
-        let (stdout, stderr, exit_code) = match result {
-            Ok(Ok(output)) => output,
-            Ok(Err(e)) => {
-                return Err(ToolError::Other(e));
+        let mut session_info = None;
+        let CommandOutput {
+            stdout,
+            stderr,
+            exit_code,
+            stopped,
+        } = match &ctx.shell_session {
+            // A terminal, input or per-call overrides need their own process; it starts where
+            // the session is (or in `cwd`), without the session's exported variables
+            Some(_) if params.pty || params.stdin.is_some() || params.cwd.is_some() || !params.env.is_empty() => {
+                self.run_once(&params, timeout, &cwd, ctx).await?
             }
-            Err(_) => {
-                // Timeout - try to kill the process
-                let _ = child.kill().await;
-                return Err(ToolError::Timeout(params.timeout));
+            Some(session) => {
+                let output = session
+                    .run(
+                        &params.command,
+                        timeout,
+                        &ctx.working_dir,
+                        ctx.sandbox.as_deref(),
+                        &ctx.allowed_roots(),
+                        &|stream, line| ctx.report_progress(stream, line),
+                    )
+                    .await
+                    .map_err(|e| match e {
+                        SessionError::Timeout => ToolError::Timeout(params.timeout),
+                        e => e.into(),
+                    })?;
+                session_info = Some((output.cwd, output.env_changed, output.shell_exited));
+                CommandOutput {
+                    stdout: output.stdout,
+                    stderr: output.stderr,
+                    exit_code: output.exit_code,
+                    stopped: output.stopped,
+                }
             }
+            None => self.run_once(&params, timeout, &cwd, ctx).await?,
         };
 
-        // 5. Format output
+        // Format output
         tracing::debug!(
             stdout_preview = &stdout[..stdout.len().min(100)],
             stderr_preview = &stderr[..stderr.len().min(100)],
@@ -206,20 +535,45 @@ impl Tool for BashTool {
             final_output.push_str("(No output)");
         }
 
+        if let Some(note) = &stopped {
+            final_output.push_str("\n\n");
+            final_output.push_str(note);
+        }
+
+        match &session_info {
+            Some((_, _, true)) => final_output.push_str(
+                "\n\n(The command exited the shell; the next command starts a fresh session in the \
+                 working directory.)",
+            ),
+            Some((cwd, _, _)) if !cwd.starts_with(&ctx.working_dir) => final_output.push_str(&format!(
+                "\n\n(The shell is now in {}, outside the working directory {}.)",
+                cwd.display(),
+                ctx.working_dir.display()
+            )),
+            _ => {}
+        }
+
         tracing::debug!(
             final_output_len = final_output.len(),
             final_output_preview = &final_output[..final_output.len().min(100)],
             "bash final_output constructed"
         );
 
-        // 6. Determine if command failed
+        // Determine if command failed (or was stopped by the sandbox)
+        let sandbox_warning = match &ctx.sandbox {
+            Some(sandbox) => sandbox.check_output(exit_code, &stderr, &final_output)?,
+            None => None,
+        };
+        if let Some(warning) = &sandbox_warning {
+            final_output.push_str(&format!("\n\n({})", warning));
+        }
         let title = if !params.description.is_empty() {
             params.description.clone()
         } else {
             format!("$ {}", params.command)
         };
 
-        // 7. Return result
+        // Return result
         tracing::debug!(
             exit_code = ?exit_code,
             stdout_bytes = stdout.len(),
@@ -227,78 +581,43 @@ impl Tool for BashTool {
             "tool bash done"
         );
 
-        Ok(ToolResult::new(title, final_output)
+        let mut result = ToolResult::new(title, final_output)
             .with_metadata("exit_code", json!(exit_code))
             .with_metadata("command", json!(params.command))
-            .with_metadata("success", json!(exit_code == Some(0))))
-    }
-}
-
-/// Validate command for common issues that may cause timeouts or unintended behavior
-fn validate_command(command: &str, working_dir: &std::path::Path) -> Result<(), ToolError> {
-    fn strip_wrapping_quotes(s: &str) -> &str {
-        let s = s.trim();
-        if (s.starts_with('"') && s.ends_with('"')) || (s.starts_with('\'') && s.ends_with('\'')) {
-            &s[1..s.len().saturating_sub(1)]
-        } else {
-            s
+            .with_metadata("success", json!(exit_code == Some(0)))
+            .with_metadata("sandboxed", json!(ctx.sandbox.is_some()))
+            .with_metadata("sandbox_warning", json!(sandbox_warning))
+            .with_metadata("pty", json!(params.pty))
+            .with_metadata("session", json!(session_info.is_some()));
+        if let Some((cwd, env_changed, _)) = session_info {
+            result = result
+                .with_metadata("cwd", json!(cwd))
+                .with_metadata("env_changed", json!(env_changed));
+        } else if params.cwd.is_some() {
+            result = result.with_metadata("cwd", json!(cwd));
         }
+        Ok(result)
     }
-
-    // Check for filesystem-wide searches that ignore working_dir.
-    // This is a guardrail: tools should default to searching under working_dir unless explicitly requested.
-    let mut remaining = command;
-    while let Some(idx) = remaining.find("find ") {
-        remaining = &remaining[idx + "find ".len()..];
-        let after = remaining.trim_start();
-        let first = after
-            .split_whitespace()
-            .next()
-            .unwrap_or("");
-
-        // `find` default path is `.` if the first arg starts with '-' (e.g., `find -name ...`).
-        if first.is_empty() || first.starts_with('-') {
-            continue;
-        }
-
-        let first = strip_wrapping_quotes(first);
-
-        // Common safe cases.
-        if first == "." || first.starts_with("./") || first == "$PWD" || first.starts_with("$PWD/") {
-            continue;
-        }
-
-        // Guard against home/root scans.
-        if first == "/" || first.starts_with("/home") || first.starts_with("~") || first.starts_with("$HOME") {
-            return Err(ToolError::InvalidParams(format!(
-                "Command attempts to search outside the current working directory.\n\
-                 Working directory: {}\n\
-                 Detected: find {}\n\
-                 Suggestion: Use 'find .' (or Glob/Grep tools) to search within the project directory.",
-                working_dir.display(),
-                first
-            )));
-        }
-
-        // Absolute path: allow only if under working_dir.
-        if first.starts_with('/') && !std::path::Path::new(first).starts_with(working_dir) {
-            return Err(ToolError::InvalidParams(format!(
-                "Command attempts to search an absolute path outside the working directory.\n\
-                 Working directory: {}\n\
-                 Detected: find {}\n\
-                 Suggestion: Use 'find .' (or Glob/Grep tools) to search within the project directory.",
-                working_dir.display(),
-                first
-            )));
-        }
-    }
-
-    Ok(())
 }
 
-/// Helper function to read a stream to string
-async fn read_to_string<R: AsyncReadExt + Unpin>(reader: &mut R) -> anyhow::Result<String> {
+/// Read a stream to string, reporting each line as progress of the tool call; stops early once
+/// the command has printed more than `cap` allows
+async fn read_streaming<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    stream: &'static str,
+    ctx: &ToolContext,
+    cap: &OutputCap,
+) -> anyhow::Result<String> {
+    let mut reader = BufReader::new(reader);
     let mut buffer = Vec::new();
-    reader.read_to_end(&mut buffer).await?;
+    loop {
+        let start = buffer.len();
+        let n = reader.read_until(b'\n', &mut buffer).await?;
+        if n == 0 || !cap.add(n) {
+            break;
+        }
+        let line = String::from_utf8_lossy(&buffer[start..]);
+        ctx.report_progress(stream, line.trim_end_matches(['\n', '\r']));
+    }
     Ok(String::from_utf8_lossy(&buffer).to_string())
 }
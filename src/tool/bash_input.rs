@@ -0,0 +1,164 @@
+use super::base::{Tool, ToolContext, ToolError, ToolResult};
+use crate::process::StreamFilter;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+/// BashInput tool - Send keystrokes to a background shell running under a pty
+pub struct BashInputTool;
+
+/// Longest wait for output after sending input
+const MAX_WAIT_MS: u64 = 10_000;
+
+#[derive(Debug, Deserialize)]
+struct BashInputParams {
+    shell_id: String,
+    #[serde(default)]
+    input: String,
+    #[serde(default)]
+    keys: Vec<String>,
+    #[serde(default = "default_wait_ms")]
+    wait_ms: u64,
+}
+
+fn default_wait_ms() -> u64 {
+    500
+}
+
+/// Bytes a terminal sends for a named key
+fn key_bytes(name: &str) -> Option<&'static [u8]> {
+    let bytes: &[u8] = match name.to_ascii_lowercase().as_str() {
+        "enter" | "return" => b"\r",
+        "tab" => b"\t",
+        "space" => b" ",
+        "backspace" => b"\x7f",
+        "escape" | "esc" => b"\x1b",
+        "up" => b"\x1b[A",
+        "down" => b"\x1b[B",
+        "right" => b"\x1b[C",
+        "left" => b"\x1b[D",
+        "ctrl-c" => b"\x03",
+        "ctrl-d" => b"\x04",
+        "ctrl-z" => b"\x1a",
+        _ => return None,
+    };
+    Some(bytes)
+}
+
+#[async_trait::async_trait]
+impl Tool for BashInputTool {
+    fn id(&self) -> &str {
+        "bash_input"
+    }
+
+    fn description(&self) -> &str {
+        "Send input to a background shell started with pty=true, e.g. to answer a prompt or \
+         press 'q' in a pager. Sends the text as typed, then the named keys, and returns the \
+         output that appeared shortly after."
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "shell_id": {
+                    "type": "string",
+                    "description": "Background shell ID (returned by bash with run_in_background=true and pty=true)"
+                },
+                "input": {
+                    "type": "string",
+                    "description": "Text to type (no newline is added; use keys: [\"enter\"])",
+                    "default": ""
+                },
+                "keys": {
+                    "type": "array",
+                    "items": {
+                        "type": "string",
+                        "enum": ["enter", "tab", "space", "backspace", "escape", "up", "down", "left", "right", "ctrl-c", "ctrl-d", "ctrl-z"]
+                    },
+                    "description": "Keys to press after the text, in order"
+                },
+                "wait_ms": {
+                    "type": "integer",
+                    "description": "How long to wait for output after sending (default: 500, max: 10000)",
+                    "default": 500
+                }
+            },
+            "required": ["shell_id"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let params: BashInputParams = serde_json::from_value(params)
+            .map_err(|e| ToolError::InvalidParams(e.to_string()))?;
+
+        let mut data = params.input.clone().into_bytes();
+        for key in &params.keys {
+            let bytes = key_bytes(key)
+                .ok_or_else(|| ToolError::InvalidParams(format!("Unknown key '{}'", key)))?;
+            data.extend_from_slice(bytes);
+        }
+        if data.is_empty() {
+            return Err(ToolError::InvalidParams(
+                "Nothing to send: give input and/or keys".to_string(),
+            ));
+        }
+
+        tracing::debug!(shell_id = %params.shell_id, bytes = data.len(), "bash_input start");
+
+        let seq_before = ctx
+            .shell_manager
+            .get_next_seq(&params.shell_id)
+            .await
+            .ok_or_else(|| {
+                ToolError::InvalidParams(format!("Background shell '{}' not found", params.shell_id))
+            })?;
+
+        ctx.shell_manager
+            .send_input(&params.shell_id, &data)
+            .await
+            .map_err(|e| ToolError::InvalidParams(e.to_string()))?;
+
+        tokio::time::sleep(Duration::from_millis(params.wait_ms.min(MAX_WAIT_MS))).await;
+
+        let new_output = ctx
+            .shell_manager
+            .get_output_since(&params.shell_id, seq_before, StreamFilter::Stdout)
+            .await
+            .unwrap_or_default();
+        let new_lines: Vec<String> = new_output.lines.into_iter().map(|l| l.text).collect();
+        let pending = ctx
+            .shell_manager
+            .get_pending_line(&params.shell_id)
+            .await
+            .unwrap_or_default();
+        let status = ctx.shell_manager.get_status(&params.shell_id).await;
+
+        let mut output = String::new();
+        for line in &new_lines {
+            output.push_str(line);
+            output.push('\n');
+        }
+        output.push_str(&pending);
+        if output.is_empty() {
+            output.push_str("(No new output)");
+        }
+        if let Some(crate::process::background_shell::ShellStatus::Completed { exit_code }) = &status {
+            output.push_str(&format!("\n\n(Shell exited with code {:?})", exit_code));
+        }
+
+        Ok(ToolResult::new(format!("Input to {}", params.shell_id), output)
+            .with_metadata("shell_id", json!(params.shell_id))
+            .with_metadata("bytes_sent", json!(data.len()))
+            .with_metadata("new_stdout_lines", json!(new_lines.len()))
+            .with_metadata("next_seq", json!(new_output.next_seq))
+            .with_metadata("running", json!(matches!(
+                status,
+                Some(crate::process::background_shell::ShellStatus::Running)
+            ))))
+    }
+}
@@ -1,4 +1,6 @@
 use super::base::{Tool, ToolContext, ToolError, ToolResult};
+use crate::process::output_log::MAX_LOG_BYTES;
+use crate::process::{Stream, StreamFilter};
 use serde::Deserialize;
 use serde_json::json;
 
@@ -9,9 +11,16 @@ pub struct BashOutputTool;
 struct BashOutputParams {
     shell_id: String,
     #[serde(default)]
-    offset: usize,
+    since_seq: u64,
+    /// The old per-stream stdout line offset, which `since_seq` does not translate to
+    #[serde(default)]
+    offset: Option<u64>,
+    #[serde(default)]
+    stream: StreamFilter,
     #[serde(default)]
     filter: Option<String>,
+    #[serde(default)]
+    timestamps: bool,
 }
 
 #[async_trait::async_trait]
@@ -22,8 +31,10 @@ impl Tool for BashOutputTool {
 
     fn description(&self) -> &str {
         "Monitor output from a background shell process. \
-         Returns new stdout/stderr lines since last check. \
-         Supports optional regex filtering."
+         Returns the lines printed since `since_seq` (every line has a sequence number; pass \
+         the returned next_seq to get only newer lines), stdout and stderr interleaved in the \
+         order they were printed or just one stream. Supports optional regex filtering. \
+         Only the most recent output is kept; lines evicted before they were read are reported."
     }
 
     fn input_schema(&self) -> serde_json::Value {
@@ -34,14 +45,25 @@ impl Tool for BashOutputTool {
                     "type": "string",
                     "description": "Background shell ID (returned by bash tool with run_in_background=true)"
                 },
-                "offset": {
+                "since_seq": {
                     "type": "integer",
-                    "description": "Line offset to start reading from (default: 0 = all lines)",
+                    "description": "Sequence number to start reading from: next_seq of the previous call (default: 0 = all output)",
                     "default": 0
                 },
+                "stream": {
+                    "type": "string",
+                    "enum": ["stdout", "stderr", "combined"],
+                    "description": "Which output to return; combined interleaves both, marking stderr lines (default: combined)",
+                    "default": "combined"
+                },
                 "filter": {
                     "type": "string",
                     "description": "Optional regex pattern to filter output lines"
+                },
+                "timestamps": {
+                    "type": "boolean",
+                    "description": "Prefix each line with when it was printed, relative to the shell's start (default: false)",
+                    "default": false
                 }
             },
             "required": ["shell_id"]
@@ -55,10 +77,18 @@ impl Tool for BashOutputTool {
     ) -> Result<ToolResult, ToolError> {
         let params: BashOutputParams = serde_json::from_value(params)
             .map_err(|e| ToolError::InvalidParams(e.to_string()))?;
+        if params.offset.is_some() {
+            return Err(ToolError::InvalidParams(
+                "`offset` is no longer supported: pass `since_seq`, the next_seq returned by the \
+                 previous call, which counts stdout and stderr lines together"
+                    .to_string(),
+            ));
+        }
 
         tracing::debug!(
             shell_id = %params.shell_id,
-            offset = params.offset,
+            since_seq = params.since_seq,
+            stream = ?params.stream,
             filter = ?params.filter,
             "bash_output start"
         );
@@ -80,26 +110,22 @@ impl Tool for BashOutputTool {
                 ToolError::InvalidParams(format!("Failed to get status for shell '{}'", params.shell_id))
             })?;
 
-        // Get stdout and stderr since offset
-        let mut stdout_lines = ctx
+        let slice = ctx
             .shell_manager
-            .get_stdout_since(&params.shell_id, params.offset)
-            .await
-            .unwrap_or_default();
-        let mut stderr_lines = ctx
-            .shell_manager
-            .get_stderr_since(&params.shell_id, params.offset)
+            .get_output_since(&params.shell_id, params.since_seq, params.stream)
             .await
             .unwrap_or_default();
+        let mut lines = slice.lines;
 
         // Apply filter if specified
         if let Some(ref filter_pattern) = params.filter {
             let regex = regex::Regex::new(filter_pattern)
                 .map_err(|e| ToolError::InvalidParams(format!("Invalid regex: {}", e)))?;
 
-            stdout_lines.retain(|line| regex.is_match(line));
-            stderr_lines.retain(|line| regex.is_match(line));
+            lines.retain(|line| regex.is_match(&line.text));
         }
+        let new_stdout = lines.iter().filter(|l| l.stream == Stream::Stdout).count();
+        let new_stderr = lines.len() - new_stdout;
 
         // Get total line counts
         let (total_stdout, total_stderr) = ctx
@@ -127,36 +153,63 @@ impl Tool for BashOutputTool {
             total_stdout, total_stderr
         ));
 
-        // New output since offset
-        if !stdout_lines.is_empty() {
-            output.push_str("=== STDOUT (new) ===\n");
-            for line in &stdout_lines {
-                output.push_str(line);
-                output.push('\n');
-            }
-            output.push('\n');
+        // Output evicted between the last read and now
+        if slice.missed > 0 {
+            output.push_str(&format!(
+                "[{} lines (seq {} to {}) were dropped before they were read: only the last {} MiB of output is kept]\n\n",
+                slice.missed,
+                params.since_seq,
+                params.since_seq + slice.missed - 1,
+                MAX_LOG_BYTES / (1024 * 1024)
+            ));
         }
 
-        if !stderr_lines.is_empty() {
-            output.push_str("=== STDERR (new) ===\n");
-            for line in &stderr_lines {
-                output.push_str(line);
+        // New output since since_seq
+        if !lines.is_empty() {
+            output.push_str(match params.stream {
+                StreamFilter::Stdout => "=== STDOUT (new) ===\n",
+                StreamFilter::Stderr => "=== STDERR (new) ===\n",
+                StreamFilter::Combined => "=== OUTPUT (new, stderr lines marked) ===\n",
+            });
+            for line in &lines {
+                if params.timestamps {
+                    output.push_str(&format!("[+{:.3}s] ", line.at.as_secs_f64()));
+                }
+                if params.stream == StreamFilter::Combined && line.stream == Stream::Stderr {
+                    output.push_str("[stderr] ");
+                }
+                output.push_str(&line.text);
                 output.push('\n');
             }
             output.push('\n');
         }
 
-        if stdout_lines.is_empty() && stderr_lines.is_empty() {
-            output.push_str("(No new output since offset)\n");
+        // A pty shell's unfinished line, typically a prompt waiting for bash_input
+        let pending = if params.stream.includes(Stream::Stdout) {
+            ctx.shell_manager
+                .get_pending_line(&params.shell_id)
+                .await
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+        if !pending.is_empty() {
+            output.push_str("=== CURRENT LINE (no newline yet) ===\n");
+            output.push_str(&pending);
+            output.push_str("\n\n");
         }
 
-        let new_offset = params.offset + stdout_lines.len() + stderr_lines.len();
+        if lines.is_empty() && pending.is_empty() {
+            output.push_str(&format!("(No new output since seq {})\n", params.since_seq));
+        }
+        output.push_str(&format!("Next since_seq: {}\n", slice.next_seq));
 
         tracing::debug!(
             shell_id = %params.shell_id,
-            stdout_lines = stdout_lines.len(),
-            stderr_lines = stderr_lines.len(),
-            new_offset,
+            stdout_lines = new_stdout,
+            stderr_lines = new_stderr,
+            missed = slice.missed,
+            next_seq = slice.next_seq,
             "bash_output done"
         );
 
@@ -167,9 +220,11 @@ impl Tool for BashOutputTool {
         )
         .with_metadata("shell_id", json!(params.shell_id))
         .with_metadata("status", json!(status_str))
-        .with_metadata("new_stdout_lines", json!(stdout_lines.len()))
-        .with_metadata("new_stderr_lines", json!(stderr_lines.len()))
-        .with_metadata("new_offset", json!(new_offset))
+        .with_metadata("new_stdout_lines", json!(new_stdout))
+        .with_metadata("new_stderr_lines", json!(new_stderr))
+        .with_metadata("missed_lines", json!(slice.missed))
+        .with_metadata("next_seq", json!(slice.next_seq))
+        .with_metadata("new_offset", json!(slice.next_seq))
         .with_metadata("total_stdout_lines", json!(total_stdout))
         .with_metadata("total_stderr_lines", json!(total_stderr)))
     }
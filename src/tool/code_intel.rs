@@ -0,0 +1,775 @@
+use super::base::{Tool, ToolContext, ToolError, ToolResult};
+use crate::config::station::LspServerSettings;
+use crate::lsp::client::uri_to_path;
+use crate::lsp::{LspClient, LspError, LspManager, ServerHandle};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Maximum locations or symbols listed in one result
+const MAX_RESULTS: usize = 200;
+
+/// Truncate displayed source lines like grep does
+const MAX_LINE_LENGTH: usize = 500;
+
+/// How long to wait for a server to publish diagnostics for a freshly synced file
+const DIAGNOSTICS_WAIT: Duration = Duration::from_secs(10);
+
+/// Files inspected when guessing which language servers a workspace needs
+const MAX_DETECT_FILES: usize = 5000;
+
+/// CodeIntel tool - semantic code navigation through language servers
+pub struct CodeIntelTool {
+    lsp: Arc<LspManager>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Operation {
+    Definition,
+    References,
+    Hover,
+    DocumentSymbols,
+    WorkspaceSymbols,
+    Diagnostics,
+}
+
+impl Operation {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Operation::Definition => "definition",
+            Operation::References => "references",
+            Operation::Hover => "hover",
+            Operation::DocumentSymbols => "document_symbols",
+            Operation::WorkspaceSymbols => "workspace_symbols",
+            Operation::Diagnostics => "diagnostics",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CodeIntelParams {
+    operation: Operation,
+    #[serde(default)]
+    file_path: Option<PathBuf>,
+    /// 1-based
+    #[serde(default)]
+    line: Option<usize>,
+    /// 1-based, in characters
+    #[serde(default)]
+    column: Option<usize>,
+    #[serde(default)]
+    symbol: Option<String>,
+}
+
+/// A position in a file, as shown to the model and as sent to the server
+struct Target {
+    path: PathBuf,
+    text: String,
+    /// 0-based
+    line: usize,
+    /// 0-based UTF-16 offset, as LSP expects
+    character: usize,
+}
+
+impl Target {
+    fn position(&self) -> Value {
+        json!({ "line": self.line, "character": self.character })
+    }
+
+    /// 1-based character column
+    fn column(&self) -> usize {
+        let line = self.text.lines().nth(self.line).unwrap_or("");
+        char_column(line, self.character) + 1
+    }
+}
+
+/// A location in a result list
+struct Location {
+    path: PathBuf,
+    /// 0-based
+    line: usize,
+    /// Symbol results show the symbol instead of the source line
+    label: Option<String>,
+}
+
+impl Default for CodeIntelTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CodeIntelTool {
+    /// Create a tool using the default language servers
+    pub fn new() -> Self {
+        Self::with_manager(Arc::new(LspManager::new(HashMap::new())))
+    }
+
+    /// Use an existing manager (and its running servers)
+    pub fn with_manager(lsp: Arc<LspManager>) -> Self {
+        Self { lsp }
+    }
+
+    /// Apply per-language server overrides from the `[lsp.<language>]` config sections
+    pub fn with_server_settings(mut self, settings: HashMap<String, LspServerSettings>) -> Self {
+        self.lsp = Arc::new(LspManager::new(settings));
+        self
+    }
+
+    /// Server for `path`, started if the project is trusted
+    async fn server_for(&self, path: &Path, ctx: &ToolContext) -> Result<ServerHandle, ToolError> {
+        if !ctx.trusted_project {
+            return Err(LspError::Untrusted.into());
+        }
+        Ok(self.lsp.server_for(path, &ctx.working_dir, &ctx.env_filter).await?)
+    }
+
+    /// Open (or refresh) `path` on its server
+    async fn open(
+        &self,
+        path: &Path,
+        text: &str,
+        ctx: &ToolContext,
+    ) -> Result<(Arc<LspClient>, url::Url), ToolError> {
+        let server = self.server_for(path, ctx).await?;
+        let uri = server
+            .client
+            .sync_document(path, &server.language_id, text)
+            .await?;
+        Ok((server.client, uri))
+    }
+
+    /// Servers to ask a workspace-wide question: the file's server, the running ones, or
+    /// servers for languages found in the working directory
+    async fn workspace_clients(
+        &self,
+        file_path: Option<&Path>,
+        ctx: &ToolContext,
+    ) -> Result<Vec<Arc<LspClient>>, ToolError> {
+        if let Some(path) = file_path {
+            let server = self.server_for(path, ctx).await?;
+            return Ok(vec![server.client]);
+        }
+
+        let running = self.lsp.running(&ctx.working_dir).await;
+        if !running.is_empty() {
+            return Ok(running);
+        }
+
+        let mut samples: HashMap<String, PathBuf> = HashMap::new();
+        let walker = ignore::WalkBuilder::new(&ctx.working_dir).build();
+        for entry in walker.flatten().take(MAX_DETECT_FILES) {
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            if let Some(language) = self.lsp.language_for(entry.path()) {
+                samples
+                    .entry(language.to_string())
+                    .or_insert_with(|| entry.path().to_path_buf());
+            }
+        }
+        if samples.is_empty() {
+            return Err(ToolError::InvalidParams(
+                "No files with a configured language server found; pass file_path".to_string(),
+            ));
+        }
+
+        let mut samples: Vec<(String, PathBuf)> = samples.into_iter().collect();
+        samples.sort();
+        let mut clients = Vec::new();
+        for (_, path) in samples {
+            clients.push(self.server_for(&path, ctx).await?.client);
+        }
+        Ok(clients)
+    }
+
+    /// Resolve file/line/column/symbol parameters to a position
+    async fn target(&self, params: &CodeIntelParams, ctx: &ToolContext) -> Result<Target, ToolError> {
+        let Some(file_path) = &params.file_path else {
+            let symbol = params.symbol.as_deref().ok_or_else(|| {
+                ToolError::InvalidParams(format!(
+                    "{} requires file_path with line/column, or symbol",
+                    params.operation.as_str()
+                ))
+            })?;
+            return self.locate_symbol(symbol, ctx).await;
+        };
+
+        let path = ctx.resolve_path(file_path)?;
+        let text = read_source(&path).await?;
+
+        let (line, character) = match (params.line, params.column, params.symbol.as_deref()) {
+            (Some(line), column, symbol) => {
+                let line_text = text.lines().nth(line.wrapping_sub(1)).ok_or_else(|| {
+                    ToolError::InvalidParams(format!(
+                        "line {} is out of range ({} has {} lines)",
+                        line,
+                        path.display(),
+                        text.lines().count()
+                    ))
+                })?;
+                let byte = match (column, symbol) {
+                    (Some(column), _) => line_text
+                        .char_indices()
+                        .nth(column.saturating_sub(1))
+                        .map_or(line_text.len(), |(i, _)| i),
+                    (None, Some(symbol)) => find_word(line_text, symbol).ok_or_else(|| {
+                        ToolError::InvalidParams(format!(
+                            "'{}' not found on line {} of {}",
+                            symbol,
+                            line,
+                            path.display()
+                        ))
+                    })?,
+                    (None, None) => line_text.len() - line_text.trim_start().len(),
+                };
+                (line - 1, utf16_len(&line_text[..byte]))
+            }
+            (None, _, Some(symbol)) => text
+                .lines()
+                .enumerate()
+                .find_map(|(idx, line_text)| {
+                    find_word(line_text, symbol).map(|byte| (idx, utf16_len(&line_text[..byte])))
+                })
+                .ok_or_else(|| {
+                    ToolError::InvalidParams(format!("'{}' not found in {}", symbol, path.display()))
+                })?,
+            (None, _, None) => {
+                return Err(ToolError::InvalidParams(
+                    "Provide line (and optionally column) or symbol".to_string(),
+                ))
+            }
+        };
+
+        Ok(Target {
+            path,
+            text,
+            line,
+            character,
+        })
+    }
+
+    /// Find where `symbol` is declared using workspace symbols
+    async fn locate_symbol(&self, symbol: &str, ctx: &ToolContext) -> Result<Target, ToolError> {
+        let mut candidates = Vec::new();
+        for client in self.workspace_clients(None, ctx).await? {
+            let result = client
+                .request("workspace/symbol", json!({ "query": symbol }))
+                .await?;
+            candidates.extend(result.as_array().cloned().unwrap_or_default());
+        }
+
+        let exact = candidates
+            .iter()
+            .find(|s| s["name"].as_str() == Some(symbol))
+            .or_else(|| candidates.first())
+            .ok_or_else(|| {
+                ToolError::InvalidParams(format!("Symbol '{}' not found in the workspace", symbol))
+            })?;
+
+        let location = &exact["location"];
+        let path = location["uri"]
+            .as_str()
+            .and_then(uri_to_path)
+            .ok_or_else(|| ToolError::InvalidParams(format!("Symbol '{}' has no file location", symbol)))?;
+        let line = location["range"]["start"]["line"].as_u64().unwrap_or(0) as usize;
+        let start = location["range"]["start"]["character"].as_u64().unwrap_or(0) as usize;
+
+        let text = read_source(&path).await?;
+        // The range may start at a keyword (`pub fn`); point at the name itself
+        let line_text = text.lines().nth(line).unwrap_or("");
+        let from = byte_offset(line_text, start);
+        let character = match find_word(&line_text[from..], symbol) {
+            Some(byte) => utf16_len(&line_text[..from + byte]),
+            None => start,
+        };
+
+        Ok(Target {
+            path,
+            text,
+            line,
+            character,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for CodeIntelTool {
+    fn id(&self) -> &str {
+        "code_intel"
+    }
+
+    fn description(&self) -> &str {
+        "Semantic code navigation through language servers (rust-analyzer, pyright, \
+         typescript-language-server, gopls). Unlike grep, it resolves which symbol a name refers to.\n\
+         Operations:\n\
+         - definition / references / hover: the symbol at file_path + line + column (1-based). \
+         Instead of a column, pass symbol to use its first occurrence on the line (or in the file \
+         if line is omitted), or pass only symbol to look it up in the workspace.\n\
+         - document_symbols: outline of file_path\n\
+         - workspace_symbols: symbols matching the symbol query\n\
+         - diagnostics: errors and warnings the language server reports for file_path\n\
+         The first query for a language starts its server, which may take a while to index."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "operation": {
+                    "type": "string",
+                    "enum": ["definition", "references", "hover", "document_symbols", "workspace_symbols", "diagnostics"],
+                    "description": "What to look up"
+                },
+                "file_path": {
+                    "type": "string",
+                    "description": "File to query (absolute or relative)"
+                },
+                "line": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "1-based line number"
+                },
+                "column": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "1-based column (character) on the line"
+                },
+                "symbol": {
+                    "type": "string",
+                    "description": "Symbol name: locates the position when column is omitted, or the query for workspace_symbols"
+                }
+            },
+            "required": ["operation"]
+        })
+    }
+
+    async fn execute(&self, params: Value, ctx: &ToolContext) -> Result<ToolResult, ToolError> {
+        let params: CodeIntelParams = serde_json::from_value(params)
+            .map_err(|e| ToolError::InvalidParams(e.to_string()))?;
+
+        tracing::debug!(
+            operation = params.operation.as_str(),
+            file_path = ?params.file_path,
+            line = ?params.line,
+            column = ?params.column,
+            symbol = ?params.symbol,
+            "tool code_intel start"
+        );
+
+        let relative = |path: &Path| -> String {
+            path.strip_prefix(&ctx.working_dir)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        };
+
+        let (title, output, total) = match params.operation {
+            Operation::Definition | Operation::References => {
+                let target = self.target(&params, ctx).await?;
+                let (client, uri) = self.open(&target.path, &target.text, ctx).await?;
+                let (method, label, extra) = match params.operation {
+                    Operation::Definition => ("textDocument/definition", "definitions", json!({})),
+                    _ => (
+                        "textDocument/references",
+                        "references",
+                        json!({ "context": { "includeDeclaration": true } }),
+                    ),
+                };
+                let mut request = json!({
+                    "textDocument": { "uri": uri },
+                    "position": target.position()
+                });
+                merge(&mut request, extra);
+                let result = client.request(method, request).await?;
+
+                let locations = parse_locations(&result);
+                let at = format!(
+                    "{}:{}:{}",
+                    relative(&target.path),
+                    target.line + 1,
+                    target.column()
+                );
+                let output = if locations.is_empty() {
+                    format!("No {} found for the symbol at {}\n", label, at)
+                } else {
+                    format_locations(label, &locations, &relative)
+                };
+                (format!("{}: {}", params.operation.as_str(), at), output, locations.len())
+            }
+            Operation::Hover => {
+                let target = self.target(&params, ctx).await?;
+                let (client, uri) = self.open(&target.path, &target.text, ctx).await?;
+                let result = client
+                    .request(
+                        "textDocument/hover",
+                        json!({ "textDocument": { "uri": uri }, "position": target.position() }),
+                    )
+                    .await?;
+
+                let at = format!(
+                    "{}:{}:{}",
+                    relative(&target.path),
+                    target.line + 1,
+                    target.column()
+                );
+                let text = hover_text(&result["contents"]);
+                let (output, total) = if text.trim().is_empty() {
+                    (format!("No hover information at {}\n", at), 0)
+                } else {
+                    (format!("Hover at {}:\n\n{}\n", at, text.trim_end()), 1)
+                };
+                (format!("hover: {}", at), output, total)
+            }
+            Operation::DocumentSymbols => {
+                let file_path = params.file_path.as_ref().ok_or_else(|| {
+                    ToolError::InvalidParams("document_symbols requires file_path".to_string())
+                })?;
+                let path = ctx.resolve_path(file_path)?;
+                let text = read_source(&path).await?;
+                let (client, uri) = self.open(&path, &text, ctx).await?;
+                let result = client
+                    .request(
+                        "textDocument/documentSymbol",
+                        json!({ "textDocument": { "uri": uri } }),
+                    )
+                    .await?;
+
+                let mut locations = Vec::new();
+                for symbol in result.as_array().into_iter().flatten() {
+                    collect_document_symbols(symbol, &path, 0, &mut locations);
+                }
+                let output = if locations.is_empty() {
+                    format!("No symbols found in {}\n", relative(&path))
+                } else {
+                    format_locations("symbols", &locations, &relative)
+                };
+                (
+                    format!("document_symbols: {}", relative(&path)),
+                    output,
+                    locations.len(),
+                )
+            }
+            Operation::WorkspaceSymbols => {
+                let query = params.symbol.as_deref().ok_or_else(|| {
+                    ToolError::InvalidParams("workspace_symbols requires symbol".to_string())
+                })?;
+                let file_path = params
+                    .file_path
+                    .as_ref()
+                    .map(|p| ctx.resolve_path(p))
+                    .transpose()?;
+
+                let mut locations = Vec::new();
+                for client in self.workspace_clients(file_path.as_deref(), ctx).await? {
+                    let result = client
+                        .request("workspace/symbol", json!({ "query": query }))
+                        .await?;
+                    for symbol in result.as_array().into_iter().flatten() {
+                        let Some(path) = symbol["location"]["uri"].as_str().and_then(uri_to_path)
+                        else {
+                            continue;
+                        };
+                        let mut label = symbol_label(symbol);
+                        if let Some(container) = symbol["containerName"].as_str().filter(|c| !c.is_empty()) {
+                            label.push_str(&format!(" (in {})", container));
+                        }
+                        locations.push(Location {
+                            path,
+                            line: symbol["location"]["range"]["start"]["line"].as_u64().unwrap_or(0)
+                                as usize,
+                            label: Some(label),
+                        });
+                    }
+                }
+                let output = if locations.is_empty() {
+                    format!("No symbols found matching: {}\n", query)
+                } else {
+                    format_locations("symbols", &locations, &relative)
+                };
+                (format!("workspace_symbols: {}", query), output, locations.len())
+            }
+            Operation::Diagnostics => {
+                let file_path = params.file_path.as_ref().ok_or_else(|| {
+                    ToolError::InvalidParams("diagnostics requires file_path".to_string())
+                })?;
+                let path = ctx.resolve_path(file_path)?;
+                let text = read_source(&path).await?;
+                let (client, uri) = self.open(&path, &text, ctx).await?;
+
+                let diagnostics = client.diagnostics(&uri, DIAGNOSTICS_WAIT).await;
+                let locations: Vec<Location> = diagnostics
+                    .iter()
+                    .flatten()
+                    .map(|d| Location {
+                        path: path.clone(),
+                        line: d["range"]["start"]["line"].as_u64().unwrap_or(0) as usize,
+                        label: Some(diagnostic_label(d)),
+                    })
+                    .collect();
+                let output = match diagnostics {
+                    None => format!(
+                        "The language server published no diagnostics for {} within {}s\n",
+                        relative(&path),
+                        DIAGNOSTICS_WAIT.as_secs()
+                    ),
+                    Some(_) if locations.is_empty() => {
+                        format!("No problems reported in {}\n", relative(&path))
+                    }
+                    Some(_) => format_locations("diagnostics", &locations, &relative),
+                };
+                (format!("diagnostics: {}", relative(&path)), output, locations.len())
+            }
+        };
+
+        tracing::debug!(operation = params.operation.as_str(), total, "tool code_intel done");
+
+        Ok(ToolResult::new(title, output)
+            .with_metadata("operation", json!(params.operation.as_str()))
+            .with_metadata("total_results", json!(total)))
+    }
+}
+
+async fn read_source(path: &Path) -> Result<String, ToolError> {
+    if !path.exists() {
+        return Err(ToolError::FileNotFound(path.to_path_buf()));
+    }
+    let bytes = tokio::fs::read(path)
+        .await
+        .map_err(|e| ToolError::Other(e.into()))?;
+    String::from_utf8(bytes).map_err(|_| ToolError::BinaryFile(path.to_path_buf()))
+}
+
+/// Add the fields of `extra` to the object `target`
+fn merge(target: &mut Value, extra: Value) {
+    if let (Some(target), Value::Object(extra)) = (target.as_object_mut(), extra) {
+        target.extend(extra);
+    }
+}
+
+/// Normalize `Location | Location[] | LocationLink[] | null`
+fn parse_locations(result: &Value) -> Vec<Location> {
+    let items = match result {
+        Value::Array(items) => items.clone(),
+        Value::Null => Vec::new(),
+        single => vec![single.clone()],
+    };
+    items
+        .iter()
+        .filter_map(|item| {
+            let (uri, range) = match item.get("targetUri") {
+                Some(uri) => (uri, &item["targetSelectionRange"]),
+                None => (&item["uri"], &item["range"]),
+            };
+            Some(Location {
+                path: uri.as_str().and_then(uri_to_path)?,
+                line: range["start"]["line"].as_u64()? as usize,
+                label: None,
+            })
+        })
+        .collect()
+}
+
+/// Flatten `DocumentSymbol[]` (indented by nesting) or `SymbolInformation[]`
+fn collect_document_symbols(symbol: &Value, path: &Path, depth: usize, out: &mut Vec<Location>) {
+    let range = symbol
+        .get("selectionRange")
+        .unwrap_or(&symbol["location"]["range"]);
+    out.push(Location {
+        path: path.to_path_buf(),
+        line: range["start"]["line"].as_u64().unwrap_or(0) as usize,
+        label: Some(format!("{}{}", "  ".repeat(depth), symbol_label(symbol))),
+    });
+    for child in symbol["children"].as_array().into_iter().flatten() {
+        collect_document_symbols(child, path, depth + 1, out);
+    }
+}
+
+fn symbol_label(symbol: &Value) -> String {
+    let name = symbol["name"].as_str().unwrap_or("?");
+    let kind = symbol["kind"].as_u64().map_or("symbol", symbol_kind);
+    format!("{} {}", kind, name)
+}
+
+/// Names for LSP `SymbolKind` values
+fn symbol_kind(kind: u64) -> &'static str {
+    const KINDS: [&str; 26] = [
+        "file", "module", "namespace", "package", "class", "method", "property", "field",
+        "constructor", "enum", "interface", "function", "variable", "constant", "string",
+        "number", "boolean", "array", "object", "key", "null", "enum member", "struct", "event",
+        "operator", "type parameter",
+    ];
+    kind.checked_sub(1)
+        .and_then(|i| KINDS.get(i as usize))
+        .copied()
+        .unwrap_or("symbol")
+}
+
+fn diagnostic_label(diagnostic: &Value) -> String {
+    let severity = match diagnostic["severity"].as_u64() {
+        Some(1) => "error",
+        Some(2) => "warning",
+        Some(3) => "info",
+        Some(4) => "hint",
+        _ => "error",
+    };
+    let message = diagnostic["message"].as_str().unwrap_or("").replace('\n', " ");
+    match diagnostic["source"].as_str() {
+        Some(source) => format!("{}: {} [{}]", severity, message, source),
+        None => format!("{}: {}", severity, message),
+    }
+}
+
+/// Render `MarkedString | MarkedString[] | MarkupContent` as text
+fn hover_text(contents: &Value) -> String {
+    match contents {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(hover_text)
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        Value::Object(object) => match (object.get("language"), object.get("value")) {
+            (Some(language), Some(value)) => format!(
+                "```{}\n{}\n```",
+                language.as_str().unwrap_or(""),
+                value.as_str().unwrap_or("")
+            ),
+            (None, Some(value)) => value.as_str().unwrap_or("").to_string(),
+            _ => String::new(),
+        },
+        _ => String::new(),
+    }
+}
+
+/// List locations grouped by file, in grep's content format
+fn format_locations(label: &str, locations: &[Location], relative: &dyn Fn(&Path) -> String) -> String {
+    let mut sorted: Vec<&Location> = locations.iter().collect();
+    // Symbol lists keep the server's order (document outline); plain locations are sorted
+    if sorted.iter().all(|l| l.label.is_none()) {
+        sorted.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+        sorted.dedup_by(|a, b| a.path == b.path && a.line == b.line);
+    }
+
+    let mut groups: Vec<(&Path, Vec<&Location>)> = Vec::new();
+    for location in sorted.iter().take(MAX_RESULTS) {
+        match groups.iter_mut().find(|(path, _)| *path == location.path.as_path()) {
+            Some((_, group)) => group.push(location),
+            None => groups.push((location.path.as_path(), vec![location])),
+        }
+    }
+
+    let mut output = format!("Found {} {} in {} files:\n\n", sorted.len(), label, groups.len());
+    let mut sources: HashMap<&Path, Vec<String>> = HashMap::new();
+    for (path, group) in &groups {
+        output.push_str(&format!("{}:\n", relative(path)));
+        for location in group {
+            let content = match &location.label {
+                Some(label) => label.clone(),
+                None => {
+                    let lines = sources.entry(path).or_insert_with(|| {
+                        std::fs::read(path)
+                            .map(|b| String::from_utf8_lossy(&b).lines().map(str::to_string).collect())
+                            .unwrap_or_default()
+                    });
+                    truncate_line(lines.get(location.line).map_or("", String::as_str))
+                }
+            };
+            output.push_str(&format!("> {:>4}\u{2502} {}\n", location.line + 1, content));
+        }
+        output.push('\n');
+    }
+
+    if sorted.len() > MAX_RESULTS {
+        output.push_str(&format!("(Showing {} of {} {})\n", MAX_RESULTS, sorted.len(), label));
+    }
+    output
+}
+
+fn truncate_line(line: &str) -> String {
+    if line.len() <= MAX_LINE_LENGTH {
+        return line.to_string();
+    }
+    let mut cut = MAX_LINE_LENGTH;
+    while !line.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    format!("{}...", &line[..cut])
+}
+
+/// Byte offset of the first whole-word occurrence of `word` in `line`
+fn find_word(line: &str, word: &str) -> Option<usize> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    line.match_indices(word).map(|(i, _)| i).find(|&i| {
+        let before = line[..i].chars().next_back();
+        let after = line[i + word.len()..].chars().next();
+        !before.is_some_and(is_ident) && !after.is_some_and(is_ident)
+    })
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+/// Byte offset of a UTF-16 offset in `line`
+fn byte_offset(line: &str, utf16: usize) -> usize {
+    let mut units = 0;
+    for (i, c) in line.char_indices() {
+        if units >= utf16 {
+            return i;
+        }
+        units += c.len_utf16();
+    }
+    line.len()
+}
+
+/// Character index of a UTF-16 offset in `line`
+fn char_column(line: &str, utf16: usize) -> usize {
+    line[..byte_offset(line, utf16)].chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_word_and_offsets() {
+        assert_eq!(find_word("let foobar = foo();", "foo"), Some(13));
+        assert_eq!(find_word("foo_bar", "foo"), None);
+
+        let line = "let é = 𝔘 + x;";
+        let x = find_word(line, "x").unwrap();
+        // 𝔘 is two UTF-16 units
+        assert_eq!(utf16_len(&line[..x]), 13);
+        assert_eq!(byte_offset(line, 13), x);
+        assert_eq!(char_column(line, 13), 12);
+    }
+
+    #[test]
+    fn test_parse_locations_and_links() {
+        let single = json!({
+            "uri": "file:///src/a.rs",
+            "range": { "start": { "line": 3, "character": 0 }, "end": { "line": 3, "character": 1 } }
+        });
+        let links = json!([{
+            "targetUri": "file:///src/b.rs",
+            "targetRange": { "start": { "line": 0, "character": 0 }, "end": { "line": 9, "character": 0 } },
+            "targetSelectionRange": { "start": { "line": 2, "character": 4 }, "end": { "line": 2, "character": 7 } }
+        }]);
+
+        let parsed = parse_locations(&single);
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].path, PathBuf::from("/src/a.rs"));
+        assert_eq!(parsed[0].line, 3);
+
+        let parsed = parse_locations(&links);
+        assert_eq!(parsed[0].path, PathBuf::from("/src/b.rs"));
+        assert_eq!(parsed[0].line, 2);
+
+        assert!(parse_locations(&Value::Null).is_empty());
+    }
+}
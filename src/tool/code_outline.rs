@@ -0,0 +1,203 @@
+use super::base::{Tool, ToolContext, ToolError, ToolResult};
+use super::outline::{self, OutlineItem};
+use super::read::format_line;
+use super::text_file;
+use serde::Deserialize;
+use serde_json::json;
+use std::path::PathBuf;
+
+/// Outline lines are a declaration's first line, cut to this many characters
+const MAX_SIGNATURE_CHARS: usize = 200;
+
+/// Source shown for one symbol is capped like a read
+const MAX_SYMBOL_BYTES: usize = 50 * 1024;
+
+/// CodeOutline tool - lists a file's declarations, or shows the source of one of them
+pub struct CodeOutlineTool;
+
+#[derive(Debug, Deserialize)]
+struct CodeOutlineParams {
+    file_path: PathBuf,
+    #[serde(default)]
+    symbol: Option<String>,
+}
+
+impl CodeOutlineTool {
+    /// One outline line: the declaration's first line in read format, with its range
+    fn outline_line(item: &OutlineItem, lines: &[&str]) -> String {
+        let line = lines.get(item.line - 1).copied().unwrap_or("").trim_end();
+        let signature = match line.char_indices().nth(MAX_SIGNATURE_CHARS) {
+            Some((cut, _)) => format!("{}...", &line[..cut]),
+            None => line.to_string(),
+        };
+        let range = if item.start_line == item.end_line {
+            format!("line {}", item.start_line)
+        } else {
+            format!("lines {}-{}", item.start_line, item.end_line)
+        };
+        format!("{}  ({} {}, {})", format_line(item.line, &signature), item.kind, item.qualified_name, range)
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for CodeOutlineTool {
+    fn id(&self) -> &str {
+        "code_outline"
+    }
+
+    fn description(&self) -> &str {
+        "Show the structure of a source file (Rust, Python, TypeScript/JavaScript, Go) without \
+         reading all of it: modules, types, traits/interfaces, impl blocks, functions and methods, \
+         each with its declaration line and line range. \
+         Pass symbol (e.g. \"parse\", \"Parser::parse\" or \"Parser.parse\") to get just that item's \
+         source, including its doc comments. Line numbers match the read tool, so an item's range \
+         can also be read with offset=start-1 and limit=end-start+1."
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "file_path": {
+                    "type": "string",
+                    "description": "Path to the source file (absolute or relative)"
+                },
+                "symbol": {
+                    "type": "string",
+                    "description": "Name of one item to show the source of instead of the outline; may be qualified with its container"
+                }
+            },
+            "required": ["file_path"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let params: CodeOutlineParams = serde_json::from_value(params)
+            .map_err(|e| ToolError::InvalidParams(e.to_string()))?;
+
+        tracing::debug!(
+            working_dir = %ctx.working_dir.display(),
+            file_path = %params.file_path.display(),
+            symbol = ?params.symbol,
+            "tool code_outline start"
+        );
+
+        // 1. Resolve and read the file
+        let filepath = ctx.resolve_path(&params.file_path)?;
+        if !filepath.exists() {
+            return Err(ToolError::FileNotFound(filepath));
+        }
+        let bytes = tokio::fs::read(&filepath)
+            .await
+            .map_err(|e| ToolError::Other(e.into()))?;
+        let Some((content, _)) = text_file::decode(&bytes) else {
+            return Err(ToolError::BinaryFile(filepath));
+        };
+
+        // 2. Parse
+        let outline = outline::outline(&filepath, &content)?;
+        let lines: Vec<&str> = content.lines().collect();
+        let relative = filepath
+            .strip_prefix(&ctx.working_dir)
+            .unwrap_or(&filepath)
+            .display()
+            .to_string();
+
+        // 3a. Outline mode
+        let Some(symbol) = params.symbol else {
+            let mut output = if outline.items.is_empty() {
+                format!("No declarations found in {} ({} lines)\n", relative, lines.len())
+            } else {
+                format!(
+                    "Outline of {} ({}, {} lines, {} items):\n\n",
+                    relative,
+                    outline.language,
+                    lines.len(),
+                    outline.items.len()
+                )
+            };
+            for item in &outline.items {
+                output.push_str(&Self::outline_line(item, &lines));
+                output.push('\n');
+            }
+
+            tracing::debug!(
+                resolved_path = %filepath.display(),
+                items = outline.items.len(),
+                "tool code_outline done"
+            );
+
+            return Ok(ToolResult::new(format!("outline: {}", relative), output)
+                .with_metadata("language", json!(outline.language))
+                .with_metadata("total_lines", json!(lines.len()))
+                .with_metadata("items", json!(outline.items.len())));
+        };
+
+        // 3b. Symbol mode: the item's source, like a read of its line range
+        let matches = outline.find(&symbol);
+        let Some(item) = matches.first() else {
+            return Err(ToolError::InvalidParams(format!(
+                "No item named '{}' in {}. Call code_outline without symbol to list the items.",
+                symbol, relative
+            )));
+        };
+        // Counts as reading the file (the whole file was decoded), like read with offset/limit
+        ctx.file_states.record(&filepath, &bytes);
+
+        let start = item.doc_start_line;
+        let end = item.end_line.min(lines.len());
+        let mut output = String::new();
+        let mut truncated_at = None;
+        for line_num in start..=end {
+            let formatted = format_line(line_num, lines[line_num - 1]);
+            if output.len() + formatted.len() + 1 > MAX_SYMBOL_BYTES {
+                truncated_at = Some(line_num);
+                break;
+            }
+            output.push_str(&formatted);
+            output.push('\n');
+        }
+        output.push('\n');
+
+        match truncated_at {
+            Some(line_num) => output.push_str(&format!(
+                "(Output truncated at {} bytes. Use read with offset={} to see the rest of {} up to line {})",
+                MAX_SYMBOL_BYTES,
+                line_num - 1,
+                item.qualified_name,
+                end
+            )),
+            None => output.push_str(&format!(
+                "({} {}: lines {}-{} of {})",
+                item.kind, item.qualified_name, start, end, relative
+            )),
+        }
+        if matches.len() > 1 {
+            let others: Vec<String> = matches[1..]
+                .iter()
+                .map(|other| format!("{} {} at line {}", other.kind, other.qualified_name, other.line))
+                .collect();
+            output.push_str(&format!("\n(Other matches: {})", others.join(", ")));
+        }
+
+        tracing::debug!(
+            resolved_path = %filepath.display(),
+            symbol = %item.qualified_name,
+            start,
+            end,
+            "tool code_outline done"
+        );
+
+        Ok(ToolResult::new(format!("{}: {}", relative, item.qualified_name), output)
+            .with_metadata("language", json!(outline.language))
+            .with_metadata("symbol", json!(item.qualified_name))
+            .with_metadata("kind", json!(item.kind))
+            .with_metadata("start_line", json!(start))
+            .with_metadata("end_line", json!(end))
+            .with_metadata("matches", json!(matches.len())))
+    }
+}
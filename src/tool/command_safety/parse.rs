@@ -0,0 +1,435 @@
+//! Shell-word parser: splits a command line into the simple commands it would run
+//!
+//! Covers what matters for safety checks rather than full POSIX grammar: quoting and escapes,
+//! `;` `&&` `||` `&` `|` and newlines, subshells and braces, redirections (including here-docs),
+//! and `$(...)`, backtick and `<(...)` substitutions, whose commands are listed too. Words keep
+//! parameter expansions and substitutions as written (e.g. `$HOME/x`).
+
+/// One command with its arguments, as the shell would run it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimpleCommand {
+    /// Command name and arguments, with quotes removed
+    pub words: Vec<String>,
+    /// Leading `NAME=value` words
+    pub assignments: Vec<String>,
+    pub redirects: Vec<Redirect>,
+    /// Index of the command whose output is piped into this one
+    pub piped_from: Option<usize>,
+}
+
+impl SimpleCommand {
+    pub fn name(&self) -> Option<&str> {
+        self.words.first().map(String::as_str)
+    }
+
+    pub fn args(&self) -> &[String] {
+        self.words.get(1..).unwrap_or(&[])
+    }
+
+    /// The command as one line, for messages
+    pub fn display(&self) -> String {
+        self.words.join(" ")
+    }
+}
+
+/// A redirection such as `> out.txt` or `2>&1`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    /// The operator without its file descriptor (`>`, `>>`, `<`, `&>`, `>&`, `<<`, ...)
+    pub op: String,
+    pub target: String,
+}
+
+impl Redirect {
+    /// Whether the redirection writes to its target file
+    pub fn writes(&self) -> bool {
+        matches!(self.op.as_str(), ">" | ">>" | ">|" | "&>" | "&>>" | "<>")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseError {
+    #[error("unterminated {0}")]
+    Unterminated(&'static str),
+}
+
+/// Reserved words dropped at the start of a command (`if`, `do`, ...)
+const KEYWORDS: &[&str] = &[
+    "if", "then", "else", "elif", "fi", "do", "done", "while", "until", "!", "{", "}", "esac", "time",
+];
+
+/// Parse `input` into the simple commands it contains, in order of appearance
+pub fn parse(input: &str) -> Result<Vec<SimpleCommand>, ParseError> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+        commands: Vec::new(),
+    };
+    parser.parse_list(false)?;
+    Ok(parser.commands)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    commands: Vec<SimpleCommand>,
+}
+
+/// The command being assembled
+#[derive(Default)]
+struct Pending {
+    command: SimpleCommand,
+    word: Option<String>,
+    /// Operator waiting for its target word
+    redirect: Option<String>,
+    /// Here-doc delimiters whose bodies start after the next newline
+    heredocs: Vec<(String, bool)>,
+}
+
+impl Parser {
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    /// Parse commands until the input ends or, when `nested`, an unmatched `)`
+    fn parse_list(&mut self, nested: bool) -> Result<(), ParseError> {
+        let mut pending = Pending::default();
+        let mut depth = 0usize;
+        let mut pipe_from: Option<usize> = None;
+
+        while let Some(c) = self.peek(0) {
+            match c {
+                ' ' | '\t' => {
+                    self.pos += 1;
+                    self.finish_word(&mut pending);
+                }
+                '\n' | ';' => {
+                    self.pos += 1;
+                    self.finish_command(&mut pending, &mut pipe_from, false);
+                    if c == '\n' {
+                        self.skip_heredocs(&mut pending);
+                    }
+                }
+                '&' if self.peek(1) == Some('>') => {
+                    self.finish_word(&mut pending);
+                    let op = if self.peek(2) == Some('>') { "&>>" } else { "&>" };
+                    self.pos += op.len();
+                    pending.redirect = Some(op.to_string());
+                }
+                '&' | '|' => {
+                    let doubled = self.peek(1) == Some(c);
+                    let pipe = c == '|' && !doubled;
+                    self.pos += if doubled || (pipe && self.peek(1) == Some('&')) { 2 } else { 1 };
+                    self.finish_command(&mut pending, &mut pipe_from, pipe);
+                }
+                '(' => {
+                    self.pos += 1;
+                    depth += 1;
+                    self.finish_command(&mut pending, &mut pipe_from, false);
+                }
+                ')' => {
+                    if depth == 0 && nested {
+                        break;
+                    }
+                    self.pos += 1;
+                    depth = depth.saturating_sub(1);
+                    self.finish_command(&mut pending, &mut pipe_from, false);
+                }
+                '<' | '>' if self.peek(1) == Some('(') => {
+                    // Process substitution: its commands run too; the word stays as written
+                    let start = self.pos;
+                    self.pos += 2;
+                    self.parse_list(true)?;
+                    self.expect_close(')', "process substitution")?;
+                    let raw: String = self.chars[start..self.pos].iter().collect();
+                    pending.word.get_or_insert_with(String::new).push_str(&raw);
+                }
+                '<' | '>' => self.read_redirect(&mut pending),
+                '#' if pending.word.is_none() => {
+                    while self.peek(0).is_some_and(|c| c != '\n') {
+                        self.pos += 1;
+                    }
+                }
+                _ => {
+                    let word = self.read_word_part()?;
+                    pending.word.get_or_insert_with(String::new).push_str(&word);
+                }
+            }
+        }
+
+        if nested && self.peek(0).is_none() {
+            return Err(ParseError::Unterminated("command substitution"));
+        }
+        self.finish_command(&mut pending, &mut pipe_from, false);
+        Ok(())
+    }
+
+    fn expect_close(&mut self, close: char, what: &'static str) -> Result<(), ParseError> {
+        if self.peek(0) == Some(close) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(ParseError::Unterminated(what))
+        }
+    }
+
+    /// Read a redirection operator; its target is the next word
+    fn read_redirect(&mut self, pending: &mut Pending) {
+        // A word made only of digits right before the operator is its file descriptor
+        if pending
+            .word
+            .as_ref()
+            .is_some_and(|w| !w.is_empty() && w.chars().all(|c| c.is_ascii_digit()))
+        {
+            pending.word = None;
+        }
+        self.finish_word(pending);
+
+        let first = self.chars[self.pos];
+        let mut op = first.to_string();
+        self.pos += 1;
+        while let Some(next) = self.peek(0) {
+            let extends = matches!(
+                (op.as_str(), next),
+                (">", '>' | '|' | '&') | ("<", '<' | '&' | '>') | ("<<", '<' | '-')
+            );
+            if !extends {
+                break;
+            }
+            op.push(next);
+            self.pos += 1;
+        }
+        pending.redirect = Some(op);
+    }
+
+    /// Read one unquoted, quoted or substituted piece of a word
+    fn read_word_part(&mut self) -> Result<String, ParseError> {
+        let c = self.chars[self.pos];
+        self.pos += 1;
+        match c {
+            '\\' => match self.peek(0) {
+                Some('\n') => {
+                    self.pos += 1;
+                    Ok(String::new())
+                }
+                Some(escaped) => {
+                    self.pos += 1;
+                    Ok(escaped.to_string())
+                }
+                None => Ok(String::new()),
+            },
+            '\'' => {
+                let start = self.pos;
+                while self.peek(0).is_some_and(|c| c != '\'') {
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                self.expect_close('\'', "single quote")?;
+                Ok(text)
+            }
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match self.peek(0) {
+                        None => return Err(ParseError::Unterminated("double quote")),
+                        Some('"') => {
+                            self.pos += 1;
+                            return Ok(text);
+                        }
+                        Some('\\') => {
+                            self.pos += 1;
+                            match self.peek(0) {
+                                Some(e @ ('$' | '`' | '"' | '\\')) => {
+                                    self.pos += 1;
+                                    text.push(e);
+                                }
+                                Some('\n') => self.pos += 1,
+                                _ => text.push('\\'),
+                            }
+                        }
+                        Some('$' | '`') => text.push_str(&self.read_word_part()?),
+                        Some(other) => {
+                            self.pos += 1;
+                            text.push(other);
+                        }
+                    }
+                }
+            }
+            '$' => self.read_dollar(),
+            '`' => {
+                let start = self.pos;
+                while self.peek(0).is_some_and(|c| c != '`') {
+                    if self.peek(0) == Some('\\') {
+                        self.pos += 1;
+                    }
+                    self.pos += 1;
+                }
+                let inner: String = self.chars[start..self.pos.min(self.chars.len())].iter().collect();
+                self.expect_close('`', "backquote")?;
+                self.commands.extend(parse(&inner)?);
+                Ok(format!("`{}`", inner))
+            }
+            other => Ok(other.to_string()),
+        }
+    }
+
+    /// `$(...)`, `$((...))`, `${...}` or a plain `$`, kept as written
+    fn read_dollar(&mut self) -> Result<String, ParseError> {
+        let start = self.pos - 1;
+        match (self.peek(0), self.peek(1)) {
+            (Some('('), Some('(')) => {
+                self.skip_balanced('(', ')', "arithmetic expansion")?;
+            }
+            (Some('('), _) => {
+                self.pos += 1;
+                self.parse_list(true)?;
+                self.expect_close(')', "command substitution")?;
+            }
+            (Some('{'), _) => {
+                self.skip_balanced('{', '}', "parameter expansion")?;
+            }
+            _ => {}
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    /// Skip from an opening delimiter to its match (no commands inside)
+    fn skip_balanced(&mut self, open: char, close: char, what: &'static str) -> Result<(), ParseError> {
+        let mut depth = 0usize;
+        while let Some(c) = self.peek(0) {
+            self.pos += 1;
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(());
+                }
+            }
+        }
+        Err(ParseError::Unterminated(what))
+    }
+
+    /// Skip here-document bodies that start at the current line
+    fn skip_heredocs(&mut self, pending: &mut Pending) {
+        for (delimiter, strip_tabs) in std::mem::take(&mut pending.heredocs) {
+            while self.pos < self.chars.len() {
+                let start = self.pos;
+                while self.peek(0).is_some_and(|c| c != '\n') {
+                    self.pos += 1;
+                }
+                let line: String = self.chars[start..self.pos].iter().collect();
+                self.pos = (self.pos + 1).min(self.chars.len());
+                let line = if strip_tabs { line.trim_start_matches('\t') } else { &line };
+                if line == delimiter {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn finish_word(&mut self, pending: &mut Pending) {
+        let Some(word) = pending.word.take() else {
+            return;
+        };
+
+        if let Some(op) = pending.redirect.take() {
+            if op.starts_with("<<") && op != "<<<" {
+                pending.heredocs.push((word.clone(), op == "<<-"));
+            }
+            pending.command.redirects.push(Redirect { op, target: word });
+            return;
+        }
+
+        let command = &mut pending.command;
+        if command.words.is_empty() {
+            if is_assignment(&word) {
+                command.assignments.push(word);
+                return;
+            }
+            if command.assignments.is_empty() && KEYWORDS.contains(&word.as_str()) {
+                return;
+            }
+        }
+        command.words.push(word);
+    }
+
+    /// End the current command; `pipe` means the next one reads its output
+    fn finish_command(&mut self, pending: &mut Pending, pipe_from: &mut Option<usize>, pipe: bool) {
+        self.finish_word(pending);
+        let mut command = std::mem::take(&mut pending.command);
+        let has_content = !command.words.is_empty() || !command.redirects.is_empty();
+        if has_content {
+            command.piped_from = pipe_from.take();
+            self.commands.push(command);
+        }
+        *pipe_from = if pipe && has_content {
+            Some(self.commands.len() - 1)
+        } else {
+            None
+        };
+    }
+}
+
+fn is_assignment(word: &str) -> bool {
+    let Some((name, _)) = word.split_once('=') else {
+        return false;
+    };
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(commands: &[SimpleCommand]) -> Vec<String> {
+        commands.iter().map(SimpleCommand::display).collect()
+    }
+
+    #[test]
+    fn test_splits_lists_pipelines_and_substitutions() {
+        let commands = parse(
+            "cd / && ls -R | head; echo \"$(find / -name x)\" `id -u` >out.txt 2>&1 || FOO=1 xargs find /tmp",
+        )
+        .unwrap();
+        assert_eq!(
+            words(&commands),
+            vec![
+                "cd /",
+                "ls -R",
+                "head",
+                "find / -name x",
+                "id -u",
+                "echo $(find / -name x) `id -u`",
+                "xargs find /tmp",
+            ]
+        );
+        assert_eq!(commands[2].piped_from, Some(1));
+        assert_eq!(commands[1].piped_from, None);
+        assert_eq!(
+            commands[5].redirects,
+            vec![
+                Redirect { op: ">".to_string(), target: "out.txt".to_string() },
+                Redirect { op: ">&".to_string(), target: "1".to_string() },
+            ]
+        );
+        assert_eq!(commands[6].assignments, vec!["FOO=1"]);
+    }
+
+    #[test]
+    fn test_quotes_keywords_and_heredocs() {
+        let commands = parse(
+            "if [ -d 'a b' ]; then rm -rf \"/\"; fi\ncat <<EOF > f\nrm -rf /\nEOF\n(cd src && echo hi) # rm -rf /",
+        )
+        .unwrap();
+        assert_eq!(
+            words(&commands),
+            vec!["[ -d a b ]", "rm -rf /", "cat", "cd src", "echo hi"]
+        );
+
+        assert_eq!(parse("echo 'oops"), Err(ParseError::Unterminated("single quote")));
+        assert_eq!(parse("echo $(ls"), Err(ParseError::Unterminated("command substitution")));
+    }
+}
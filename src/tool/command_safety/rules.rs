@@ -0,0 +1,354 @@
+//! Built-in command safety rules
+
+use std::path::{Path, PathBuf};
+
+use super::{command_name, Decision, Rule, RuleContext, SimpleCommand};
+
+/// The rules the bash tool checks by default
+pub fn default_rules() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(SearchScopeRule),
+        Box::new(DestructiveCommandRule),
+        Box::new(PipeToShellRule),
+        Box::new(WriteOutsideRule),
+    ]
+}
+
+/// Arguments that aren't options, skipping the values of `value_flags` (everything after `--`)
+fn positionals<'a>(args: &'a [String], value_flags: &[&str]) -> Vec<&'a str> {
+    let mut positionals = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            positionals.extend(args.map(String::as_str));
+            break;
+        }
+        if arg.starts_with('-') && arg.len() > 1 {
+            if value_flags.contains(&arg.as_str()) {
+                args.next();
+            }
+            continue;
+        }
+        positionals.push(arg.as_str());
+    }
+    positionals
+}
+
+/// Whether a short option cluster (`-rf`) or one of the long options is present
+fn has_flag(args: &[String], short: &[char], long: &[&str]) -> bool {
+    args.iter().any(|arg| {
+        if let Some(name) = arg.strip_prefix("--") {
+            long.contains(&name)
+        } else if let Some(cluster) = arg.strip_prefix('-') {
+            cluster.chars().any(|c| short.contains(&c))
+        } else {
+            false
+        }
+    })
+}
+
+/// `/`, the home directory or a top-level directory such as `/usr` (or `/*`)
+fn is_system_path(path: &Path) -> bool {
+    path.parent().is_none_or(|parent| parent == Path::new("/"))
+        || dirs::home_dir().is_some_and(|home| home.starts_with(path))
+}
+
+/// Searches and recursive listings must stay inside the working directory: scanning `/` or
+/// the home directory is slow and pulls unrelated files into the conversation
+pub struct SearchScopeRule;
+
+impl SearchScopeRule {
+    /// Directories the command would walk, or `None` if it doesn't walk any
+    fn roots(command: &SimpleCommand) -> Option<Vec<&str>> {
+        let args = command.args();
+        let roots = match command_name(command)? {
+            "find" => {
+                let roots: Vec<&str> = args
+                    .iter()
+                    .skip_while(|arg| matches!(arg.as_str(), "-H" | "-L" | "-P"))
+                    .take_while(|arg| !arg.starts_with('-') && *arg != "(" && *arg != "!")
+                    .map(String::as_str)
+                    .collect();
+                roots
+            }
+            "ls" if has_flag(args, &['R'], &["recursive"]) => positionals(args, &[]),
+            "du" | "tree" => positionals(args, &["-d", "-L", "-I", "-P", "--max-depth"]),
+            "grep" | "egrep" | "fgrep" if has_flag(args, &['r', 'R'], &["recursive", "dereference-recursive"]) => {
+                Self::after_pattern(args, &["-A", "-B", "-C", "-m", "-e", "-f", "-d", "-D"])
+            }
+            "rgrep" => Self::after_pattern(args, &["-A", "-B", "-C", "-m", "-e", "-f"]),
+            "rg" | "ag" => Self::after_pattern(
+                args,
+                &["-A", "-B", "-C", "-m", "-e", "-f", "-g", "-t", "-T", "-j", "-M", "--glob", "--type"],
+            ),
+            "fd" | "fdfind" => positionals(args, &["-e", "-t", "-E", "-d", "--extension", "--type", "--exclude"])
+                .into_iter()
+                .skip(1)
+                .collect(),
+            _ => return None,
+        };
+        Some(if roots.is_empty() { vec!["."] } else { roots })
+    }
+
+    /// Paths of a grep-like command: positionals after the pattern, unless `-e`/`-f` gave it
+    fn after_pattern<'a>(args: &'a [String], value_flags: &[&str]) -> Vec<&'a str> {
+        let pattern_given = args
+            .iter()
+            .any(|arg| matches!(arg.as_str(), "-e" | "-f" | "--regexp" | "--file") || arg.starts_with("--regexp="));
+        let positionals = positionals(args, value_flags);
+        positionals.into_iter().skip(if pattern_given { 0 } else { 1 }).collect()
+    }
+}
+
+impl Rule for SearchScopeRule {
+    fn name(&self) -> &'static str {
+        "search-scope"
+    }
+
+    fn check(&self, command: &SimpleCommand, ctx: &RuleContext) -> Option<(Decision, String)> {
+        for root in Self::roots(command)? {
+            let Some(path) = ctx.resolve(root) else {
+                continue;
+            };
+            if ctx.is_allowed(&path) {
+                continue;
+            }
+            let problem = if is_system_path(&path) || path.starts_with("/home") {
+                "Command attempts to search outside the current working directory."
+            } else {
+                "Command attempts to search an absolute path outside the working directory."
+            };
+            return Some((
+                Decision::Deny,
+                format!(
+                    "{}\n\
+                     Working directory: {}\n\
+                     Detected: {} (searches {})\n\
+                     Suggestion: Use 'find .' (or Glob/Grep tools) to search within the project directory.",
+                    problem,
+                    ctx.working_dir.display(),
+                    command.display(),
+                    path.display()
+                ),
+            ));
+        }
+        None
+    }
+}
+
+/// Commands that destroy data or history: recursive deletes of system, home or project
+/// directories, force pushes, hard resets, disk formatting, and anything run with `sudo`.
+/// Changing mounts or entering namespaces can also undo the sandbox's read-only mounts.
+pub struct DestructiveCommandRule;
+
+impl DestructiveCommandRule {
+    fn check_rm(command: &SimpleCommand, ctx: &RuleContext) -> Option<(Decision, String)> {
+        let args = command.args();
+        if !has_flag(args, &['r', 'R'], &["recursive"]) {
+            return None;
+        }
+        if has_flag(args, &[], &["no-preserve-root"]) {
+            return Some((Decision::Deny, "rm --no-preserve-root can delete the whole filesystem.".to_string()));
+        }
+
+        let mut outcome = None;
+        for target in positionals(args, &[]) {
+            let Some(path) = ctx.resolve(target) else {
+                continue;
+            };
+            if is_system_path(&path) || (ctx.working_dir.starts_with(&path) && path != ctx.working_dir) {
+                return Some((
+                    Decision::Deny,
+                    format!("`{}` would recursively delete {}.", command.display(), path.display()),
+                ));
+            }
+            if path == ctx.working_dir {
+                outcome = Some((
+                    Decision::Ask,
+                    format!("`{}` would delete the whole working directory.", command.display()),
+                ));
+            } else if !ctx.is_allowed(&path) && outcome.is_none() {
+                outcome = Some((
+                    Decision::Ask,
+                    format!(
+                        "`{}` deletes {}, outside the working directory.",
+                        command.display(),
+                        path.display()
+                    ),
+                ));
+            }
+        }
+        outcome
+    }
+
+    fn check_git(command: &SimpleCommand) -> Option<(Decision, String)> {
+        // Skip global options (`git -C dir push`) to find the subcommand
+        let args = command.args();
+        let mut i = 0;
+        while let Some(arg) = args.get(i) {
+            if !arg.starts_with('-') {
+                break;
+            }
+            i += if matches!(arg.as_str(), "-C" | "-c") { 2 } else { 1 };
+        }
+        let subcommand = args.get(i)?.as_str();
+        let rest = &args[i + 1..];
+
+        let reason = match subcommand {
+            "push" if has_flag(rest, &['f'], &["force", "force-with-lease", "mirror"])
+                || rest.iter().any(|arg| arg.starts_with("--force-with-lease=") || arg.starts_with('+')) =>
+            {
+                "force-pushing rewrites history on the remote"
+            }
+            "reset" if has_flag(rest, &[], &["hard"]) => "a hard reset discards uncommitted changes",
+            "clean" if has_flag(rest, &['f'], &["force"]) => "git clean deletes untracked files",
+            _ => return None,
+        };
+        Some((Decision::Ask, format!("`{}`: {}.", command.display(), reason)))
+    }
+}
+
+impl Rule for DestructiveCommandRule {
+    fn name(&self) -> &'static str {
+        "destructive"
+    }
+
+    fn check(&self, command: &SimpleCommand, ctx: &RuleContext) -> Option<(Decision, String)> {
+        let name = command_name(command)?;
+        match name {
+            "rm" => Self::check_rm(command, ctx),
+            "git" => Self::check_git(command),
+            "chmod" | "chown" | "chgrp" if has_flag(command.args(), &['R'], &["recursive"]) => {
+                let path = positionals(command.args(), &["--reference"])
+                    .into_iter()
+                    .skip(1)
+                    .filter_map(|target| ctx.resolve(target))
+                    .find(|path| is_system_path(path))?;
+                Some((
+                    Decision::Deny,
+                    format!("`{}` would change permissions of everything under {}.", command.display(), path.display()),
+                ))
+            }
+            "dd" if command.args().iter().any(|arg| arg.starts_with("of=/dev/")) => Some((
+                Decision::Deny,
+                format!("`{}` writes directly to a device.", command.display()),
+            )),
+            _ if name.starts_with("mkfs") => Some((
+                Decision::Deny,
+                format!("`{}` formats a filesystem.", command.display()),
+            )),
+            "sudo" | "doas" | "su" => Some((
+                Decision::Ask,
+                format!("`{}` runs with elevated privileges.", command.display()),
+            )),
+            // A bare `mount` only lists what is mounted
+            "mount" if command.args().is_empty() => None,
+            "mount" | "umount" | "unshare" | "nsenter" | "chroot" | "pivot_root" => Some((
+                Decision::Ask,
+                format!("`{}` changes mounts or namespaces.", command.display()),
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Piping a download straight into a shell or interpreter runs code nobody has looked at
+pub struct PipeToShellRule;
+
+const DOWNLOADERS: &[&str] = &["curl", "wget"];
+const INTERPRETERS: &[&str] = &[
+    "sh", "bash", "zsh", "dash", "ksh", "fish", "python", "python3", "perl", "ruby", "node",
+];
+
+impl Rule for PipeToShellRule {
+    fn name(&self) -> &'static str {
+        "pipe-to-shell"
+    }
+
+    fn check(&self, command: &SimpleCommand, ctx: &RuleContext) -> Option<(Decision, String)> {
+        if !INTERPRETERS.contains(&command_name(command)?) {
+            return None;
+        }
+
+        let piped = ctx
+            .piped_from
+            .and_then(command_name)
+            .filter(|name| DOWNLOADERS.contains(name));
+        // `bash <(curl ...)` and `sh -c "$(curl ...)"` too
+        let substituted = DOWNLOADERS.iter().copied().find(|downloader| {
+            command.args().iter().any(|arg| {
+                ["<(", "$(", "`"]
+                    .iter()
+                    .any(|open| arg.contains(&format!("{}{}", open, downloader)))
+            })
+        });
+
+        let downloader = piped.or(substituted)?;
+        Some((
+            Decision::Ask,
+            format!(
+                "`{}` runs a script downloaded with {} without reviewing it first.",
+                command.display(),
+                downloader
+            ),
+        ))
+    }
+}
+
+/// Writes outside the working directory and temp roots (redirections, `tee`, `cp`/`mv`
+/// destinations) change files the user didn't ask about
+pub struct WriteOutsideRule;
+
+/// Device files that are fine to write to
+const HARMLESS_DEVICES: &[&str] = &["/dev/null", "/dev/stdout", "/dev/stderr", "/dev/tty"];
+
+impl WriteOutsideRule {
+    fn outside(ctx: &RuleContext, target: &str) -> Option<PathBuf> {
+        let path = ctx.resolve(target)?;
+        let harmless = HARMLESS_DEVICES.iter().any(|device| path == Path::new(device))
+            || ["/dev/fd", "/dev/tcp", "/dev/udp"].iter().any(|dir| path.starts_with(dir));
+        (!harmless && !ctx.is_allowed(&path)).then_some(path)
+    }
+}
+
+impl Rule for WriteOutsideRule {
+    fn name(&self) -> &'static str {
+        "write-outside"
+    }
+
+    fn check(&self, command: &SimpleCommand, ctx: &RuleContext) -> Option<(Decision, String)> {
+        let redirected = command
+            .redirects
+            .iter()
+            .filter(|redirect| redirect.writes())
+            .map(|redirect| redirect.target.as_str());
+        let args = command.args();
+        let written: Vec<&str> = match command_name(command) {
+            Some("tee") => positionals(args, &[]),
+            Some("cp" | "mv" | "install" | "ln") => {
+                match args.iter().position(|arg| arg == "-t" || arg == "--target-directory") {
+                    Some(flag) => args.get(flag + 1).map(String::as_str).into_iter().collect(),
+                    None => positionals(args, &["-S", "--suffix"]).last().copied().into_iter().collect(),
+                }
+            }
+            _ => Vec::new(),
+        };
+
+        let path = redirected
+            .chain(written)
+            .find_map(|target| Self::outside(ctx, target))?;
+        let decision = if path.starts_with("/dev/") && !path.starts_with("/dev/shm") {
+            Decision::Deny
+        } else {
+            Decision::Ask
+        };
+        Some((
+            decision,
+            format!(
+                "`{}` writes to {}, outside the working directory.",
+                command.display(),
+                path.display()
+            ),
+        ))
+    }
+}
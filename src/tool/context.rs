@@ -1,8 +1,14 @@
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use super::base::ToolContext;
-use crate::process::BackgroundShellManager;
+use tokio::sync::mpsc;
+
+use super::base::{ApprovalRequest, ToolContext, ToolProgress};
+use super::file_state::FileStateTracker;
+use super::output_limit::OutputLimiter;
+use crate::diagnostics::Diagnostics;
+use crate::process::{BackgroundShellManager, EnvFilter, ResourceLimits, Sandbox, ShellSession};
+use crate::subagent::SubagentEvent;
 
 impl ToolContext {
     /// Create a new tool context
@@ -19,6 +25,111 @@ impl ToolContext {
             agent: agent.into(),
             working_dir,
             shell_manager,
+            subagent_events: None,
+            file_states: FileStateTracker::new(),
+            diagnostics: None,
+            sandbox: None,
+            shell_session: None,
+            progress: None,
+            output_limiter: None,
+            env_filter: Arc::default(),
+            resource_limits: ResourceLimits::default(),
+            approvals: None,
+            trusted_project: false,
+        }
+    }
+
+    /// Forward progress of subagents launched from this context to `sink`
+    pub fn with_subagent_events(mut self, sink: mpsc::UnboundedSender<SubagentEvent>) -> Self {
+        self.subagent_events = Some(sink);
+        self
+    }
+
+    /// Share a session's record of read files (each new context otherwise starts empty)
+    pub fn with_file_states(mut self, file_states: FileStateTracker) -> Self {
+        self.file_states = file_states;
+        self
+    }
+
+    /// Run `diagnostics` after file modifications in subagents launched from this context
+    pub fn with_diagnostics(mut self, diagnostics: Arc<Diagnostics>) -> Self {
+        self.diagnostics = Some(diagnostics);
+        self
+    }
+
+    /// Run shell commands from this context in `sandbox`
+    pub fn with_sandbox(mut self, sandbox: Arc<Sandbox>) -> Self {
+        self.sandbox = Some(sandbox);
+        self
+    }
+
+    /// Run foreground `bash` commands in `session` instead of a fresh shell each time
+    pub fn with_shell_session(mut self, session: Arc<ShellSession>) -> Self {
+        self.shell_session = Some(session);
+        self
+    }
+
+    /// Send output of the running tool call to `sink` as it is produced
+    pub fn with_progress(mut self, sink: mpsc::UnboundedSender<ToolProgress>) -> Self {
+        self.progress = Some(sink);
+        self
+    }
+
+    /// Cut long tool results in subagents launched from this context with `limiter`
+    pub fn with_output_limiter(mut self, limiter: Arc<OutputLimiter>) -> Self {
+        self.output_limiter = Some(limiter);
+        self
+    }
+
+    /// Pass only the variables `filter` allows to shell commands run from this context
+    pub fn with_env_filter(mut self, filter: Arc<EnvFilter>) -> Self {
+        self.env_filter = filter;
+        self
+    }
+
+    /// Apply `limits` to shell commands run from this context
+    pub fn with_resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.resource_limits = limits;
+        self
+    }
+
+    /// Let tools run the project's own code (language servers) from this context
+    pub fn with_trusted_project(mut self, trusted: bool) -> Self {
+        self.trusted_project = trusted;
+        self
+    }
+
+    /// Ask the user through `sink` before running risky commands from this context
+    pub fn with_approvals(mut self, sink: mpsc::UnboundedSender<ApprovalRequest>) -> Self {
+        self.approvals = Some(sink);
+        self
+    }
+
+    /// Ask the user whether to run `command`; false without anyone to ask (e.g. in subagents)
+    /// or if the question goes unanswered
+    pub async fn request_approval(&self, command: &str, reasons: &str) -> bool {
+        let Some(sink) = &self.approvals else {
+            return false;
+        };
+        let (reply, answer) = tokio::sync::oneshot::channel();
+        let request = ApprovalRequest {
+            command: command.to_string(),
+            reasons: reasons.to_string(),
+            reply,
+        };
+        if sink.send(request).is_err() {
+            return false;
+        }
+        answer.await.unwrap_or(false)
+    }
+
+    /// Report a line of output while the tool runs (no-op if nobody listens)
+    pub fn report_progress(&self, stream: &'static str, line: impl Into<String>) {
+        if let Some(sink) = &self.progress {
+            let _ = sink.send(ToolProgress {
+                stream,
+                line: line.into(),
+            });
         }
     }
 
@@ -33,6 +144,17 @@ impl ToolContext {
             agent: "default".to_string(),
             working_dir,
             shell_manager: Arc::new(BackgroundShellManager::new()),
+            subagent_events: None,
+            file_states: FileStateTracker::new(),
+            diagnostics: None,
+            sandbox: None,
+            shell_session: None,
+            progress: None,
+            output_limiter: None,
+            env_filter: Arc::default(),
+            resource_limits: ResourceLimits::default(),
+            approvals: None,
+            trusted_project: false,
         })
     }
 }
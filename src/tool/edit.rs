@@ -1,4 +1,5 @@
 use super::base::{Tool, ToolContext, ToolError, ToolResult};
+use super::text_file;
 use serde::Deserialize;
 use serde_json::json;
 use similar::TextDiff;
@@ -18,7 +19,7 @@ struct EditParams {
 
 impl EditTool {
     /// Generate a unified diff between old and new content
-    fn generate_diff(filepath: &PathBuf, old: &str, new: &str) -> String {
+    pub(crate) fn generate_diff(filepath: &PathBuf, old: &str, new: &str) -> String {
         let diff = TextDiff::from_lines(old, new);
         let mut output = String::new();
 
@@ -38,7 +39,7 @@ impl EditTool {
     }
 
     /// Find all occurrences of a substring and return their positions
-    fn find_occurrences(content: &str, pattern: &str) -> Vec<usize> {
+    pub(crate) fn find_occurrences(content: &str, pattern: &str) -> Vec<usize> {
         content
             .match_indices(pattern)
             .map(|(pos, _)| pos)
@@ -55,7 +56,8 @@ impl Tool for EditTool {
     fn description(&self) -> &str {
         "Edit a file by replacing exact string matches. Performs precise string replacement \
          with uniqueness validation to prevent accidental modifications. Use replace_all=true \
-         to replace all occurrences, or provide enough context to make the match unique."
+         to replace all occurrences, or provide enough context to make the match unique. \
+         The file must have been read with the read tool first, and not changed since."
     }
 
     fn input_schema(&self) -> serde_json::Value {
@@ -89,8 +91,11 @@ impl Tool for EditTool {
         params: serde_json::Value,
         ctx: &ToolContext,
     ) -> Result<ToolResult, ToolError> {
-        let params: EditParams = serde_json::from_value(params)
+        let mut params: EditParams = serde_json::from_value(params)
             .map_err(|e| ToolError::InvalidParams(e.to_string()))?;
+        // Content is matched with LF line endings regardless of the file's own
+        params.old_string = text_file::normalize_newlines(&params.old_string);
+        params.new_string = text_file::normalize_newlines(&params.new_string);
 
         tracing::debug!(
             working_dir = %ctx.working_dir.display(),
@@ -114,27 +119,27 @@ impl Tool for EditTool {
             return Err(ToolError::FileNotFound(filepath));
         }
 
-        // 4. Read file content
-        let content = tokio::fs::read_to_string(&filepath)
+        // 4. Read and decode file content, remembering its encoding and line endings
+        let bytes = tokio::fs::read(&filepath)
             .await
-            .map_err(|e| {
-                // Check if it's a binary file error (UTF-8 validation failure)
-                let err_str = e.to_string().to_lowercase();
-                if err_str.contains("utf-8") || err_str.contains("utf8") {
-                    ToolError::BinaryFile(filepath.clone())
-                } else {
-                    ToolError::Other(e.into())
-                }
-            })?;
+            .map_err(|e| ToolError::Other(e.into()))?;
+        let Some((content, format)) = text_file::decode(&bytes) else {
+            return Err(ToolError::BinaryFile(filepath));
+        };
+
+        // 5. Refuse to edit a file the model hasn't seen in its current state, or one that
+        //    can't be written back without changing other lines
+        ctx.file_states.check(&filepath, &bytes)?;
+        format.check_editable(&filepath)?;
 
-        // 5. Find all occurrences of old_string
+        // 6. Find all occurrences of old_string
         let positions = Self::find_occurrences(&content, &params.old_string);
 
         if positions.is_empty() {
             return Err(ToolError::OldStringNotFound(params.old_string.clone()));
         }
 
-        // 6. Validate uniqueness if replace_all is false
+        // 7. Validate uniqueness if replace_all is false
         if !params.replace_all && positions.len() > 1 {
             return Err(ToolError::MultipleMatches {
                 count: positions.len(),
@@ -142,7 +147,7 @@ impl Tool for EditTool {
             });
         }
 
-        // 7. Perform replacement
+        // 8. Perform replacement
         let new_content = if params.replace_all {
             content.replace(&params.old_string, &params.new_string)
         } else {
@@ -150,15 +155,17 @@ impl Tool for EditTool {
             content.replacen(&params.old_string, &params.new_string, 1)
         };
 
-        // 8. Generate diff
+        // 9. Generate diff
         let diff = Self::generate_diff(&filepath, &content, &new_content);
 
-        // 9. Write the file
-        tokio::fs::write(&filepath, &new_content)
+        // 10. Write the file in its original format
+        let new_bytes = format.encode(&new_content)?;
+        tokio::fs::write(&filepath, &new_bytes)
             .await
             .map_err(|e| ToolError::Other(e.into()))?;
+        ctx.file_states.record_write(&filepath, &new_bytes);
 
-        // 10. Build output message
+        // 11. Build output message
         let replacement_count = positions.len();
         let mut output = format!(
             "Successfully edited: {}\n",
@@ -178,7 +185,7 @@ impl Tool for EditTool {
             "tool edit done"
         );
 
-        // 11. Return result
+        // 12. Return result
         Ok(ToolResult::new(filepath.to_string_lossy(), output)
             .with_metadata("filepath", json!(filepath.to_string_lossy()))
             .with_metadata(
@@ -187,7 +194,10 @@ impl Tool for EditTool {
             )
             .with_metadata("old_length", json!(params.old_string.len()))
             .with_metadata("new_length", json!(params.new_string.len()))
-            .with_metadata("replace_all", json!(params.replace_all)))
+            .with_metadata("replace_all", json!(params.replace_all))
+            .with_metadata("encoding", json!(format.encoding_name()))
+            .with_metadata("line_ending", json!(format.line_ending.as_str()))
+            .with_metadata("bom", json!(format.bom)))
     }
 }
 
@@ -198,13 +208,18 @@ mod tests {
     use std::io::Write;
 
     fn create_test_context() -> ToolContext {
-        ToolContext {
-            session_id: "test-session".to_string(),
-            message_id: "test-message".to_string(),
-            agent: "test-agent".to_string(),
-            working_dir: PathBuf::from("/tmp"),
-            shell_manager: std::sync::Arc::new(crate::process::BackgroundShellManager::new()),
-        }
+        ToolContext::new(
+            "test-session",
+            "test-message",
+            "test-agent",
+            PathBuf::from("/tmp"),
+            std::sync::Arc::new(crate::process::BackgroundShellManager::new()),
+        )
+    }
+
+    /// Record the file as read, as the read tool would
+    fn mark_read(ctx: &ToolContext, path: &std::path::Path) {
+        ctx.file_states.record(path, &std::fs::read(path).unwrap());
     }
 
     #[tokio::test]
@@ -223,6 +238,7 @@ mod tests {
         });
 
         let ctx = create_test_context();
+        mark_read(&ctx, file.path());
         let result = tool.execute(params, &ctx).await.unwrap();
 
         assert!(result.output.contains("Hi Earth"));
@@ -250,6 +266,7 @@ mod tests {
         });
 
         let ctx = create_test_context();
+        mark_read(&ctx, file.path());
         let result = tool.execute(params, &ctx).await.unwrap();
 
         assert_eq!(result.metadata.get("replacements").unwrap(), &json!(3));
@@ -274,6 +291,7 @@ mod tests {
         });
 
         let ctx = create_test_context();
+        mark_read(&ctx, file.path());
         let result = tool.execute(params, &ctx).await;
 
         assert!(result.is_err());
@@ -299,6 +317,7 @@ mod tests {
         });
 
         let ctx = create_test_context();
+        mark_read(&ctx, file.path());
         let result = tool.execute(params, &ctx).await;
 
         assert!(result.is_err());
@@ -341,6 +360,7 @@ mod tests {
         });
 
         let ctx = create_test_context();
+        mark_read(&ctx, file.path());
         let _result = tool.execute(params, &ctx).await.unwrap();
 
         let content = std::fs::read_to_string(file.path()).unwrap();
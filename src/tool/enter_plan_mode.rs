@@ -133,13 +133,13 @@ mod tests {
 
     fn create_test_context() -> (ToolContext, tempfile::TempDir) {
         let temp_dir = tempdir().unwrap();
-        let ctx = ToolContext {
-            session_id: "test-session".to_string(),
-            message_id: "test-message".to_string(),
-            agent: "test-agent".to_string(),
-            working_dir: temp_dir.path().to_path_buf(),
-            shell_manager: Arc::new(crate::process::BackgroundShellManager::new()),
-        };
+        let ctx = ToolContext::new(
+            "test-session",
+            "test-message",
+            "test-agent",
+            temp_dir.path().to_path_buf(),
+            Arc::new(crate::process::BackgroundShellManager::new()),
+        );
         (ctx, temp_dir)
     }
 
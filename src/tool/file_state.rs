@@ -0,0 +1,139 @@
+//! Tracks which files the model has read so edits don't clobber unseen changes
+
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use super::base::ToolError;
+
+/// What a file looked like when it was last read or written by a tool
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileState {
+    modified: Option<SystemTime>,
+    hash: u64,
+}
+
+/// Per-session record of files read by the model
+///
+/// `read` records every file it returns; `edit` and `write` refuse to modify an existing file
+/// unless it was read and is unchanged on disk since. Clones share the same record.
+///
+/// Files written by tools are also collected until taken, so the agent can run diagnostics on them.
+#[derive(Debug, Clone, Default)]
+pub struct FileStateTracker {
+    states: Arc<Mutex<HashMap<PathBuf, FileState>>>,
+    modified: Arc<Mutex<BTreeSet<PathBuf>>>,
+}
+
+impl FileStateTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `content` as the current state of `path` (after a read or a successful write)
+    pub fn record(&self, path: &Path, content: &[u8]) {
+        let state = FileState {
+            modified: modified_time(path),
+            hash: content_hash(content),
+        };
+        self.lock().insert(path.to_path_buf(), state);
+    }
+
+    /// Record `content` as written to `path` by a tool, marking the file as modified
+    pub fn record_write(&self, path: &Path, content: &[u8]) {
+        self.record(path, content);
+        self.modified
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path.to_path_buf());
+    }
+
+    /// Remove and return the files written since the last call, sorted by path
+    pub fn take_modified(&self) -> Vec<PathBuf> {
+        let mut modified = self.modified.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::take(&mut *modified).into_iter().collect()
+    }
+
+    /// Check whether `path` has been read
+    pub fn is_tracked(&self, path: &Path) -> bool {
+        self.lock().contains_key(path)
+    }
+
+    /// Verify that `path`, whose current content is `content`, was read and has not changed since
+    pub fn check(&self, path: &Path, content: &[u8]) -> Result<(), ToolError> {
+        let mut states = self.lock();
+        let Some(state) = states.get_mut(path) else {
+            return Err(ToolError::FileNotRead(path.to_path_buf()));
+        };
+
+        let modified = modified_time(path);
+        if modified.is_some() && modified == state.modified {
+            return Ok(());
+        }
+        // The timestamp moved; only a content change counts (e.g. `touch` is harmless)
+        if content_hash(content) != state.hash {
+            return Err(ToolError::FileModifiedSinceRead(path.to_path_buf()));
+        }
+        state.modified = modified;
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, FileState>> {
+        // A panic while holding the lock cannot leave the map inconsistent
+        self.states.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn content_hash(content: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_requires_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        std::fs::write(&path, "one").unwrap();
+
+        let tracker = FileStateTracker::new();
+        assert!(matches!(
+            tracker.check(&path, b"one"),
+            Err(ToolError::FileNotRead(_))
+        ));
+
+        tracker.record(&path, b"one");
+        assert!(tracker.check(&path, b"one").is_ok());
+    }
+
+    #[test]
+    fn test_check_detects_content_change_but_not_touch() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        std::fs::write(&path, "one").unwrap();
+
+        let tracker = FileStateTracker::new();
+        tracker.record(&path, b"one");
+
+        let later = SystemTime::now() + std::time::Duration::from_secs(5);
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(later).unwrap();
+        assert!(tracker.check(&path, b"one").is_ok());
+
+        file.set_modified(later + std::time::Duration::from_secs(5)).unwrap();
+        assert!(matches!(
+            tracker.check(&path, b"two"),
+            Err(ToolError::FileModifiedSinceRead(_))
+        ));
+    }
+}
@@ -0,0 +1,250 @@
+use super::{check_rev, relative, run_git, MAX_OUTPUT_BYTES};
+use crate::tool::base::{Tool, ToolContext, ToolError, ToolResult};
+use crate::tool::read::format_line;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// Lines blamed per call
+const MAX_BLAME_LINES: usize = 500;
+
+/// GitBlame tool - who last changed each line of a file, for a line range
+pub struct GitBlameTool;
+
+#[derive(Debug, Deserialize)]
+struct GitBlameParams {
+    file_path: PathBuf,
+    #[serde(default)]
+    start_line: Option<usize>,
+    #[serde(default)]
+    end_line: Option<usize>,
+    #[serde(default)]
+    rev: Option<String>,
+}
+
+/// One line of `git blame --line-porcelain`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameLine {
+    pub hash: String,
+    pub line: usize,
+    pub author: String,
+    /// Author time, seconds since the epoch
+    pub time: i64,
+    pub summary: String,
+    pub text: String,
+}
+
+impl BlameLine {
+    pub fn parse_all(text: &str) -> Vec<BlameLine> {
+        let mut lines = Vec::new();
+        let mut current: Option<BlameLine> = None;
+        for raw in text.lines() {
+            if let Some(content) = raw.strip_prefix('\t') {
+                if let Some(mut line) = current.take() {
+                    line.text = content.to_string();
+                    lines.push(line);
+                }
+                continue;
+            }
+            match current.as_mut() {
+                None => {
+                    // Header: <hash> <original line> <final line> [<lines in group>]
+                    let mut parts = raw.split(' ');
+                    let hash = parts.next().unwrap_or_default();
+                    let line = parts.nth(1).and_then(|n| n.parse().ok());
+                    if let (true, Some(line)) = (hash.len() >= 40, line) {
+                        current = Some(BlameLine {
+                            hash: hash.to_string(),
+                            line,
+                            author: String::new(),
+                            time: 0,
+                            summary: String::new(),
+                            text: String::new(),
+                        });
+                    }
+                }
+                Some(line) => {
+                    if let Some(author) = raw.strip_prefix("author ") {
+                        line.author = author.to_string();
+                    } else if let Some(time) = raw.strip_prefix("author-time ") {
+                        line.time = time.parse().unwrap_or_default();
+                    } else if let Some(summary) = raw.strip_prefix("summary ") {
+                        line.summary = summary.to_string();
+                    }
+                }
+            }
+        }
+        lines
+    }
+
+    /// `abc12345 Author      2026-10-18    12→text`
+    pub fn format(&self) -> String {
+        let date = chrono::DateTime::from_timestamp(self.time, 0)
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        let author: String = self.author.chars().take(16).collect();
+        format!(
+            "{} {:<16} {} {}",
+            &self.hash[..8],
+            author,
+            date,
+            format_line(self.line, &self.text)
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for GitBlameTool {
+    fn id(&self) -> &str {
+        "git_blame"
+    }
+
+    fn description(&self) -> &str {
+        "Show who last changed each line of a file: commit, author and date next to the line, \
+         with line numbers as in read. Give start_line/end_line to blame a range (at most 500 \
+         lines per call) and rev to blame the file as of an older commit. The commits involved \
+         are listed with their subjects; use git_show to see one."
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "file_path": {
+                    "type": "string",
+                    "description": "Path to the file (absolute or relative)"
+                },
+                "start_line": {
+                    "type": "integer",
+                    "description": "First line to blame, 1-based (default: 1)"
+                },
+                "end_line": {
+                    "type": "integer",
+                    "description": "Last line to blame, inclusive (default: start_line + 499 or the end of the file)"
+                },
+                "rev": {
+                    "type": "string",
+                    "description": "Blame the file as of this commit (default: the working tree)"
+                }
+            },
+            "required": ["file_path"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let params: GitBlameParams = serde_json::from_value(params)
+            .map_err(|e| ToolError::InvalidParams(e.to_string()))?;
+
+        tracing::debug!(
+            file_path = %params.file_path.display(),
+            start_line = ?params.start_line,
+            end_line = ?params.end_line,
+            rev = ?params.rev,
+            "tool git_blame start"
+        );
+
+        let filepath = ctx.resolve_path(&params.file_path)?;
+        if params.rev.is_none() && !filepath.exists() {
+            return Err(ToolError::FileNotFound(filepath));
+        }
+
+        let start = params.start_line.unwrap_or(1).max(1);
+        let end = match params.end_line {
+            Some(end) if end < start => {
+                return Err(ToolError::InvalidParams(format!(
+                    "end_line ({}) is before start_line ({})",
+                    end, start
+                )))
+            }
+            Some(end) => end.min(start + MAX_BLAME_LINES - 1),
+            None => start + MAX_BLAME_LINES - 1,
+        };
+
+        let mut args = vec![
+            "blame".to_string(),
+            "--no-textconv".to_string(),
+            "--line-porcelain".to_string(),
+        ];
+        // One line more than shown, to tell whether the file goes on (git stops at its end)
+        args.push(format!("-L{},{}", start, end + 1));
+        if let Some(rev) = &params.rev {
+            args.push(check_rev(rev)?.to_string());
+        }
+        args.push("--".to_string());
+        args.push(filepath.display().to_string());
+
+        let blamed = BlameLine::parse_all(&run_git(ctx, &args).await?);
+        let name = relative(ctx, &filepath);
+
+        let mut output = String::new();
+        let mut shown = 0;
+        for line in blamed.iter().take(end + 1 - start) {
+            let formatted = line.format();
+            if output.len() + formatted.len() + 1 > MAX_OUTPUT_BYTES {
+                break;
+            }
+            output.push_str(&formatted);
+            output.push('\n');
+            shown += 1;
+        }
+
+        let last = blamed[..shown].last().map_or(start, |l| l.line);
+        if shown < blamed.len() && params.end_line.is_none_or(|e| e > last) {
+            output.push_str(&format!(
+                "\n(Showing lines {}-{}. Call again with start_line={} to see more.)\n",
+                start,
+                last,
+                last + 1
+            ));
+        }
+
+        // Each commit once, in order of first appearance
+        let mut seen = HashSet::new();
+        let commits: Vec<&BlameLine> = blamed[..shown]
+            .iter()
+            .filter(|l| seen.insert(l.hash.as_str()))
+            .collect();
+        if !commits.is_empty() {
+            output.push_str("\nCommits:\n");
+            for commit in &commits {
+                output.push_str(&format!("  {} {}\n", &commit.hash[..8], commit.summary));
+            }
+        }
+
+        tracing::debug!(lines = shown, commits = commits.len(), "tool git_blame done");
+
+        Ok(ToolResult::new(format!("git blame {}:{}-{}", name, start, last), output)
+            .with_metadata("start_line", json!(start))
+            .with_metadata("end_line", json!(last))
+            .with_metadata("lines", json!(shown))
+            .with_metadata("commits", json!(commits.len())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line_porcelain() {
+        let hash = "a".repeat(40);
+        let text = format!(
+            "{hash} 1 1 2\nauthor Ada\nauthor-mail <ada@example.com>\nauthor-time 1760000000\n\
+             summary Add main\nfilename src/main.rs\n\tfn main() {{\n\
+             {hash} 2 2\nauthor Ada\nauthor-time 1760000000\nsummary Add main\nfilename src/main.rs\n\t}}\n"
+        );
+        let lines = BlameLine::parse_all(&text);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].line, 1);
+        assert_eq!(lines[0].author, "Ada");
+        assert_eq!(lines[0].summary, "Add main");
+        assert_eq!(lines[1].text, "}");
+        assert!(lines[0].format().starts_with("aaaaaaaa Ada              2025-10-09 "));
+        assert!(lines[0].format().ends_with("1\u{2192}fn main() {"));
+    }
+}
@@ -0,0 +1,150 @@
+use super::{bound, check_rev, format_stats, parse_numstat, pathspec, run_git, MAX_OUTPUT_BYTES};
+use crate::tool::base::{Tool, ToolContext, ToolError, ToolResult};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::PathBuf;
+
+/// GitDiff tool - per-file stats and the patch for unstaged, staged or committed changes
+pub struct GitDiffTool;
+
+#[derive(Debug, Deserialize)]
+struct GitDiffParams {
+    #[serde(default)]
+    range: Option<String>,
+    #[serde(default)]
+    staged: bool,
+    #[serde(default)]
+    path: Option<PathBuf>,
+    #[serde(default)]
+    context_lines: Option<u32>,
+    #[serde(default)]
+    stat_only: bool,
+}
+
+#[async_trait::async_trait]
+impl Tool for GitDiffTool {
+    fn id(&self) -> &str {
+        "git_diff"
+    }
+
+    fn description(&self) -> &str {
+        "Show a git diff with per-file stats (lines added/removed) followed by the patch. \
+         By default shows unstaged changes; staged=true shows what would be committed; \
+         range compares commits (e.g. \"HEAD~3\", \"main..feature\", \"main...HEAD\"). \
+         Optionally limited to a path. Long patches are cut; use stat_only or path to narrow down."
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "range": {
+                    "type": "string",
+                    "description": "Commit or range to diff against (default: the index, i.e. unstaged changes)"
+                },
+                "staged": {
+                    "type": "boolean",
+                    "description": "Diff the index instead of the working tree (staged changes)",
+                    "default": false
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Only diff this file or directory"
+                },
+                "context_lines": {
+                    "type": "integer",
+                    "description": "Lines of context around each change (default: 3)"
+                },
+                "stat_only": {
+                    "type": "boolean",
+                    "description": "Only return the per-file stats, not the patch",
+                    "default": false
+                }
+            },
+            "required": []
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let params: GitDiffParams = serde_json::from_value(params)
+            .map_err(|e| ToolError::InvalidParams(e.to_string()))?;
+
+        tracing::debug!(
+            range = ?params.range,
+            staged = params.staged,
+            path = ?params.path,
+            "tool git_diff start"
+        );
+
+        let mut selection = Vec::new();
+        if params.staged {
+            selection.push("--cached".to_string());
+        }
+        if let Some(range) = &params.range {
+            selection.push(check_rev(range)?.to_string());
+        }
+        selection.extend(pathspec(ctx, params.path.as_ref())?);
+
+        let mut numstat_args = vec![
+            "diff".to_string(),
+            "--no-textconv".to_string(),
+            "--numstat".to_string(),
+            "-z".to_string(),
+        ];
+        numstat_args.extend(selection.iter().cloned());
+        let stats = parse_numstat(&run_git(ctx, &numstat_args).await?);
+
+        let what = match (&params.range, params.staged) {
+            (Some(range), true) => format!("staged changes against {}", range),
+            (Some(range), false) => range.clone(),
+            (None, true) => "staged changes".to_string(),
+            (None, false) => "unstaged changes".to_string(),
+        };
+        if stats.is_empty() {
+            return Ok(ToolResult::new(format!("git diff: {}", what), format!("No differences ({})\n", what))
+                .with_metadata("files_changed", json!(0))
+                .with_metadata("insertions", json!(0))
+                .with_metadata("deletions", json!(0)));
+        }
+
+        let mut output = format_stats(&stats);
+        let mut truncated = false;
+        if !params.stat_only {
+            let mut patch_args = vec![
+                "diff".to_string(),
+                "--no-ext-diff".to_string(),
+                "--no-textconv".to_string(),
+                format!("--unified={}", params.context_lines.unwrap_or(3)),
+            ];
+            patch_args.extend(selection);
+            let patch = run_git(ctx, &patch_args).await?;
+            let (shown, cut) = bound(&patch, MAX_OUTPUT_BYTES);
+            truncated = cut;
+
+            output.push('\n');
+            output.push_str(shown);
+            if truncated {
+                output.push_str(&format!(
+                    "\n(Patch truncated at {} of {} bytes. Pass path to see one file's changes.)\n",
+                    shown.len(),
+                    patch.len()
+                ));
+            }
+        }
+
+        let insertions: u64 = stats.iter().filter_map(|s| s.added).sum();
+        let deletions: u64 = stats.iter().filter_map(|s| s.removed).sum();
+        tracing::debug!(files = stats.len(), insertions, deletions, truncated, "tool git_diff done");
+
+        Ok(ToolResult::new(format!("git diff: {}", what), output)
+            .with_metadata("files_changed", json!(stats.len()))
+            .with_metadata("insertions", json!(insertions))
+            .with_metadata("deletions", json!(deletions))
+            .with_metadata("files", json!(stats.iter().map(|s| &s.path).collect::<Vec<_>>()))
+            .with_metadata("truncated", json!(truncated)))
+    }
+}
@@ -0,0 +1,219 @@
+use super::{check_rev, pathspec, run_git, MAX_OUTPUT_BYTES};
+use crate::tool::base::{Tool, ToolContext, ToolError, ToolResult};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::PathBuf;
+
+/// Commits listed when no limit is given, and the most that can be asked for
+const DEFAULT_MAX_COUNT: usize = 20;
+const MAX_COUNT_CEILING: usize = 500;
+
+/// Fields of one commit, separated by 0x1f, commits by 0x1e
+pub(crate) const COMMIT_FORMAT: &str = "--format=%H%x1f%h%x1f%an%x1f%ae%x1f%aI%x1f%s%x1e";
+
+/// GitLog tool - commit history with author, date, path and message filters
+pub struct GitLogTool;
+
+#[derive(Debug, Deserialize)]
+struct GitLogParams {
+    #[serde(default)]
+    range: Option<String>,
+    #[serde(default)]
+    path: Option<PathBuf>,
+    #[serde(default)]
+    author: Option<String>,
+    #[serde(default)]
+    since: Option<String>,
+    #[serde(default)]
+    until: Option<String>,
+    #[serde(default)]
+    grep: Option<String>,
+    #[serde(default)]
+    max_count: Option<usize>,
+}
+
+/// One commit of a log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Commit {
+    pub hash: String,
+    pub short_hash: String,
+    pub author: String,
+    pub email: String,
+    /// Author date, ISO 8601
+    pub date: String,
+    pub subject: String,
+}
+
+impl Commit {
+    /// Parse output produced with [`COMMIT_FORMAT`]
+    pub fn parse_all(text: &str) -> Vec<Commit> {
+        text.split('\x1e')
+            .filter_map(|record| {
+                let fields: Vec<&str> = record.trim_start_matches('\n').split('\x1f').collect();
+                let [hash, short_hash, author, email, date, subject] = fields[..] else {
+                    return None;
+                };
+                Some(Commit {
+                    hash: hash.to_string(),
+                    short_hash: short_hash.to_string(),
+                    author: author.to_string(),
+                    email: email.to_string(),
+                    date: date.to_string(),
+                    subject: subject.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// `abc1234 2026-10-18 Author Name: subject`
+    pub fn line(&self) -> String {
+        format!(
+            "{} {} {}: {}",
+            self.short_hash,
+            self.date.get(..10).unwrap_or(&self.date),
+            self.author,
+            self.subject
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for GitLogTool {
+    fn id(&self) -> &str {
+        "git_log"
+    }
+
+    fn description(&self) -> &str {
+        "List git commits, newest first, one per line: short hash, date, author and subject. \
+         Filter by range (e.g. \"main..HEAD\"), path, author, date (since/until, e.g. \"2 weeks ago\" \
+         or \"2024-01-31\") and message regex (grep). Use git_show for a commit's full message and diff."
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "range": {
+                    "type": "string",
+                    "description": "Revision or range to list (default: HEAD)"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Only commits touching this file or directory"
+                },
+                "author": {
+                    "type": "string",
+                    "description": "Only commits whose author name or email matches this pattern"
+                },
+                "since": {
+                    "type": "string",
+                    "description": "Only commits after this date"
+                },
+                "until": {
+                    "type": "string",
+                    "description": "Only commits before this date"
+                },
+                "grep": {
+                    "type": "string",
+                    "description": "Only commits whose message matches this regex"
+                },
+                "max_count": {
+                    "type": "integer",
+                    "description": "Most commits to list (default: 20, max: 500)"
+                }
+            },
+            "required": []
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let params: GitLogParams = serde_json::from_value(params)
+            .map_err(|e| ToolError::InvalidParams(e.to_string()))?;
+
+        tracing::debug!(
+            range = ?params.range,
+            path = ?params.path,
+            author = ?params.author,
+            "tool git_log start"
+        );
+
+        let max_count = params
+            .max_count
+            .unwrap_or(DEFAULT_MAX_COUNT)
+            .clamp(1, MAX_COUNT_CEILING);
+        let mut args = vec![
+            "log".to_string(),
+            COMMIT_FORMAT.to_string(),
+            // One more than asked for, to tell whether there are more
+            format!("--max-count={}", max_count + 1),
+        ];
+        if let Some(author) = &params.author {
+            args.push(format!("--author={}", author));
+        }
+        if let Some(since) = &params.since {
+            args.push(format!("--since={}", since));
+        }
+        if let Some(until) = &params.until {
+            args.push(format!("--until={}", until));
+        }
+        if let Some(grep) = &params.grep {
+            args.push(format!("--grep={}", grep));
+            args.push("--extended-regexp".to_string());
+        }
+        if let Some(range) = &params.range {
+            args.push(check_rev(range)?.to_string());
+        }
+        args.extend(pathspec(ctx, params.path.as_ref())?);
+
+        let mut commits = Commit::parse_all(&run_git(ctx, &args).await?);
+        let more = commits.len() > max_count;
+        commits.truncate(max_count);
+
+        let mut output = String::new();
+        let mut shown = 0;
+        for commit in &commits {
+            let line = commit.line();
+            if output.len() + line.len() + 1 > MAX_OUTPUT_BYTES {
+                break;
+            }
+            output.push_str(&line);
+            output.push('\n');
+            shown += 1;
+        }
+        if commits.is_empty() {
+            output.push_str("No commits found\n");
+        } else if shown < commits.len() || more {
+            output.push_str(&format!(
+                "\n(Showing the first {} commits; narrow the filters or use range to see older ones)\n",
+                shown
+            ));
+        }
+
+        tracing::debug!(commits = shown, more, "tool git_log done");
+
+        Ok(ToolResult::new(format!("git log ({} commits)", shown), output)
+            .with_metadata("count", json!(shown))
+            .with_metadata("has_more", json!(more || shown < commits.len()))
+            .with_metadata("commits", json!(&commits[..shown])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commits() {
+        let text = "aaaa\x1fa\x1fAda\x1fada@example.com\x1f2026-10-01T12:00:00+02:00\x1fFirst\x1e\n\
+                    bbbb\x1fb\x1fBob\x1fbob@example.com\x1f2026-10-02T08:30:00+00:00\x1fSecond: with colon\x1e\n";
+        let commits = Commit::parse_all(text);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].author, "Ada");
+        assert_eq!(commits[1].subject, "Second: with colon");
+        assert_eq!(commits[0].line(), "a 2026-10-01 Ada: First");
+    }
+}
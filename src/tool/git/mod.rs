@@ -0,0 +1,209 @@
+//! Git tools: structured status, diff, log, blame and show
+//!
+//! Each tool runs `git` in the working directory and parses its machine-readable output
+//! (porcelain, numstat, custom log formats) into a compact summary, so the model does not have
+//! to interpret git's human output. Paths go through [`ToolContext::resolve_path`] and results
+//! are cut to [`MAX_OUTPUT_BYTES`].
+
+pub mod blame;
+pub mod diff;
+pub mod log;
+pub mod show;
+pub mod status;
+
+use super::base::{ToolContext, ToolError};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+/// Longest a git command may run
+const GIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Diffs, logs and blames are cut to this many bytes, below the `[tool_output]` default so the
+/// tools can say how to narrow down instead of the output being cut at an arbitrary point
+pub const MAX_OUTPUT_BYTES: usize = 24 * 1024;
+
+/// Run `git <args>` in the working directory and return its stdout. A non-zero exit is a
+/// `CommandFailed` with git's own message.
+pub(crate) async fn run_git(ctx: &ToolContext, args: &[String]) -> Result<String, ToolError> {
+    tracing::debug!(args = ?args, "running git");
+
+    let mut command = tokio::process::Command::new("git");
+    ctx.env_filter.apply(&mut command);
+    command
+        .args(["-c", "color.ui=false", "-c", "core.quotepath=false"])
+        // A repository's fsmonitor hook is a program it chooses; don't let it run
+        .args(["-c", "core.fsmonitor=false"])
+        .args(args)
+        .current_dir(&ctx.working_dir)
+        // Read-only commands should not take the index lock or ask for credentials
+        .env("GIT_OPTIONAL_LOCKS", "0")
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let child = command
+        .spawn()
+        .map_err(|e| ToolError::Other(anyhow::anyhow!("failed to run git: {}", e)))?;
+
+    let output = tokio::time::timeout(GIT_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| ToolError::Timeout(GIT_TIMEOUT.as_millis() as u64))?
+        .map_err(|e| ToolError::Other(e.into()))?;
+
+    if !output.status.success() {
+        return Err(ToolError::CommandFailed {
+            code: output.status.code(),
+            message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// A revision or range given by the model, checked so it cannot be taken for an option
+pub(crate) fn check_rev(rev: &str) -> Result<&str, ToolError> {
+    let rev = rev.trim();
+    if rev.is_empty() || rev.starts_with('-') {
+        return Err(ToolError::InvalidParams(format!(
+            "Invalid revision '{}': give a commit, branch, tag or range such as HEAD~3..HEAD",
+            rev
+        )));
+    }
+    Ok(rev)
+}
+
+/// Resolve an optional path parameter for use after `--`
+pub(crate) fn pathspec(ctx: &ToolContext, path: Option<&PathBuf>) -> Result<Vec<String>, ToolError> {
+    match path {
+        Some(path) => {
+            let resolved = ctx.resolve_path(path)?;
+            Ok(vec!["--".to_string(), resolved.display().to_string()])
+        }
+        None => Ok(Vec::new()),
+    }
+}
+
+/// `path` relative to the working directory, for display
+pub(crate) fn relative(ctx: &ToolContext, path: &Path) -> String {
+    path.strip_prefix(&ctx.working_dir)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// Lines added and removed in one file (`None` for binary files)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStat {
+    pub path: String,
+    /// Previous path of a renamed or copied file
+    pub old_path: Option<String>,
+    pub added: Option<u64>,
+    pub removed: Option<u64>,
+}
+
+/// Parse `--numstat -z` output
+pub(crate) fn parse_numstat(text: &str) -> Vec<FileStat> {
+    let mut stats = Vec::new();
+    let mut fields = text.split('\0');
+    while let Some(entry) = fields.next() {
+        let entry = entry.trim_start_matches('\n');
+        if entry.is_empty() {
+            continue;
+        }
+        let mut parts = entry.splitn(3, '\t');
+        let (Some(added), Some(removed), Some(path)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        // A rename has an empty path, followed by the old and new paths
+        let (path, old_path) = if path.is_empty() {
+            let old = fields.next().unwrap_or_default().to_string();
+            let new = fields.next().unwrap_or_default().to_string();
+            (new, Some(old))
+        } else {
+            (path.to_string(), None)
+        };
+        stats.push(FileStat {
+            path,
+            old_path,
+            added: added.parse().ok(),
+            removed: removed.parse().ok(),
+        });
+    }
+    stats
+}
+
+/// Totals line and one line per file, like `git diff --stat`
+pub(crate) fn format_stats(stats: &[FileStat]) -> String {
+    let added: u64 = stats.iter().filter_map(|s| s.added).sum();
+    let removed: u64 = stats.iter().filter_map(|s| s.removed).sum();
+    let mut output = format!(
+        "{} files changed, {} insertions(+), {} deletions(-)\n",
+        stats.len(),
+        added,
+        removed
+    );
+    for stat in stats {
+        let name = match &stat.old_path {
+            Some(old) => format!("{} -> {}", old, stat.path),
+            None => stat.path.clone(),
+        };
+        match (stat.added, stat.removed) {
+            (Some(a), Some(r)) => output.push_str(&format!("  {}  +{} -{}\n", name, a, r)),
+            _ => output.push_str(&format!("  {}  (binary)\n", name)),
+        }
+    }
+    output
+}
+
+/// `text` cut to `max` bytes at a line boundary; the flag says whether anything was cut
+pub(crate) fn bound(text: &str, max: usize) -> (&str, bool) {
+    if text.len() <= max {
+        return (text, false);
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    if let Some(newline) = text[..end].rfind('\n') {
+        end = newline + 1;
+    }
+    (&text[..end], true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_numstat() {
+        let text = "3\t1\tsrc/a.rs\0-\t-\timg.png\0\
+                    0\t0\t\0old.rs\0new.rs\0";
+        let stats = parse_numstat(text);
+        assert_eq!(stats.len(), 3);
+        assert_eq!(stats[0].path, "src/a.rs");
+        assert_eq!((stats[0].added, stats[0].removed), (Some(3), Some(1)));
+        assert_eq!(stats[1].added, None);
+        assert_eq!(stats[2].path, "new.rs");
+        assert_eq!(stats[2].old_path.as_deref(), Some("old.rs"));
+
+        let formatted = format_stats(&stats);
+        assert!(formatted.starts_with("3 files changed, 3 insertions(+), 1 deletions(-)\n"));
+        assert!(formatted.contains("  img.png  (binary)\n"));
+        assert!(formatted.contains("  old.rs -> new.rs  +0 -0\n"));
+    }
+
+    #[test]
+    fn test_check_rev_rejects_options() {
+        assert!(check_rev("HEAD~2..HEAD").is_ok());
+        assert!(check_rev("--output=/tmp/x").is_err());
+        assert!(check_rev(" ").is_err());
+    }
+
+    #[test]
+    fn test_bound_cuts_at_line_boundary() {
+        assert_eq!(bound("abc\n", 10), ("abc\n", false));
+        assert_eq!(bound("one\ntwo\nthree\n", 10), ("one\ntwo\n", true));
+    }
+}
@@ -0,0 +1,152 @@
+use super::{bound, check_rev, format_stats, parse_numstat, pathspec, run_git, MAX_OUTPUT_BYTES};
+use crate::tool::base::{Tool, ToolContext, ToolError, ToolResult};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::PathBuf;
+
+/// Header fields of a commit, separated by 0x1f; the message comes last
+const HEADER_FORMAT: &str = "--format=%H%x1f%an%x1f%ae%x1f%aI%x1f%cn%x1f%cI%x1f%P%x1f%B";
+
+/// GitShow tool - one commit's metadata, full message, stats and patch
+pub struct GitShowTool;
+
+#[derive(Debug, Deserialize)]
+struct GitShowParams {
+    #[serde(default)]
+    rev: Option<String>,
+    #[serde(default)]
+    path: Option<PathBuf>,
+    #[serde(default)]
+    stat_only: bool,
+}
+
+#[async_trait::async_trait]
+impl Tool for GitShowTool {
+    fn id(&self) -> &str {
+        "git_show"
+    }
+
+    fn description(&self) -> &str {
+        "Show one git commit: hash, author, dates, parents and full message, then per-file stats \
+         and the patch (for merge commits, the changes against the first parent). Optionally \
+         limited to a path. Long patches are cut; use stat_only or path to narrow down."
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "rev": {
+                    "type": "string",
+                    "description": "Commit to show: hash, branch, tag or expression like HEAD~2 (default: HEAD)"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Only show changes to this file or directory"
+                },
+                "stat_only": {
+                    "type": "boolean",
+                    "description": "Only return the commit and its per-file stats, not the patch",
+                    "default": false
+                }
+            },
+            "required": []
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let params: GitShowParams = serde_json::from_value(params)
+            .map_err(|e| ToolError::InvalidParams(e.to_string()))?;
+
+        tracing::debug!(rev = ?params.rev, path = ?params.path, "tool git_show start");
+
+        let rev = check_rev(params.rev.as_deref().unwrap_or("HEAD"))?.to_string();
+        let paths = pathspec(ctx, params.path.as_ref())?;
+
+        // Header
+        let header = run_git(
+            ctx,
+            &["show".to_string(), "--no-patch".to_string(), HEADER_FORMAT.to_string(), rev.clone()],
+        )
+        .await?;
+        let fields: Vec<&str> = header.splitn(8, '\x1f').collect();
+        let [hash, author, email, date, committer, commit_date, parents, message] = fields[..] else {
+            return Err(ToolError::InvalidParams(format!("'{}' is not a commit", rev)));
+        };
+        let message = message.trim_end();
+        let subject = message.lines().next().unwrap_or_default();
+
+        let mut output = format!("commit {}\nAuthor: {} <{}>\nDate:   {}\n", hash, author, email, date);
+        if committer != author || commit_date != date {
+            output.push_str(&format!("Commit: {} ({})\n", committer, commit_date));
+        }
+        if !parents.is_empty() {
+            output.push_str(&format!("Parents: {}\n", parents));
+        }
+        output.push('\n');
+        for line in message.lines() {
+            output.push_str(&format!("    {}\n", line));
+        }
+        output.push('\n');
+
+        // Stats and patch, against the first parent for merges
+        let mut numstat_args: Vec<String> =
+            ["show", "--format=", "--first-parent", "--no-textconv", "--numstat", "-z"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+        numstat_args.push(rev.clone());
+        numstat_args.extend(paths.iter().cloned());
+        let stats = parse_numstat(&run_git(ctx, &numstat_args).await?);
+        output.push_str(&format_stats(&stats));
+
+        let mut truncated = false;
+        if !params.stat_only && !stats.is_empty() {
+            let mut patch_args: Vec<String> = [
+                "show",
+                "--format=",
+                "--first-parent",
+                "--no-ext-diff",
+                "--no-textconv",
+                "--patch",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+            patch_args.push(rev.clone());
+            patch_args.extend(paths);
+            let patch = run_git(ctx, &patch_args).await?;
+            let patch = patch.trim_start_matches('\n');
+            let (shown, cut) = bound(patch, MAX_OUTPUT_BYTES);
+            truncated = cut;
+
+            output.push('\n');
+            output.push_str(shown);
+            if truncated {
+                output.push_str(&format!(
+                    "\n(Patch truncated at {} of {} bytes. Pass path to see one file's changes.)\n",
+                    shown.len(),
+                    patch.len()
+                ));
+            }
+        }
+
+        tracing::debug!(hash, files = stats.len(), truncated, "tool git_show done");
+
+        let short = hash.get(..8).unwrap_or(hash);
+        Ok(ToolResult::new(format!("{} {}", short, subject), output)
+            .with_metadata("hash", json!(hash))
+            .with_metadata("author", json!(author))
+            .with_metadata("date", json!(date))
+            .with_metadata("parents", json!(parents.split_whitespace().collect::<Vec<_>>()))
+            .with_metadata("subject", json!(subject))
+            .with_metadata("files_changed", json!(stats.len()))
+            .with_metadata("insertions", json!(stats.iter().filter_map(|s| s.added).sum::<u64>()))
+            .with_metadata("deletions", json!(stats.iter().filter_map(|s| s.removed).sum::<u64>()))
+            .with_metadata("truncated", json!(truncated)))
+    }
+}
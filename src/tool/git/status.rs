@@ -0,0 +1,208 @@
+use super::{pathspec, run_git};
+use crate::tool::base::{Tool, ToolContext, ToolError, ToolResult};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::PathBuf;
+
+/// Entries listed per group (the rest are counted)
+const MAX_ENTRIES_PER_GROUP: usize = 200;
+
+/// GitStatus tool - working tree status grouped into staged, unstaged, untracked and conflicted
+pub struct GitStatusTool;
+
+#[derive(Debug, Deserialize)]
+struct GitStatusParams {
+    #[serde(default)]
+    path: Option<PathBuf>,
+}
+
+/// Parsed `git status --porcelain=v1 -z --branch`
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Status {
+    /// `## ...` line without the marker, e.g. `main...origin/main [ahead 1]`
+    pub branch: String,
+    pub staged: Vec<String>,
+    pub unstaged: Vec<String>,
+    pub untracked: Vec<String>,
+    pub conflicted: Vec<String>,
+}
+
+impl Status {
+    pub fn parse(text: &str) -> Self {
+        let mut status = Status::default();
+        let mut entries = text.split('\0');
+        while let Some(entry) = entries.next() {
+            if let Some(branch) = entry.strip_prefix("## ") {
+                status.branch = branch.to_string();
+                continue;
+            }
+            if entry.len() < 4 {
+                continue;
+            }
+            let (code, path) = entry.split_at(3);
+            let mut code = code.chars();
+            let (x, y) = (code.next().unwrap_or(' '), code.next().unwrap_or(' '));
+
+            // Renames and copies are followed by the original path
+            let path = if matches!(x, 'R' | 'C') || matches!(y, 'R' | 'C') {
+                format!("{} -> {}", entries.next().unwrap_or_default(), path)
+            } else {
+                path.to_string()
+            };
+
+            match (x, y) {
+                ('?', '?') => status.untracked.push(path),
+                ('!', '!') => {}
+                ('D', 'D') | ('A', 'A') | ('U', _) | (_, 'U') => {
+                    status.conflicted.push(format!("{}: {}", conflict_name(x, y), path))
+                }
+                _ => {
+                    if x != ' ' {
+                        status.staged.push(format!("{}: {}", change_name(x), path));
+                    }
+                    if y != ' ' {
+                        status.unstaged.push(format!("{}: {}", change_name(y), path));
+                    }
+                }
+            }
+        }
+        status
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.staged.is_empty()
+            && self.unstaged.is_empty()
+            && self.untracked.is_empty()
+            && self.conflicted.is_empty()
+    }
+}
+
+fn change_name(code: char) -> &'static str {
+    match code {
+        'M' => "modified",
+        'A' => "added",
+        'D' => "deleted",
+        'R' => "renamed",
+        'C' => "copied",
+        'T' => "type changed",
+        _ => "changed",
+    }
+}
+
+fn conflict_name(x: char, y: char) -> &'static str {
+    match (x, y) {
+        ('D', 'D') => "both deleted",
+        ('A', 'U') => "added by us",
+        ('U', 'D') => "deleted by them",
+        ('U', 'A') => "added by them",
+        ('D', 'U') => "deleted by us",
+        ('A', 'A') => "both added",
+        _ => "both modified",
+    }
+}
+
+fn push_group(output: &mut String, title: &str, entries: &[String]) {
+    if entries.is_empty() {
+        return;
+    }
+    output.push_str(&format!("{} ({}):\n", title, entries.len()));
+    for entry in entries.iter().take(MAX_ENTRIES_PER_GROUP) {
+        output.push_str(&format!("  {}\n", entry));
+    }
+    if entries.len() > MAX_ENTRIES_PER_GROUP {
+        output.push_str(&format!(
+            "  ... and {} more\n",
+            entries.len() - MAX_ENTRIES_PER_GROUP
+        ));
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for GitStatusTool {
+    fn id(&self) -> &str {
+        "git_status"
+    }
+
+    fn description(&self) -> &str {
+        "Show the git working tree status: the current branch and its upstream, then changed files \
+         grouped as staged, unstaged, untracked and conflicted. Optionally limited to a path."
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Only show changes under this file or directory"
+                }
+            },
+            "required": []
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let params: GitStatusParams = serde_json::from_value(params)
+            .map_err(|e| ToolError::InvalidParams(e.to_string()))?;
+
+        tracing::debug!(path = ?params.path, "tool git_status start");
+
+        let mut args: Vec<String> = ["status", "--porcelain=v1", "-z", "--branch", "--untracked-files=all"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        args.extend(pathspec(ctx, params.path.as_ref())?);
+        let status = Status::parse(&run_git(ctx, &args).await?);
+
+        let mut output = format!("On branch {}\n", status.branch);
+        if status.is_clean() {
+            output.push_str("Nothing to commit, working tree clean\n");
+        } else {
+            output.push('\n');
+            push_group(&mut output, "Conflicted", &status.conflicted);
+            push_group(&mut output, "Staged", &status.staged);
+            push_group(&mut output, "Unstaged", &status.unstaged);
+            push_group(&mut output, "Untracked", &status.untracked);
+        }
+
+        tracing::debug!(
+            staged = status.staged.len(),
+            unstaged = status.unstaged.len(),
+            untracked = status.untracked.len(),
+            conflicted = status.conflicted.len(),
+            "tool git_status done"
+        );
+
+        Ok(ToolResult::new("git status", output)
+            .with_metadata("branch", json!(status.branch))
+            .with_metadata("staged", json!(status.staged.len()))
+            .with_metadata("unstaged", json!(status.unstaged.len()))
+            .with_metadata("untracked", json!(status.untracked.len()))
+            .with_metadata("conflicted", json!(status.conflicted.len())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_porcelain() {
+        let text = "## main...origin/main [ahead 1]\0M  staged.rs\0 M unstaged.rs\0MM both.rs\0\
+                    R  new.rs\0old.rs\0?? notes.txt\0UU conflict.rs\0";
+        let status = Status::parse(text);
+        assert_eq!(status.branch, "main...origin/main [ahead 1]");
+        assert_eq!(
+            status.staged,
+            vec!["modified: staged.rs", "modified: both.rs", "renamed: old.rs -> new.rs"]
+        );
+        assert_eq!(status.unstaged, vec!["modified: unstaged.rs", "modified: both.rs"]);
+        assert_eq!(status.untracked, vec!["notes.txt"]);
+        assert_eq!(status.conflicted, vec!["both modified: conflict.rs"]);
+        assert!(!status.is_clean());
+    }
+}
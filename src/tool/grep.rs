@@ -38,11 +38,15 @@ impl GrepTool {
     }
 
     /// Search for pattern in a single file
+    ///
+    /// In multiline mode the pattern runs over the whole file and a match may span several
+    /// lines; otherwise each line is matched on its own.
     fn search_file(
         &self,
         file_path: &Path,
         pattern: &regex::Regex,
         context_lines: usize,
+        multiline: bool,
     ) -> anyhow::Result<Vec<Match>> {
         // Skip binary files
         if Self::is_binary_file(file_path).unwrap_or(false) {
@@ -55,38 +59,65 @@ impl GrepTool {
         };
 
         let lines: Vec<&str> = content.lines().collect();
+
+        // Matched line ranges (0-based, inclusive)
+        let spans: Vec<(usize, usize)> = if multiline {
+            let line_starts: Vec<usize> = std::iter::once(0)
+                .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+                .collect();
+            let line_of = |offset: usize| line_starts.partition_point(|&start| start <= offset) - 1;
+
+            pattern
+                .find_iter(&content)
+                .filter(|m| !m.is_empty())
+                .map(|m| {
+                    let start = line_of(m.start());
+                    (start, line_of(m.end() - 1).max(start))
+                })
+                .collect()
+        } else {
+            lines
+                .iter()
+                .enumerate()
+                .filter(|(_, line)| pattern.is_match(line))
+                .map(|(idx, _)| (idx, idx))
+                .collect()
+        };
+
         let mut matches = Vec::new();
 
-        for (line_idx, line) in lines.iter().enumerate() {
-            if pattern.is_match(line) {
-                // Collect context lines
-                let start = line_idx.saturating_sub(context_lines);
-                let end = (line_idx + context_lines + 1).min(lines.len());
-
-                let mut context = Vec::new();
-                for i in start..end {
-                    let prefix = if i == line_idx { ">" } else { " " };
-                    let line_num = i + 1; // 1-based line numbers
-
-                    // Truncate long lines
-                    let line_text = if lines[i].len() > self.max_line_length {
-                        format!("{}...", &lines[i][..self.max_line_length])
-                    } else {
-                        lines[i].to_string()
-                    };
-
-                    context.push(ContextLine {
-                        line_number: line_num,
-                        content: line_text,
-                        prefix: prefix.to_string(),
-                    });
-                }
+        for (first, last) in spans {
+            // Collect context lines
+            let start = first.saturating_sub(context_lines);
+            let end = (last + context_lines + 1).min(lines.len());
+
+            let mut context = Vec::new();
+            for (i, line) in lines.iter().enumerate().take(end).skip(start) {
+                let prefix = if (first..=last).contains(&i) { ">" } else { " " };
+                let line_num = i + 1; // 1-based line numbers
+
+                // Truncate long lines
+                let line_text = if line.len() > self.max_line_length {
+                    let mut cut = self.max_line_length;
+                    while !line.is_char_boundary(cut) {
+                        cut -= 1;
+                    }
+                    format!("{}...", &line[..cut])
+                } else {
+                    line.to_string()
+                };
 
-                matches.push(Match {
-                    file_path: file_path.to_path_buf(),
-                    context,
+                context.push(ContextLine {
+                    line_number: line_num,
+                    content: line_text,
+                    prefix: prefix.to_string(),
                 });
             }
+
+            matches.push(Match {
+                file_path: file_path.to_path_buf(),
+                context,
+            });
         }
 
         Ok(matches)
@@ -108,6 +139,32 @@ struct GrepParams {
     include_patterns: Vec<String>,
     #[serde(default)]
     exclude_patterns: Vec<String>,
+    #[serde(default)]
+    output_mode: OutputMode,
+    #[serde(default)]
+    multiline: bool,
+    /// File type filter (e.g. "rust", "py"), using ripgrep's type definitions
+    #[serde(default, rename = "type")]
+    file_type: Option<String>,
+    /// Maximum entries to return (lines, files or counts); overrides max_results
+    #[serde(default)]
+    head_limit: Option<usize>,
+    /// Entries to skip before applying head_limit
+    #[serde(default)]
+    offset: usize,
+}
+
+/// What the grep tool reports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum OutputMode {
+    /// Matching lines with optional context
+    #[default]
+    Content,
+    /// Paths of matching files, most recently modified first
+    FilesWithMatches,
+    /// Number of matches per file
+    Count,
 }
 
 fn default_path() -> PathBuf {
@@ -122,6 +179,24 @@ fn default_max_results() -> usize {
     100
 }
 
+impl OutputMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            OutputMode::Content => "content",
+            OutputMode::FilesWithMatches => "files_with_matches",
+            OutputMode::Count => "count",
+        }
+    }
+}
+
+/// All matches found in one file
+#[derive(Debug)]
+struct FileMatches {
+    file_path: PathBuf,
+    modified: std::time::SystemTime,
+    matches: Vec<Match>,
+}
+
 #[derive(Debug)]
 struct Match {
     file_path: PathBuf,
@@ -143,7 +218,9 @@ impl Tool for GrepTool {
 
     fn description(&self) -> &str {
         "Search file contents using regex patterns. \
-         Supports context lines, case sensitivity, include/exclude patterns. \
+         Output modes: matching lines (content), matching file paths (files_with_matches) \
+         or per-file counts (count). Supports context lines, case sensitivity, multiline \
+         patterns, file type and include/exclude filters, and offset/head_limit pagination. \
          Respects .gitignore files. Skips binary files automatically."
     }
 
@@ -186,6 +263,35 @@ impl Tool for GrepTool {
                     "items": {"type": "string"},
                     "description": "Glob patterns for files to exclude",
                     "default": []
+                },
+                "output_mode": {
+                    "type": "string",
+                    "enum": ["content", "files_with_matches", "count"],
+                    "description": "content: matching lines (default); files_with_matches: \
+                                    file paths sorted by modification time, newest first; \
+                                    count: number of matches per file",
+                    "default": "content"
+                },
+                "multiline": {
+                    "type": "boolean",
+                    "description": "Match across line boundaries ('.' matches newlines, \
+                                    ^/$ match at line starts/ends)",
+                    "default": false
+                },
+                "type": {
+                    "type": "string",
+                    "description": "Only search files of this type (e.g. 'rust', 'py', 'js', 'go')"
+                },
+                "head_limit": {
+                    "type": "integer",
+                    "description": "Maximum entries to return: lines, files or counts \
+                                    depending on output_mode (default: max_results)"
+                },
+                "offset": {
+                    "type": "integer",
+                    "description": "Number of entries to skip before head_limit is applied \
+                                    (default: 0)",
+                    "default": 0
                 }
             },
             "required": ["pattern"]
@@ -210,13 +316,11 @@ impl Tool for GrepTool {
         );
 
         // 1. Validate and compile regex
-        let regex_pattern = if params.case_sensitive {
-            params.pattern.clone()
-        } else {
-            format!("(?i){}", params.pattern)
-        };
-
-        let pattern = regex::Regex::new(&regex_pattern)
+        let pattern = regex::RegexBuilder::new(&params.pattern)
+            .case_insensitive(!params.case_sensitive)
+            .multi_line(params.multiline)
+            .dot_matches_new_line(params.multiline)
+            .build()
             .map_err(|e| ToolError::InvalidParams(format!("Invalid regex: {}", e)))?;
 
         // 2. Resolve search path
@@ -264,20 +368,43 @@ impl Tool for GrepTool {
             builder.overrides(overrides);
         }
 
+        // Restrict to a file type (e.g. "rust" => *.rs), using ripgrep's definitions
+        if let Some(file_type) = &params.file_type {
+            let mut types_builder = ignore::types::TypesBuilder::new();
+            types_builder.add_defaults();
+            types_builder.select(file_type);
+            let types = types_builder
+                .build()
+                .map_err(|e| ToolError::InvalidParams(format!("Invalid type filter: {}", e)))?;
+            builder.types(types);
+        }
+
         // 4. Search files with timeout
-        let max_results = params.max_results.min(self.max_results);
-        let context_lines = params.context_lines;
+        let output_mode = params.output_mode;
+        let multiline = params.multiline;
+        let limit = params
+            .head_limit
+            .unwrap_or(params.max_results)
+            .min(self.max_results);
+        let offset = params.offset;
+        let context_lines = match output_mode {
+            OutputMode::Content => params.context_lines,
+            _ => 0,
+        };
 
         let search_future = tokio::task::spawn_blocking(move || {
             let walker = builder.build();
-            let mut all_matches = Vec::new();
+            let tool = GrepTool::new();
+            let mut file_results = Vec::new();
+            let mut matches_found = 0;
+            let mut files_matched = 0;
             let mut files_searched = 0;
             let mut binary_files_skipped = 0;
 
             for entry in walker {
-                if all_matches.len() >= max_results {
-                    break;
-                }
+                // Once content mode has filled the requested page, later files are only
+                // counted (without context), so the totals stay right
+                let page_full = output_mode == OutputMode::Content && matches_found >= offset + limit;
 
                 let entry = match entry {
                     Ok(e) => e,
@@ -299,103 +426,178 @@ impl Tool for GrepTool {
                 }
 
                 // Search this file
-                let tool = GrepTool::new();
-                match tool.search_file(file_path, &pattern, context_lines) {
-                    Ok(mut matches) => {
-                        all_matches.append(&mut matches);
-                    }
-                    Err(_) => continue,
+                let context_lines = if page_full { 0 } else { context_lines };
+                let matches = match tool.search_file(file_path, &pattern, context_lines, multiline) {
+                    Ok(matches) if !matches.is_empty() => matches,
+                    _ => continue,
+                };
+
+                matches_found += matches.len();
+                files_matched += 1;
+                if page_full {
+                    continue;
                 }
+                let modified = entry
+                    .metadata()
+                    .ok()
+                    .and_then(|m| m.modified().ok())
+                    .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+                file_results.push(FileMatches {
+                    file_path: file_path.to_path_buf(),
+                    modified,
+                    matches,
+                });
             }
 
-            (all_matches, files_searched, binary_files_skipped)
+            (file_results, matches_found, files_matched, files_searched, binary_files_skipped)
         });
 
-        let (all_matches, files_searched, binary_files_skipped) =
+        let (file_results, total_matches, total_files, files_searched, binary_files_skipped) =
             tokio::time::timeout(self.timeout, search_future)
                 .await
                 .map_err(|_| ToolError::Timeout(self.timeout.as_millis() as u64))?
                 .map_err(|e| ToolError::Other(e.into()))?;
 
-        // 5. Format output
-        let total_matches = all_matches.len();
-        let matches_to_show = all_matches.into_iter().take(max_results).collect::<Vec<_>>();
-        let shown_matches = matches_to_show.len();
+        let relative = |path: &Path| -> String {
+            path.strip_prefix(&ctx.working_dir)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        };
 
+        // 5. Format output
         let mut output = String::new();
 
-        if shown_matches == 0 {
+        let shown = if total_matches == 0 {
             output.push_str(&format!("No matches found for pattern: {}\n", params.pattern));
             output.push_str(&format!("Searched {} files", files_searched));
             if binary_files_skipped > 0 {
                 output.push_str(&format!(" (skipped {} binary files)", binary_files_skipped));
             }
+            0
         } else {
-            // Group matches by file
-            let mut files_with_matches: std::collections::HashMap<PathBuf, Vec<&Match>> =
-                std::collections::HashMap::new();
-            for m in &matches_to_show {
-                files_with_matches
-                    .entry(m.file_path.clone())
-                    .or_insert_with(Vec::new)
-                    .push(m);
-            }
+            let shown = match output_mode {
+                OutputMode::Content => {
+                    let page: Vec<&Match> = file_results
+                        .iter()
+                        .flat_map(|f| &f.matches)
+                        .skip(offset)
+                        .take(limit)
+                        .collect();
+
+                    // Group matches by file, in search order
+                    let mut groups: Vec<(&Path, Vec<&Match>)> = Vec::new();
+                    for m in &page {
+                        match groups.last_mut() {
+                            Some((path, group)) if *path == m.file_path.as_path() => group.push(m),
+                            _ => groups.push((m.file_path.as_path(), vec![m])),
+                        }
+                    }
 
-            output.push_str(&format!(
-                "Found {} matches in {} files:\n\n",
-                shown_matches,
-                files_with_matches.len()
-            ));
-
-            for (file_path, matches) in files_with_matches {
-                let relative_path = file_path
-                    .strip_prefix(&ctx.working_dir)
-                    .unwrap_or(&file_path);
-
-                output.push_str(&format!("{}:\n", relative_path.display()));
-
-                for m in matches {
-                    // Show context lines
-                    for ctx_line in &m.context {
-                        output.push_str(&format!(
-                            "{} {:>4}\u{2502} {}\n",
-                            ctx_line.prefix, ctx_line.line_number, ctx_line.content
-                        ));
+                    output.push_str(&format!(
+                        "Found {} matches in {} files:\n\n",
+                        page.len(),
+                        groups.len()
+                    ));
+
+                    for (file_path, matches) in &groups {
+                        output.push_str(&format!("{}:\n", relative(file_path)));
+
+                        for m in matches {
+                            // Show context lines
+                            for ctx_line in &m.context {
+                                output.push_str(&format!(
+                                    "{} {:>4}\u{2502} {}\n",
+                                    ctx_line.prefix, ctx_line.line_number, ctx_line.content
+                                ));
+                            }
+                            output.push('\n');
+                        }
                     }
-                    output.push('\n');
+                    page.len()
                 }
-            }
+                OutputMode::FilesWithMatches => {
+                    let mut files: Vec<&FileMatches> = file_results.iter().collect();
+                    files.sort_by(|a, b| {
+                        b.modified
+                            .cmp(&a.modified)
+                            .then_with(|| a.file_path.cmp(&b.file_path))
+                    });
+                    let page: Vec<&FileMatches> = files.into_iter().skip(offset).take(limit).collect();
+
+                    output.push_str(&format!(
+                        "Found {} files matching (most recently modified first):\n",
+                        total_files
+                    ));
+                    for f in &page {
+                        output.push_str(&format!("{}\n", relative(&f.file_path)));
+                    }
+                    page.len()
+                }
+                OutputMode::Count => {
+                    let mut files: Vec<&FileMatches> = file_results.iter().collect();
+                    files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+                    let page: Vec<&FileMatches> = files.into_iter().skip(offset).take(limit).collect();
+
+                    output.push_str(&format!(
+                        "Found {} matches in {} files:\n",
+                        total_matches, total_files
+                    ));
+                    for f in &page {
+                        output.push_str(&format!("{}: {}\n", relative(&f.file_path), f.matches.len()));
+                    }
+                    page.len()
+                }
+            };
 
-            if total_matches > shown_matches {
+            let (total, unit) = match output_mode {
+                OutputMode::Content => (total_matches, "matches"),
+                _ => (total_files, "files"),
+            };
+            if offset > 0 || total > offset + shown {
                 output.push_str(&format!(
-                    "(Showing {} of {} matches. Use max_results to see more)\n",
-                    shown_matches, total_matches
+                    "\n(Showing {} of {} {}, starting at offset {}. Use offset/head_limit to see more)\n",
+                    shown, total, unit, offset
                 ));
-            } else {
+            } else if output_mode == OutputMode::Content {
                 output.push_str(&format!("(Total {} matches)\n", total_matches));
             }
 
             if binary_files_skipped > 0 {
                 output.push_str(&format!("(Skipped {} binary files)\n", binary_files_skipped));
             }
-        }
+            shown
+        };
 
         tracing::debug!(
+            ?output_mode,
             total_matches,
-            shown_matches,
+            total_files,
+            shown,
             files_searched,
             binary_files_skipped,
             "tool grep done"
         );
 
         // 6. Return result
-        Ok(ToolResult::new(
-            format!("grep: {}", params.pattern),
-            output,
-        )
-        .with_metadata("total_matches", json!(total_matches))
-        .with_metadata("shown_matches", json!(shown_matches))
-        .with_metadata("files_searched", json!(files_searched))
-        .with_metadata("binary_files_skipped", json!(binary_files_skipped)))
+        let mut result = ToolResult::new(format!("grep: {}", params.pattern), output)
+            .with_metadata("output_mode", json!(output_mode.as_str()))
+            .with_metadata("total_files", json!(total_files))
+            .with_metadata("files_searched", json!(files_searched))
+            .with_metadata("binary_files_skipped", json!(binary_files_skipped))
+            .with_metadata("offset", json!(offset));
+
+        // files_with_matches lists files, so it reports file totals only
+        result = match output_mode {
+            OutputMode::Content => result
+                .with_metadata("total_matches", json!(total_matches))
+                .with_metadata("shown_matches", json!(shown)),
+            OutputMode::Count => result
+                .with_metadata("total_matches", json!(total_matches))
+                .with_metadata("shown_files", json!(shown)),
+            OutputMode::FilesWithMatches => result.with_metadata("shown_files", json!(shown)),
+        };
+
+        Ok(result)
     }
 }
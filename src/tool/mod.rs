@@ -2,14 +2,26 @@ pub mod base;
 pub mod context;
 pub mod bash;
 pub mod bash_output;
+pub mod bash_input;
 pub mod kill_shell;
 pub mod read;
 pub mod write;
 pub mod grep;
 pub mod glob;
+pub mod code_intel;
+pub mod code_outline;
+pub mod command_safety;
+pub mod git;
+pub mod output_limit;
 pub mod edit;
+pub mod file_state;
+pub mod text_file;
+pub mod multi_edit;
+pub mod apply_patch;
 pub mod todo;
 pub mod notebook;
+pub mod pdf;
+pub mod outline;
 pub mod web_fetch;
 pub mod web_search;
 pub mod task;
@@ -38,9 +50,13 @@ impl ToolRegistry {
         tools.insert("write".to_string(), Arc::new(write::WriteTool));
         tools.insert("grep".to_string(), Arc::new(grep::GrepTool::new()));
         tools.insert("glob".to_string(), Arc::new(glob::GlobTool::new()));
+        tools.insert("code_intel".to_string(), Arc::new(code_intel::CodeIntelTool::new()));
+        tools.insert("code_outline".to_string(), Arc::new(code_outline::CodeOutlineTool));
 
         // Register extended tools (Phase 1)
         tools.insert("edit".to_string(), Arc::new(edit::EditTool));
+        tools.insert("multi_edit".to_string(), Arc::new(multi_edit::MultiEditTool));
+        tools.insert("apply_patch".to_string(), Arc::new(apply_patch::ApplyPatchTool));
         tools.insert("todo_write".to_string(), Arc::new(todo::TodoWriteTool::new()));
 
         // Register file operation tools (Phase 1 - Additional)
@@ -48,8 +64,16 @@ impl ToolRegistry {
 
         // Register background process tools (Phase 2)
         tools.insert("bash_output".to_string(), Arc::new(bash_output::BashOutputTool));
+        tools.insert("bash_input".to_string(), Arc::new(bash_input::BashInputTool));
         tools.insert("kill_shell".to_string(), Arc::new(kill_shell::KillShellTool));
 
+        // Register git tools
+        tools.insert("git_status".to_string(), Arc::new(git::status::GitStatusTool));
+        tools.insert("git_diff".to_string(), Arc::new(git::diff::GitDiffTool));
+        tools.insert("git_log".to_string(), Arc::new(git::log::GitLogTool));
+        tools.insert("git_blame".to_string(), Arc::new(git::blame::GitBlameTool));
+        tools.insert("git_show".to_string(), Arc::new(git::show::GitShowTool));
+
         // Register web integration tools (Phase 3)
         tools.insert("web_fetch".to_string(), Arc::new(web_fetch::WebFetchTool::new()));
 
@@ -0,0 +1,193 @@
+use super::base::{Tool, ToolContext, ToolError, ToolResult};
+use super::edit::EditTool;
+use super::text_file;
+use serde::Deserialize;
+use serde_json::json;
+use std::path::PathBuf;
+
+/// MultiEdit tool - applies several string replacements to one file atomically
+pub struct MultiEditTool;
+
+#[derive(Debug, Deserialize)]
+struct MultiEditParams {
+    file_path: PathBuf,
+    edits: Vec<EditOperation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EditOperation {
+    old_string: String,
+    new_string: String,
+    #[serde(default)]
+    replace_all: bool,
+}
+
+impl MultiEditTool {
+    /// Apply one edit to `content`, returning the new content and the number of replacements
+    fn apply(content: &str, edit: &EditOperation) -> Result<(String, usize), ToolError> {
+        if edit.old_string == edit.new_string {
+            return Err(ToolError::OldNewIdentical);
+        }
+
+        let positions = EditTool::find_occurrences(content, &edit.old_string);
+        if positions.is_empty() {
+            return Err(ToolError::OldStringNotFound(edit.old_string.clone()));
+        }
+        if !edit.replace_all && positions.len() > 1 {
+            return Err(ToolError::MultipleMatches {
+                count: positions.len(),
+                positions,
+            });
+        }
+
+        if edit.replace_all {
+            Ok((content.replace(&edit.old_string, &edit.new_string), positions.len()))
+        } else {
+            Ok((content.replacen(&edit.old_string, &edit.new_string, 1), 1))
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for MultiEditTool {
+    fn id(&self) -> &str {
+        "multi_edit"
+    }
+
+    fn description(&self) -> &str {
+        "Apply several exact string replacements to one file in a single call. Edits are applied \
+         in order, each against the result of the previous one, with the same uniqueness rules \
+         as the edit tool. The file is only written if every edit succeeds. \
+         The file must have been read with the read tool first, and not changed since."
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "file_path": {
+                    "type": "string",
+                    "description": "Path to the file to edit (absolute or relative)"
+                },
+                "edits": {
+                    "type": "array",
+                    "description": "Replacements to apply in order",
+                    "minItems": 1,
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "old_string": {
+                                "type": "string",
+                                "description": "The exact string to replace (must match exactly, including whitespace)"
+                            },
+                            "new_string": {
+                                "type": "string",
+                                "description": "The replacement string"
+                            },
+                            "replace_all": {
+                                "type": "boolean",
+                                "default": false,
+                                "description": "If true, replace all occurrences. If false (default), the match must be unique."
+                            }
+                        },
+                        "required": ["old_string", "new_string"]
+                    }
+                }
+            },
+            "required": ["file_path", "edits"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let mut params: MultiEditParams = serde_json::from_value(params)
+            .map_err(|e| ToolError::InvalidParams(e.to_string()))?;
+        // Content is matched with LF line endings regardless of the file's own
+        for edit in &mut params.edits {
+            edit.old_string = text_file::normalize_newlines(&edit.old_string);
+            edit.new_string = text_file::normalize_newlines(&edit.new_string);
+        }
+
+        tracing::debug!(
+            working_dir = %ctx.working_dir.display(),
+            file_path = %params.file_path.display(),
+            edits = params.edits.len(),
+            "tool multi_edit start"
+        );
+
+        if params.edits.is_empty() {
+            return Err(ToolError::InvalidParams(
+                "edits must contain at least one edit".to_string(),
+            ));
+        }
+
+        // 1. Resolve file path and read the current content
+        let filepath = ctx.resolve_path(&params.file_path)?;
+        if !filepath.exists() {
+            return Err(ToolError::FileNotFound(filepath));
+        }
+
+        let bytes = tokio::fs::read(&filepath)
+            .await
+            .map_err(|e| ToolError::Other(e.into()))?;
+        let Some((content, format)) = text_file::decode(&bytes) else {
+            return Err(ToolError::BinaryFile(filepath));
+        };
+
+        // 2. Refuse to edit a file the model hasn't seen in its current state, or one that
+        //    can't be written back without changing other lines
+        ctx.file_states.check(&filepath, &bytes)?;
+        format.check_editable(&filepath)?;
+
+        // 3. Apply every edit in memory; any failure leaves the file untouched
+        let total = params.edits.len();
+        let mut new_content = content.clone();
+        let mut replacements = 0;
+        for (idx, edit) in params.edits.iter().enumerate() {
+            let (updated, count) =
+                Self::apply(&new_content, edit).map_err(|e| ToolError::EditFailed {
+                    index: idx + 1,
+                    total,
+                    source: Box::new(e),
+                })?;
+            new_content = updated;
+            replacements += count;
+        }
+
+        // 4. Write once, in the file's original format, and report a single diff
+        let diff = EditTool::generate_diff(&filepath, &content, &new_content);
+        let new_bytes = format.encode(&new_content)?;
+        tokio::fs::write(&filepath, &new_bytes)
+            .await
+            .map_err(|e| ToolError::Other(e.into()))?;
+        ctx.file_states.record_write(&filepath, &new_bytes);
+
+        let mut output = format!(
+            "Successfully applied {} edit(s) to: {}\n",
+            total,
+            filepath.display()
+        );
+        output.push_str(&format!("Replacements made: {} occurrence(s)\n\n", replacements));
+        output.push_str(&diff);
+
+        tracing::debug!(
+            resolved_path = %filepath.display(),
+            edits = total,
+            replacements,
+            old_size = content.len(),
+            new_size = new_content.len(),
+            "tool multi_edit done"
+        );
+
+        Ok(ToolResult::new(filepath.to_string_lossy(), output)
+            .with_metadata("filepath", json!(filepath.to_string_lossy()))
+            .with_metadata("edits", json!(total))
+            .with_metadata("replacements", json!(replacements))
+            .with_metadata("encoding", json!(format.encoding_name()))
+            .with_metadata("line_ending", json!(format.line_ending.as_str()))
+            .with_metadata("bom", json!(format.bom)))
+    }
+}
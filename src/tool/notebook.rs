@@ -24,37 +24,45 @@ fn default_edit_mode() -> String {
 
 /// Jupyter notebook structure
 #[derive(Debug, Deserialize, Serialize)]
-struct Notebook {
-    cells: Vec<Cell>,
-    metadata: Value,
-    nbformat: i32,
-    nbformat_minor: i32,
+pub(crate) struct Notebook {
+    pub(crate) cells: Vec<Cell>,
+    pub(crate) metadata: Value,
+    pub(crate) nbformat: i32,
+    pub(crate) nbformat_minor: i32,
 }
 
 /// Jupyter notebook cell
 #[derive(Debug, Clone, Deserialize, Serialize)]
-struct Cell {
+pub(crate) struct Cell {
     #[serde(skip_serializing_if = "Option::is_none")]
-    id: Option<String>,
-    cell_type: String,
-    source: SourceLines,
+    pub(crate) id: Option<String>,
+    pub(crate) cell_type: String,
+    pub(crate) source: SourceLines,
     #[serde(default)]
-    metadata: Value,
+    pub(crate) metadata: Value,
     #[serde(skip_serializing_if = "Option::is_none")]
-    execution_count: Option<Value>,
+    pub(crate) execution_count: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    outputs: Option<Vec<Value>>,
+    pub(crate) outputs: Option<Vec<Value>>,
 }
 
 /// Jupyter notebook source can be a string or array of strings
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(untagged)]
-enum SourceLines {
+pub(crate) enum SourceLines {
     Single(String),
     Multiple(Vec<String>),
 }
 
 impl SourceLines {
+    /// The full source text
+    pub(crate) fn text(&self) -> String {
+        match self {
+            SourceLines::Single(s) => s.clone(),
+            SourceLines::Multiple(lines) => lines.concat(),
+        }
+    }
+
     fn from_string(s: String) -> Self {
         // Split into lines preserving newlines
         let lines: Vec<String> = s.split_inclusive('\n').map(|s| s.to_string()).collect();
@@ -90,6 +98,98 @@ impl Cell {
     }
 }
 
+/// Maximum lines shown per cell output when rendering
+const MAX_OUTPUT_LINES: usize = 50;
+
+/// Maximum characters shown per cell output when rendering
+const MAX_OUTPUT_CHARS: usize = 2000;
+
+/// Render notebook cells `offset..offset + limit` as text, with the cell ids `notebook_edit` expects
+pub(crate) fn render_notebook(notebook: &Notebook, offset: usize, limit: usize) -> String {
+    let total = notebook.cells.len();
+    let end = offset.saturating_add(limit).min(total);
+    let mut sections = Vec::new();
+
+    for (idx, cell) in notebook.cells.iter().enumerate().take(end).skip(offset) {
+        let mut header = format!(
+            "--- Cell {}/{} · id: {} · {}",
+            idx + 1,
+            total,
+            cell.id.as_deref().unwrap_or("(none)"),
+            cell.cell_type
+        );
+        if let Some(count) = cell.execution_count.as_ref().and_then(Value::as_i64) {
+            header.push_str(&format!(" [{}]", count));
+        }
+        header.push_str(" ---");
+
+        let mut section = vec![header, cell.source.text().trim_end().to_string()];
+        for output in cell.outputs.iter().flatten() {
+            if let Some(text) = render_output(output) {
+                section.push(format!("[output]\n{}", truncate_output(&text)));
+            }
+        }
+        sections.push(section.join("\n"));
+    }
+
+    let footer = if end < total {
+        format!(
+            "(Notebook has more cells. Use offset={} to read beyond cell {})",
+            end, end
+        )
+    } else {
+        format!("(End of notebook - {} cells total)", total)
+    };
+    sections.push(footer);
+    sections.join("\n\n")
+}
+
+/// Text representation of a single cell output (None when it has nothing to show)
+fn render_output(output: &Value) -> Option<String> {
+    let joined = |v: &Value| match v {
+        Value::String(s) => s.clone(),
+        Value::Array(parts) => parts.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    };
+
+    match output.get("output_type").and_then(Value::as_str)? {
+        "stream" => output.get("text").map(joined),
+        "execute_result" | "display_data" => {
+            let data = output.get("data")?;
+            if let Some(text) = data.get("text/plain") {
+                return Some(joined(text));
+            }
+            let kinds: Vec<&str> = data.as_object()?.keys().map(String::as_str).collect();
+            Some(format!("[{} output omitted]", kinds.join(", ")))
+        }
+        "error" => {
+            let field = |name| output.get(name).and_then(Value::as_str).unwrap_or("");
+            Some(format!("{}: {}", field("ename"), field("evalue")))
+        }
+        _ => None,
+    }
+}
+
+fn truncate_output(text: &str) -> String {
+    let text = text.trim_end();
+    let lines: Vec<&str> = text.lines().collect();
+    let mut shown = lines[..lines.len().min(MAX_OUTPUT_LINES)].join("\n");
+    let mut truncated = lines.len() > MAX_OUTPUT_LINES;
+
+    if shown.len() > MAX_OUTPUT_CHARS {
+        let mut cut = MAX_OUTPUT_CHARS;
+        while !shown.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        shown.truncate(cut);
+        truncated = true;
+    }
+    if truncated {
+        shown.push_str("\n... (output truncated)");
+    }
+    shown
+}
+
 #[async_trait::async_trait]
 impl Tool for NotebookEditTool {
     fn id(&self) -> &str {
@@ -0,0 +1,432 @@
+//! Structural outlines of source files (modules, types, functions, impl blocks) via tree-sitter
+
+use std::path::Path;
+
+use tree_sitter::{Language, Node, Parser};
+
+use super::base::ToolError;
+
+/// What counts as an outline item in one language
+struct LanguageSpec {
+    name: &'static str,
+    language: fn() -> Language,
+    /// Node kind, item label, whether nested items are listed (through the node's `body`)
+    items: &'static [(&'static str, &'static str, bool)],
+    /// Wrappers whose inner declaration is the item (`export ...`, decorated definitions)
+    transparent: &'static [&'static str],
+    /// Attributes and comments that belong to the following item
+    leading: &'static [&'static str],
+    /// Joins container and item names in qualified names
+    separator: &'static str,
+}
+
+const RUST: LanguageSpec = LanguageSpec {
+    name: "rust",
+    language: || tree_sitter_rust::LANGUAGE.into(),
+    items: &[
+        ("mod_item", "module", true),
+        ("struct_item", "struct", false),
+        ("enum_item", "enum", false),
+        ("union_item", "union", false),
+        ("trait_item", "trait", true),
+        ("impl_item", "impl", true),
+        ("function_item", "function", false),
+        ("function_signature_item", "function", false),
+        ("type_item", "type", false),
+        ("const_item", "const", false),
+        ("static_item", "static", false),
+        ("macro_definition", "macro", false),
+    ],
+    transparent: &[],
+    leading: &["attribute_item", "line_comment", "block_comment"],
+    separator: "::",
+};
+
+const PYTHON: LanguageSpec = LanguageSpec {
+    name: "python",
+    language: || tree_sitter_python::LANGUAGE.into(),
+    items: &[
+        ("class_definition", "class", true),
+        ("function_definition", "function", false),
+    ],
+    transparent: &["decorated_definition"],
+    leading: &["comment"],
+    separator: ".",
+};
+
+const TS_ITEMS: &[(&str, &str, bool)] = &[
+    ("internal_module", "namespace", true),
+    ("module", "module", true),
+    ("class_declaration", "class", true),
+    ("abstract_class_declaration", "class", true),
+    ("interface_declaration", "interface", false),
+    ("type_alias_declaration", "type", false),
+    ("enum_declaration", "enum", false),
+    ("function_declaration", "function", false),
+    ("generator_function_declaration", "function", false),
+    ("function_signature", "function", false),
+    ("method_definition", "method", false),
+    ("abstract_method_signature", "method", false),
+    ("lexical_declaration", "function", false),
+];
+
+const TYPESCRIPT: LanguageSpec = LanguageSpec {
+    name: "typescript",
+    language: || tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+    items: TS_ITEMS,
+    transparent: &["export_statement", "ambient_declaration"],
+    leading: &["comment", "decorator"],
+    separator: ".",
+};
+
+/// Also used for JavaScript, which the TSX grammar parses
+const TSX: LanguageSpec = LanguageSpec {
+    name: "tsx",
+    language: || tree_sitter_typescript::LANGUAGE_TSX.into(),
+    ..TYPESCRIPT
+};
+
+const GO: LanguageSpec = LanguageSpec {
+    name: "go",
+    language: || tree_sitter_go::LANGUAGE.into(),
+    items: &[
+        ("function_declaration", "function", false),
+        ("method_declaration", "method", false),
+        ("type_declaration", "type", false),
+    ],
+    transparent: &[],
+    leading: &["comment"],
+    separator: ".",
+};
+
+/// Extensions with outline support, for error messages
+pub const SUPPORTED_EXTENSIONS: &str = "rs, py, pyi, ts, mts, cts, tsx, js, jsx, mjs, cjs, go";
+
+fn spec_for(path: &Path) -> Option<&'static LanguageSpec> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "rs" => &RUST,
+        "py" | "pyi" => &PYTHON,
+        "ts" | "mts" | "cts" => &TYPESCRIPT,
+        "tsx" | "js" | "jsx" | "mjs" | "cjs" => &TSX,
+        "go" => &GO,
+        _ => return None,
+    })
+}
+
+/// Whether `path` has a language with outline support
+pub fn is_supported(path: &Path) -> bool {
+    spec_for(path).is_some()
+}
+
+/// One declaration in a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlineItem {
+    /// `function`, `struct`, `impl`, ...
+    pub kind: &'static str,
+    pub name: String,
+    /// Name including its containers, e.g. `Foo::new` or `Foo.bar`
+    pub qualified_name: String,
+    /// Nesting depth (0 for top-level items)
+    pub depth: usize,
+    /// 1-based line of the declaration itself
+    pub line: usize,
+    /// 1-based first line, including decorators and `export` but not doc comments
+    pub start_line: usize,
+    /// 1-based first line including leading doc comments and attributes
+    pub doc_start_line: usize,
+    /// 1-based last line
+    pub end_line: usize,
+}
+
+/// Outline of one file
+#[derive(Debug, Clone)]
+pub struct Outline {
+    /// Language name (`rust`, `python`, `typescript`, `tsx`, `go`)
+    pub language: &'static str,
+    pub items: Vec<OutlineItem>,
+}
+
+impl Outline {
+    /// Items named `symbol`, matching the qualified name exactly, then as a suffix
+    /// (`new` or `Foo::new`), in file order
+    pub fn find(&self, symbol: &str) -> Vec<&OutlineItem> {
+        let exact: Vec<&OutlineItem> = self
+            .items
+            .iter()
+            .filter(|item| item.qualified_name == symbol)
+            .collect();
+        if !exact.is_empty() {
+            return exact;
+        }
+        self.items
+            .iter()
+            .filter(|item| {
+                item.name == symbol
+                    || item.qualified_name.ends_with(&format!("::{}", symbol))
+                    || item.qualified_name.ends_with(&format!(".{}", symbol))
+            })
+            .collect()
+    }
+}
+
+/// Parse `text` (the content of `path`) into an outline
+pub fn outline(path: &Path, text: &str) -> Result<Outline, ToolError> {
+    let spec = spec_for(path).ok_or_else(|| {
+        ToolError::InvalidParams(format!(
+            "No outline support for {} (supported extensions: {})",
+            path.display(),
+            SUPPORTED_EXTENSIONS
+        ))
+    })?;
+
+    let mut parser = Parser::new();
+    parser
+        .set_language(&(spec.language)())
+        .map_err(|e| ToolError::Other(e.into()))?;
+    let tree = parser
+        .parse(text, None)
+        .ok_or_else(|| ToolError::Other(anyhow::anyhow!("Failed to parse {}", path.display())))?;
+
+    let mut collector = Collector {
+        spec,
+        source: text.as_bytes(),
+        items: Vec::new(),
+    };
+    collector.collect(tree.root_node(), 0, "", None);
+
+    Ok(Outline {
+        language: spec.name,
+        items: collector.items,
+    })
+}
+
+struct Collector<'a> {
+    spec: &'static LanguageSpec,
+    source: &'a [u8],
+    items: Vec<OutlineItem>,
+}
+
+impl Collector<'_> {
+    /// Collect the items among `node`'s children; `wrapper` is a transparent parent whose
+    /// range the item takes
+    fn collect(&mut self, node: Node, depth: usize, container: &str, wrapper: Option<Node>) {
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            if self.spec.transparent.contains(&child.kind()) {
+                self.collect(child, depth, container, Some(wrapper.unwrap_or(child)));
+                continue;
+            }
+
+            let Some(&(_, kind, nested)) = self.spec.items.iter().find(|(k, _, _)| *k == child.kind())
+            else {
+                continue;
+            };
+            let Some(name) = self.name(child) else {
+                continue;
+            };
+
+            let outer = wrapper.unwrap_or(child);
+            let qualified_name = match (container, self.receiver(child)) {
+                (_, Some(receiver)) => format!("{}{}{}", receiver, self.spec.separator, name),
+                ("", None) => name.clone(),
+                (container, None) => format!("{}{}{}", container, self.spec.separator, name),
+            };
+
+            self.items.push(OutlineItem {
+                kind,
+                name,
+                qualified_name: qualified_name.clone(),
+                depth,
+                line: child.start_position().row + 1,
+                start_line: outer.start_position().row + 1,
+                doc_start_line: self.leading_start(outer) + 1,
+                end_line: outer.end_position().row + 1,
+            });
+
+            if nested {
+                if let Some(body) = child.child_by_field_name("body") {
+                    self.collect(body, depth + 1, &qualified_name, None);
+                }
+            }
+        }
+    }
+
+    fn text(&self, node: Node) -> &str {
+        node.utf8_text(self.source).unwrap_or("")
+    }
+
+    /// Name of an item node, or None if it isn't an outline item after all
+    fn name(&self, node: Node) -> Option<String> {
+        match node.kind() {
+            // `impl Trait for Type` is named after the type, without generics
+            "impl_item" => {
+                let ty = self.text(node.child_by_field_name("type")?);
+                Some(ty.split('<').next().unwrap_or(ty).trim().to_string())
+            }
+            // Only the first spec of `type ( A ...; B ... )` names the declaration
+            "type_declaration" => {
+                let mut cursor = node.walk();
+                let spec = node
+                    .named_children(&mut cursor)
+                    .find(|c| c.kind() == "type_spec" || c.kind() == "type_alias")?;
+                Some(self.text(spec.child_by_field_name("name")?).to_string())
+            }
+            // `const f = () => ...` is listed as a function; other variables are not
+            "lexical_declaration" => {
+                let mut cursor = node.walk();
+                let declarator = node
+                    .named_children(&mut cursor)
+                    .find(|c| c.kind() == "variable_declarator")?;
+                let value = declarator.child_by_field_name("value")?;
+                if !matches!(
+                    value.kind(),
+                    "arrow_function" | "function_expression" | "function" | "generator_function"
+                ) {
+                    return None;
+                }
+                Some(self.text(declarator.child_by_field_name("name")?).to_string())
+            }
+            _ => Some(self.text(node.child_by_field_name("name")?).to_string()),
+        }
+    }
+
+    /// Receiver type of a Go method, which qualifies its name
+    fn receiver(&self, node: Node) -> Option<String> {
+        if node.kind() != "method_declaration" {
+            return None;
+        }
+        let receiver = node.child_by_field_name("receiver")?;
+        let mut cursor = receiver.walk();
+        let param = receiver.named_children(&mut cursor).next()?;
+        let ty = self.text(param.child_by_field_name("type")?);
+        let ty = ty.trim_start_matches('*');
+        Some(ty.split('[').next().unwrap_or(ty).to_string())
+    }
+
+    /// 0-based first row of the doc comments and attributes directly above `node`
+    fn leading_start(&self, node: Node) -> usize {
+        let mut start = node.start_position().row;
+        let mut prev = node.prev_named_sibling();
+        while let Some(sibling) = prev {
+            if !self.spec.leading.contains(&sibling.kind()) || sibling.end_position().row + 1 < start {
+                break;
+            }
+            start = sibling.start_position().row;
+            prev = sibling.prev_named_sibling();
+        }
+        start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(path: &str, text: &str) -> Vec<(String, usize, usize, usize)> {
+        outline(Path::new(path), text)
+            .unwrap()
+            .items
+            .into_iter()
+            .map(|i| (format!("{} {}", i.kind, i.qualified_name), i.depth, i.start_line, i.end_line))
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_outline() {
+        let text = "\
+/// A point
+#[derive(Debug)]
+pub struct Point {
+    x: i32,
+}
+
+impl Point {
+    pub fn new() -> Self {
+        Point { x: 0 }
+    }
+}
+
+mod tests {
+    fn helper() {}
+}
+";
+        assert_eq!(
+            summary("lib.rs", text),
+            vec![
+                ("struct Point".to_string(), 0, 3, 5),
+                ("impl Point".to_string(), 0, 7, 11),
+                ("function Point::new".to_string(), 1, 8, 10),
+                ("module tests".to_string(), 0, 13, 15),
+                ("function tests::helper".to_string(), 1, 14, 14),
+            ]
+        );
+
+        let outline = outline(Path::new("lib.rs"), text).unwrap();
+        assert_eq!(outline.items[0].doc_start_line, 1);
+        assert_eq!(outline.find("new")[0].qualified_name, "Point::new");
+        assert_eq!(outline.find("Point").len(), 2);
+    }
+
+    #[test]
+    fn test_python_typescript_and_go_outlines() {
+        let python = "\
+class Greeter:
+    @staticmethod
+    def hello():
+        pass
+
+def main():
+    def inner():
+        pass
+";
+        assert_eq!(
+            summary("app.py", python),
+            vec![
+                ("class Greeter".to_string(), 0, 1, 4),
+                ("function Greeter.hello".to_string(), 1, 2, 4),
+                ("function main".to_string(), 0, 6, 8),
+            ]
+        );
+
+        let typescript = "\
+export interface Shape { area(): number }
+export class Circle {
+  area(): number { return 1; }
+}
+export const make = () => new Circle();
+const radius = 2;
+";
+        assert_eq!(
+            summary("shapes.ts", typescript),
+            vec![
+                ("interface Shape".to_string(), 0, 1, 1),
+                ("class Circle".to_string(), 0, 2, 4),
+                ("method Circle.area".to_string(), 1, 3, 3),
+                ("function make".to_string(), 0, 5, 5),
+            ]
+        );
+
+        let go = "\
+package main
+
+type Server struct {
+	port int
+}
+
+func (s *Server) Start() error {
+	return nil
+}
+
+func main() {}
+";
+        assert_eq!(
+            summary("main.go", go),
+            vec![
+                ("type Server".to_string(), 0, 3, 5),
+                ("method Server.Start".to_string(), 0, 7, 9),
+                ("function main".to_string(), 0, 11, 11),
+            ]
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! Size limits for tool results given to the model (`[tool_output]`)
+//!
+//! A result over its tool's limit keeps its first and last lines, with a marker in between
+//! saying how much was left out. The full output is saved to a file in a per-session temp
+//! directory, which the model can page through with `read`.
+
+use std::path::{Path, PathBuf};
+
+use crate::config::station::ToolOutputSettings;
+
+/// Cuts long tool results down to the configured size (shared by an agent and its subagents)
+#[derive(Debug)]
+pub struct OutputLimiter {
+    settings: ToolOutputSettings,
+    /// Where full outputs are saved; created on first use, removed when the limiter is dropped
+    spill_dir: PathBuf,
+}
+
+impl OutputLimiter {
+    pub fn new(settings: ToolOutputSettings) -> Self {
+        let spill_dir = std::env::temp_dir().join(format!(
+            "ok-tool-output-{}",
+            uuid::Uuid::new_v4().simple()
+        ));
+        Self {
+            settings,
+            spill_dir,
+        }
+    }
+
+    /// Directory full outputs are saved in
+    pub fn spill_dir(&self) -> &Path {
+        &self.spill_dir
+    }
+
+    /// `output` of a `tool_name` call, cut to the tool's limit. The full output is saved as
+    /// `<tool_name>-<tool_use_id>.txt` first.
+    pub fn limit(&self, tool_name: &str, tool_use_id: &str, output: String) -> String {
+        let Some(limit) = self.settings.limit_for(tool_name) else {
+            return output;
+        };
+        if output.len() <= limit {
+            return output;
+        }
+
+        let (head_end, tail_start) = split_points(&output, limit);
+        let omitted = &output[head_end..tail_start];
+        let saved = match self.spill(tool_name, tool_use_id, &output) {
+            Ok(path) => format!(
+                "the full output ({} lines) is saved in {}; use read with offset/limit to see the rest",
+                line_count(&output),
+                path.display()
+            ),
+            Err(e) => {
+                tracing::warn!(tool_name, error = %e, "failed to save full tool output");
+                format!("the full output could not be saved: {}", e)
+            }
+        };
+        tracing::debug!(
+            tool_name,
+            total_bytes = output.len(),
+            omitted_bytes = omitted.len(),
+            "tool output truncated"
+        );
+
+        let mut result = String::with_capacity(limit + 256);
+        result.push_str(&output[..head_end]);
+        if !result.is_empty() && !result.ends_with('\n') {
+            result.push('\n');
+        }
+        result.push_str(&format!(
+            "[... {} lines ({} bytes) omitted; {} ...]\n",
+            line_count(omitted),
+            omitted.len(),
+            saved
+        ));
+        result.push_str(&output[tail_start..]);
+        result
+    }
+
+    fn spill(&self, tool_name: &str, tool_use_id: &str, output: &str) -> std::io::Result<PathBuf> {
+        std::fs::create_dir_all(&self.spill_dir)?;
+        let name: String = format!("{}-{}.txt", tool_name, tool_use_id)
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || "-_.".contains(c) { c } else { '_' })
+            .collect();
+        let path = self.spill_dir.join(name);
+        std::fs::write(&path, output)?;
+        Ok(path)
+    }
+}
+
+impl Drop for OutputLimiter {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.spill_dir);
+    }
+}
+
+/// End of the head and start of the tail kept from `output`, about `limit / 2` bytes each,
+/// moved to line boundaries when there is one
+fn split_points(output: &str, limit: usize) -> (usize, usize) {
+    let half = limit / 2;
+
+    let mut head_end = half;
+    while !output.is_char_boundary(head_end) {
+        head_end -= 1;
+    }
+    if let Some(newline) = output[..head_end].rfind('\n') {
+        head_end = newline + 1;
+    }
+
+    let mut tail_start = output.len() - (limit - half);
+    while !output.is_char_boundary(tail_start) {
+        tail_start += 1;
+    }
+    if let Some(newline) = output[tail_start..].find('\n') {
+        if tail_start + newline + 1 < output.len() {
+            tail_start += newline + 1;
+        }
+    }
+    (head_end, tail_start.max(head_end))
+}
+
+/// Lines in `text`, counting a final line without a newline
+fn line_count(text: &str) -> usize {
+    text.lines().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(max_bytes: usize) -> OutputLimiter {
+        OutputLimiter::new(ToolOutputSettings {
+            max_bytes,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_short_output_is_unchanged() {
+        let limiter = limiter(100);
+        assert_eq!(limiter.limit("bash", "toolu_1", "ok\n".to_string()), "ok\n");
+        assert!(!limiter.spill_dir().exists());
+    }
+
+    #[test]
+    fn test_long_output_keeps_head_and_tail_and_saves_the_rest() {
+        let limiter = limiter(200);
+        let output: String = (1..=100).map(|i| format!("line {}\n", i)).collect();
+
+        let limited = limiter.limit("bash", "toolu/1", output.clone());
+        assert!(limited.len() < 400, "{}", limited);
+        assert!(limited.starts_with("line 1\nline 2\n"));
+        assert!(limited.ends_with("line 99\nline 100\n"));
+        assert!(limited.contains(" lines ("), "{}", limited);
+        assert!(limited.contains("bytes) omitted; the full output (100 lines) is saved in"));
+
+        let saved = limiter.spill_dir().join("bash-toolu_1.txt");
+        assert!(limited.contains(&saved.display().to_string()));
+        assert_eq!(std::fs::read_to_string(&saved).unwrap(), output);
+
+        let dir = limiter.spill_dir().to_path_buf();
+        drop(limiter);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_per_tool_limits() {
+        let mut settings = ToolOutputSettings {
+            max_bytes: 10,
+            ..Default::default()
+        };
+        settings.limits.insert("grep".to_string(), 0);
+        settings.limits.insert("bash_output".to_string(), 1000);
+        let limiter = OutputLimiter::new(settings);
+        let output = "x".repeat(500);
+
+        assert_eq!(limiter.limit("grep", "a", output.clone()), output);
+        assert_eq!(limiter.limit("bash_output", "b", output.clone()), output);
+        assert_eq!(limiter.limit("read", "c", output.clone()), output);
+        assert_ne!(limiter.limit("web_fetch", "d", output.clone()), output);
+    }
+
+    #[test]
+    fn test_split_points_respect_char_boundaries() {
+        let output = "é".repeat(100);
+        let (head_end, tail_start) = split_points(&output, 51);
+        assert!(output.is_char_boundary(head_end));
+        assert!(output.is_char_boundary(tail_start));
+        assert!(head_end <= tail_start);
+    }
+}
@@ -0,0 +1,112 @@
+//! PDF text extraction for the Read tool
+
+use super::base::ToolError;
+
+/// Maximum pages extracted in a single read
+pub const MAX_PAGES_PER_READ: usize = 20;
+
+/// Text extracted from a range of PDF pages
+#[derive(Debug)]
+pub struct PdfText {
+    pub total_pages: usize,
+    /// (page number, text) pairs, 1-based, in ascending order
+    pub pages: Vec<(u32, String)>,
+    /// Pages requested but not extracted because of `MAX_PAGES_PER_READ`
+    pub pages_omitted: usize,
+}
+
+/// Parse a page selection such as `"3"`, `"1-5"` or `"1,3,7-9"` into sorted page numbers
+pub fn parse_page_ranges(spec: &str, total_pages: usize) -> Result<Vec<u32>, ToolError> {
+    let invalid = |reason: String| ToolError::InvalidParams(format!("Invalid pages '{}': {}", spec, reason));
+    let parse_page = |s: &str| -> Result<u32, ToolError> {
+        let page: u32 = s
+            .trim()
+            .parse()
+            .map_err(|_| invalid(format!("'{}' is not a page number", s.trim())))?;
+        if page == 0 || page as usize > total_pages {
+            return Err(invalid(format!(
+                "page {} is out of range (document has {} pages)",
+                page, total_pages
+            )));
+        }
+        Ok(page)
+    };
+
+    let mut pages = Vec::new();
+    for part in spec.split(',').filter(|p| !p.trim().is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (parse_page(start)?, parse_page(end)?);
+                if start > end {
+                    return Err(invalid(format!("range {}-{} is reversed", start, end)));
+                }
+                pages.extend(start..=end);
+            }
+            None => pages.push(parse_page(part)?),
+        }
+    }
+
+    if pages.is_empty() {
+        return Err(invalid("no pages selected".to_string()));
+    }
+
+    pages.sort_unstable();
+    pages.dedup();
+    Ok(pages)
+}
+
+/// Extract the text of the selected pages (all pages when `pages` is None), up to
+/// `MAX_PAGES_PER_READ` at a time
+pub fn extract_text(bytes: &[u8], pages: Option<&str>) -> Result<PdfText, ToolError> {
+    let document = lopdf::Document::load_mem(bytes)
+        .map_err(|e| ToolError::Other(anyhow::anyhow!("Failed to parse PDF: {}", e)))?;
+    let total_pages = document.get_pages().len();
+
+    let mut selected = match pages {
+        Some(spec) => parse_page_ranges(spec, total_pages)?,
+        None => (1..=total_pages as u32).collect(),
+    };
+    let pages_omitted = selected.len().saturating_sub(MAX_PAGES_PER_READ);
+    selected.truncate(MAX_PAGES_PER_READ);
+
+    let pages = selected
+        .into_iter()
+        .map(|page| {
+            // A page that cannot be decoded should not hide the rest of the document
+            let text = document.extract_text(&[page]).unwrap_or_else(|e| {
+                tracing::debug!(page, error = %e, "failed to extract pdf page text");
+                String::new()
+            });
+            (page, text)
+        })
+        .collect();
+
+    Ok(PdfText {
+        total_pages,
+        pages,
+        pages_omitted,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_page_ranges() {
+        assert_eq!(parse_page_ranges("3", 5).unwrap(), vec![3]);
+        assert_eq!(parse_page_ranges("1-3", 5).unwrap(), vec![1, 2, 3]);
+        assert_eq!(parse_page_ranges("5, 1-2,2", 5).unwrap(), vec![1, 2, 5]);
+    }
+
+    #[test]
+    fn test_parse_page_ranges_rejects_invalid() {
+        for spec in ["0", "6", "3-1", "x", "", "1-"] {
+            assert!(
+                matches!(parse_page_ranges(spec, 5), Err(ToolError::InvalidParams(_))),
+                "spec {:?} should be rejected",
+                spec
+            );
+        }
+    }
+}
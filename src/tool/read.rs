@@ -1,8 +1,10 @@
 use super::base::{Tool, ToolContext, ToolError, ToolResult};
+use super::notebook::{render_notebook, Notebook};
+use super::pdf;
+use super::text_file;
 use serde::Deserialize;
 use serde_json::json;
 use std::path::PathBuf;
-use tokio::io::AsyncReadExt;
 
 /// Read tool - reads file contents with line numbers and smart truncation
 pub struct ReadTool {
@@ -18,52 +20,79 @@ impl ReadTool {
         }
     }
 
-    /// Check if a file is likely binary by examining extension and content
-    async fn is_binary_file(path: &PathBuf) -> anyhow::Result<bool> {
-        // 1. Check file extension
-        if let Some(ext) = path.extension() {
-            let ext_str = ext.to_str().unwrap_or("");
-            const BINARY_EXTS: &[&str] = &[
-                "zip", "tar", "gz", "exe", "dll", "so", "jar",
-                "wasm", "pyc", "bin", "dat", "db", "sqlite",
-                "png", "jpg", "jpeg", "gif", "bmp", "ico",
-                "mp3", "mp4", "avi", "mov", "pdf",
-            ];
-            if BINARY_EXTS.contains(&ext_str) {
-                return Ok(true);
-            }
-        }
-
-        // 2. Check file content (first 4KB)
-        let mut file = match tokio::fs::File::open(path).await {
-            Ok(f) => f,
-            Err(_) => return Ok(false), // Can't open = not binary
-        };
+    /// Check if a file is binary by its extension (content is checked when decoding)
+    fn has_binary_extension(path: &std::path::Path) -> bool {
+        const BINARY_EXTS: &[&str] = &[
+            "zip", "tar", "gz", "exe", "dll", "so", "jar",
+            "wasm", "pyc", "bin", "dat", "db", "sqlite",
+            "png", "jpg", "jpeg", "gif", "bmp", "ico",
+            "mp3", "mp4", "avi", "mov",
+        ];
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| BINARY_EXTS.contains(&ext))
+    }
 
-        let mut buffer = [0u8; 4096];
-        let n = match file.read(&mut buffer).await {
-            Ok(n) => n,
-            Err(_) => return Ok(false),
-        };
+    /// Extract text from the selected PDF pages
+    async fn read_pdf(
+        &self,
+        filepath: &PathBuf,
+        pages: Option<&str>,
+        ctx: &ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let bytes = tokio::fs::read(filepath)
+            .await
+            .map_err(|e| ToolError::Other(e.into()))?;
+        let text = pdf::extract_text(&bytes, pages)?;
+        ctx.file_states.record(filepath, &bytes);
 
-        if n == 0 {
-            return Ok(false); // Empty file is not binary
+        let sections: Vec<String> = text
+            .pages
+            .iter()
+            .map(|(page, content)| {
+                format!("--- Page {} of {} ---\n{}", page, text.total_pages, content.trim_end())
+            })
+            .collect();
+
+        let mut output = sections.join("\n\n");
+        if output.len() > self.max_bytes {
+            let mut cut = self.max_bytes;
+            while !output.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            output.truncate(cut);
+            output.push_str(&format!(
+                "\n\n(Output truncated at {} bytes. Use pages to read fewer pages at a time)",
+                self.max_bytes
+            ));
+        } else if text.pages_omitted > 0 {
+            let next = text.pages.last().map(|(p, _)| p + 1).unwrap_or(1);
+            output.push_str(&format!(
+                "\n\n({} more pages selected. At most {} pages are read at once; use pages=\"{}-...\" to continue)",
+                text.pages_omitted,
+                pdf::MAX_PAGES_PER_READ,
+                next
+            ));
+        } else {
+            output.push_str(&format!("\n\n(End of selection - PDF has {} pages)", text.total_pages));
         }
 
-        // Check for null bytes (strong indicator of binary)
-        if buffer[..n].contains(&0) {
-            return Ok(true);
-        }
+        let pages_read: Vec<u32> = text.pages.iter().map(|(p, _)| *p).collect();
+        Ok(ToolResult::new(filepath.to_string_lossy(), output)
+            .with_metadata("total_pages", json!(text.total_pages))
+            .with_metadata("pages_read", json!(pages_read)))
+    }
+}
 
-        // Count non-printable characters
-        let non_printable = buffer[..n]
-            .iter()
-            .filter(|&&b| b < 9 || (b > 13 && b < 32))
-            .count();
+/// Format a line the way `read` shows it: right-aligned 1-based line number, an arrow, the text
+pub(crate) fn format_line(line_num: usize, line: &str) -> String {
+    format!("{:>5}\u{2192}{}", line_num, line)
+}
 
-        // If more than 30% non-printable, it's likely binary
-        Ok(non_printable as f64 / n as f64 > 0.3)
-    }
+fn has_extension(path: &std::path::Path, ext: &str) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case(ext))
 }
 
 #[derive(Debug, Deserialize)]
@@ -73,6 +102,9 @@ struct ReadParams {
     offset: usize,
     #[serde(default = "default_limit")]
     limit: usize,
+    /// Page selection for PDF files, e.g. "1-5"
+    #[serde(default)]
+    pages: Option<String>,
 }
 
 fn default_limit() -> usize {
@@ -87,7 +119,9 @@ impl Tool for ReadTool {
 
     fn description(&self) -> &str {
         "Read file contents with line numbers and smart truncation. \
-         Supports offset/limit for large files. Detects binary files."
+         Supports offset/limit for large files. Detects binary files. \
+         Jupyter notebooks (.ipynb) are rendered cell by cell with cell ids (offset/limit select cells); \
+         PDF text is extracted page by page (use pages to select, e.g. \"1-5\")."
     }
 
     fn input_schema(&self) -> serde_json::Value {
@@ -107,6 +141,10 @@ impl Tool for ReadTool {
                     "type": "integer",
                     "description": "Maximum number of lines to read (default: 2000)",
                     "default": 2000
+                },
+                "pages": {
+                    "type": "string",
+                    "description": "Pages to read from a PDF, e.g. \"3\", \"1-5\" or \"1,3,7-9\" (default: all, at most 20 per read)"
                 }
             },
             "required": ["file_path"]
@@ -137,15 +175,48 @@ impl Tool for ReadTool {
             return Err(ToolError::FileNotFound(filepath));
         }
 
+        // PDFs and notebooks get dedicated renderings
+        if has_extension(&filepath, "pdf") {
+            return self.read_pdf(&filepath, params.pages.as_deref(), ctx).await;
+        }
+        if params.pages.is_some() {
+            return Err(ToolError::InvalidParams(
+                "pages is only supported for PDF files".to_string(),
+            ));
+        }
+        if has_extension(&filepath, "ipynb") {
+            let content = tokio::fs::read_to_string(&filepath)
+                .await
+                .map_err(|e| ToolError::Other(e.into()))?;
+            // Malformed notebooks fall back to the plain text view below
+            if let Ok(notebook) = serde_json::from_str::<Notebook>(&content) {
+                ctx.file_states.record(&filepath, content.as_bytes());
+                let output = render_notebook(&notebook, params.offset, params.limit);
+                let cells_read = notebook
+                    .cells
+                    .len()
+                    .saturating_sub(params.offset)
+                    .min(params.limit);
+                return Ok(ToolResult::new(filepath.to_string_lossy(), output)
+                    .with_metadata("total_cells", json!(notebook.cells.len()))
+                    .with_metadata("cells_read", json!(cells_read)));
+            }
+        }
+
         // 3. Check if binary
-        if Self::is_binary_file(&filepath).await? {
+        if Self::has_binary_extension(&filepath) {
             return Err(ToolError::BinaryFile(filepath));
         }
 
-        // 4. Read file content
-        let content = tokio::fs::read_to_string(&filepath)
+        // 4. Read and decode file content (UTF-8, UTF-16 or legacy single-byte text)
+        let bytes = tokio::fs::read(&filepath)
             .await
             .map_err(|e| ToolError::Other(e.into()))?;
+        let Some((content, format)) = text_file::decode(&bytes) else {
+            return Err(ToolError::BinaryFile(filepath));
+        };
+
+        ctx.file_states.record(&filepath, &bytes);
 
         let lines: Vec<&str> = content.lines().collect();
         let total_lines = lines.len();
@@ -170,7 +241,7 @@ impl Tool for ReadTool {
                 line.to_string()
             };
 
-            let formatted = format!("{:>5}\u{2192}{}", line_num, truncated_line);
+            let formatted = format_line(line_num, &truncated_line);
             let line_bytes = formatted.as_bytes().len() + 1; // +1 for newline
 
             // Check if we've exceeded max bytes
@@ -219,6 +290,9 @@ impl Tool for ReadTool {
         )
         .with_metadata("total_lines", json!(total_lines))
         .with_metadata("lines_read", json!(output_lines.len()))
-        .with_metadata("truncated", json!(truncated_by_bytes || last_line < total_lines)))
+        .with_metadata("truncated", json!(truncated_by_bytes || last_line < total_lines))
+        .with_metadata("encoding", json!(format.encoding_name()))
+        .with_metadata("line_ending", json!(format.line_ending_name()))
+        .with_metadata("bom", json!(format.bom)))
     }
 }
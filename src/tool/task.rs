@@ -1,5 +1,7 @@
+use crate::config::station::SubagentSettings;
 use crate::llm::anthropic::AnthropicClient;
 use crate::subagent::config::{SubagentConfig, SubagentType};
+use crate::subagent::event::{SubagentEvent, SubagentEventKind};
 use crate::subagent::runner::SubagentRunner;
 use crate::tool::base::{Tool, ToolContext, ToolError, ToolResult};
 use crate::tool::ToolRegistry;
@@ -18,16 +20,46 @@ use std::sync::Arc;
 /// Each subagent runs independently with:
 /// - Filtered tool access (only tools appropriate for the task)
 /// - Custom system prompt (specialized instructions)
-/// - Turn limit (max 10 turns in MVP)
+/// - Turn limit (per-type default, overridable via config or the `max_turns` parameter)
 /// - Independent conversation history
 pub struct TaskTool {
     llm_client: Arc<AnthropicClient>,
+    subagent_settings: HashMap<String, SubagentSettings>,
 }
 
 impl TaskTool {
     /// Create a new TaskTool with the given LLM client
     pub fn new(llm_client: Arc<AnthropicClient>) -> Self {
-        Self { llm_client }
+        Self {
+            llm_client,
+            subagent_settings: HashMap::new(),
+        }
+    }
+
+    /// Apply per-type overrides from the `[subagents.<type>]` config sections
+    pub fn with_subagent_settings(mut self, settings: HashMap<String, SubagentSettings>) -> Self {
+        self.subagent_settings = settings;
+        self
+    }
+
+    /// Resolve the subagent configuration, applying config and per-call turn limits.
+    ///
+    /// Precedence: `max_turns` from the Task call, then the config override, then the type default.
+    fn resolve_config(
+        &self,
+        subagent_type: &SubagentType,
+        max_turns: Option<usize>,
+    ) -> SubagentConfig {
+        let config = SubagentConfig::for_type(subagent_type);
+        let configured = self
+            .subagent_settings
+            .get(&config.name)
+            .and_then(|s| s.max_turns);
+
+        match max_turns.or(configured) {
+            Some(limit) => config.with_max_turns(limit),
+            None => config,
+        }
     }
 
     /// Create a filtered tool registry containing only tools allowed for the subagent
@@ -82,6 +114,9 @@ struct TaskParams {
     /// Optional model to use (sonnet, opus, haiku)
     #[serde(default = "default_model")]
     model: String,
+    /// Optional override of the subagent's turn limit
+    #[serde(default)]
+    max_turns: Option<usize>,
 }
 
 fn default_model() -> String {
@@ -104,7 +139,9 @@ impl Tool for TaskTool {
          - Use Explore to find files, understand code structure, or answer architecture questions\n\
          - Use Plan to design implementation approaches for new features\n\
          - Subagents run independently and return their results when complete\n\
-         - Max 10 conversation turns per subagent (MVP limitation)"
+         - Each subagent type has a turn limit (Explore/Plan: 40); raise it with max_turns for \
+         large explorations. When the limit is hit the subagent summarizes its partial findings \
+         and the result is marked incomplete."
     }
 
     fn input_schema(&self) -> serde_json::Value {
@@ -129,6 +166,11 @@ impl Tool for TaskTool {
                     "enum": ["sonnet", "opus", "haiku"],
                     "description": "Optional model to use (defaults to sonnet). \
                                     Use haiku for simple tasks to reduce cost."
+                },
+                "max_turns": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Optional turn limit for this task (defaults to the subagent type's limit)"
                 }
             },
             "required": ["description", "prompt", "subagent_type"]
@@ -172,12 +214,15 @@ impl Tool for TaskTool {
             "launching subagent"
         );
 
-        // Get subagent configuration
-        let config = SubagentConfig::for_type(&subagent_type);
+        // Get subagent configuration (with turn limit overrides applied)
+        let config = self.resolve_config(&subagent_type, params.max_turns);
+        let max_turns = config.max_turns;
 
         // Create filtered tool registry (security boundary)
         let filtered_registry = self.create_filtered_registry(&config);
 
+        let subagent_name = config.name.clone();
+
         // Create and run subagent
         let mut subagent_runner = SubagentRunner::new(
             agent_id.clone(),
@@ -185,7 +230,32 @@ impl Tool for TaskTool {
             filtered_registry,
             ctx.working_dir.clone(),
             (*self.llm_client).clone(),
-        );
+        )
+        .with_shell_manager(ctx.shell_manager.clone())
+        .with_env_filter(ctx.env_filter.clone())
+        .with_resource_limits(ctx.resource_limits)
+        .with_trusted_project(ctx.trusted_project);
+        if let Some(diagnostics) = &ctx.diagnostics {
+            subagent_runner = subagent_runner.with_diagnostics(diagnostics.clone());
+        }
+        if let Some(sandbox) = &ctx.sandbox {
+            subagent_runner = subagent_runner.with_sandbox(sandbox.clone());
+        }
+        if let Some(limiter) = &ctx.output_limiter {
+            subagent_runner = subagent_runner.with_output_limiter(limiter.clone());
+        }
+
+        // Stream progress to the parent when it listens for subagent events
+        if let Some(sink) = &ctx.subagent_events {
+            let _ = sink.send(SubagentEvent {
+                subagent_id: agent_id.clone(),
+                subagent_type: subagent_name,
+                kind: SubagentEventKind::Started {
+                    description: params.description.clone(),
+                },
+            });
+            subagent_runner = subagent_runner.with_event_sink(sink.clone());
+        }
 
         // Execute the task (blocks until complete or max turns)
         let result = subagent_runner
@@ -203,18 +273,40 @@ impl Tool for TaskTool {
         tracing::info!(
             agent_id = %agent_id,
             turns = result.turns,
+            incomplete = result.incomplete,
             output_len = result.output.len(),
             "subagent task completed successfully"
         );
 
+        let mut output = if result.incomplete {
+            format!(
+                "[Incomplete: the subagent reached its {}-turn limit. The summary below covers \
+                 its findings so far; re-run with a higher max_turns to continue.]\n\n{}",
+                max_turns, result.output
+            )
+        } else {
+            result.output
+        };
+
+        if !result.terminated_shells.is_empty() {
+            output.push_str(&format!(
+                "\n\n[Background shells left running by the subagent were terminated: {}. \
+                 Their output is still available via bash_output.]",
+                result.terminated_shells.join(", ")
+            ));
+        }
+
         // Return formatted result
         Ok(ToolResult::new(
             format!("Subagent task: {}", params.description),
-            result.output,
+            output,
         )
         .with_metadata("agent_id", json!(agent_id))
         .with_metadata("subagent_type", json!(params.subagent_type))
         .with_metadata("turns", json!(result.turns))
+        .with_metadata("max_turns", json!(max_turns))
+        .with_metadata("incomplete", json!(result.incomplete))
+        .with_metadata("terminated_shells", json!(result.terminated_shells))
         .with_metadata("conversation_length", json!(result.conversation.len())))
     }
 }
@@ -241,13 +333,13 @@ mod tests {
     }
 
     fn create_test_context() -> ToolContext {
-        ToolContext {
-            session_id: "test-session".to_string(),
-            message_id: "test-msg".to_string(),
-            agent: "test-agent".to_string(),
-            working_dir: PathBuf::from("/tmp"),
-            shell_manager: Arc::new(BackgroundShellManager::new()),
-        }
+        ToolContext::new(
+            "test-session",
+            "test-msg",
+            "test-agent",
+            PathBuf::from("/tmp"),
+            Arc::new(BackgroundShellManager::new()),
+        )
     }
 
     #[test]
@@ -290,6 +382,23 @@ mod tests {
         assert!(filtered.get("edit").is_some());
     }
 
+    #[test]
+    fn test_resolve_config_turn_limit_precedence() {
+        let mut settings = HashMap::new();
+        settings.insert(
+            "Explore".to_string(),
+            SubagentSettings { max_turns: Some(60) },
+        );
+        let tool = TaskTool::new(create_test_llm_client()).with_subagent_settings(settings);
+
+        // Type default when nothing is configured
+        assert_eq!(tool.resolve_config(&SubagentType::Plan, None).max_turns, 40);
+        // Config override
+        assert_eq!(tool.resolve_config(&SubagentType::Explore, None).max_turns, 60);
+        // Per-call override wins
+        assert_eq!(tool.resolve_config(&SubagentType::Explore, Some(5)).max_turns, 5);
+    }
+
     #[tokio::test]
     async fn test_invalid_subagent_type() {
         let llm_client = create_test_llm_client();
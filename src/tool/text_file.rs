@@ -0,0 +1,298 @@
+//! Decoding and re-encoding of text files, so edits keep a file's encoding, BOM and line endings
+//!
+//! Text is handed to the tools LF-normalized and written back in one encoding with one line
+//! ending. Files that mix line endings, or UTF-8 with stray bytes of another encoding, cannot be
+//! written back that way without changing lines the edit didn't touch, so edits to them are
+//! refused (see [`TextFormat::check_editable`]).
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+use std::path::Path;
+
+use super::base::ToolError;
+
+/// Bytes inspected when guessing whether content is binary or BOM-less UTF-16
+const SNIFF_BYTES: usize = 4096;
+
+/// Line terminator used by a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::CrLf => "CRLF",
+        }
+    }
+}
+
+/// How a text file is stored on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextFormat {
+    pub encoding: &'static Encoding,
+    /// The file starts with a byte order mark
+    pub bom: bool,
+    /// The most common line ending; the other one is used too when `mixed_line_endings`
+    pub line_ending: LineEnding,
+    pub mixed_line_endings: bool,
+    /// The file is UTF-8 apart from bytes that are not (shown as U+FFFD)
+    pub invalid_bytes: bool,
+}
+
+impl Default for TextFormat {
+    fn default() -> Self {
+        Self {
+            encoding: UTF_8,
+            bom: false,
+            line_ending: LineEnding::Lf,
+            mixed_line_endings: false,
+            invalid_bytes: false,
+        }
+    }
+}
+
+impl TextFormat {
+    /// Encoding name as reported in tool metadata (e.g. `UTF-8`, `UTF-16LE`, `windows-1252`)
+    pub fn encoding_name(&self) -> &'static str {
+        self.encoding.name()
+    }
+
+    /// Line ending as reported in tool metadata: `LF`, `CRLF` or `mixed`
+    pub fn line_ending_name(&self) -> &'static str {
+        if self.mixed_line_endings {
+            "mixed"
+        } else {
+            self.line_ending.as_str()
+        }
+    }
+
+    /// Refuse to edit a file that [`encode`](Self::encode) could not write back unchanged
+    /// outside the edited text
+    pub fn check_editable(&self, path: &Path) -> Result<(), ToolError> {
+        let reason = if self.invalid_bytes {
+            "it is UTF-8 but contains bytes that are not valid UTF-8"
+        } else if self.mixed_line_endings {
+            "it mixes LF and CRLF line endings"
+        } else {
+            return Ok(());
+        };
+        Err(ToolError::UnsupportedFormat {
+            path: path.to_path_buf(),
+            reason: reason.to_string(),
+        })
+    }
+
+    /// Convert LF-normalized text back to bytes in this format
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, ToolError> {
+        let text = match self.line_ending {
+            LineEnding::Lf => std::borrow::Cow::Borrowed(text),
+            LineEnding::CrLf => std::borrow::Cow::Owned(text.replace('\n', "\r\n")),
+        };
+
+        let mut bytes = Vec::with_capacity(text.len() + 3);
+        // encoding_rs only decodes UTF-16, so encode it by hand
+        if self.encoding == UTF_16LE || self.encoding == UTF_16BE {
+            let little_endian = self.encoding == UTF_16LE;
+            let units = std::iter::once('\u{FEFF}' as u16)
+                .filter(|_| self.bom)
+                .chain(text.encode_utf16());
+            for unit in units {
+                bytes.extend(if little_endian {
+                    unit.to_le_bytes()
+                } else {
+                    unit.to_be_bytes()
+                });
+            }
+            return Ok(bytes);
+        }
+
+        if self.bom && self.encoding == UTF_8 {
+            bytes.extend_from_slice(b"\xEF\xBB\xBF");
+        }
+        let (encoded, _, unmappable) = self.encoding.encode(&text);
+        if unmappable {
+            return Err(ToolError::InvalidParams(format!(
+                "The new content contains characters that cannot be represented in the file's {} encoding",
+                self.encoding_name()
+            )));
+        }
+        bytes.extend_from_slice(&encoded);
+        Ok(bytes)
+    }
+}
+
+/// Decode file bytes into LF-normalized text and the format they were stored in
+///
+/// Returns None when the content looks binary.
+pub fn decode(bytes: &[u8]) -> Option<(String, TextFormat)> {
+    let (encoding, bom) = match Encoding::for_bom(bytes) {
+        Some((encoding, bom_len)) => (encoding, Some(bom_len)),
+        None => (detect_encoding(bytes)?, None),
+    };
+
+    let body = &bytes[bom.unwrap_or(0)..];
+    let (text, invalid_bytes) = match encoding.decode_without_bom_handling_and_without_replacement(body) {
+        Some(text) => (text.into_owned(), false),
+        // Stray bytes in otherwise UTF-8 text (e.g. one Latin-1 character pasted in)
+        None if encoding == UTF_8 => (String::from_utf8_lossy(body).into_owned(), true),
+        None => return None,
+    };
+
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    let line_ending = if crlf > lf {
+        LineEnding::CrLf
+    } else {
+        LineEnding::Lf
+    };
+
+    let format = TextFormat {
+        encoding,
+        bom: bom.is_some(),
+        line_ending,
+        mixed_line_endings: crlf > 0 && lf > 0,
+        invalid_bytes,
+    };
+    Some((normalize_newlines(&text), format))
+}
+
+/// Convert CRLF line endings to LF
+pub fn normalize_newlines(text: &str) -> String {
+    text.replace("\r\n", "\n")
+}
+
+/// Guess the encoding of content without a BOM (None for binary content)
+fn detect_encoding(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(SNIFF_BYTES)];
+    if sample.is_empty() {
+        return Some(UTF_8);
+    }
+
+    // Mostly-ASCII UTF-16 has a zero in every other byte
+    let pairs = sample.len() / 2;
+    if pairs > 0 && sample.len().is_multiple_of(2) {
+        let zeros_at = |parity: usize| {
+            sample.iter().skip(parity).step_by(2).filter(|&&b| b == 0).count() as f64 / pairs as f64
+        };
+        let (even, odd) = (zeros_at(0), zeros_at(1));
+        if odd > 0.4 && even < 0.05 {
+            return Some(UTF_16LE);
+        }
+        if even > 0.4 && odd < 0.05 {
+            return Some(UTF_16BE);
+        }
+    }
+
+    // Null bytes are a strong indicator of binary
+    if sample.contains(&0) {
+        return None;
+    }
+
+    // If more than 30% non-printable, it's likely binary
+    let non_printable = sample
+        .iter()
+        .filter(|&&b| b < 9 || (b > 13 && b < 32))
+        .count();
+    if non_printable as f64 / sample.len() as f64 > 0.3 {
+        return None;
+    }
+
+    // Any valid multi-byte UTF-8 makes this UTF-8 with stray bytes rather than legacy text
+    let utf8 = bytes
+        .utf8_chunks()
+        .any(|chunk| !chunk.valid().is_ascii());
+    if utf8 {
+        Some(UTF_8)
+    } else {
+        // Legacy single-byte text (a superset of Latin-1)
+        Some(WINDOWS_1252)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(bytes: &[u8]) -> (String, TextFormat, Vec<u8>) {
+        let (text, format) = decode(bytes).expect("text content");
+        let encoded = format.encode(&text).unwrap();
+        (text, format, encoded)
+    }
+
+    #[test]
+    fn test_utf8_crlf_with_bom() {
+        let bytes = b"\xEF\xBB\xBFone\r\ntwo\r\n";
+        let (text, format, encoded) = round_trip(bytes);
+        assert_eq!(text, "one\ntwo\n");
+        assert_eq!(format.encoding_name(), "UTF-8");
+        assert!(format.bom);
+        assert_eq!(format.line_ending, LineEnding::CrLf);
+        assert_eq!(encoded, bytes);
+    }
+
+    #[test]
+    fn test_utf16_with_and_without_bom() {
+        let mut le = vec![0xFF, 0xFE];
+        le.extend("héllo\n".encode_utf16().flat_map(u16::to_le_bytes));
+        let (text, format, encoded) = round_trip(&le);
+        assert_eq!(text, "héllo\n");
+        assert_eq!(format.encoding_name(), "UTF-16LE");
+        assert_eq!(encoded, le);
+
+        let be: Vec<u8> = "plain text\n".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        let (text, format, encoded) = round_trip(&be);
+        assert_eq!(text, "plain text\n");
+        assert_eq!(format.encoding_name(), "UTF-16BE");
+        assert!(!format.bom);
+        assert_eq!(encoded, be);
+    }
+
+    #[test]
+    fn test_latin1_fallback_and_unmappable() {
+        let bytes = b"caf\xE9\n";
+        let (text, format, encoded) = round_trip(bytes);
+        assert_eq!(text, "café\n");
+        assert_eq!(format.encoding_name(), "windows-1252");
+        assert_eq!(encoded, bytes);
+
+        assert!(matches!(
+            format.encode("snowman ☃"),
+            Err(ToolError::InvalidParams(_))
+        ));
+    }
+
+    #[test]
+    fn test_mixed_files_are_not_editable() {
+        let path = Path::new("mixed.txt");
+
+        let (text, format) = decode(b"one\r\ntwo\nthree\r\n").unwrap();
+        assert_eq!(text, "one\ntwo\nthree\n");
+        assert_eq!(format.line_ending, LineEnding::CrLf);
+        assert_eq!(format.line_ending_name(), "mixed");
+        assert!(matches!(
+            format.check_editable(path),
+            Err(ToolError::UnsupportedFormat { reason, .. }) if reason.contains("LF and CRLF")
+        ));
+
+        // One Latin-1 byte in UTF-8 text doesn't turn the file into windows-1252
+        let (text, format) = decode(b"na\xC3\xAFve caf\xE9\n").unwrap();
+        assert_eq!(text, "naïve caf\u{FFFD}\n");
+        assert_eq!(format.encoding_name(), "UTF-8");
+        assert!(format.invalid_bytes);
+        assert!(format.check_editable(path).is_err());
+
+        let (_, format) = decode(b"caf\xE9\r\n").unwrap();
+        assert!(format.check_editable(path).is_ok());
+    }
+
+    #[test]
+    fn test_binary_is_rejected() {
+        assert!(decode(&[0x00, 0x01, 0x02, 0xFF, 0xFE, 0xFD]).is_none());
+        assert!(decode(b"\x01\x02\x03\x04\x05abc").is_none());
+    }
+}
@@ -293,13 +293,13 @@ mod tests {
     use tempfile::tempdir;
 
     fn create_test_context(session_id: &str) -> ToolContext {
-        ToolContext {
-            session_id: session_id.to_string(),
-            message_id: "test-message".to_string(),
-            agent: "test-agent".to_string(),
-            working_dir: PathBuf::from("/tmp"),
-            shell_manager: std::sync::Arc::new(crate::process::BackgroundShellManager::new()),
-        }
+        ToolContext::new(
+            session_id,
+            "test-message",
+            "test-agent",
+            PathBuf::from("/tmp"),
+            std::sync::Arc::new(crate::process::BackgroundShellManager::new()),
+        )
     }
 
     #[tokio::test]
@@ -283,13 +283,13 @@ mod tests {
     #[tokio::test]
     async fn test_tool_validates_empty_query() {
         let tool = WebSearchTool::new();
-        let ctx = ToolContext {
-            session_id: "test".to_string(),
-            message_id: "test".to_string(),
-            agent: "test".to_string(),
-            working_dir: std::path::PathBuf::from("/tmp"),
-            shell_manager: Arc::new(crate::process::BackgroundShellManager::new()),
-        };
+        let ctx = ToolContext::new(
+            "test",
+            "test",
+            "test",
+            std::path::PathBuf::from("/tmp"),
+            Arc::new(crate::process::BackgroundShellManager::new()),
+        );
 
         let params = json!({
             "query": "   "  // Empty/whitespace query
@@ -1,4 +1,5 @@
 use super::base::{Tool, ToolContext, ToolError, ToolResult};
+use super::text_file::{self, TextFormat};
 use serde::Deserialize;
 use serde_json::json;
 use similar::TextDiff;
@@ -43,7 +44,8 @@ impl Tool for WriteTool {
 
     fn description(&self) -> &str {
         "Write content to a file. Creates the file if it doesn't exist, \
-         or overwrites it if it does. Shows a diff of changes for existing files."
+         or overwrites it if it does. Shows a diff of changes for existing files. \
+         An existing file must have been read with the read tool first, and not changed since."
     }
 
     fn input_schema(&self) -> serde_json::Value {
@@ -81,21 +83,41 @@ impl Tool for WriteTool {
         // 1. Resolve file path (relative to working directory)
         let filepath = ctx.resolve_path(&params.file_path)?;
 
-        // 2. Read old content if file exists
-        let old_content = if filepath.exists() {
-            tokio::fs::read_to_string(&filepath).await.ok()
+        // 2. Read old content if file exists, refusing to overwrite changes the model hasn't seen
+        let existed = filepath.exists();
+        let old_file = if existed {
+            let bytes = tokio::fs::read(&filepath)
+                .await
+                .map_err(|e| ToolError::Other(e.into()))?;
+            ctx.file_states.check(&filepath, &bytes)?;
+            text_file::decode(&bytes)
         } else {
             None
         };
 
-        // 3. Generate diff or creation message
-        let diff = if let Some(old) = &old_content {
-            Self::generate_diff(&filepath, old, &params.content)
+        // 3. Keep an existing text file's encoding, BOM and line endings; new files are
+        //    written as given
+        let (new_content, new_bytes, format) = match &old_file {
+            Some((_, format)) => {
+                let content = text_file::normalize_newlines(&params.content);
+                let bytes = format.encode(&content)?;
+                (content, bytes, *format)
+            }
+            None => (
+                params.content.clone(),
+                params.content.clone().into_bytes(),
+                TextFormat::default(),
+            ),
+        };
+
+        // 4. Generate diff or creation message
+        let diff = if let Some((old, _)) = &old_file {
+            Self::generate_diff(&filepath, old, &new_content)
         } else {
             format!("Creating new file: {}\n", filepath.display())
         };
 
-        // 4. Create parent directories if needed
+        // 5. Create parent directories if needed
         if let Some(parent) = filepath.parent() {
             if !parent.exists() {
                 tokio::fs::create_dir_all(parent)
@@ -104,29 +126,33 @@ impl Tool for WriteTool {
             }
         }
 
-        // 5. Write the file
-        tokio::fs::write(&filepath, &params.content)
+        // 6. Write the file
+        tokio::fs::write(&filepath, &new_bytes)
             .await
             .map_err(|e| ToolError::Other(e.into()))?;
+        ctx.file_states.record_write(&filepath, &new_bytes);
 
-        // 6. Build output message
+        // 7. Build output message
         let mut output = format!("Successfully wrote to: {}\n\n", filepath.display());
         output.push_str(&diff);
 
         tracing::debug!(
             resolved_path = %filepath.display(),
-            existed = old_content.is_some(),
-            bytes_written = params.content.len(),
+            existed,
+            bytes_written = new_bytes.len(),
             "tool write done"
         );
 
-        // 7. Return result
+        // 8. Return result
         Ok(ToolResult::new(
             filepath.to_string_lossy(),
             output,
         )
         .with_metadata("filepath", json!(filepath.to_string_lossy()))
-        .with_metadata("existed", json!(old_content.is_some()))
-        .with_metadata("bytes_written", json!(params.content.len())))
+        .with_metadata("existed", json!(existed))
+        .with_metadata("bytes_written", json!(new_bytes.len()))
+        .with_metadata("encoding", json!(format.encoding_name()))
+        .with_metadata("line_ending", json!(format.line_ending.as_str()))
+        .with_metadata("bom", json!(format.bom)))
     }
 }
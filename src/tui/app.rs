@@ -1,7 +1,10 @@
 use crate::event::{Event, EventResult};
 use crate::agent::{AgentEvent, AgentRunner};
+use crate::config::Config;
 use crate::llm::anthropic::AnthropicClient;
-use crate::tui::{ChatMessage, ErrorDetails, InputWidget, MessageList};
+use crate::repo_map::RepoMap;
+use crate::tui::shell_panel::{ShellPanelAction, ShellSnapshot, ShellTail, TAIL_LINES};
+use crate::tui::{ChatMessage, ErrorDetails, InputWidget, MessageList, ShellPanel, SubagentGroup, ToolOutputTail};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
@@ -10,12 +13,22 @@ use ratatui::{
     widgets::Paragraph,
     Frame,
 };
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
 /// Spinner frames for loading animation
 const SPINNER_FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
 
+/// How often background shell state is refreshed (status bar counter only)
+const SHELL_REFRESH_IDLE: Duration = Duration::from_secs(1);
+/// How often background shell state is refreshed while the `/bashes` panel is open
+const SHELL_REFRESH_PANEL: Duration = Duration::from_millis(250);
+
+/// Least time between turns started by background shell notifications
+const NOTIFICATION_TURN_INTERVAL: Duration = Duration::from_secs(5);
+/// Turns started by notifications before the agent waits for the user again
+const MAX_NOTIFICATION_TURNS: usize = 3;
+
 /// Main application state
 pub struct App {
     /// UI-agnostic agent runner (conversation + tool loop)
@@ -40,11 +53,31 @@ pub struct App {
     needs_render: bool,
     /// Last terminal size to detect resizes
     last_terminal_size: (u16, u16),
+    /// Background shell panel (open while `/bashes` is shown)
+    shell_panel: Option<ShellPanel>,
+    /// Snapshots of background shell state, produced by refresh tasks
+    shell_snapshot_tx: mpsc::UnboundedSender<ShellSnapshot>,
+    shell_snapshot_rx: mpsc::UnboundedReceiver<ShellSnapshot>,
+    /// Whether a snapshot refresh task is in flight
+    shell_refresh_pending: bool,
+    /// When the last snapshot refresh was requested
+    last_shell_refresh: Option<Instant>,
+    /// Number of running background shells (from the latest snapshot)
+    running_shells: usize,
+    /// When the last turn started by shell notifications began
+    last_notification_turn: Option<Instant>,
+    /// Turns started by shell notifications since the user last sent a message
+    notification_turns: usize,
+    /// Tool call whose risky command waits for the user's y/n
+    pending_approval: Option<String>,
+    /// Repository maps built for `/map`
+    repo_map_tx: mpsc::UnboundedSender<RepoMap>,
+    repo_map_rx: mpsc::UnboundedReceiver<RepoMap>,
 }
 
 impl App {
     /// Create a new application instance
-    pub fn new(llm_client: AnthropicClient) -> Self {
+    pub fn new(llm_client: AnthropicClient, config: &Config) -> Self {
         let mut message_list = MessageList::new();
         let mut current_id = 0;
 
@@ -63,12 +96,22 @@ impl App {
 
         message_list.add_message(ChatMessage::system(
             current_id,
-            "Controls: Enter=send | Shift+Enter=newline | ↑↓=scroll | End=bottom | Ctrl+C=quit".to_string(),
+            "Controls: Enter=send | Shift+Enter=newline | ↑↓=scroll | End=bottom | Ctrl+O=toggle subagent details | /bashes=background shells | /map=repository map | Esc=skip diagnostics | Ctrl+C=quit".to_string(),
         ));
         current_id += 1;
 
+        // Project checkers wait for the user's go-ahead
+        let agent = AgentRunner::with_config(llm_client, config);
+        if let Some(notice) = agent.diagnostics_notice() {
+            message_list.add_message(ChatMessage::system(current_id, notice));
+            current_id += 1;
+        }
+
+        let (shell_snapshot_tx, shell_snapshot_rx) = mpsc::unbounded_channel();
+        let (repo_map_tx, repo_map_rx) = mpsc::unbounded_channel();
+
         Self {
-            agent: AgentRunner::new(llm_client),
+            agent,
             message_list,
             current_message_id: current_id,
             input: InputWidget::new(),
@@ -79,6 +122,25 @@ impl App {
             streaming_start_time: None,
             needs_render: true,  // Start with initial render needed
             last_terminal_size: (0, 0),  // Will be set on first render
+            shell_panel: None,
+            shell_snapshot_tx,
+            shell_snapshot_rx,
+            shell_refresh_pending: false,
+            last_shell_refresh: None,
+            running_shells: 0,
+            last_notification_turn: None,
+            notification_turns: 0,
+            pending_approval: None,
+            repo_map_tx,
+            repo_map_rx,
+        }
+    }
+
+    /// Kill all background shells (called on exit)
+    pub async fn shutdown(&self) {
+        let killed = self.agent.shell_manager().kill_all().await;
+        if killed > 0 {
+            tracing::info!(killed, "killed background shells on exit");
         }
     }
 
@@ -130,6 +192,209 @@ impl App {
         self.should_quit
     }
 
+    /// Apply finished shell snapshots and schedule the next refresh
+    fn poll_shells(&mut self) {
+        while let Ok(snapshot) = self.shell_snapshot_rx.try_recv() {
+            self.shell_refresh_pending = false;
+
+            let running = snapshot
+                .shells
+                .iter()
+                .filter(|s| s.status == crate::process::background_shell::ShellStatus::Running)
+                .count();
+            if running != self.running_shells {
+                self.running_shells = running;
+                self.mark_dirty();
+            }
+
+            if let Some(panel) = &mut self.shell_panel {
+                panel.update(snapshot);
+                self.mark_dirty();
+            }
+        }
+
+        let interval = if self.shell_panel.is_some() {
+            SHELL_REFRESH_PANEL
+        } else {
+            SHELL_REFRESH_IDLE
+        };
+        let due = self
+            .last_shell_refresh
+            .is_none_or(|last| last.elapsed() >= interval);
+        if due && !self.shell_refresh_pending {
+            self.request_shell_refresh();
+        }
+    }
+
+    /// Collect a shell snapshot in the background (the manager's lock is async)
+    fn request_shell_refresh(&mut self) {
+        let manager = self.agent.shell_manager();
+        let tailing = self
+            .shell_panel
+            .as_ref()
+            .and_then(|panel| panel.tailing())
+            .map(str::to_string);
+        let tx = self.shell_snapshot_tx.clone();
+
+        self.shell_refresh_pending = true;
+        self.last_shell_refresh = Some(Instant::now());
+
+        tokio::spawn(async move {
+            let shells = manager.summary().await;
+            let tail = match tailing {
+                Some(shell_id) => {
+                    let last = |lines: Vec<String>| {
+                        let skip = lines.len().saturating_sub(TAIL_LINES);
+                        lines.into_iter().skip(skip).collect::<Vec<_>>()
+                    };
+                    let stdout = manager.get_stdout(&shell_id).await.unwrap_or_default();
+                    let stderr = manager.get_stderr(&shell_id).await.unwrap_or_default();
+                    Some(ShellTail {
+                        shell_id,
+                        stdout: last(stdout),
+                        stderr: last(stderr),
+                    })
+                }
+                None => None,
+            };
+            let _ = tx.send(ShellSnapshot { shells, tail });
+        });
+    }
+
+    /// Build the repository map in the background for `/map`
+    fn request_repo_map(&mut self) {
+        let build = self.agent.build_repo_map();
+        let tx = self.repo_map_tx.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(build.await);
+        });
+
+        self.message_list.add_message(ChatMessage::system(
+            self.current_message_id,
+            "Building repository map...".to_string(),
+        ));
+        self.current_message_id += 1;
+        self.mark_dirty();
+    }
+
+    /// Let the project's diagnostics checkers and language servers run, now and in later
+    /// sessions
+    fn trust_project(&mut self) {
+        let message = if !self.agent.trust_project() {
+            "This project is already trusted.".to_string()
+        } else {
+            let saved = crate::config::load_or_create_config().and_then(|mut config| {
+                config.diagnostics.trust(self.agent.working_dir());
+                crate::config::save_config(&config)
+            });
+            match saved {
+                Ok(()) => "Project trusted; its diagnostics checkers and language servers run from now on.".to_string(),
+                Err(e) => format!(
+                    "Project trusted for this session (saving the config failed: {})",
+                    e
+                ),
+            }
+        };
+        self.message_list
+            .add_message(ChatMessage::system(self.current_message_id, message));
+        self.current_message_id += 1;
+        self.mark_dirty();
+    }
+
+    /// Show repository maps that finished building
+    fn poll_repo_map(&mut self) {
+        while let Ok(map) = self.repo_map_rx.try_recv() {
+            self.message_list.add_message(ChatMessage::system(
+                self.current_message_id,
+                format!(
+                    "Repository map ({} of {} files, {} symbols):\n\n{}",
+                    map.shown_files,
+                    map.total_files,
+                    map.shown_symbols,
+                    map.text.trim_end()
+                ),
+            ));
+            self.current_message_id += 1;
+            self.message_list.enable_auto_scroll();
+            self.mark_dirty();
+        }
+    }
+
+    /// Let the agent react to background shell notifications that arrive while it is idle.
+    /// At most one such turn per [`NOTIFICATION_TURN_INTERVAL`], and [`MAX_NOTIFICATION_TURNS`]
+    /// in a row; after that the updates wait for the user's next message.
+    fn start_notification_turn_if_idle(&mut self) {
+        if self.is_loading
+            || self.notification_turns >= MAX_NOTIFICATION_TURNS
+            || !self.agent.has_shell_notifications()
+        {
+            return;
+        }
+        if self
+            .last_notification_turn
+            .is_some_and(|last| last.elapsed() < NOTIFICATION_TURN_INTERVAL)
+        {
+            return;
+        }
+
+        if let Some(receiver) = self.agent.start_notification_turn() {
+            self.last_notification_turn = Some(Instant::now());
+            self.notification_turns += 1;
+            self.message_list.add_message(ChatMessage::system(
+                self.current_message_id,
+                "🔔 Background shell update - resuming the agent".to_string(),
+            ));
+            self.current_message_id += 1;
+            if self.notification_turns == MAX_NOTIFICATION_TURNS {
+                self.message_list.add_message(ChatMessage::system(
+                    self.current_message_id,
+                    "Further background shell updates will be passed on with your next message"
+                        .to_string(),
+                ));
+                self.current_message_id += 1;
+            }
+            self.is_loading = true;
+            self.streaming_start_time = None;
+            self.stream_receiver = Some(receiver);
+            self.mark_dirty();
+        }
+    }
+
+    /// Open the `/bashes` panel
+    fn open_shell_panel(&mut self) {
+        self.shell_panel = Some(ShellPanel::new());
+        self.request_shell_refresh();
+        self.mark_dirty();
+    }
+
+    /// Handle a key while the `/bashes` panel is open
+    fn handle_shell_panel_key(&mut self, key: KeyEvent) {
+        let Some(panel) = &mut self.shell_panel else {
+            return;
+        };
+
+        match panel.handle_key(key) {
+            ShellPanelAction::Continue => {}
+            ShellPanelAction::Refresh => self.request_shell_refresh(),
+            ShellPanelAction::Kill(shell_id) => {
+                let manager = self.agent.shell_manager();
+                let tx = self.shell_snapshot_tx.clone();
+                self.shell_refresh_pending = true;
+                tokio::spawn(async move {
+                    if let Err(e) = manager.kill(&shell_id).await {
+                        tracing::warn!(shell_id = %shell_id, error = %e, "failed to kill shell");
+                    }
+                    let _ = tx.send(ShellSnapshot {
+                        shells: manager.summary().await,
+                        tail: None,
+                    });
+                });
+            }
+            ShellPanelAction::Close => self.shell_panel = None,
+        }
+        self.mark_dirty();
+    }
+
     /// Check for agent events.
     pub(crate) fn poll_stream(&mut self) -> Option<AgentEvent> {
         if let Some(receiver) = &mut self.stream_receiver {
@@ -186,6 +451,8 @@ impl App {
                 content,
                 is_error,
             } => {
+                // The result replaces the live output tail
+                self.message_list.remove_tool_output(&tool_use_id);
                 let ui_prefix = if is_error { "❌" } else { "✅" };
                 let ui = format!("{} Tool result: {} ({})\n{}", ui_prefix, tool_name, tool_use_id, content);
                 self.message_list
@@ -242,6 +509,61 @@ impl App {
                 self.current_message_id += 1;
                 self.mark_dirty();
             }
+            AgentEvent::SubagentProgress {
+                parent_tool_use_id,
+                event,
+            } => {
+                if let Some(group) = self.message_list.get_subagent_group_mut(&parent_tool_use_id) {
+                    group.apply(&event.kind);
+                } else {
+                    let mut group = SubagentGroup::new(parent_tool_use_id, &event);
+                    group.apply(&event.kind);
+                    self.message_list
+                        .add_message(ChatMessage::subagent_group(self.current_message_id, group));
+                    self.current_message_id += 1;
+                }
+                self.mark_dirty();
+            }
+            AgentEvent::ToolProgress {
+                tool_use_id,
+                tool_name,
+                progress,
+            } => {
+                if let Some(tail) = self.message_list.get_tool_output_mut(&tool_use_id) {
+                    tail.push(&progress);
+                } else {
+                    let mut tail = ToolOutputTail::new(tool_use_id, tool_name);
+                    tail.push(&progress);
+                    self.message_list
+                        .add_message(ChatMessage::tool_output(self.current_message_id, tail));
+                    self.current_message_id += 1;
+                }
+                self.mark_dirty();
+            }
+            AgentEvent::DiagnosticsStart { files } => {
+                self.message_list.add_message(ChatMessage::system(
+                    self.current_message_id,
+                    format!("🩺 Running diagnostics for {} modified file(s)... (Esc to skip)", files),
+                ));
+                self.current_message_id += 1;
+                self.mark_dirty();
+            }
+            AgentEvent::CommandApprovalRequest {
+                tool_use_id,
+                command,
+                reasons,
+            } => {
+                let msg = format!(
+                    "⚠️  The agent wants to run:\n  {}\n{}\nRun it? (y = yes, n = no)",
+                    command, reasons
+                );
+                self.message_list
+                    .add_message(ChatMessage::system(self.current_message_id, msg));
+                self.current_message_id += 1;
+                self.message_list.enable_auto_scroll();
+                self.pending_approval = Some(tool_use_id);
+                self.mark_dirty();
+            }
             AgentEvent::PlanApprovalRequest {
                 plan_content,
                 plan_file,
@@ -270,6 +592,12 @@ impl App {
                 self.should_quit = true;
                 Ok(())
             }
+            Event::Tick => {
+                self.poll_shells();
+                self.poll_repo_map();
+                self.start_notification_turn_if_idle();
+                Ok(())
+            }
             _ => Ok(()),
         }
     }
@@ -300,6 +628,43 @@ impl App {
             return Ok(());
         }
 
+        // A command waiting for approval takes the answer before anything else
+        if let Some(tool_use_id) = self.pending_approval.clone() {
+            let approved = match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => true,
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => false,
+                _ => return Ok(()),
+            };
+            self.agent.respond_approval(&tool_use_id, approved);
+            self.pending_approval = None;
+            self.message_list.add_message(ChatMessage::system(
+                self.current_message_id,
+                if approved { "✅ Command approved" } else { "🚫 Command refused" }.to_string(),
+            ));
+            self.current_message_id += 1;
+            self.mark_dirty();
+            return Ok(());
+        }
+
+        // The /bashes panel captures all other keys while open
+        if self.shell_panel.is_some() {
+            self.handle_shell_panel_key(key);
+            return Ok(());
+        }
+
+        // Esc skips diagnostics checkers running for the current turn
+        if key.code == KeyCode::Esc && self.is_loading {
+            self.agent.cancel_diagnostics();
+            return Ok(());
+        }
+
+        // Handle Ctrl+O to expand/collapse subagent progress groups
+        if key.code == KeyCode::Char('o') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.message_list.toggle_subagent_groups();
+            self.mark_dirty();
+            return Ok(());
+        }
+
         // Handle scroll keys (Up/Down/PageUp/PageDown/Home/End)
         match key.code {
             KeyCode::Up => {
@@ -377,6 +742,24 @@ impl App {
             return;
         }
 
+        // Local commands are handled by the UI, not sent to the model
+        if text.trim() == "/bashes" {
+            self.open_shell_panel();
+            return;
+        }
+        if text.trim() == "/map" {
+            self.request_repo_map();
+            return;
+        }
+        // `/diagnostics trust` is the older name
+        if matches!(text.trim(), "/trust" | "/diagnostics trust") {
+            self.trust_project();
+            return;
+        }
+
+        // The user is back: shell notifications may start turns again
+        self.notification_turns = 0;
+
         // Add user message
         let user_msg = ChatMessage::user(self.current_message_id, text.clone());
         self.message_list.add_message(user_msg);
@@ -403,6 +786,10 @@ impl App {
         self.render_chat(frame, chunks[0]);
         self.render_status(frame, chunks[1]);
         self.render_input(frame, chunks[2]);
+
+        if let Some(panel) = &self.shell_panel {
+            panel.render(frame);
+        }
     }
 
     /// Render chat history
@@ -423,6 +810,11 @@ impl App {
         } else {
             format!("✓ Ready · Messages: {}", self.message_list.len())
         };
+        let status_text = if self.running_shells > 0 {
+            format!("{} · Jobs: {} running (/bashes)", status_text, self.running_shells)
+        } else {
+            status_text
+        };
 
         let lines = vec![
             // Top separator line
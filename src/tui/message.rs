@@ -1,4 +1,7 @@
+use crate::subagent::{SubagentEvent, SubagentEventKind};
+use crate::tool::base::ToolProgress;
 use chrono::{DateTime, Local};
+use std::collections::VecDeque;
 
 /// Type of error that occurred
 #[derive(Debug, Clone, PartialEq)]
@@ -103,6 +106,163 @@ pub enum MessageRole {
     Assistant,
     System,
     Error,
+    /// Nested progress of a subagent, grouped under its Task call
+    Subagent,
+    /// Live output of a running tool call
+    ToolOutput,
+}
+
+/// One line in a subagent group (a tool call, optionally completed with its result)
+#[derive(Debug, Clone)]
+pub struct SubagentStep {
+    pub tool_use_id: Option<String>,
+    pub line: String,
+}
+
+/// Live view of a subagent launched by a Task call, rendered as an indented, collapsible group
+#[derive(Debug, Clone)]
+pub struct SubagentGroup {
+    pub parent_tool_use_id: String,
+    pub subagent_type: String,
+    pub description: String,
+    pub steps: Vec<SubagentStep>,
+    /// Final status ("done in 3 turns"); `None` while the subagent is still running
+    pub status: Option<String>,
+    pub collapsed: bool,
+}
+
+impl SubagentGroup {
+    pub fn new(parent_tool_use_id: String, event: &SubagentEvent) -> Self {
+        Self {
+            parent_tool_use_id,
+            subagent_type: event.subagent_type.clone(),
+            description: String::new(),
+            steps: Vec::new(),
+            status: None,
+            collapsed: false,
+        }
+    }
+
+    /// Fold one subagent event into the group
+    pub fn apply(&mut self, kind: &SubagentEventKind) {
+        match kind {
+            SubagentEventKind::Started { description } => {
+                self.description = description.clone();
+            }
+            SubagentEventKind::Text(text) => {
+                self.steps.push(SubagentStep {
+                    tool_use_id: None,
+                    line: format!("💬 {}", crate::subagent::event::truncate_line(text)),
+                });
+            }
+            SubagentEventKind::ToolUse {
+                tool_use_id,
+                tool_name,
+                input_summary,
+            } => {
+                self.steps.push(SubagentStep {
+                    tool_use_id: Some(tool_use_id.clone()),
+                    line: format!("{}: {} {}", self.subagent_type, tool_name, input_summary),
+                });
+            }
+            SubagentEventKind::ToolResult {
+                tool_use_id,
+                summary,
+                is_error,
+                ..
+            } => {
+                if let Some(step) = self
+                    .steps
+                    .iter_mut()
+                    .rev()
+                    .find(|s| s.tool_use_id.as_deref() == Some(tool_use_id.as_str()))
+                {
+                    let marker = if *is_error { "❌ " } else { "" };
+                    step.line.push_str(&format!(" → {}{}", marker, summary));
+                }
+            }
+            SubagentEventKind::Finished { turns, incomplete } => {
+                self.status = Some(if *incomplete {
+                    format!("incomplete after {} turns", turns)
+                } else {
+                    format!("done in {} turns", turns)
+                });
+                // Finished groups fold away; Ctrl+O expands them again.
+                self.collapsed = true;
+            }
+        }
+    }
+
+    /// Text shown in the message list: a header plus one indented line per step
+    pub fn display_text(&self) -> String {
+        let marker = if self.collapsed { "▸" } else { "▾" };
+        let status = self.status.as_deref().unwrap_or("running…");
+        let mut text = format!(
+            "{} {}: {} ({}, {} steps)",
+            marker,
+            self.subagent_type,
+            self.description,
+            status,
+            self.steps.len()
+        );
+
+        if !self.collapsed {
+            for step in &self.steps {
+                text.push_str(&format!("\n  │ {}", step.line));
+            }
+        }
+
+        text
+    }
+}
+
+/// Last lines of output of a tool call that is still running (e.g. a long `cargo test`)
+#[derive(Debug, Clone)]
+pub struct ToolOutputTail {
+    pub tool_use_id: String,
+    pub tool_name: String,
+    /// The most recent lines, oldest first
+    pub lines: VecDeque<String>,
+    pub total_lines: usize,
+}
+
+impl ToolOutputTail {
+    /// Lines kept (and shown)
+    pub const MAX_LINES: usize = 8;
+
+    pub fn new(tool_use_id: String, tool_name: String) -> Self {
+        Self {
+            tool_use_id,
+            tool_name,
+            lines: VecDeque::new(),
+            total_lines: 0,
+        }
+    }
+
+    pub fn push(&mut self, progress: &ToolProgress) {
+        let line = crate::subagent::event::truncate_line(&progress.line);
+        self.lines.push_back(if progress.stream == "stderr" {
+            format!("[stderr] {}", line)
+        } else {
+            line
+        });
+        if self.lines.len() > Self::MAX_LINES {
+            self.lines.pop_front();
+        }
+        self.total_lines += 1;
+    }
+
+    /// Text shown in the message list: a header plus the tail, indented
+    pub fn display_text(&self) -> String {
+        let mut text = format!("⏳ {} running… ({} lines of output)", self.tool_name, self.total_lines);
+        if self.total_lines > self.lines.len() {
+            text.push_str("\n  │ …");
+        }
+        for line in &self.lines {
+            text.push_str(&format!("\n  │ {}", line));
+        }
+        text
+    }
 }
 
 /// Represents a single chat message in the conversation
@@ -115,6 +275,10 @@ pub struct ChatMessage {
     #[allow(dead_code)]
     pub timestamp: DateTime<Local>,
     pub is_complete: bool,  // false indicates streaming in progress
+    /// Subagent progress group (only for `MessageRole::Subagent`)
+    pub subagent: Option<SubagentGroup>,
+    /// Live output tail (only for `MessageRole::ToolOutput`)
+    pub tool_output: Option<ToolOutputTail>,
 }
 
 impl ChatMessage {
@@ -126,6 +290,8 @@ impl ChatMessage {
             content,
             timestamp: Local::now(),
             is_complete: true,
+            subagent: None,
+            tool_output: None,
         }
     }
 
@@ -137,6 +303,8 @@ impl ChatMessage {
             content: String::new(),
             timestamp: Local::now(),
             is_complete: false,
+            subagent: None,
+            tool_output: None,
         }
     }
 
@@ -148,6 +316,8 @@ impl ChatMessage {
             content,
             timestamp: Local::now(),
             is_complete: true,
+            subagent: None,
+            tool_output: None,
         }
     }
 
@@ -159,6 +329,8 @@ impl ChatMessage {
             content,
             timestamp: Local::now(),
             is_complete: true,
+            subagent: None,
+            tool_output: None,
         }
     }
 
@@ -170,6 +342,34 @@ impl ChatMessage {
             content: details.format_for_display(),
             timestamp: details.timestamp,
             is_complete: true,
+            subagent: None,
+            tool_output: None,
+        }
+    }
+
+    /// Create a subagent progress group message
+    pub fn subagent_group(id: usize, group: SubagentGroup) -> Self {
+        Self {
+            id,
+            role: MessageRole::Subagent,
+            content: String::new(),
+            timestamp: Local::now(),
+            is_complete: true,
+            subagent: Some(group),
+            tool_output: None,
+        }
+    }
+
+    /// Create a live tool output message
+    pub fn tool_output(id: usize, tail: ToolOutputTail) -> Self {
+        Self {
+            id,
+            role: MessageRole::ToolOutput,
+            content: String::new(),
+            timestamp: Local::now(),
+            is_complete: true,
+            subagent: None,
+            tool_output: Some(tail),
         }
     }
 
@@ -217,4 +417,74 @@ mod tests {
         msg.complete();
         assert!(msg.is_complete);
     }
+
+    fn subagent_event(kind: SubagentEventKind) -> SubagentEvent {
+        SubagentEvent {
+            subagent_id: "agent-1".to_string(),
+            subagent_type: "Explore".to_string(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_subagent_group_tracks_tool_calls() {
+        let started = subagent_event(SubagentEventKind::Started {
+            description: "Find foo".to_string(),
+        });
+        let mut group = SubagentGroup::new("toolu_task".to_string(), &started);
+        group.apply(&started.kind);
+        group.apply(&SubagentEventKind::ToolUse {
+            tool_use_id: "toolu_1".to_string(),
+            tool_name: "grep".to_string(),
+            input_summary: "'foo'".to_string(),
+        });
+        group.apply(&SubagentEventKind::ToolResult {
+            tool_use_id: "toolu_1".to_string(),
+            tool_name: "grep".to_string(),
+            summary: "12 matches".to_string(),
+            is_error: false,
+        });
+
+        let text = group.display_text();
+        assert!(text.starts_with("▾ Explore: Find foo (running…, 1 steps)"));
+        assert!(text.contains("  │ Explore: grep 'foo' → 12 matches"));
+    }
+
+    #[test]
+    fn test_tool_output_tail_keeps_last_lines() {
+        let mut tail = ToolOutputTail::new("toolu_1".to_string(), "bash".to_string());
+        for i in 0..10 {
+            tail.push(&ToolProgress {
+                stream: "stdout",
+                line: format!("test {} ... ok", i),
+            });
+        }
+        tail.push(&ToolProgress {
+            stream: "stderr",
+            line: "warning: slow".to_string(),
+        });
+
+        let text = tail.display_text();
+        assert!(text.starts_with("⏳ bash running… (11 lines of output)\n  │ …"));
+        assert!(!text.contains("test 2 ..."));
+        assert!(text.contains("  │ test 3 ... ok"));
+        assert!(text.ends_with("  │ [stderr] warning: slow"));
+    }
+
+    #[test]
+    fn test_subagent_group_collapses_when_finished() {
+        let event = subagent_event(SubagentEventKind::Finished {
+            turns: 3,
+            incomplete: false,
+        });
+        let mut group = SubagentGroup::new("toolu_task".to_string(), &event);
+        group.apply(&SubagentEventKind::Text("Looking around".to_string()));
+        group.apply(&event.kind);
+
+        assert!(group.collapsed);
+        let text = group.display_text();
+        assert!(text.starts_with("▸ Explore:"));
+        assert!(text.contains("done in 3 turns"));
+        assert!(!text.contains("Looking around"));
+    }
 }
@@ -1,4 +1,4 @@
-use crate::tui::message::{ChatMessage, MessageRole};
+use crate::tui::message::{ChatMessage, MessageRole, SubagentGroup, ToolOutputTail};
 use ratatui::{
     layout::Rect,
     style::{Color, Modifier, Style},
@@ -96,6 +96,50 @@ impl MessageList {
             .find(|msg| !msg.is_complete)
     }
 
+    /// Get the subagent group rendered under the given Task call, if it exists
+    pub fn get_subagent_group_mut(&mut self, parent_tool_use_id: &str) -> Option<&mut SubagentGroup> {
+        self.messages
+            .iter_mut()
+            .rev()
+            .filter_map(|msg| msg.subagent.as_mut())
+            .find(|group| group.parent_tool_use_id == parent_tool_use_id)
+    }
+
+    /// Get the live output tail of a running tool call, if it exists
+    pub fn get_tool_output_mut(&mut self, tool_use_id: &str) -> Option<&mut ToolOutputTail> {
+        self.messages
+            .iter_mut()
+            .rev()
+            .filter_map(|msg| msg.tool_output.as_mut())
+            .find(|tail| tail.tool_use_id == tool_use_id)
+    }
+
+    /// Remove the live output tail of a tool call (once its result is shown)
+    pub fn remove_tool_output(&mut self, tool_use_id: &str) {
+        let position = self.messages.iter().position(|msg| {
+            msg.tool_output
+                .as_ref()
+                .is_some_and(|tail| tail.tool_use_id == tool_use_id)
+        });
+        if let Some(index) = position {
+            self.messages.remove(index);
+            self.render_cache.remove(index);
+        }
+    }
+
+    /// Collapse all subagent groups, or expand them all if every group is already collapsed
+    pub fn toggle_subagent_groups(&mut self) {
+        let collapse = self
+            .messages
+            .iter()
+            .filter_map(|msg| msg.subagent.as_ref())
+            .any(|group| !group.collapsed);
+
+        for group in self.messages.iter_mut().filter_map(|msg| msg.subagent.as_mut()) {
+            group.collapsed = collapse;
+        }
+    }
+
     /// Get the display content for a message (used for both height calculation and rendering)
     /// This ensures height calculation and rendering use the exact same content
     fn get_display_content(&self, message_idx: usize) -> String {
@@ -105,6 +149,13 @@ impl MessageList {
 
         let message = &self.messages[message_idx];
 
+        if let Some(group) = &message.subagent {
+            return group.display_text();
+        }
+        if let Some(tail) = &message.tool_output {
+            return tail.display_text();
+        }
+
         // Apply the same transformations for both height and rendering
         if !message.is_complete {
             // Streaming message
@@ -261,6 +312,8 @@ impl MessageList {
             MessageRole::Assistant => (Color::LightGreen, "•"),
             MessageRole::System => (Color::LightBlue, "-"),
             MessageRole::Error => (Color::LightRed, "⚠"),
+            MessageRole::Subagent => (Color::LightMagenta, "↳"),
+            MessageRole::ToolOutput => (Color::Gray, "↳"),
         };
 
         // Get the same display content used in height calculation
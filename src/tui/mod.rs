@@ -3,9 +3,11 @@ pub mod input;
 pub mod message;
 pub mod message_list;
 pub mod question;
+pub mod shell_panel;
 
 pub use app::App;
 pub use input::InputWidget;
-pub use message::{ChatMessage, ErrorDetails, ErrorType};
+pub use message::{ChatMessage, ErrorDetails, ErrorType, SubagentGroup, ToolOutputTail};
 pub use message_list::MessageList;
 pub use question::{QuestionWidget, QuestionWidgetAction};
+pub use shell_panel::{ShellPanel, ShellPanelAction};
@@ -0,0 +1,411 @@
+use crate::process::background_shell::ShellStatus;
+use crate::process::manager::ShellSummary;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    Frame,
+};
+
+/// Number of output lines fetched for the live tail view
+pub const TAIL_LINES: usize = 200;
+
+/// Point-in-time view of the background shells, produced off the UI thread
+#[derive(Debug, Clone, Default)]
+pub struct ShellSnapshot {
+    pub shells: Vec<ShellSummary>,
+    /// Last lines of output for the shell being tailed, if any
+    pub tail: Option<ShellTail>,
+}
+
+/// Recent output of a single shell
+#[derive(Debug, Clone)]
+pub struct ShellTail {
+    pub shell_id: String,
+    pub stdout: Vec<String>,
+    pub stderr: Vec<String>,
+}
+
+/// Background shell panel opened with `/bashes`
+pub struct ShellPanel {
+    shells: Vec<ShellSummary>,
+    selected: usize,
+    /// Shell whose output is being tailed (None = list view)
+    tailing: Option<String>,
+    tail: Option<ShellTail>,
+}
+
+impl ShellPanel {
+    /// Create an empty panel (filled by the next snapshot)
+    pub fn new() -> Self {
+        Self {
+            shells: Vec::new(),
+            selected: 0,
+            tailing: None,
+            tail: None,
+        }
+    }
+
+    /// Shell whose output should be included in the next snapshot
+    pub fn tailing(&self) -> Option<&str> {
+        self.tailing.as_deref()
+    }
+
+    /// Replace the displayed data with a fresh snapshot
+    pub fn update(&mut self, snapshot: ShellSnapshot) {
+        self.shells = snapshot.shells;
+        self.selected = self.selected.min(self.shells.len().saturating_sub(1));
+        self.tail = snapshot
+            .tail
+            .filter(|tail| self.tailing.as_deref() == Some(tail.shell_id.as_str()));
+    }
+
+    fn selected_shell(&self) -> Option<&ShellSummary> {
+        self.shells.get(self.selected)
+    }
+
+    /// Handle keyboard input
+    pub fn handle_key(&mut self, key: KeyEvent) -> ShellPanelAction {
+        if self.tailing.is_some() {
+            // Tail view
+            return match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    self.tailing = None;
+                    self.tail = None;
+                    ShellPanelAction::Refresh
+                }
+                KeyCode::Char('k') => match self.tailing.clone() {
+                    Some(id) => ShellPanelAction::Kill(id),
+                    None => ShellPanelAction::Continue,
+                },
+                _ => ShellPanelAction::Continue,
+            };
+        }
+
+        // List view
+        match key.code {
+            KeyCode::Up => {
+                self.selected = self.selected.saturating_sub(1);
+                ShellPanelAction::Continue
+            }
+            KeyCode::Down => {
+                if self.selected + 1 < self.shells.len() {
+                    self.selected += 1;
+                }
+                ShellPanelAction::Continue
+            }
+            KeyCode::Enter | KeyCode::Char('t') => match self.selected_shell() {
+                Some(shell) => {
+                    self.tailing = Some(shell.id.clone());
+                    ShellPanelAction::Refresh
+                }
+                None => ShellPanelAction::Continue,
+            },
+            KeyCode::Char('k') => match self.selected_shell() {
+                Some(shell) if shell.status == ShellStatus::Running => {
+                    ShellPanelAction::Kill(shell.id.clone())
+                }
+                _ => ShellPanelAction::Continue,
+            },
+            KeyCode::Esc | KeyCode::Char('q') => ShellPanelAction::Close,
+            _ => ShellPanelAction::Continue,
+        }
+    }
+
+    /// Render the panel as a centered dialog
+    pub fn render(&self, frame: &mut Frame) {
+        let area = frame.area();
+
+        let dialog_width = 100.min(area.width.saturating_sub(4));
+        let dialog_height = 30.min(area.height.saturating_sub(4));
+
+        let dialog_area = Rect {
+            x: (area.width.saturating_sub(dialog_width)) / 2,
+            y: (area.height.saturating_sub(dialog_height)) / 2,
+            width: dialog_width,
+            height: dialog_height,
+        };
+
+        frame.render_widget(Clear, dialog_area);
+
+        let title = match &self.tailing {
+            Some(id) => format!(" Output: {} ", id),
+            None => format!(" Background shells ({}) ", self.shells.len()),
+        };
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(Span::styled(
+                title,
+                Style::default()
+                    .fg(Color::LightBlue)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .border_style(Style::default().fg(Color::Cyan));
+
+        frame.render_widget(block.clone(), dialog_area);
+
+        let inner = block.inner(dialog_area);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(3),    // Shell list or output
+                Constraint::Length(1), // Help text
+            ])
+            .split(inner);
+
+        if self.tailing.is_some() {
+            self.render_tail(frame, chunks[0]);
+        } else {
+            self.render_list(frame, chunks[0]);
+        }
+
+        self.render_help(frame, chunks[1]);
+    }
+
+    /// Render the shell list
+    fn render_list(&self, frame: &mut Frame, area: Rect) {
+        if self.shells.is_empty() {
+            let paragraph = Paragraph::new(Line::from(Span::styled(
+                "No background shells.",
+                Style::default().fg(Color::DarkGray),
+            )));
+            frame.render_widget(paragraph, area);
+            return;
+        }
+
+        let items: Vec<ListItem> = self
+            .shells
+            .iter()
+            .enumerate()
+            .map(|(idx, shell)| {
+                let (status, status_color) = status_label(shell);
+                let style = if idx == self.selected {
+                    Style::default()
+                        .fg(Color::Black)
+                        .bg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+
+                ListItem::new(vec![
+                    Line::from(vec![
+                        Span::styled(format!("{} ", shell.id), style),
+                        Span::styled(status, Style::default().fg(status_color)),
+                    ]),
+                    Line::from(Span::styled(
+                        format!(
+                            "    $ {} · up {} · {} out / {} err lines",
+                            shell.command,
+                            format_uptime(shell.uptime_secs),
+                            shell.stdout_lines,
+                            shell.stderr_lines
+                        ),
+                        Style::default().fg(Color::DarkGray),
+                    )),
+                ])
+            })
+            .collect();
+
+        frame.render_widget(List::new(items), area);
+    }
+
+    /// Render the live output tail of the selected shell
+    fn render_tail(&self, frame: &mut Frame, area: Rect) {
+        let mut lines: Vec<Line> = Vec::new();
+
+        if let Some(shell) = self
+            .shells
+            .iter()
+            .find(|s| Some(s.id.as_str()) == self.tailing.as_deref())
+        {
+            let (status, status_color) = status_label(shell);
+            lines.push(Line::from(vec![
+                Span::styled(format!("$ {} ", shell.command), Style::default().fg(Color::Yellow)),
+                Span::styled(status, Style::default().fg(status_color)),
+            ]));
+        }
+
+        match &self.tail {
+            Some(tail) => {
+                lines.extend(tail.stdout.iter().map(|l| Line::from(l.as_str())));
+                lines.extend(tail.stderr.iter().map(|l| {
+                    Line::from(Span::styled(
+                        format!("[stderr] {}", l),
+                        Style::default().fg(Color::LightRed),
+                    ))
+                }));
+            }
+            None => lines.push(Line::from(Span::styled(
+                "Loading output…",
+                Style::default().fg(Color::DarkGray),
+            ))),
+        }
+
+        // Keep the newest lines in view
+        let visible = area.height as usize;
+        let skip = lines.len().saturating_sub(visible);
+        let lines: Vec<Line> = lines.into_iter().skip(skip).collect();
+
+        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false });
+        frame.render_widget(paragraph, area);
+    }
+
+    /// Render help text
+    fn render_help(&self, frame: &mut Frame, area: Rect) {
+        let help_text = if self.tailing.is_some() {
+            "k=kill │ Esc=back"
+        } else {
+            "↑↓=navigate │ Enter=tail output │ k=kill │ Esc=close"
+        };
+
+        let paragraph = Paragraph::new(Line::from(Span::styled(
+            help_text,
+            Style::default().fg(Color::DarkGray),
+        )));
+
+        frame.render_widget(paragraph, area);
+    }
+}
+
+impl Default for ShellPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Actions returned by the shell panel
+#[derive(Debug, PartialEq)]
+pub enum ShellPanelAction {
+    /// Keep showing the panel
+    Continue,
+    /// The view changed and needs a fresh snapshot
+    Refresh,
+    /// Kill the shell with this ID
+    Kill(String),
+    /// Close the panel
+    Close,
+}
+
+/// Status text and color for a shell row
+fn status_label(shell: &ShellSummary) -> (String, Color) {
+    match &shell.status {
+        ShellStatus::Running => ("running".to_string(), Color::LightGreen),
+        ShellStatus::Completed { .. } => match shell.exit_code {
+            Some(0) => ("exit 0".to_string(), Color::DarkGray),
+            Some(137) => ("killed (exit 137)".to_string(), Color::LightRed),
+            Some(code) => (format!("exit {}", code), Color::LightRed),
+            None => ("terminated by signal".to_string(), Color::LightRed),
+        },
+        ShellStatus::Failed { error } => (format!("failed: {}", error), Color::LightRed),
+    }
+}
+
+/// Format an uptime as `45s`, `3m05s` or `2h01m`
+fn format_uptime(secs: u64) -> String {
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+    use std::time::SystemTime;
+
+    fn shell(id: &str, status: ShellStatus) -> ShellSummary {
+        let exit_code = match status {
+            ShellStatus::Completed { exit_code } => exit_code,
+            _ => None,
+        };
+        ShellSummary {
+            id: id.to_string(),
+            command: "sleep 10".to_string(),
+            status,
+            exit_code,
+            stdout_lines: 0,
+            stderr_lines: 0,
+            uptime_secs: 5,
+            started_at: SystemTime::now(),
+            owner: None,
+        }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn panel_with_shells() -> ShellPanel {
+        let mut panel = ShellPanel::new();
+        panel.update(ShellSnapshot {
+            shells: vec![
+                shell("shell_a", ShellStatus::Running),
+                shell("shell_b", ShellStatus::Completed { exit_code: Some(0) }),
+            ],
+            tail: None,
+        });
+        panel
+    }
+
+    #[test]
+    fn test_kill_only_running_shells() {
+        let mut panel = panel_with_shells();
+        assert_eq!(
+            panel.handle_key(key(KeyCode::Char('k'))),
+            ShellPanelAction::Kill("shell_a".to_string())
+        );
+
+        panel.handle_key(key(KeyCode::Down));
+        assert_eq!(panel.handle_key(key(KeyCode::Char('k'))), ShellPanelAction::Continue);
+    }
+
+    #[test]
+    fn test_tail_view_navigation() {
+        let mut panel = panel_with_shells();
+        assert_eq!(panel.handle_key(key(KeyCode::Enter)), ShellPanelAction::Refresh);
+        assert_eq!(panel.tailing(), Some("shell_a"));
+
+        // Tails for other shells are ignored
+        panel.update(ShellSnapshot {
+            shells: panel.shells.clone(),
+            tail: Some(ShellTail {
+                shell_id: "shell_b".to_string(),
+                stdout: vec!["x".to_string()],
+                stderr: Vec::new(),
+            }),
+        });
+        assert!(panel.tail.is_none());
+
+        // Esc returns to the list, a second Esc closes the panel
+        assert_eq!(panel.handle_key(key(KeyCode::Esc)), ShellPanelAction::Refresh);
+        assert_eq!(panel.tailing(), None);
+        assert_eq!(panel.handle_key(key(KeyCode::Esc)), ShellPanelAction::Close);
+    }
+
+    #[test]
+    fn test_selection_clamped_when_shells_disappear() {
+        let mut panel = panel_with_shells();
+        panel.handle_key(key(KeyCode::Down));
+        panel.update(ShellSnapshot {
+            shells: vec![shell("shell_a", ShellStatus::Running)],
+            tail: None,
+        });
+        assert_eq!(panel.selected, 0);
+    }
+
+    #[test]
+    fn test_format_uptime() {
+        assert_eq!(format_uptime(45), "45s");
+        assert_eq!(format_uptime(185), "3m05s");
+        assert_eq!(format_uptime(7260), "2h01m");
+    }
+}
@@ -0,0 +1,325 @@
+//! Integration tests for the top-level agent loop (driven by a scripted mock LLM server)
+
+mod common;
+
+use common::{MockBlock, MockLlmServer};
+use ok::agent::{AgentEvent, AgentRunner};
+use ok::llm::anthropic::AnthropicClient;
+use ok::subagent::SubagentEventKind;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_subagent_progress_is_forwarded_before_task_result() {
+    let server = MockLlmServer::start(vec![
+        // Parent: launch an Explore subagent
+        vec![MockBlock::tool_use(
+            "toolu_task",
+            "task",
+            json!({
+                "description": "Find manifests",
+                "prompt": "List the Cargo manifests",
+                "subagent_type": "Explore"
+            }),
+        )],
+        // Subagent: one tool call, then the answer
+        vec![MockBlock::tool_use("toolu_glob", "glob", json!({ "pattern": "Cargo.toml" }))],
+        vec![MockBlock::text("Cargo.toml is the only manifest")],
+        // Parent: final answer
+        vec![MockBlock::text("Done")],
+    ])
+    .await;
+
+    let agent = AgentRunner::new(AnthropicClient::new(server.station()));
+    let mut rx = agent.start_turn("Explore the repo".to_string());
+
+    let mut events = Vec::new();
+    while let Some(event) = rx.recv().await {
+        let done = matches!(event, AgentEvent::TurnComplete);
+        events.push(event);
+        if done {
+            break;
+        }
+    }
+
+    let progress: Vec<(usize, &SubagentEventKind)> = events
+        .iter()
+        .enumerate()
+        .filter_map(|(i, e)| match e {
+            AgentEvent::SubagentProgress {
+                parent_tool_use_id,
+                event,
+            } => {
+                assert_eq!(parent_tool_use_id, "toolu_task");
+                assert_eq!(event.subagent_type, "Explore");
+                Some((i, &event.kind))
+            }
+            _ => None,
+        })
+        .collect();
+
+    assert!(matches!(
+        progress.first(),
+        Some((_, SubagentEventKind::Started { description })) if description == "Find manifests"
+    ));
+    assert!(progress.iter().any(|(_, k)| matches!(
+        k,
+        SubagentEventKind::ToolUse { tool_name, .. } if tool_name == "glob"
+    )));
+    assert!(matches!(
+        progress.last(),
+        Some((_, SubagentEventKind::Finished { turns: 2, incomplete: false }))
+    ));
+
+    // Every progress event arrives before the Task tool's own result.
+    let task_result_idx = events
+        .iter()
+        .position(|e| matches!(e, AgentEvent::ToolResult { tool_name, .. } if tool_name == "task"))
+        .expect("task result event");
+    assert!(progress.iter().all(|(i, _)| *i < task_result_idx));
+}
+
+#[tokio::test]
+async fn test_bash_output_is_streamed_before_tool_result() {
+    let server = MockLlmServer::start(vec![
+        vec![MockBlock::tool_use(
+            "toolu_bash",
+            "bash",
+            json!({ "command": "echo compiling; echo 'warning: unused' >&2; echo finished" }),
+        )],
+        vec![MockBlock::text("Built")],
+    ])
+    .await;
+
+    let agent = AgentRunner::new(AnthropicClient::new(server.station()));
+    let mut rx = agent.start_turn("Build it".to_string());
+
+    let mut events = Vec::new();
+    while let Some(event) = rx.recv().await {
+        let done = matches!(event, AgentEvent::TurnComplete);
+        events.push(event);
+        if done {
+            break;
+        }
+    }
+
+    let progress: Vec<(usize, &str, &str)> = events
+        .iter()
+        .enumerate()
+        .filter_map(|(i, e)| match e {
+            AgentEvent::ToolProgress {
+                tool_use_id,
+                tool_name,
+                progress,
+            } => {
+                assert_eq!(tool_use_id, "toolu_bash");
+                assert_eq!(tool_name, "bash");
+                Some((i, progress.stream, progress.line.as_str()))
+            }
+            _ => None,
+        })
+        .collect();
+    let stdout: Vec<&str> = progress.iter().filter(|p| p.1 == "stdout").map(|p| p.2).collect();
+    assert_eq!(stdout, ["compiling", "finished"]);
+    assert!(progress.iter().any(|p| p.1 == "stderr" && p.2 == "warning: unused"));
+
+    let result_idx = events
+        .iter()
+        .position(|e| matches!(e, AgentEvent::ToolResult { tool_name, .. } if tool_name == "bash"))
+        .expect("bash result event");
+    assert!(progress.iter().all(|(i, _, _)| *i < result_idx));
+
+    // The model still gets the complete output in one result
+    let requests = server.requests();
+    let result = requests[1]["messages"].as_array().unwrap().last().unwrap()["content"][0]["content"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert!(result.contains("compiling\nfinished"), "{}", result);
+    assert!(result.contains("--- STDERR ---\nwarning: unused"));
+}
+
+#[tokio::test]
+async fn test_risky_command_waits_for_the_users_answer() {
+    // sudo needs approval; `|| echo` keeps the command harmless where sudo can't run
+    let command = "sudo -n echo approved-run || echo approved-run";
+    let server = MockLlmServer::start(vec![
+        vec![MockBlock::tool_use("toolu_yes", "bash", json!({ "command": command }))],
+        vec![MockBlock::tool_use("toolu_no", "bash", json!({ "command": command }))],
+        vec![MockBlock::text("Done")],
+    ])
+    .await;
+
+    let agent = AgentRunner::new(AnthropicClient::new(server.station()));
+    let mut rx = agent.start_turn("Run it".to_string());
+
+    let mut asked = Vec::new();
+    while let Some(event) = rx.recv().await {
+        match event {
+            AgentEvent::CommandApprovalRequest {
+                tool_use_id,
+                command: requested,
+                reasons,
+            } => {
+                assert_eq!(requested, command);
+                assert!(reasons.contains("sudo"), "{}", reasons);
+                agent.respond_approval(&tool_use_id, tool_use_id == "toolu_yes");
+                asked.push(tool_use_id);
+            }
+            AgentEvent::TurnComplete => break,
+            _ => {}
+        }
+    }
+    assert_eq!(asked, ["toolu_yes", "toolu_no"]);
+
+    let requests = server.requests();
+    let result = |request: usize| {
+        requests[request]["messages"].as_array().unwrap().last().unwrap()["content"][0]["content"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    };
+    assert!(result(1).contains("approved-run"), "{}", result(1));
+    let refused = result(2);
+    assert!(refused.contains("The user did not approve this command"), "{}", refused);
+    assert!(!refused.contains("approved-run\n"), "{}", refused);
+}
+
+async fn run_to_completion(mut rx: tokio::sync::mpsc::UnboundedReceiver<AgentEvent>) {
+    while let Some(event) = rx.recv().await {
+        if matches!(event, AgentEvent::TurnComplete) {
+            break;
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_background_shell_completion_starts_notification_turn() {
+    let server = MockLlmServer::start(vec![
+        vec![MockBlock::tool_use(
+            "toolu_bash",
+            "bash",
+            json!({ "command": "sleep 0.3; echo built", "run_in_background": true }),
+        )],
+        vec![MockBlock::text("Build started")],
+        vec![MockBlock::text("The build finished")],
+    ])
+    .await;
+
+    let agent = AgentRunner::new(AnthropicClient::new(server.station()));
+    run_to_completion(agent.start_turn("Build it".to_string())).await;
+
+    // Wait for the background shell to report its exit
+    tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        while !agent.has_shell_notifications() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("no shell notification");
+
+    let rx = agent.start_notification_turn().expect("notification turn");
+    run_to_completion(rx).await;
+    assert!(agent.start_notification_turn().is_none());
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    let last_message = requests[2]["messages"].as_array().unwrap().last().unwrap().clone();
+    assert_eq!(last_message["role"], "user");
+    let reminder = last_message["content"].as_str().unwrap();
+    assert!(reminder.starts_with("<system-reminder>"));
+    assert!(reminder.contains("(`sleep 0.3; echo built`) exited with code 0."));
+}
+
+#[tokio::test]
+async fn test_repo_map_starts_session_and_refreshes_after_edits() {
+    let server = MockLlmServer::start(vec![
+        vec![MockBlock::tool_use(
+            "toolu_write",
+            "write",
+            json!({ "file_path": "target/repo_map_refresh_test.txt", "content": "scratch\n" }),
+        )],
+        vec![MockBlock::text("Written")],
+        vec![MockBlock::text("Still here")],
+    ])
+    .await;
+
+    let mut config = ok::config::Config::default();
+    config.repo_map.enabled = true;
+    config.repo_map.token_budget = 400;
+    config.repo_map.refresh_after_files = 1;
+    let agent = AgentRunner::with_config(AnthropicClient::new(server.station()), &config);
+    run_to_completion(agent.start_turn("Write a scratch file".to_string())).await;
+    run_to_completion(agent.start_turn("Anything else?".to_string())).await;
+    let _ = std::fs::remove_file("target/repo_map_refresh_test.txt");
+
+    let texts = |message: &serde_json::Value| -> Vec<String> {
+        message["content"]
+            .as_array()
+            .map(|blocks| {
+                blocks
+                    .iter()
+                    .filter_map(|b| b["text"].as_str().or(b["content"].as_str()))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_else(|| vec![message["content"].as_str().unwrap_or("").to_string()])
+    };
+
+    // The first message carries the map, once
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    let first = texts(&requests[0]["messages"][0]);
+    assert_eq!(first[0], "Write a scratch file");
+    assert!(first[1].starts_with("<system-reminder>\nRepository map"));
+    assert!(first[1].contains("src/"));
+
+    // Writing a file reaches refresh_after_files, so the tool result is followed by a fresh map
+    let messages = requests[1]["messages"].as_array().unwrap();
+    let after_write = texts(messages.last().unwrap());
+    assert!(after_write
+        .iter()
+        .any(|t| t.starts_with("<system-reminder>\nRepository map, refreshed")));
+
+    // Later turns don't repeat it
+    let messages = requests[2]["messages"].as_array().unwrap();
+    assert_eq!(texts(messages.last().unwrap()), vec!["Anything else?".to_string()]);
+}
+
+#[tokio::test]
+async fn test_long_tool_output_is_cut_and_saved_for_read() {
+    let server = MockLlmServer::start(vec![
+        vec![MockBlock::tool_use(
+            "toolu_build",
+            "bash",
+            json!({ "command": "for i in $(seq 1 2000); do echo \"warning: unused variable $i\"; done" }),
+        )],
+        vec![MockBlock::text("Lots of warnings")],
+    ])
+    .await;
+
+    let mut config = ok::config::Config::default();
+    config.tool_output.max_bytes = 2_000;
+    let agent = AgentRunner::with_config(AnthropicClient::new(server.station()), &config);
+    run_to_completion(agent.start_turn("Build it".to_string())).await;
+
+    let requests = server.requests();
+    let result = requests[1]["messages"].as_array().unwrap().last().unwrap()["content"][0]["content"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert!(result.len() < 3_000, "{}", result);
+    assert!(result.contains("warning: unused variable 1\n"));
+    assert!(result.trim_end().ends_with("warning: unused variable 2000"));
+    assert!(result.contains("lines ("), "{}", result);
+    assert!(result.contains("omitted; the full output"), "{}", result);
+
+    // The marker names a file with everything the command printed
+    let path = result
+        .split("is saved in ")
+        .nth(1)
+        .and_then(|rest| rest.split(';').next())
+        .expect("saved output path");
+    let saved = std::fs::read_to_string(path).unwrap();
+    assert_eq!(saved.lines().count(), 2000);
+    assert!(saved.contains("warning: unused variable 1000\n"));
+}
@@ -279,6 +279,167 @@ impl TestFixture {
     }
 }
 
+/// One scripted content block returned by [`MockLlmServer`].
+#[derive(Debug, Clone)]
+pub enum MockBlock {
+    Text(String),
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+}
+
+impl MockBlock {
+    pub fn text(text: &str) -> Self {
+        MockBlock::Text(text.to_string())
+    }
+
+    pub fn tool_use(id: &str, name: &str, input: serde_json::Value) -> Self {
+        MockBlock::ToolUse {
+            id: id.to_string(),
+            name: name.to_string(),
+            input,
+        }
+    }
+}
+
+/// Minimal Anthropic-compatible streaming server for offline agent tests.
+///
+/// Each `/v1/messages` request is answered with the next scripted reply (a list of content
+/// blocks) as an SSE stream; request bodies are recorded for assertions.
+pub struct MockLlmServer {
+    pub base_url: String,
+    requests: std::sync::Arc<Mutex<Vec<serde_json::Value>>>,
+}
+
+impl MockLlmServer {
+    pub async fn start(replies: Vec<Vec<MockBlock>>) -> Self {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind mock LLM server");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let replies = std::sync::Arc::new(Mutex::new(std::collections::VecDeque::from(replies)));
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                let recorded = recorded.clone();
+                let replies = replies.clone();
+
+                tokio::spawn(async move {
+                    // Read headers, then exactly Content-Length bytes of body.
+                    let mut buf = Vec::new();
+                    let mut chunk = [0u8; 4096];
+                    let header_end = loop {
+                        let n = socket.read(&mut chunk).await.unwrap_or(0);
+                        if n == 0 {
+                            return;
+                        }
+                        buf.extend_from_slice(&chunk[..n]);
+                        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                            break pos + 4;
+                        }
+                    };
+                    let headers = String::from_utf8_lossy(&buf[..header_end]).to_lowercase();
+                    let content_length = headers
+                        .lines()
+                        .find_map(|l| l.strip_prefix("content-length:"))
+                        .and_then(|v| v.trim().parse::<usize>().ok())
+                        .unwrap_or(0);
+                    while buf.len() < header_end + content_length {
+                        let n = socket.read(&mut chunk).await.unwrap_or(0);
+                        if n == 0 {
+                            break;
+                        }
+                        buf.extend_from_slice(&chunk[..n]);
+                    }
+                    let body: serde_json::Value =
+                        serde_json::from_slice(&buf[header_end..]).unwrap_or_default();
+                    recorded.lock().unwrap().push(body);
+
+                    let blocks = replies
+                        .lock()
+                        .unwrap()
+                        .pop_front()
+                        .unwrap_or_else(|| vec![MockBlock::text("(no scripted reply)")]);
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\nconnection: close\r\n\r\n{}",
+                        Self::sse_body(&blocks)
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                    let _ = socket.shutdown().await;
+                });
+            }
+        });
+
+        Self { base_url, requests }
+    }
+
+    /// Request bodies received so far, in order
+    pub fn requests(&self) -> Vec<serde_json::Value> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Station pointing at this server
+    pub fn station(&self) -> ok::config::station::Station {
+        ok::config::station::Station {
+            id: "mock".to_string(),
+            name: "Mock".to_string(),
+            provider: ok::config::station::Provider::Anthropic,
+            api_key: "test-key".to_string(),
+            api_base: Some(self.base_url.clone()),
+            model: "mock-model".to_string(),
+            max_tokens: Some(1024),
+            temperature: None,
+        }
+    }
+
+    fn sse_body(blocks: &[MockBlock]) -> String {
+        fn event(name: &str, data: serde_json::Value) -> String {
+            format!("event: {}\ndata: {}\n\n", name, data)
+        }
+
+        let mut body = String::new();
+        for (index, block) in blocks.iter().enumerate() {
+            match block {
+                MockBlock::Text(text) => {
+                    body.push_str(&event(
+                        "content_block_start",
+                        serde_json::json!({"index": index, "content_block": {"type": "text", "text": ""}}),
+                    ));
+                    body.push_str(&event(
+                        "content_block_delta",
+                        serde_json::json!({"index": index, "delta": {"type": "text_delta", "text": text}}),
+                    ));
+                }
+                MockBlock::ToolUse { id, name, input } => {
+                    body.push_str(&event(
+                        "content_block_start",
+                        serde_json::json!({"index": index, "content_block": {"type": "tool_use", "id": id, "name": name, "input": {}}}),
+                    ));
+                    body.push_str(&event(
+                        "content_block_delta",
+                        serde_json::json!({"index": index, "delta": {"type": "input_json_delta", "partial_json": input.to_string()}}),
+                    ));
+                }
+            }
+            body.push_str(&event(
+                "content_block_stop",
+                serde_json::json!({"index": index}),
+            ));
+        }
+        body.push_str(&event("message_stop", serde_json::json!({})));
+        body
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
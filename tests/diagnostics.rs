@@ -0,0 +1,244 @@
+//! Integration tests for post-edit diagnostics
+
+mod common;
+
+use common::TestFixture;
+use ok::config::station::{BashSettings, DiagnosticsSettings};
+use ok::diagnostics::{Diagnostics, CONFIG_PATH};
+use ok::process::{EnvFilter, ResourceLimits};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+fn load(fixture: &TestFixture, config: &str) -> Diagnostics {
+    fixture.create_dir(".ok");
+    fixture.create_file(CONFIG_PATH, config);
+    Diagnostics::load(&fixture.path())
+        .expect("valid config")
+        .expect("config present")
+}
+
+#[tokio::test]
+async fn test_reports_diagnostics_for_modified_files_only() {
+    let fixture = TestFixture::new();
+    let main = fixture.create_file("main.py", "print(x)\n");
+    fixture.create_file("other.py", "print(y)\n");
+
+    let diagnostics = load(
+        &fixture,
+        r#"
+debounce_ms = 0
+
+[[checkers]]
+glob = "*.py"
+command = "echo 'main.py:1:7: error: undefined name x'; echo 'other.py:1:7: warning: undefined name y'; exit 1"
+"#,
+    );
+
+    let report = diagnostics.check(&[main]).await.expect("checker triggered");
+    assert!(report.starts_with("<system-reminder>"));
+    assert!(report.contains("1 error(s), 0 other diagnostic(s) in main.py"));
+    assert!(report.contains("- main.py:1:7: error: undefined name x"));
+    assert!(!report.contains("- other.py"));
+}
+
+#[tokio::test]
+async fn test_clean_run_and_unmatched_files() {
+    let fixture = TestFixture::new();
+    let lib = fixture.create_file("lib.rs", "fn main() {}\n");
+    let notes = fixture.create_file("notes.txt", "hello\n");
+
+    let diagnostics = load(
+        &fixture,
+        r#"
+debounce_ms = 0
+
+[[checkers]]
+glob = "*.rs"
+command = "true"
+format = "rustc-json"
+"#,
+    );
+
+    assert!(diagnostics.check(&[notes]).await.is_none());
+
+    let report = diagnostics.check(&[lib]).await.unwrap();
+    assert!(report.contains("`true`: no problems reported in lib.rs"));
+}
+
+#[tokio::test]
+async fn test_checkers_get_the_filtered_environment() {
+    let fixture = TestFixture::new();
+    let main = fixture.create_file("main.py", "print(x)\n");
+
+    let settings = BashSettings {
+        env_deny: vec!["HOME".to_string()],
+        ..BashSettings::default()
+    };
+    let diagnostics = load(
+        &fixture,
+        r#"
+debounce_ms = 0
+
+[[checkers]]
+glob = "*.py"
+command = "echo \"main.py:1:1: error: home=${HOME:-unset}\""
+"#,
+    )
+    .with_env_filter(Arc::new(EnvFilter::new(&settings)));
+
+    let report = diagnostics.check(&[main]).await.unwrap();
+    assert!(report.contains("error: home=unset"), "{}", report);
+}
+
+#[tokio::test]
+async fn test_modifications_close_together_are_checked_once() {
+    let fixture = TestFixture::new();
+    let a = fixture.create_file("a.py", "x\n");
+    let b = fixture.create_file("b.py", "y\n");
+
+    let diagnostics = Arc::new(load(
+        &fixture,
+        r#"
+debounce_ms = 300
+
+[[checkers]]
+glob = "*.py"
+command = "echo run >> runs.log; echo 'a.py:1:1: error: bad a'; echo 'b.py:1:1: error: bad b'"
+"#,
+    ));
+
+    // The agent and a subagent report their edits within the debounce window
+    let first = tokio::spawn({
+        let diagnostics = diagnostics.clone();
+        async move { diagnostics.check(&[a]).await.unwrap() }
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let second = diagnostics.check(&[b]).await.unwrap();
+    let first = first.await.unwrap();
+
+    let runs = std::fs::read_to_string(fixture.path().join("runs.log")).unwrap();
+    assert_eq!(runs.lines().count(), 1, "checker ran more than once");
+    assert!(first.contains("- a.py:1:1: error: bad a") && !first.contains("- b.py"), "{}", first);
+    assert!(second.contains("- b.py:1:1: error: bad b") && !second.contains("- a.py"), "{}", second);
+}
+
+#[tokio::test]
+async fn test_cancel_stops_running_checkers() {
+    let fixture = TestFixture::new();
+    let main = fixture.create_file("main.py", "x\n");
+
+    let diagnostics = Arc::new(load(
+        &fixture,
+        r#"
+debounce_ms = 0
+
+[[checkers]]
+glob = "*.py"
+command = "sleep 30"
+"#,
+    ));
+
+    let start = Instant::now();
+    let check = tokio::spawn({
+        let diagnostics = diagnostics.clone();
+        async move { diagnostics.check(&[main]).await.unwrap() }
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    diagnostics.cancel();
+
+    let report = check.await.unwrap();
+    assert!(report.contains("cancelled by the user"), "{}", report);
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
+
+#[tokio::test]
+async fn test_timeout_stops_what_the_checker_started() {
+    let fixture = TestFixture::new();
+    let main = fixture.create_file("main.py", "x\n");
+
+    let diagnostics = load(
+        &fixture,
+        r#"
+debounce_ms = 0
+timeout_secs = 1
+
+[[checkers]]
+glob = "*.py"
+command = "(sleep 2; touch late) & sleep 30"
+"#,
+    );
+
+    let report = diagnostics.check(&[main]).await.unwrap();
+    assert!(report.contains("could not be run: timed out after 1s"), "{}", report);
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert!(!fixture.path().join("late").exists(), "background process survived the timeout");
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_checkers_get_the_resource_limits() {
+    let fixture = TestFixture::new();
+    let main = fixture.create_file("main.py", "x\n");
+    let limited = |command: &str, limits: ResourceLimits| {
+        load(
+            &fixture,
+            &format!(
+                "debounce_ms = 0\ntimeout_secs = 30\n\n[[checkers]]\nglob = \"*.py\"\ncommand = '{}'\n",
+                command
+            ),
+        )
+        .with_resource_limits(limits)
+    };
+
+    // CPU time: a runaway checker is stopped after a second of CPU, not at the timeout
+    let start = Instant::now();
+    let diagnostics = limited(
+        r#"bash -c "while :; do :; done"; echo "main.py:1:1: error: exit=$?""#,
+        ResourceLimits { cpu_seconds: Some(1), ..Default::default() },
+    );
+    let report = diagnostics.check(std::slice::from_ref(&main)).await.unwrap();
+    // SIGXCPU at the soft limit or SIGKILL at the hard one
+    assert!(report.contains("error: exit=152") || report.contains("error: exit=137"), "{}", report);
+    assert!(start.elapsed() < Duration::from_secs(20));
+
+    // Memory: a 200 MB allocation fails under a 64 MiB limit
+    let diagnostics = limited(
+        r#"x=$(head -c 200000000 /dev/zero | tr "\0" a); echo "main.py:1:1: error: len=${#x}""#,
+        ResourceLimits { memory_bytes: Some(64 * 1024 * 1024), ..Default::default() },
+    );
+    let report = diagnostics.check(std::slice::from_ref(&main)).await.unwrap();
+    assert!(!report.contains("len=200000000"), "{}", report);
+
+    // Output: an endless printer is stopped once it passes the limit
+    let start = Instant::now();
+    let diagnostics = limited(
+        "yes main.py:1:1: error: again",
+        ResourceLimits { output_bytes: Some(10_000), ..Default::default() },
+    );
+    let report = diagnostics.check(&[main]).await.unwrap();
+    assert!(report.contains("printed more than 10000 bytes and was stopped"), "{}", report);
+    assert!(start.elapsed() < Duration::from_secs(20));
+}
+
+#[test]
+fn test_projects_must_be_trusted() {
+    let fixture = TestFixture::new();
+    let mut settings = DiagnosticsSettings::default();
+    assert!(!settings.trusts(&fixture.path()));
+
+    settings.trust(&fixture.path());
+    settings.trust(&fixture.path());
+    assert_eq!(settings.trusted_projects.len(), 1);
+    assert!(settings.trusts(&fixture.path()));
+    assert!(!settings.trusts(&fixture.path().join("sub")));
+}
+
+#[test]
+fn test_missing_and_invalid_config() {
+    let fixture = TestFixture::new();
+    assert!(Diagnostics::load(&fixture.path()).unwrap().is_none());
+
+    fixture.create_dir(".ok");
+    fixture.create_file(CONFIG_PATH, "[[checkers]]\nglob = \"[\"\ncommand = \"true\"\n");
+    assert!(Diagnostics::load(&fixture.path()).is_err());
+}
@@ -1,4 +1,4 @@
-use ok::llm::types::{ContentBlock, Message, ToolUse};
+use ok::llm::types::{ContentBlock, Message, ToolChoice, ToolUse};
 use serde_json::json;
 
 #[test]
@@ -76,3 +76,31 @@ fn serializes_assistant_blocks_with_tool_use() {
         })
     );
 }
+
+#[test]
+fn serializes_tool_choice_as_typed_object() {
+    assert_eq!(serde_json::to_value(ToolChoice::None).unwrap(), json!({ "type": "none" }));
+    assert_eq!(serde_json::to_value(ToolChoice::Auto).unwrap(), json!({ "type": "auto" }));
+    assert_eq!(serde_json::to_value(ToolChoice::Any).unwrap(), json!({ "type": "any" }));
+}
+
+#[test]
+fn append_text_turns_content_into_blocks() {
+    let mut msg = Message::user("hi");
+    msg.append_text("<system-reminder>x</system-reminder>");
+    assert_eq!(
+        serde_json::to_value(&msg).unwrap(),
+        json!({
+            "role": "user",
+            "content": [
+                { "type": "text", "text": "hi" },
+                { "type": "text", "text": "<system-reminder>x</system-reminder>" }
+            ]
+        })
+    );
+
+    let mut msg = Message::user_with_tool_result("toolu_1".to_string(), "out".to_string());
+    msg.append_text("note");
+    let value = serde_json::to_value(&msg).unwrap();
+    assert_eq!(value["content"][1], json!({ "type": "text", "text": "note" }));
+}
@@ -0,0 +1,221 @@
+//! Integration tests for the subagent runner loop (driven by a scripted mock LLM server)
+
+mod common;
+
+use common::{MockBlock, MockLlmServer, TestFixture};
+use ok::llm::anthropic::AnthropicClient;
+use ok::subagent::{SubagentConfig, SubagentRunner, SubagentType};
+use ok::tool::ToolRegistry;
+use serde_json::json;
+use std::sync::Arc;
+
+fn create_runner(server: &MockLlmServer, config: SubagentConfig, fixture: &TestFixture) -> SubagentRunner {
+    SubagentRunner::new(
+        "test-agent".to_string(),
+        config,
+        Arc::new(ToolRegistry::new()),
+        fixture.path(),
+        AnthropicClient::new(server.station()),
+    )
+}
+
+fn glob_call(id: &str) -> Vec<MockBlock> {
+    vec![MockBlock::tool_use(id, "glob", json!({ "pattern": "*.txt" }))]
+}
+
+#[tokio::test]
+async fn test_subagent_completes_within_turn_limit() {
+    let fixture = TestFixture::new();
+    fixture.create_file("notes.txt", "hello");
+
+    let server = MockLlmServer::start(vec![
+        glob_call("toolu_1"),
+        vec![MockBlock::text("Found notes.txt")],
+    ])
+    .await;
+
+    let config = SubagentConfig::for_type(&SubagentType::Explore);
+    let mut runner = create_runner(&server, config, &fixture);
+    let result = runner.run_task("Find text files".to_string()).await.unwrap();
+
+    assert_eq!(result.output, "Found notes.txt");
+    assert_eq!(result.turns, 2);
+    assert!(!result.incomplete);
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn test_subagent_summarizes_when_turn_limit_reached() {
+    let fixture = TestFixture::new();
+    fixture.create_file("notes.txt", "hello");
+
+    let server = MockLlmServer::start(vec![
+        glob_call("toolu_1"),
+        glob_call("toolu_2"),
+        vec![MockBlock::text("Partial findings: notes.txt exists")],
+    ])
+    .await;
+
+    let config = SubagentConfig::for_type(&SubagentType::Explore).with_max_turns(2);
+    let mut runner = create_runner(&server, config, &fixture);
+    let result = runner.run_task("Explore everything".to_string()).await.unwrap();
+
+    assert!(result.incomplete);
+    assert_eq!(result.turns, 2);
+    assert_eq!(result.output, "Partial findings: notes.txt exists");
+
+    // The final request forbids tool use but still carries the tool definitions.
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    let last = &requests[2];
+    assert_eq!(last["tool_choice"], json!({ "type": "none" }));
+    assert!(last["tools"].as_array().is_some_and(|t| !t.is_empty()));
+    let last_message = last["messages"].as_array().unwrap().last().unwrap();
+    assert!(last_message["content"]
+        .as_str()
+        .unwrap()
+        .contains("maximum number of turns"));
+
+    // Earlier requests leave tool choice to the model.
+    assert!(requests[0].get("tool_choice").is_none());
+}
+
+#[tokio::test]
+async fn test_subagent_turn_limit_with_empty_summary_is_an_error() {
+    let fixture = TestFixture::new();
+
+    let server = MockLlmServer::start(vec![glob_call("toolu_1"), vec![MockBlock::text("")]]).await;
+
+    let config = SubagentConfig::for_type(&SubagentType::Explore).with_max_turns(1);
+    let mut runner = create_runner(&server, config, &fixture);
+    let result = runner.run_task("Explore".to_string()).await;
+
+    assert!(matches!(
+        result,
+        Err(ok::subagent::SubagentError::MaxTurnsExceeded(1))
+    ));
+}
+
+#[tokio::test]
+async fn test_subagent_reports_progress_to_event_sink() {
+    use ok::subagent::SubagentEventKind;
+
+    let fixture = TestFixture::new();
+    fixture.create_file("notes.txt", "hello");
+
+    let server = MockLlmServer::start(vec![
+        vec![MockBlock::text("Looking for text files"), glob_call("toolu_1").remove(0)],
+        vec![MockBlock::text("Found notes.txt")],
+    ])
+    .await;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let config = SubagentConfig::for_type(&SubagentType::Explore);
+    let mut runner = create_runner(&server, config, &fixture).with_event_sink(tx);
+    runner.run_task("Find text files".to_string()).await.unwrap();
+    drop(runner);
+
+    let mut kinds = Vec::new();
+    while let Some(event) = rx.recv().await {
+        assert_eq!(event.subagent_id, "test-agent");
+        assert_eq!(event.subagent_type, "Explore");
+        kinds.push(event.kind);
+    }
+
+    assert_eq!(
+        kinds,
+        vec![
+            SubagentEventKind::Text("Looking for text files".to_string()),
+            SubagentEventKind::ToolUse {
+                tool_use_id: "toolu_1".to_string(),
+                tool_name: "glob".to_string(),
+                input_summary: "*.txt".to_string(),
+            },
+            SubagentEventKind::ToolResult {
+                tool_use_id: "toolu_1".to_string(),
+                tool_name: "glob".to_string(),
+                summary: "1 matches".to_string(),
+                is_error: false,
+            },
+            SubagentEventKind::Text("Found notes.txt".to_string()),
+            SubagentEventKind::Finished {
+                turns: 2,
+                incomplete: false,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_subagent_background_shells_use_shared_manager_and_are_cleaned_up() {
+    let fixture = TestFixture::new();
+
+    let server = MockLlmServer::start(vec![
+        vec![MockBlock::tool_use(
+            "toolu_1",
+            "bash",
+            json!({ "command": "echo started; sleep 30", "run_in_background": true }),
+        )],
+        vec![MockBlock::text("Started the server")],
+    ])
+    .await;
+
+    let manager = Arc::new(ok::process::BackgroundShellManager::new());
+    let config = SubagentConfig::for_type(&SubagentType::Bash);
+    let mut runner =
+        create_runner(&server, config, &fixture).with_shell_manager(manager.clone());
+    let result = runner.run_task("Start the server".to_string()).await.unwrap();
+
+    // The shell was registered in the shared manager, tagged with the subagent's ID...
+    let owned = manager.list_ids_owned_by("test-agent").await;
+    assert_eq!(owned.len(), 1);
+
+    // ...and killed (but kept, with its output) once the subagent finished.
+    assert_eq!(result.terminated_shells, owned);
+    assert_eq!(
+        manager.get_status(&owned[0]).await,
+        Some(ok::process::background_shell::ShellStatus::Completed { exit_code: Some(137) })
+    );
+}
+
+#[tokio::test]
+async fn test_subagent_receives_diagnostics_after_writing_files() {
+    let fixture = TestFixture::new();
+
+    let server = MockLlmServer::start(vec![
+        vec![MockBlock::tool_use(
+            "toolu_1",
+            "write",
+            json!({ "file_path": "app.py", "content": "print(x)\n" }),
+        )],
+        vec![MockBlock::text("Wrote app.py")],
+    ])
+    .await;
+
+    let diagnostics = ok::diagnostics::Diagnostics::new(
+        fixture.path(),
+        toml::from_str(
+            r#"
+debounce_ms = 0
+
+[[checkers]]
+glob = "*.py"
+command = "echo 'app.py:1:7: error: undefined name x'"
+"#,
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    let config = SubagentConfig::for_type(&SubagentType::GeneralPurpose);
+    let mut runner =
+        create_runner(&server, config, &fixture).with_diagnostics(Arc::new(diagnostics));
+    runner.run_task("Write app.py".to_string()).await.unwrap();
+
+    // The report is attached to the tool result sent with the next request
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    let last_message = requests[1]["messages"].as_array().unwrap().last().unwrap().to_string();
+    assert!(last_message.contains("Diagnostics after your last file changes"));
+    assert!(last_message.contains("app.py:1:7: error: undefined name x"));
+}
@@ -0,0 +1,243 @@
+//! Integration tests for the apply_patch tool
+
+mod common;
+
+use common::TestFixture;
+use ok::tool::{apply_patch::ApplyPatchTool, base::*, read::ReadTool};
+use serde_json::json;
+use std::sync::Arc;
+
+/// Helper to create a tool context for testing
+fn create_test_context(working_dir: std::path::PathBuf) -> ToolContext {
+    ToolContext::new(
+        "test_session",
+        "test_msg",
+        "test_station",
+        working_dir,
+        Arc::new(ok::process::BackgroundShellManager::new()),
+    )
+}
+
+/// Read `file_paths` so the patch may change them
+async fn read_first(ctx: &ToolContext, file_paths: &[&str]) {
+    for file_path in file_paths {
+        ReadTool::new()
+            .execute(json!({ "file_path": file_path }), ctx)
+            .await
+            .expect("read before patching");
+    }
+}
+
+#[tokio::test]
+async fn test_apply_patch_multiple_files() {
+    let fixture = TestFixture::new();
+    fixture.create_dir("src");
+    fixture.create_file("src/lib.rs", "pub fn a() {}\n\npub fn b() {}\n");
+    fixture.create_file("old.txt", "obsolete\n");
+
+    let patch = "\
+diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+-pub fn a() {}
++pub fn a() -> u8 { 1 }
+ 
+ pub fn b() {}
+diff --git a/notes.md b/notes.md
+new file mode 100644
+--- /dev/null
++++ b/notes/notes.md
+@@ -0,0 +1,2 @@
++# Notes
++created by patch
+--- a/old.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-obsolete
+";
+
+    let tool = ApplyPatchTool;
+    let ctx = create_test_context(fixture.path());
+    read_first(&ctx, &["src/lib.rs", "old.txt"]).await;
+    let result = tool.execute(json!({ "patch": patch }), &ctx).await.unwrap();
+
+    assert_eq!(
+        fixture.read_file("src/lib.rs"),
+        "pub fn a() -> u8 { 1 }\n\npub fn b() {}\n"
+    );
+    assert_eq!(fixture.read_file("notes/notes.md"), "# Notes\ncreated by patch\n");
+    assert!(!fixture.file_exists("old.txt"));
+
+    assert_eq!(result.metadata.get("files_changed"), Some(&json!(3)));
+    let actions: Vec<&str> = result.metadata["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["action"].as_str().unwrap())
+        .collect();
+    assert_eq!(actions, vec!["modified", "created", "deleted"]);
+    assert!(result.output.contains("+pub fn a() -> u8 { 1 }"));
+    assert!(result.output.contains("+created by patch"));
+    assert!(result.output.contains("-obsolete"));
+}
+
+#[tokio::test]
+async fn test_apply_patch_tolerates_shifted_lines() {
+    let fixture = TestFixture::new();
+    let content: String = (1..=30).map(|i| format!("line {}\n", i)).collect();
+    fixture.create_file("data.txt", &content);
+
+    // Header claims line 10, but the target is at line 20
+    let patch = "--- a/data.txt\n+++ b/data.txt\n@@ -10,3 +10,3 @@\n line 19\n-line 20\n+LINE TWENTY\n line 21\n";
+
+    let tool = ApplyPatchTool;
+    let ctx = create_test_context(fixture.path());
+    read_first(&ctx, &["data.txt"]).await;
+    let result = tool.execute(json!({ "patch": patch }), &ctx).await.unwrap();
+
+    assert!(fixture.read_file("data.txt").contains("line 19\nLINE TWENTY\nline 21\n"));
+    assert!(result.output.contains("offset +9 lines"));
+}
+
+#[tokio::test]
+async fn test_apply_patch_is_all_or_nothing() {
+    let fixture = TestFixture::new();
+    fixture.create_file("a.txt", "alpha\n");
+    fixture.create_file("b.txt", "beta\n");
+
+    // The first file would apply, the second doesn't match
+    let patch = "\
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1 @@
+-alpha
++ALPHA
+--- a/b.txt
++++ b/b.txt
+@@ -1 +1 @@
+-gamma
++GAMMA
+";
+
+    let tool = ApplyPatchTool;
+    let ctx = create_test_context(fixture.path());
+    read_first(&ctx, &["a.txt", "b.txt"]).await;
+    let result = tool.execute(json!({ "patch": patch }), &ctx).await;
+
+    match result {
+        Err(ToolError::PatchFailed { path, reason }) => {
+            assert!(path.ends_with("b.txt"));
+            assert!(reason.contains("hunk 1"));
+        }
+        other => panic!("Expected PatchFailed error, got {:?}", other),
+    }
+    assert_eq!(fixture.read_file("a.txt"), "alpha\n");
+    assert_eq!(fixture.read_file("b.txt"), "beta\n");
+}
+
+#[tokio::test]
+async fn test_apply_patch_rejects_bad_input() {
+    let fixture = TestFixture::new();
+    fixture.create_file("exists.txt", "here\n");
+
+    let tool = ApplyPatchTool;
+    let ctx = create_test_context(fixture.path());
+
+    let result = tool.execute(json!({ "patch": "not a diff" }), &ctx).await;
+    assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+
+    // Creating a file that already exists
+    let patch = "--- /dev/null\n+++ b/exists.txt\n@@ -0,0 +1 @@\n+new\n";
+    let result = tool.execute(json!({ "patch": patch }), &ctx).await;
+    assert!(matches!(result, Err(ToolError::PatchFailed { .. })));
+    assert_eq!(fixture.read_file("exists.txt"), "here\n");
+
+    // Modifying a missing file
+    let patch = "--- a/missing.txt\n+++ b/missing.txt\n@@ -1 +1 @@\n-a\n+b\n";
+    let result = tool.execute(json!({ "patch": patch }), &ctx).await;
+    assert!(matches!(result, Err(ToolError::FileNotFound(_))));
+
+    // Paths stay inside the working directory
+    let patch = "--- /dev/null\n+++ /etc/ok-apply-patch-test\n@@ -0,0 +1 @@\n+nope\n";
+    let result = tool.execute(json!({ "patch": patch }), &ctx).await;
+    assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+}
+
+#[tokio::test]
+async fn test_apply_patch_requires_every_target_read() {
+    let fixture = TestFixture::new();
+    fixture.create_file("a.txt", "alpha\n");
+    fixture.create_file("b.txt", "beta\n");
+
+    let patch = "\
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1 @@
+-alpha
++ALPHA
+--- a/b.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-beta
+";
+
+    let tool = ApplyPatchTool;
+    let ctx = create_test_context(fixture.path());
+
+    // The deleted file was never read
+    read_first(&ctx, &["a.txt"]).await;
+    let result = tool.execute(json!({ "patch": patch }), &ctx).await;
+    assert!(matches!(&result, Err(ToolError::FileNotRead(path)) if path.ends_with("b.txt")), "{:?}", result);
+    assert_eq!(fixture.read_file("a.txt"), "alpha\n");
+    assert!(fixture.file_exists("b.txt"));
+
+    // Both read, then one changed behind the model's back
+    read_first(&ctx, &["b.txt"]).await;
+    fixture.create_file("a.txt", "alpha\nand more\n");
+    let result = tool.execute(json!({ "patch": patch }), &ctx).await;
+    assert!(matches!(result, Err(ToolError::FileModifiedSinceRead(_))));
+    assert!(fixture.file_exists("b.txt"));
+
+    read_first(&ctx, &["a.txt"]).await;
+    tool.execute(json!({ "patch": patch }), &ctx).await.unwrap();
+    assert_eq!(fixture.read_file("a.txt"), "ALPHA\nand more\n");
+    assert!(!fixture.file_exists("b.txt"));
+}
+
+#[tokio::test]
+async fn test_apply_patch_keeps_encoding_and_line_endings() {
+    let fixture = TestFixture::new();
+    let win = fixture.path().join("win.txt");
+    let latin = fixture.path().join("latin.txt");
+    std::fs::write(&win, b"\xEF\xBB\xBFfirst\r\nsecond\r\n").unwrap();
+    std::fs::write(&latin, b"caf\xE9\n").unwrap();
+
+    // The model sees LF-normalized, decoded text and patches that
+    let patch = "\
+--- a/win.txt
++++ b/win.txt
+@@ -1,2 +1,3 @@
+ first
++inserted
+ second
+--- a/latin.txt
++++ b/latin.txt
+@@ -1 +1 @@
+-café
++crème
+";
+
+    let tool = ApplyPatchTool;
+    let ctx = create_test_context(fixture.path());
+    read_first(&ctx, &["win.txt", "latin.txt"]).await;
+    tool.execute(json!({ "patch": patch }), &ctx).await.unwrap();
+
+    assert_eq!(std::fs::read(&win).unwrap(), b"\xEF\xBB\xBFfirst\r\ninserted\r\nsecond\r\n");
+    assert_eq!(std::fs::read(&latin).unwrap(), b"cr\xE8me\n");
+
+    // Consecutive patches need no new read
+    let patch = "--- a/latin.txt\n+++ b/latin.txt\n@@ -1 +1 @@\n-crème\n+crêpe\n";
+    tool.execute(json!({ "patch": patch }), &ctx).await.unwrap();
+    assert_eq!(std::fs::read(&latin).unwrap(), b"cr\xEApe\n");
+}
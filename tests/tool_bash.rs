@@ -174,8 +174,647 @@ async fn test_bash_validation_allows_find_dot() {
     assert!(output.output.contains("test.txt"));
 }
 
+#[tokio::test]
+async fn test_bash_validation_follows_cd_and_wrappers() {
+    let fixture = TestFixture::new();
+    let tool = BashTool;
+    let ctx = create_test_context(fixture.path());
+
+    for command in ["cd / && ls -R", "ls | xargs find /", "echo \"$(find ~ -name id_rsa)\""] {
+        let result = tool.execute(json!({ "command": command }), &ctx).await;
+        match result {
+            Err(ToolError::InvalidParams(msg)) => {
+                assert!(msg.contains("Working directory"), "{}", msg);
+            }
+            other => panic!("Expected {} to be denied, got {:?}", command, other),
+        }
+    }
+
+    // Quoting doesn't hide the path, and the user is never asked about a denied command
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let asking = create_test_context(fixture.path()).with_approvals(tx);
+    let result = tool.execute(json!({ "command": "rm -rf \"/\"" }), &asking).await;
+    assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+    assert!(rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_bash_risky_command_needs_approval() {
+    let fixture = TestFixture::new();
+    let tool = BashTool;
+
+    // Not a git repository, so running it is harmless
+    let command = "git reset --hard HEAD~1";
+
+    // Nobody to ask (e.g. a subagent): refused, and the model can't approve it itself
+    let ctx = create_test_context(fixture.path());
+    for params in [json!({ "command": command }), json!({ "command": command, "approved": true })] {
+        match tool.execute(params, &ctx).await {
+            Err(ToolError::ApprovalDenied { command: asked, reasons }) => {
+                assert_eq!(asked, command);
+                assert!(reasons.contains("discards uncommitted changes"));
+            }
+            other => panic!("Expected ApprovalDenied, got {:?}", other),
+        }
+    }
+
+    // The user answers through the approval channel
+    for approved in [false, true] {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<ApprovalRequest>();
+        let ctx = create_test_context(fixture.path()).with_approvals(tx);
+        let user = tokio::spawn(async move {
+            let request = rx.recv().await.expect("approval requested");
+            assert_eq!(request.command, command);
+            assert!(request.reasons.contains("discards uncommitted changes"));
+            request.reply.send(approved).unwrap();
+        });
+        let result = tool.execute(json!({ "command": command }), &ctx).await;
+        user.await.unwrap();
+        if approved {
+            // It ran (and failed outside a repository)
+            assert_eq!(result.unwrap().metadata.get("success"), Some(&json!(false)));
+        } else {
+            assert!(matches!(result, Err(ToolError::ApprovalDenied { .. })));
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_bash_reports_lines_while_running() {
+    let fixture = TestFixture::new();
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let ctx = create_test_context(fixture.path()).with_progress(tx);
+
+    let running = tokio::spawn({
+        let ctx = ctx.clone();
+        async move {
+            BashTool
+                .execute(json!({ "command": "echo first; sleep 1; echo second" }), &ctx)
+                .await
+        }
+    });
+
+    // The first line arrives while the command is still sleeping
+    let first = tokio::time::timeout(std::time::Duration::from_millis(800), rx.recv())
+        .await
+        .expect("no progress while the command runs")
+        .unwrap();
+    assert_eq!(first, ToolProgress { stream: "stdout", line: "first".to_string() });
+    assert!(!running.is_finished());
+
+    let result = running.await.unwrap().unwrap();
+    assert_eq!(result.output, "first\nsecond\n");
+    assert_eq!(rx.recv().await.unwrap().line, "second");
+
+    // Persistent sessions and pty commands stream too
+    for ctx in [session_context(fixture.path()), create_test_context(fixture.path())] {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let ctx = ctx.with_progress(tx);
+        BashTool
+            .execute(json!({ "command": "echo a; echo b >&2", "pty": ctx.shell_session.is_none() }), &ctx)
+            .await
+            .unwrap();
+        let mut lines = Vec::new();
+        while let Ok(progress) = rx.try_recv() {
+            lines.push(progress.line);
+        }
+        lines.sort();
+        assert_eq!(lines, ["a", "b"]);
+    }
+}
+
+#[tokio::test]
+async fn test_bash_env_stdin_and_cwd_overrides() {
+    let fixture = TestFixture::new();
+    fixture.create_dir("sub");
+    let ctx = create_test_context(fixture.path());
+
+    let result = BashTool
+        .execute(
+            json!({
+                "command": "pwd; echo \"$GREETING\"; tr a-z A-Z",
+                "env": { "GREETING": "hi there" },
+                "stdin": "from stdin\n",
+                "cwd": "sub"
+            }),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        result.output,
+        format!("{}\nhi there\nFROM STDIN\n", fixture.path().join("sub").display())
+    );
+    assert_eq!(result.metadata.get("cwd"), Some(&json!(fixture.path().join("sub"))));
+
+    // cwd goes through the same checks as file paths
+    for cwd in ["/etc", "../..", "missing"] {
+        let result = BashTool.execute(json!({ "command": "pwd", "cwd": cwd }), &ctx).await;
+        assert!(matches!(result, Err(ToolError::InvalidParams(_))), "{}: {:?}", cwd, result);
+    }
+    let result = BashTool
+        .execute(json!({ "command": "cat", "stdin": "x", "pty": true }), &ctx)
+        .await;
+    assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+
+    // In a persistent session, overrides run in their own process and leave the shell alone
+    let ctx = session_context(fixture.path());
+    BashTool.execute(json!({ "command": "export MODE=session" }), &ctx).await.unwrap();
+    let result = BashTool
+        .execute(json!({ "command": "pwd; echo \"[$MODE] $EXTRA\"", "cwd": "sub", "env": { "EXTRA": "1" } }), &ctx)
+        .await
+        .unwrap();
+    assert!(result.output.ends_with("sub\n[] 1\n"), "{}", result.output);
+    let result = BashTool.execute(json!({ "command": "pwd; echo \"[$MODE]\"" }), &ctx).await.unwrap();
+    assert_eq!(result.output, format!("{}\n[session]\n", fixture.path().display()));
+}
+
+#[tokio::test]
+async fn test_bash_scrubs_secrets_from_environment() {
+    std::env::set_var("OK_BASH_TEST_API_KEY", "sk-secret");
+    std::env::set_var("OK_BASH_TEST_VISIBLE", "shown");
+    let fixture = TestFixture::new();
+    let command = "echo \"[$OK_BASH_TEST_API_KEY] [$OK_BASH_TEST_VISIBLE]\"";
+
+    // Secret-looking variables are removed by default, everywhere commands run
+    for ctx in [create_test_context(fixture.path()), session_context(fixture.path())] {
+        let result = BashTool.execute(json!({ "command": command }), &ctx).await.unwrap();
+        assert_eq!(result.output, "[] [shown]\n");
+    }
+
+    let ctx = create_test_context(fixture.path());
+    let result = BashTool
+        .execute(
+            json!({
+                "command": format!("{}; echo \"$EXTRA\"; cat", command),
+                "run_in_background": true,
+                "env": { "EXTRA": "background" },
+                "stdin": "piped"
+            }),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    let shell_id = result.metadata["shell_id"].as_str().unwrap().to_string();
+    let mut stdout = Vec::new();
+    for _ in 0..200 {
+        stdout = ctx.shell_manager.get_stdout(&shell_id).await.unwrap_or_default();
+        if stdout.len() == 3 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(stdout, ["[] [shown]", "background", "piped"]);
+
+    // An explicit env entry is always passed; an allowlist keeps only what it names
+    let settings = ok::config::station::BashSettings {
+        env_allow: vec!["PATH".to_string(), "OK_BASH_TEST_*".to_string()],
+        ..Default::default()
+    };
+    let ctx = ctx.with_env_filter(Arc::new(ok::process::EnvFilter::new(&settings)));
+    let result = BashTool
+        .execute(
+            json!({ "command": format!("{}; echo \"[$HOME] $TOKEN\"", command), "env": { "TOKEN": "given" } }),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.output, "[] [shown]\n[] given\n");
+}
+
+fn session_context(working_dir: std::path::PathBuf) -> ToolContext {
+    create_test_context(working_dir).with_shell_session(Arc::new(ok::process::ShellSession::new()))
+}
+
+#[tokio::test]
+async fn test_bash_session_keeps_cwd_and_exports() {
+    let fixture = TestFixture::new();
+    fixture.create_dir("sub");
+    fixture.create_file("sub/marker.txt", "here");
+    let tool = BashTool;
+    let ctx = session_context(fixture.path());
+
+    let result = tool
+        .execute(json!({ "command": "cd sub && export BUILD_MODE=release" }), &ctx)
+        .await
+        .unwrap();
+    assert_eq!(result.metadata.get("session"), Some(&json!(true)));
+    assert_eq!(result.metadata.get("env_changed"), Some(&json!(true)));
+
+    let result = tool
+        .execute(json!({ "command": "cat marker.txt; echo \" $BUILD_MODE\"" }), &ctx)
+        .await
+        .unwrap();
+    assert!(result.output.contains("here release"), "{}", result.output);
+    assert_eq!(result.metadata.get("cwd"), Some(&json!(fixture.path().join("sub"))));
+    assert_eq!(result.metadata.get("env_changed"), Some(&json!(false)));
+
+    // Without a session every command starts over
+    let result = BashTool
+        .execute(json!({ "command": "pwd; echo \"[$BUILD_MODE]\"" }), &create_test_context(fixture.path()))
+        .await
+        .unwrap();
+    assert!(result.output.contains("[]"));
+    assert!(!result.metadata.contains_key("cwd"));
+}
+
+#[tokio::test]
+async fn test_bash_session_reset_and_drift_note() {
+    let fixture = TestFixture::new();
+    let tool = BashTool;
+    let ctx = session_context(fixture.path());
+
+    let result = tool
+        .execute(json!({ "command": "cd / && export LEFT=1" }), &ctx)
+        .await
+        .unwrap();
+    assert!(result.output.contains("outside the working directory"), "{}", result.output);
+
+    // Safety checks follow the shell: `find .` now searches outside the working directory
+    let result = tool.execute(json!({ "command": "find . -name x" }), &ctx).await;
+    assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+
+    let result = tool
+        .execute(json!({ "command": "", "reset": true }), &ctx)
+        .await
+        .unwrap();
+    assert_eq!(result.metadata.get("cwd"), Some(&json!(fixture.path())));
+
+    let result = tool
+        .execute(json!({ "command": "pwd; echo \"[$LEFT]\"" }), &ctx)
+        .await
+        .unwrap();
+    assert!(result.output.contains(&format!("{}\n[]", fixture.path().display())), "{}", result.output);
+    assert!(!result.output.contains("outside the working directory"));
+
+    // reset needs a session
+    let result = tool
+        .execute(json!({ "command": "true", "reset": true }), &create_test_context(fixture.path()))
+        .await;
+    assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+}
+
+#[tokio::test]
+async fn test_bash_session_restarts_after_exit_and_timeout() {
+    let fixture = TestFixture::new();
+    let tool = BashTool;
+    let ctx = session_context(fixture.path());
+
+    tool.execute(json!({ "command": "export KEPT=1" }), &ctx).await.unwrap();
+    let result = tool.execute(json!({ "command": "exit 4" }), &ctx).await.unwrap();
+    assert_eq!(result.metadata.get("exit_code"), Some(&json!(4)));
+    assert!(result.output.contains("exited the shell"));
+
+    let result = tool.execute(json!({ "command": "echo \"[$KEPT]\"" }), &ctx).await.unwrap();
+    assert!(result.output.contains("[]"));
+
+    tool.execute(json!({ "command": "export KEPT=2" }), &ctx).await.unwrap();
+    let result = tool
+        .execute(json!({ "command": "sleep 10", "timeout": 100 }), &ctx)
+        .await;
+    assert!(matches!(result, Err(ToolError::Timeout(100))));
+
+    let result = tool.execute(json!({ "command": "echo \"[$KEPT]\"" }), &ctx).await.unwrap();
+    assert!(result.output.contains("[]"));
+    assert_eq!(result.metadata.get("exit_code"), Some(&json!(0)));
+}
+
+/// A context whose shell commands run in a sandbox, or `None` if this machine can't set one up
+async fn sandboxed_context(working_dir: std::path::PathBuf) -> Option<ToolContext> {
+    let settings = ok::config::station::SandboxSettings {
+        enabled: true,
+        ..Default::default()
+    };
+    let sandbox = Arc::new(ok::process::Sandbox::new(&settings));
+    if let Err(e) = sandbox.ensure_available().await {
+        eprintln!("skipping sandbox test: {}", e);
+        return None;
+    }
+    Some(create_test_context(working_dir).with_sandbox(sandbox))
+}
+
+/// `ctx` with a user who approves every command that asks
+fn approving(ctx: ToolContext) -> ToolContext {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<ApprovalRequest>();
+    tokio::spawn(async move {
+        while let Some(request) = rx.recv().await {
+            let _ = request.reply.send(true);
+        }
+    });
+    ctx.with_approvals(tx)
+}
+
+#[tokio::test]
+async fn test_bash_sandbox_limits_writes_to_allowed_roots() {
+    let fixture = TestFixture::new();
+    let Some(ctx) = sandboxed_context(fixture.path()).await else {
+        return;
+    };
+    let ctx = approving(ctx);
+    let tool = BashTool;
+
+    let result = tool
+        .execute(json!({ "command": "echo inside > inside.txt && cat inside.txt" }), &ctx)
+        .await
+        .unwrap();
+    assert!(result.output.contains("inside"));
+    assert_eq!(result.metadata.get("sandboxed"), Some(&json!(true)));
+
+    // Outside the working directory and temp roots (the fixture lives in the temp dir); even
+    // once approved, the sandbox stops the write
+    let outside = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("target/sandbox-escape.txt");
+    let result = tool
+        .execute(
+            json!({ "command": format!("echo escaped > {}", outside.display()) }),
+            &ctx,
+        )
+        .await;
+    let escaped = outside.exists();
+    let _ = std::fs::remove_file(&outside);
+
+    assert!(!escaped);
+    match result {
+        Err(ToolError::Sandbox(ok::process::sandbox::SandboxError::Violation { reason, .. })) => {
+            assert!(reason.contains("Read-only file system"));
+        }
+        other => panic!("Expected a sandbox violation, got {:?}", other),
+    }
+
+    // A command that recovers from the denied write still succeeds, with a warning
+    let result = tool
+        .execute(
+            json!({ "command": format!("echo escaped > {} || echo fallback", outside.display()) }),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    assert!(!outside.exists());
+    assert!(result.output.contains("fallback"), "{}", result.output);
+    assert!(result.output.contains("Part of the command was blocked by the sandbox"), "{}", result.output);
+    assert_eq!(result.metadata.get("success"), Some(&json!(true)));
+    assert!(result.metadata["sandbox_warning"].is_string());
+}
+
+#[tokio::test]
+async fn test_bash_sandbox_cannot_undo_its_mounts() {
+    let fixture = TestFixture::new();
+    let Some(ctx) = sandboxed_context(fixture.path()).await else {
+        return;
+    };
+    let ctx = approving(ctx);
+    let tool = BashTool;
+
+    let result = tool
+        .execute(json!({ "command": "grep CapEff /proc/self/status" }), &ctx)
+        .await
+        .unwrap();
+    assert!(result.output.contains("0000000000000000"), "{}", result.output);
+
+    // Remounting read-write, directly or from a nested user namespace, must not get a write out
+    let outside = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("target/sandbox-remount.txt");
+    for command in [
+        format!("mount -o remount,bind,rw / ; touch {}", outside.display()),
+        format!("umount -l / ; touch {}", outside.display()),
+        format!(
+            "unshare -Urm sh -c 'mount -o remount,bind,rw / ; touch {}'",
+            outside.display()
+        ),
+    ] {
+        let _ = tool.execute(json!({ "command": command }), &ctx).await;
+        let escaped = outside.exists();
+        let _ = std::fs::remove_file(&outside);
+        assert!(!escaped, "escaped the sandbox with: {}", command);
+    }
+}
+
+#[tokio::test]
+async fn test_bash_sandbox_applies_to_shell_session() {
+    let fixture = TestFixture::new();
+    let Some(ctx) = sandboxed_context(fixture.path()).await else {
+        return;
+    };
+    let ctx = approving(ctx).with_shell_session(Arc::new(ok::process::ShellSession::new()));
+    let tool = BashTool;
+
+    tool.execute(json!({ "command": "export GREETING=hi" }), &ctx).await.unwrap();
+    let outside = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("target/session-escape.txt");
+    let result = tool
+        .execute(
+            json!({ "command": format!("echo $GREETING > {}", outside.display()) }),
+            &ctx,
+        )
+        .await;
+    let escaped = outside.exists();
+    let _ = std::fs::remove_file(&outside);
+
+    assert!(!escaped);
+    assert!(matches!(result, Err(ToolError::Sandbox(_))), "{:?}", result);
+}
+
+#[tokio::test]
+async fn test_bash_sandbox_disables_network() {
+    let fixture = TestFixture::new();
+    let Some(ctx) = sandboxed_context(fixture.path()).await else {
+        return;
+    };
+
+    let result = BashTool
+        .execute(json!({ "command": "bash -c 'exec 3<>/dev/tcp/1.1.1.1/80'" }), &ctx)
+        .await;
+    let err = result.unwrap_err();
+    assert!(err.to_string().contains("network access is disabled"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_bash_sandbox_applies_to_background_shells() {
+    let fixture = TestFixture::new();
+    let Some(ctx) = sandboxed_context(fixture.path()).await else {
+        return;
+    };
+
+    let ctx = approving(ctx);
+    let outside = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("target/sandbox-background.txt");
+    let result = BashTool
+        .execute(
+            json!({
+                "command": format!("echo escaped > {}; echo done > inside.txt", outside.display()),
+                "run_in_background": true
+            }),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    let shell_id = result.metadata["shell_id"].as_str().unwrap().to_string();
+
+    tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        while !fixture.file_exists("inside.txt") {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("background shell did not run");
+    let escaped = outside.exists();
+    let _ = std::fs::remove_file(&outside);
+    assert!(!escaped);
+
+    let stderr = ctx.shell_manager.get_stderr(&shell_id).await.unwrap_or_default();
+    assert!(stderr.iter().any(|line| line.contains("Read-only file system")));
+}
+
 // TODO: Add more edge case tests
 // - Test with special characters in command
 // - Test with environment variables
 // - Test with piped commands
 // - Test with very long output
+
+#[cfg(target_os = "linux")]
+/// Starts a child and a grandchild that would outlive `sh`, recording their PIDs
+const FORKING_COMMAND: &str =
+    "sleep 300 & echo $! > child.pid; (sleep 300 & echo $! > grandchild.pid; wait) & sleep 300";
+
+#[cfg(target_os = "linux")]
+/// Wait for the pid files of `FORKING_COMMAND`, then return the PIDs
+async fn forked_pids(fixture: &TestFixture) -> Vec<u32> {
+    for _ in 0..200 {
+        let pids: Vec<u32> = ["child.pid", "grandchild.pid"]
+            .iter()
+            .filter_map(|f| std::fs::read_to_string(fixture.path().join(f)).ok())
+            .filter_map(|pid| pid.trim().parse().ok())
+            .collect();
+        if pids.len() == 2 {
+            return pids;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    panic!("the command did not start its children");
+}
+
+#[cfg(target_os = "linux")]
+/// Whether `pid` is still running (zombies waiting to be reaped don't count)
+fn process_running(pid: u32) -> bool {
+    std::fs::read_to_string(format!("/proc/{}/stat", pid))
+        .map(|stat| {
+            let state = stat.rsplit(") ").next().and_then(|rest| rest.chars().next());
+            !matches!(state, Some('Z' | 'X'))
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(target_os = "linux")]
+async fn assert_all_stopped(pids: &[u32]) {
+    for _ in 0..100 {
+        if !pids.iter().any(|&pid| process_running(pid)) {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    panic!("processes survived: {:?}", pids.iter().filter(|&&p| process_running(p)).collect::<Vec<_>>());
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_bash_timeout_stops_the_whole_process_group() {
+    // Foreground command and persistent session: a timeout stops everything the command started
+    for ctx in [create_test_context, session_context] {
+        let fixture = TestFixture::new();
+        let ctx = ctx(fixture.path());
+        let result = BashTool
+            .execute(json!({ "command": FORKING_COMMAND, "timeout": 500 }), &ctx)
+            .await;
+        assert!(matches!(result, Err(ToolError::Timeout(500))), "{:?}", result);
+        assert_all_stopped(&forked_pids(&fixture).await).await;
+    }
+
+    // Dropping a running call (e.g. the turn is cancelled) stops it too
+    let fixture = TestFixture::new();
+    let ctx = create_test_context(fixture.path());
+    let call = BashTool.execute(json!({ "command": FORKING_COMMAND }), &ctx);
+    assert!(tokio::time::timeout(std::time::Duration::from_millis(500), call).await.is_err());
+    assert_all_stopped(&forked_pids(&fixture).await).await;
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_killing_background_shell_stops_its_children() {
+    let fixture = TestFixture::new();
+    let ctx = create_test_context(fixture.path());
+    for pty in [false, true] {
+        let _ = std::fs::remove_file(fixture.path().join("child.pid"));
+        let _ = std::fs::remove_file(fixture.path().join("grandchild.pid"));
+        let result = BashTool
+            .execute(json!({ "command": FORKING_COMMAND, "run_in_background": true, "pty": pty }), &ctx)
+            .await
+            .unwrap();
+        let pids = forked_pids(&fixture).await;
+        assert!(pids.iter().all(|&pid| process_running(pid)));
+
+        ctx.shell_manager
+            .kill(result.metadata["shell_id"].as_str().unwrap())
+            .await
+            .unwrap();
+        assert_all_stopped(&pids).await;
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_bash_resource_limits() {
+    let fixture = TestFixture::new();
+    let limited = |limits: ok::process::ResourceLimits| create_test_context(fixture.path()).with_resource_limits(limits);
+
+    // CPU time: a busy loop is stopped after a second of CPU
+    let ctx = limited(ok::process::ResourceLimits {
+        cpu_seconds: Some(1),
+        ..Default::default()
+    });
+    let result = BashTool
+        .execute(json!({ "command": "while :; do :; done", "timeout": 30000 }), &ctx)
+        .await
+        .unwrap();
+    assert_ne!(result.metadata["exit_code"], json!(0));
+
+    // Memory: a 200 MB allocation fails under a 64 MiB limit
+    let ctx = limited(ok::process::ResourceLimits {
+        memory_bytes: Some(64 * 1024 * 1024),
+        ..Default::default()
+    });
+    let result = BashTool
+        .execute(
+            json!({ "command": "x=$(head -c 200000000 /dev/zero | tr '\\0' a); echo ${#x}" }),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    assert!(!result.output.contains("200000000"), "{}", result.output);
+
+    // Output: an endless printer is stopped once it passes the limit, here and in the background
+    let limits = ok::process::ResourceLimits {
+        output_bytes: Some(10_000),
+        ..Default::default()
+    };
+    let session = Arc::new(ok::process::ShellSession::new().with_limits(limits));
+    for ctx in [limited(limits), limited(limits).with_shell_session(session)] {
+        let result = BashTool
+            .execute(json!({ "command": "yes", "timeout": 10000 }), &ctx)
+            .await
+            .unwrap();
+        assert!(result.output.len() < 11_000);
+        assert!(result.output.contains("\n\n(The command printed more than 10000 bytes and was stopped.)"));
+    }
+
+    let ctx = limited(limits);
+    let result = BashTool
+        .execute(json!({ "command": "yes", "run_in_background": true }), &ctx)
+        .await
+        .unwrap();
+    let shell_id = result.metadata["shell_id"].as_str().unwrap();
+    for _ in 0..200 {
+        if ctx.shell_manager.get_status(shell_id).await != Some(ok::process::background_shell::ShellStatus::Running) {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    let stdout = ctx.shell_manager.get_stdout(shell_id).await.unwrap();
+    assert!(stdout.len() <= 5_001);
+    assert_eq!(stdout.last().unwrap(), "(The command printed more than 10000 bytes and was stopped.)");
+}
@@ -0,0 +1,149 @@
+//! Integration tests for the bash_input tool (and bash pty mode)
+
+mod common;
+
+use common::TestFixture;
+use ok::tool::{base::*, bash::BashTool, bash_input::BashInputTool, bash_output::BashOutputTool};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::time::{sleep, Duration};
+
+/// Helper to create a tool context for testing
+fn create_test_context(working_dir: std::path::PathBuf) -> ToolContext {
+    ToolContext::new(
+        "test_session",
+        "test_msg",
+        "test_station",
+        working_dir,
+        Arc::new(ok::process::BackgroundShellManager::new()),
+    )
+}
+
+async fn start_background(ctx: &ToolContext, command: &str, pty: bool) -> String {
+    let result = BashTool
+        .execute(
+            json!({ "command": command, "run_in_background": true, "pty": pty }),
+            ctx,
+        )
+        .await
+        .unwrap();
+    result.metadata["shell_id"].as_str().unwrap().to_string()
+}
+
+async fn wait_for_pending_line(ctx: &ToolContext, shell_id: &str, needle: &str) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let pending = ctx.shell_manager.get_pending_line(shell_id).await.unwrap_or_default();
+            if pending.contains(needle) {
+                break;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("timed out waiting for the prompt");
+}
+
+#[tokio::test]
+async fn test_bash_pty_foreground() {
+    let fixture = TestFixture::new();
+    let ctx = create_test_context(fixture.path());
+
+    let result = BashTool
+        .execute(
+            json!({
+                "command": "test -t 1 && echo on-a-tty; stty size; printf '\\033[1;31mred\\033[0m\\n'; echo err >&2",
+                "pty": true
+            }),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.output, "on-a-tty\n40 120\nred\nerr\n");
+    assert_eq!(result.metadata.get("pty"), Some(&json!(true)));
+    assert_eq!(result.metadata.get("exit_code"), Some(&json!(0)));
+
+    // Without a pty, the same command sees pipes
+    let result = BashTool
+        .execute(json!({ "command": "test -t 1 || echo no-tty" }), &ctx)
+        .await
+        .unwrap();
+    assert!(result.output.contains("no-tty"));
+}
+
+#[tokio::test]
+async fn test_bash_input_answers_prompt() {
+    let fixture = TestFixture::new();
+    let ctx = create_test_context(fixture.path());
+    let shell_id = start_background(&ctx, "read -p 'Name? ' name; echo \"hello $name\"", true).await;
+
+    wait_for_pending_line(&ctx, &shell_id, "Name? ").await;
+    let output = BashOutputTool
+        .execute(json!({ "shell_id": shell_id }), &ctx)
+        .await
+        .unwrap();
+    assert!(output.output.contains("CURRENT LINE"), "{}", output.output);
+    assert!(output.output.contains("Name? "));
+
+    let result = BashInputTool
+        .execute(
+            json!({ "shell_id": shell_id, "input": "world", "keys": ["enter"], "wait_ms": 300 }),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.metadata.get("bytes_sent"), Some(&json!(6)));
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let lines = ctx.shell_manager.get_stdout(&shell_id).await.unwrap_or_default();
+            if lines.iter().any(|line| line == "hello world") {
+                break;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("the answer did not reach the command");
+}
+
+#[tokio::test]
+async fn test_bash_input_sends_keys_to_running_program() {
+    let fixture = TestFixture::new();
+    let ctx = create_test_context(fixture.path());
+    let shell_id = start_background(&ctx, "printf 'ready> '; sleep 30", true).await;
+    wait_for_pending_line(&ctx, &shell_id, "ready> ").await;
+
+    let result = BashInputTool
+        .execute(json!({ "shell_id": shell_id, "keys": ["ctrl-c"], "wait_ms": 500 }), &ctx)
+        .await
+        .unwrap();
+    assert_eq!(result.metadata.get("running"), Some(&json!(false)), "{}", result.output);
+}
+
+#[tokio::test]
+async fn test_bash_input_rejects_invalid_requests() {
+    let fixture = TestFixture::new();
+    let ctx = create_test_context(fixture.path());
+
+    let result = BashInputTool
+        .execute(json!({ "shell_id": "missing", "input": "q" }), &ctx)
+        .await;
+    assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+
+    // Plain background shells read /dev/null
+    let shell_id = start_background(&ctx, "sleep 5", false).await;
+    match BashInputTool
+        .execute(json!({ "shell_id": shell_id, "input": "q" }), &ctx)
+        .await
+    {
+        Err(ToolError::InvalidParams(message)) => assert!(message.contains("pty=true")),
+        other => panic!("Expected InvalidParams, got {:?}", other),
+    }
+
+    let result = BashInputTool
+        .execute(json!({ "shell_id": shell_id, "keys": ["f13"] }), &ctx)
+        .await;
+    assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+    ctx.shell_manager.kill_all().await;
+}
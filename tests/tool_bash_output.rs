@@ -180,7 +180,7 @@ async fn test_bash_output_with_offset() {
     // First call - get all lines
     let params1 = json!({
         "shell_id": shell_id,
-        "offset": 0
+        "since_seq": 0
     });
 
     let result1 = tool.execute(params1, &ctx).await;
@@ -194,7 +194,7 @@ async fn test_bash_output_with_offset() {
     // Second call - with offset (should return no new output)
     let params2 = json!({
         "shell_id": shell_id,
-        "offset": new_offset
+        "since_seq": new_offset
     });
 
     let result2 = tool.execute(params2, &ctx).await;
@@ -206,6 +206,27 @@ async fn test_bash_output_with_offset() {
     assert_eq!(output2.metadata.get("new_stderr_lines"), Some(&json!(0)));
 }
 
+#[tokio::test]
+async fn test_bash_output_rejects_old_offset() {
+    let fixture = TestFixture::new();
+    let ctx = create_test_context(fixture.path());
+
+    let shell_id = ctx
+        .shell_manager
+        .spawn("old_offset".into(), "echo 'out'; echo 'err' >&2".into(), fixture.path())
+        .await
+        .expect("Failed to spawn shell");
+
+    // A stdout line offset is not a sequence number, so it must not be read as one
+    let result = BashOutputTool
+        .execute(json!({"shell_id": shell_id, "offset": 1}), &ctx)
+        .await;
+    match result {
+        Err(ToolError::InvalidParams(msg)) => assert!(msg.contains("since_seq"), "{}", msg),
+        other => panic!("Expected InvalidParams error, got {:?}", other.map(|r| r.output)),
+    }
+}
+
 #[tokio::test]
 async fn test_bash_output_stderr_captured() {
     let fixture = TestFixture::new();
@@ -267,7 +288,7 @@ async fn test_bash_output_no_new_output() {
     // Second call with offset matching total
     let params = json!({
         "shell_id": shell_id,
-        "offset": total_lines
+        "since_seq": total_lines
     });
 
     let result2 = tool.execute(params, &ctx).await;
@@ -431,3 +452,260 @@ async fn test_bash_output_metadata_correct() {
     assert!(total_stdout >= 2); // Should have at least 2 stdout lines
     assert!(total_stderr >= 1); // Should have at least 1 stderr line
 }
+
+#[tokio::test]
+async fn test_bash_output_interleaves_streams_by_sequence_number() {
+    let fixture = TestFixture::new();
+    let ctx = create_test_context(fixture.path());
+
+    let shell_id = ctx
+        .shell_manager
+        .spawn(
+            "interleaved".into(),
+            "echo first; sleep 0.1; echo second >&2; sleep 0.1; echo third".into(),
+            fixture.path(),
+        )
+        .await
+        .expect("Failed to spawn shell");
+
+    wait_for_line_counts(&ctx, &shell_id, 2, 1).await;
+
+    let tool = BashOutputTool;
+    let combined = tool
+        .execute(json!({"shell_id": shell_id, "timestamps": true}), &ctx)
+        .await
+        .unwrap();
+    let out = &combined.output;
+    let first = out.find("] first").expect(out);
+    let second = out.find("[stderr] second").expect(out);
+    let third = out.find("] third").expect(out);
+    assert!(first < second && second < third, "{}", out);
+    assert!(out.contains("[+0."), "{}", out);
+    assert_eq!(combined.metadata.get("next_seq"), Some(&json!(3)));
+    assert_eq!(combined.metadata.get("new_stdout_lines"), Some(&json!(2)));
+    assert_eq!(combined.metadata.get("new_stderr_lines"), Some(&json!(1)));
+
+    let stderr = tool
+        .execute(json!({"shell_id": shell_id, "stream": "stderr"}), &ctx)
+        .await
+        .unwrap();
+    assert!(stderr.output.contains("=== STDERR (new) ===\nsecond\n"), "{}", stderr.output);
+    assert!(!stderr.output.contains("first"));
+
+    // Sequence numbers count both streams
+    let rest = tool
+        .execute(json!({"shell_id": shell_id, "since_seq": 2, "stream": "stdout"}), &ctx)
+        .await
+        .unwrap();
+    assert!(rest.output.contains("third"));
+    assert!(!rest.output.contains("first"));
+    assert_eq!(rest.metadata.get("new_stdout_lines"), Some(&json!(1)));
+
+    let invalid = tool
+        .execute(json!({"shell_id": shell_id, "stream": "both"}), &ctx)
+        .await;
+    assert!(matches!(invalid, Err(ToolError::InvalidParams(_))));
+}
+
+#[tokio::test]
+async fn test_bash_output_reports_evicted_lines_and_keeps_seq_valid() {
+    let fixture = TestFixture::new();
+    let ctx = create_test_context(fixture.path());
+
+    // About 7 MiB of output, more than the log keeps
+    let shell_id = ctx
+        .shell_manager
+        .spawn(
+            "flood".into(),
+            "seq -f 'line %06g padding padding padding padding' 1 150000".into(),
+            fixture.path(),
+        )
+        .await
+        .expect("Failed to spawn shell");
+
+    tokio::time::timeout(Duration::from_secs(20), async {
+        while ctx.shell_manager.get_line_counts(&shell_id).await.unwrap_or((0, 0)).0 < 150_000 {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("timed out waiting for output");
+
+    let tool = BashOutputTool;
+    let result = tool
+        .execute(json!({"shell_id": shell_id, "stream": "stdout"}), &ctx)
+        .await
+        .unwrap();
+    let missed = result.metadata.get("missed_lines").unwrap().as_u64().unwrap();
+    assert!(missed > 0);
+    assert!(result.output.contains(&format!("[{} lines (seq 0 to {}) were dropped", missed, missed - 1)));
+    assert!(!result.output.contains("line 000001 "));
+    assert!(result.output.contains("line 150000 "));
+    assert_eq!(result.metadata.get("next_seq"), Some(&json!(150_000)));
+    assert_eq!(result.metadata.get("total_stdout_lines"), Some(&json!(150_000)));
+
+    // Offsets into the retained part still point at the same lines
+    let tail = tool
+        .execute(json!({"shell_id": shell_id, "since_seq": 149_998}), &ctx)
+        .await
+        .unwrap();
+    assert_eq!(tail.metadata.get("missed_lines"), Some(&json!(0)));
+    assert_eq!(tail.metadata.get("new_stdout_lines"), Some(&json!(2)));
+    assert!(tail.output.contains("line 149999 "));
+    assert!(tail.output.contains("line 150000 "));
+}
+
+#[tokio::test]
+async fn test_kill_owned_by_only_touches_that_owner() {
+    let fixture = TestFixture::new();
+    let ctx = create_test_context(fixture.path());
+    let manager = &ctx.shell_manager;
+
+    manager
+        .spawn_owned("sub_shell".into(), "sleep 30".into(), fixture.path(), "subagent_1")
+        .await
+        .expect("Failed to spawn shell");
+    manager
+        .spawn_owned("parent_shell".into(), "sleep 30".into(), fixture.path(), "session_1")
+        .await
+        .expect("Failed to spawn shell");
+
+    assert_eq!(manager.list_ids_owned_by("subagent_1").await, vec!["sub_shell"]);
+
+    let killed = manager.kill_owned_by("subagent_1").await;
+    assert_eq!(killed, vec!["sub_shell"]);
+
+    // Killed shells stay registered so their output can still be read
+    assert!(manager.exists("sub_shell").await);
+    assert_eq!(
+        manager.get_status("sub_shell").await,
+        Some(ok::process::background_shell::ShellStatus::Completed { exit_code: Some(137) })
+    );
+    assert_eq!(
+        manager.get_status("parent_shell").await,
+        Some(ok::process::background_shell::ShellStatus::Running)
+    );
+
+    // Nothing left to kill for that owner
+    assert!(manager.kill_owned_by("subagent_1").await.is_empty());
+    manager.kill("parent_shell").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_summary_reports_command_and_exit_code() {
+    let fixture = TestFixture::new();
+    let ctx = create_test_context(fixture.path());
+    let manager = &ctx.shell_manager;
+
+    manager
+        .spawn("done_shell".into(), "echo hi; exit 3".into(), fixture.path())
+        .await
+        .expect("Failed to spawn shell");
+    manager
+        .spawn("long_shell".into(), "sleep 30".into(), fixture.path())
+        .await
+        .expect("Failed to spawn shell");
+
+    wait_for_stdout_contains(&ctx, "done_shell", "hi").await;
+
+    let summary = tokio::time::timeout(Duration::from_secs(2), async {
+        loop {
+            let summary = manager.summary().await;
+            if summary.iter().any(|s| s.id == "done_shell" && s.exit_code.is_some()) {
+                break summary;
+            }
+            sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("shell did not exit");
+
+    // Oldest first
+    assert_eq!(summary[0].id, "done_shell");
+    assert_eq!(summary[0].command, "echo hi; exit 3");
+    assert_eq!(summary[0].exit_code, Some(3));
+    assert_eq!(summary[0].stdout_lines, 1);
+    assert_eq!(summary[1].id, "long_shell");
+    assert_eq!(summary[1].exit_code, None);
+
+    assert_eq!(manager.kill_all().await, 1);
+    assert_eq!(manager.kill_all().await, 0);
+}
+
+#[tokio::test]
+async fn test_owned_shell_notifies_on_exit_and_watch_match() {
+    use ok::process::{ShellNotificationKind, ShellOptions};
+
+    let fixture = TestFixture::new();
+    let ctx = create_test_context(fixture.path());
+    let manager = &ctx.shell_manager;
+
+    let options = ShellOptions {
+        owner: Some("session_1".to_string()),
+        watch: Some(regex::Regex::new("^ready").unwrap()),
+        ..Default::default()
+    };
+    manager
+        .spawn_with_options(
+            "watched".into(),
+            "echo starting; echo 'ready on :3000'; exit 4".into(),
+            fixture.path(),
+            options,
+        )
+        .await
+        .expect("Failed to spawn shell");
+
+    // The waiter records the exit without anyone polling the shell
+    let notifications = tokio::time::timeout(Duration::from_secs(2), async {
+        let mut collected = Vec::new();
+        loop {
+            collected.extend(manager.take_notifications("session_1"));
+            if collected
+                .iter()
+                .any(|n| matches!(n.kind, ShellNotificationKind::Exited { .. }))
+            {
+                break collected;
+            }
+            sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("no exit notification");
+
+    assert_eq!(
+        notifications.iter().map(|n| &n.kind).collect::<Vec<_>>(),
+        vec![
+            &ShellNotificationKind::Matched {
+                pattern: "^ready".to_string(),
+                stream: "stdout",
+                line: "ready on :3000".to_string(),
+            },
+            &ShellNotificationKind::Exited { exit_code: Some(4) },
+        ]
+    );
+    assert_eq!(
+        manager.get_status("watched").await,
+        Some(ok::process::background_shell::ShellStatus::Completed { exit_code: Some(4) })
+    );
+    assert!(!manager.has_notifications("session_1"));
+}
+
+#[tokio::test]
+async fn test_killed_shell_does_not_notify() {
+    let fixture = TestFixture::new();
+    let ctx = create_test_context(fixture.path());
+    let manager = &ctx.shell_manager;
+
+    manager
+        .spawn_owned("sleeper".into(), "sleep 30".into(), fixture.path(), "session_1")
+        .await
+        .expect("Failed to spawn shell");
+    manager.kill("sleeper").await.unwrap();
+
+    assert_eq!(
+        manager.get_status("sleeper").await,
+        Some(ok::process::background_shell::ShellStatus::Completed { exit_code: Some(137) })
+    );
+    sleep(Duration::from_millis(50)).await;
+    assert!(!manager.has_notifications("session_1"));
+}
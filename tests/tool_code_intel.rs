@@ -0,0 +1,371 @@
+//! Integration tests for the code_intel tool, against a fake language server over an in-memory pipe
+
+mod common;
+
+use common::TestFixture;
+use ok::lsp::transport::{read_message, write_message};
+use ok::lsp::{LspClient, LspManager};
+use ok::tool::{base::*, code_intel::CodeIntelTool};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::io::BufReader;
+
+const LIB_RS: &str = "\
+pub fn greet(name: &str) -> String {
+    format!(\"hello {}\", name)
+}
+
+pub fn main() {
+    let s = greet(\"world\");
+    println!(\"{}\", greet(&s));
+}
+";
+
+/// Helper to create a tool context for testing, in a project the user trusts
+fn create_test_context(working_dir: std::path::PathBuf) -> ToolContext {
+    ToolContext::new(
+        "test_session",
+        "test_msg",
+        "test_station",
+        working_dir,
+        Arc::new(ok::process::BackgroundShellManager::new()),
+    )
+    .with_trusted_project(true)
+}
+
+fn range(line: u64, start: u64, end: u64) -> Value {
+    json!({ "start": { "line": line, "character": start }, "end": { "line": line, "character": end } })
+}
+
+/// Answer requests the way a language server indexing LIB_RS would, recording each request
+async fn fake_server(stream: tokio::io::DuplexStream, uri: String, log: Arc<Mutex<Vec<Value>>>) {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = BufReader::new(reader);
+
+    while let Ok(Some(message)) = read_message(&mut reader).await {
+        log.lock().unwrap().push(message.clone());
+        let method = message["method"].as_str().unwrap_or("").to_string();
+
+        if method == "textDocument/didOpen" {
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/publishDiagnostics",
+                "params": {
+                    "uri": uri,
+                    "diagnostics": [{
+                        "range": range(5, 8, 9),
+                        "severity": 2,
+                        "source": "fake",
+                        "message": "unused variable"
+                    }]
+                }
+            });
+            write_message(&mut writer, &notification).await.unwrap();
+            continue;
+        }
+        let Some(id) = message.get("id").cloned() else { continue };
+
+        let result = match method.as_str() {
+            "initialize" => json!({ "capabilities": { "definitionProvider": true } }),
+            "textDocument/definition" => json!([{ "uri": uri, "range": range(0, 7, 12) }]),
+            "textDocument/references" => json!([
+                { "uri": uri, "range": range(6, 27, 32) },
+                { "uri": uri, "range": range(0, 7, 12) },
+                { "uri": uri, "range": range(5, 12, 17) }
+            ]),
+            "textDocument/hover" => json!({
+                "contents": { "kind": "markdown", "value": "```rust\npub fn greet(name: &str) -> String\n```" }
+            }),
+            "textDocument/documentSymbol" => json!([
+                { "name": "greet", "kind": 12, "range": range(0, 0, 1), "selectionRange": range(0, 7, 12) },
+                {
+                    "name": "main", "kind": 12, "range": range(4, 0, 1), "selectionRange": range(4, 7, 11),
+                    "children": [{ "name": "s", "kind": 13, "range": range(5, 8, 9), "selectionRange": range(5, 8, 9) }]
+                }
+            ]),
+            "workspace/symbol" => json!([{
+                "name": "greet",
+                "kind": 12,
+                "location": { "uri": uri, "range": range(0, 0, 36) }
+            }]),
+            _ => Value::Null,
+        };
+        let reply = json!({ "jsonrpc": "2.0", "id": id, "result": result });
+        write_message(&mut writer, &reply).await.unwrap();
+    }
+}
+
+/// A tool whose "rust" server is the fake one; returns the fake's request log
+async fn setup(fixture: &TestFixture) -> (CodeIntelTool, Arc<Mutex<Vec<Value>>>) {
+    let root = fixture.path();
+    let file = fixture.create_file("lib.rs", LIB_RS);
+    let uri = url::Url::from_file_path(&file).unwrap().to_string();
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let (client_end, server_end) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(fake_server(server_end, uri, log.clone()));
+
+    let (reader, writer) = tokio::io::split(client_end);
+    let client = LspClient::connect(reader, writer, &root).await.unwrap();
+    let manager = LspManager::new(HashMap::new());
+    manager.insert_client("rust", &root, client).await;
+
+    (CodeIntelTool::with_manager(Arc::new(manager)), log)
+}
+
+fn requests<'a>(log: &'a [Value], method: &str) -> Vec<&'a Value> {
+    log.iter().filter(|m| m["method"] == method).collect()
+}
+
+#[tokio::test]
+async fn test_code_intel_definition_by_position() {
+    let fixture = TestFixture::new();
+    let (tool, log) = setup(&fixture).await;
+    let ctx = create_test_context(fixture.path());
+
+    let result = tool
+        .execute(
+            json!({ "operation": "definition", "file_path": "lib.rs", "line": 6, "column": 13 }),
+            &ctx,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.title, "definition: lib.rs:6:13");
+    assert!(result.output.contains("Found 1 definitions in 1 files:"));
+    assert!(result.output.contains("lib.rs:\n>    1\u{2502} pub fn greet(name: &str) -> String {"));
+
+    let log = log.lock().unwrap();
+    assert_eq!(requests(&log, "initialize").len(), 1);
+    assert_eq!(requests(&log, "textDocument/didOpen").len(), 1);
+    let request = requests(&log, "textDocument/definition")[0];
+    assert_eq!(request["params"]["position"], json!({ "line": 5, "character": 12 }));
+}
+
+#[tokio::test]
+async fn test_code_intel_references_by_symbol_sorted_like_grep() {
+    let fixture = TestFixture::new();
+    let (tool, log) = setup(&fixture).await;
+    let ctx = create_test_context(fixture.path());
+
+    let result = tool
+        .execute(
+            json!({ "operation": "references", "file_path": "lib.rs", "line": 7, "symbol": "greet" }),
+            &ctx,
+        )
+        .await
+        .unwrap();
+
+    assert!(result.output.contains("Found 3 references in 1 files:"));
+    let first = result.output.find(">    1\u{2502}").unwrap();
+    let second = result.output.find(">    6\u{2502}").unwrap();
+    let third = result.output.find(">    7\u{2502}").unwrap();
+    assert!(first < second && second < third);
+    assert_eq!(result.metadata.get("total_results"), Some(&json!(3)));
+
+    let log = log.lock().unwrap();
+    let request = requests(&log, "textDocument/references")[0];
+    assert_eq!(request["params"]["position"], json!({ "line": 6, "character": 19 }));
+    assert_eq!(request["params"]["context"]["includeDeclaration"], json!(true));
+}
+
+#[tokio::test]
+async fn test_code_intel_hover_by_workspace_symbol() {
+    let fixture = TestFixture::new();
+    let (tool, log) = setup(&fixture).await;
+    let ctx = create_test_context(fixture.path());
+
+    let result = tool
+        .execute(json!({ "operation": "hover", "symbol": "greet" }), &ctx)
+        .await
+        .unwrap();
+
+    assert!(result.output.starts_with("Hover at lib.rs:1:8:"));
+    assert!(result.output.contains("pub fn greet(name: &str) -> String"));
+
+    // The workspace symbol's range starts at `pub`; the hover is requested on the name
+    let log = log.lock().unwrap();
+    let request = requests(&log, "textDocument/hover")[0];
+    assert_eq!(request["params"]["position"], json!({ "line": 0, "character": 7 }));
+}
+
+#[tokio::test]
+async fn test_code_intel_symbols_and_diagnostics() {
+    let fixture = TestFixture::new();
+    let (tool, log) = setup(&fixture).await;
+    let ctx = create_test_context(fixture.path());
+
+    let outline = tool
+        .execute(json!({ "operation": "document_symbols", "file_path": "lib.rs" }), &ctx)
+        .await
+        .unwrap();
+    assert!(outline.output.contains(">    1\u{2502} function greet"));
+    assert!(outline.output.contains(">    5\u{2502} function main"));
+    assert!(outline.output.contains(">    6\u{2502}   variable s"));
+
+    let symbols = tool
+        .execute(json!({ "operation": "workspace_symbols", "symbol": "gre" }), &ctx)
+        .await
+        .unwrap();
+    assert!(symbols.output.contains("Found 1 symbols in 1 files:"));
+    assert!(symbols.output.contains("function greet"));
+
+    let diagnostics = tool
+        .execute(json!({ "operation": "diagnostics", "file_path": "lib.rs" }), &ctx)
+        .await
+        .unwrap();
+    assert!(diagnostics
+        .output
+        .contains(">    6\u{2502} warning: unused variable [fake]"));
+
+    // The file was opened once and not re-sent while unchanged
+    let log = log.lock().unwrap();
+    assert_eq!(requests(&log, "textDocument/didOpen").len(), 1);
+    assert!(requests(&log, "textDocument/didChange").is_empty());
+}
+
+#[tokio::test]
+async fn test_code_intel_syncs_changed_files_and_rejects_bad_params() {
+    let fixture = TestFixture::new();
+    let (tool, log) = setup(&fixture).await;
+    let ctx = create_test_context(fixture.path());
+
+    let params = json!({ "operation": "definition", "file_path": "lib.rs", "line": 6, "symbol": "greet" });
+    tool.execute(params.clone(), &ctx).await.unwrap();
+    fixture.create_file("lib.rs", &format!("{}\n// changed\n", LIB_RS));
+    tool.execute(params, &ctx).await.unwrap();
+
+    {
+        let log = log.lock().unwrap();
+        let changes = requests(&log, "textDocument/didChange");
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0]["params"]["textDocument"]["version"], json!(2));
+    }
+
+    let missing_position = tool
+        .execute(json!({ "operation": "definition", "file_path": "lib.rs" }), &ctx)
+        .await;
+    assert!(matches!(missing_position, Err(ToolError::InvalidParams(_))));
+
+    let not_found = tool
+        .execute(json!({ "operation": "references", "file_path": "lib.rs", "symbol": "nope" }), &ctx)
+        .await;
+    assert!(matches!(not_found, Err(ToolError::InvalidParams(_))));
+
+    fixture.create_file("notes.txt", "greet\n");
+    let unsupported = tool
+        .execute(json!({ "operation": "document_symbols", "file_path": "notes.txt" }), &ctx)
+        .await;
+    assert!(matches!(unsupported, Err(ToolError::Lsp(ok::lsp::LspError::NoServer(_)))));
+}
+
+#[test]
+fn test_code_intel_server_settings_from_config() {
+    let config: ok::config::Config = toml::from_str(
+        r#"
+[lsp.rust]
+command = "ra-multiplex"
+
+[lsp.zig]
+command = "zls"
+extensions = ["zig"]
+"#,
+    )
+    .unwrap();
+
+    let manager = LspManager::new(config.lsp);
+    // An override without extensions keeps the defaults
+    assert_eq!(manager.language_for(Path::new("src/main.rs")), Some("rust"));
+    assert_eq!(manager.language_for(Path::new("build.zig")), Some("zig"));
+    assert_eq!(manager.language_for(Path::new("app.py")), Some("python"));
+    assert_eq!(manager.language_for(Path::new("README")), None);
+}
+
+/// `[lsp.<language>]` settings for a "server" that runs `script` and exits
+fn script_server(script: String, extension: &str) -> ok::config::station::LspServerSettings {
+    ok::config::station::LspServerSettings {
+        command: "sh".to_string(),
+        args: vec!["-c".to_string(), script],
+        extensions: vec![extension.to_string()],
+        language_id: None,
+    }
+}
+
+#[tokio::test]
+async fn test_lsp_servers_get_the_filtered_environment() {
+    let fixture = TestFixture::new();
+    let root = fixture.path();
+    let env_file = root.join("server-env.txt");
+    let manager = LspManager::new(HashMap::from([(
+        "rust".to_string(),
+        script_server(format!("env > {}", env_file.display()), "rs"),
+    )]));
+    let env_filter = ok::process::EnvFilter::new(&ok::config::station::BashSettings {
+        env_deny: vec!["HOME".to_string()],
+        ..Default::default()
+    });
+
+    // The script exits without answering `initialize`
+    let result = manager.server_for(&root.join("lib.rs"), &root, &env_filter).await;
+    assert!(matches!(result, Err(ok::lsp::LspError::Closed)));
+
+    let env = std::fs::read_to_string(&env_file).unwrap();
+    assert!(env.lines().any(|line| line.starts_with("PATH=")), "{}", env);
+    assert!(!env.lines().any(|line| line.starts_with("HOME=")), "{}", env);
+}
+
+#[tokio::test]
+async fn test_lsp_server_start_does_not_block_other_servers() {
+    let fixture = TestFixture::new();
+    let root = fixture.path();
+    let manager = Arc::new(LspManager::new(HashMap::from([
+        ("rust".to_string(), script_server("sleep 3".to_string(), "rs")),
+        ("python".to_string(), script_server("exit 0".to_string(), "py")),
+    ])));
+    let env_filter = Arc::new(ok::process::EnvFilter::default());
+
+    let slow = tokio::spawn({
+        let (manager, root, env_filter) = (manager.clone(), root.clone(), env_filter.clone());
+        async move { manager.server_for(&root.join("lib.rs"), &root, &env_filter).await.map(|_| ()) }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    // While the rust server is starting, other servers can be queried and started
+    let quick = std::time::Duration::from_secs(1);
+    let running = tokio::time::timeout(quick, manager.running(&root)).await.unwrap();
+    assert!(running.is_empty());
+    let other = tokio::time::timeout(quick, manager.server_for(&root.join("app.py"), &root, &env_filter))
+        .await
+        .unwrap();
+    assert!(matches!(other, Err(ok::lsp::LspError::Closed)));
+
+    assert!(matches!(slow.await.unwrap(), Err(ok::lsp::LspError::Closed)));
+}
+
+#[tokio::test]
+async fn test_code_intel_starts_servers_only_in_trusted_projects() {
+    let fixture = TestFixture::new();
+    let root = fixture.path();
+    fixture.create_file("lib.rs", LIB_RS);
+    let started = root.join("server-started");
+    let tool = CodeIntelTool::new().with_server_settings(HashMap::from([(
+        "rust".to_string(),
+        script_server(format!("touch {}", started.display()), "rs"),
+    )]));
+    let params = json!({ "operation": "document_symbols", "file_path": "lib.rs" });
+
+    // Neither a file's server nor the servers guessed for a workspace query start
+    let untrusted = create_test_context(root.clone()).with_trusted_project(false);
+    for untrusted_params in [params.clone(), json!({ "operation": "workspace_symbols", "symbol": "greet" })] {
+        let result = tool.execute(untrusted_params, &untrusted).await;
+        assert!(matches!(result, Err(ToolError::Lsp(ok::lsp::LspError::Untrusted))));
+    }
+    assert!(!started.exists(), "language server started in an untrusted project");
+
+    // The script exits without answering `initialize`
+    let trusted = tool.execute(params, &create_test_context(root)).await;
+    assert!(matches!(trusted, Err(ToolError::Lsp(ok::lsp::LspError::Closed))));
+    assert!(started.exists());
+}
@@ -0,0 +1,150 @@
+//! Integration tests for the code_outline tool
+
+mod common;
+
+use common::TestFixture;
+use ok::tool::{base::*, code_outline::CodeOutlineTool};
+use serde_json::json;
+use std::sync::Arc;
+
+const LIB_RS: &str = "\
+use std::fmt;
+
+/// A parser for key=value lines
+pub struct Parser {
+    strict: bool,
+}
+
+impl Parser {
+    pub fn new(strict: bool) -> Self {
+        Parser { strict }
+    }
+
+    /// Parse one line
+    pub fn parse(&self, line: &str) -> Option<(String, String)> {
+        let (key, value) = line.split_once('=')?;
+        Some((key.to_string(), value.to_string()))
+    }
+}
+
+impl fmt::Display for Parser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, \"Parser(strict={})\", self.strict)
+    }
+}
+
+pub fn parse(line: &str) -> Option<(String, String)> {
+    Parser::new(false).parse(line)
+}
+";
+
+/// Helper to create a tool context for testing
+fn create_test_context(working_dir: std::path::PathBuf) -> ToolContext {
+    ToolContext::new(
+        "test_session",
+        "test_msg",
+        "test_station",
+        working_dir,
+        Arc::new(ok::process::BackgroundShellManager::new()),
+    )
+}
+
+#[tokio::test]
+async fn test_code_outline_lists_items_with_ranges() {
+    let fixture = TestFixture::new();
+    fixture.create_file("lib.rs", LIB_RS);
+    let ctx = create_test_context(fixture.path());
+
+    let result = CodeOutlineTool
+        .execute(json!({ "file_path": "lib.rs" }), &ctx)
+        .await
+        .unwrap();
+
+    assert!(result.output.starts_with("Outline of lib.rs (rust, 28 lines, 7 items):"));
+    assert!(result
+        .output
+        .contains("    4\u{2192}pub struct Parser {  (struct Parser, lines 4-6)"));
+    assert!(result
+        .output
+        .contains("   14\u{2192}    pub fn parse(&self, line: &str) -> Option<(String, String)> {  (function Parser::parse, lines 14-17)"));
+    assert!(result
+        .output
+        .contains("   20\u{2192}impl fmt::Display for Parser {  (impl Parser, lines 20-24)"));
+    assert!(result
+        .output
+        .contains("   26\u{2192}pub fn parse(line: &str) -> Option<(String, String)> {  (function parse, lines 26-28)"));
+    assert_eq!(result.metadata.get("items"), Some(&json!(7)));
+
+    // Outlining is not a read: the file still can't be edited
+    assert!(!ctx.file_states.is_tracked(&fixture.path().join("lib.rs")));
+}
+
+#[tokio::test]
+async fn test_code_outline_symbol_source() {
+    let fixture = TestFixture::new();
+    fixture.create_file("lib.rs", LIB_RS);
+    let ctx = create_test_context(fixture.path());
+
+    let result = CodeOutlineTool
+        .execute(json!({ "file_path": "lib.rs", "symbol": "Parser::parse" }), &ctx)
+        .await
+        .unwrap();
+
+    // Doc comments are included; numbering matches read
+    assert!(result.output.starts_with("   13\u{2192}    /// Parse one line\n   14\u{2192}    pub fn parse"));
+    assert!(result.output.contains("   17\u{2192}    }\n\n(function Parser::parse: lines 13-17 of lib.rs)"));
+    assert!(!result.output.contains("Other matches"));
+    assert_eq!(result.metadata.get("start_line"), Some(&json!(13)));
+    assert_eq!(result.metadata.get("end_line"), Some(&json!(17)));
+    assert!(ctx.file_states.is_tracked(&fixture.path().join("lib.rs")));
+
+    // An exact top-level name wins over methods of the same name
+    let result = CodeOutlineTool
+        .execute(json!({ "file_path": "lib.rs", "symbol": "parse" }), &ctx)
+        .await
+        .unwrap();
+    assert!(result.output.contains("(function parse: lines 26-28 of lib.rs)"));
+
+    // Several items with one name: the first is shown, the rest listed
+    let result = CodeOutlineTool
+        .execute(json!({ "file_path": "lib.rs", "symbol": "Parser" }), &ctx)
+        .await
+        .unwrap();
+    assert!(result.output.starts_with("    3\u{2192}/// A parser for key=value lines\n"));
+    assert!(result
+        .output
+        .contains("(Other matches: impl Parser at line 8, impl Parser at line 20)"));
+}
+
+#[tokio::test]
+async fn test_code_outline_python_and_errors() {
+    let fixture = TestFixture::new();
+    fixture.create_file(
+        "app.py",
+        "import os\n\n\nclass App:\n    @property\n    def name(self):\n        return 'app'\n",
+    );
+    fixture.create_file("notes.txt", "hello\n");
+    let ctx = create_test_context(fixture.path());
+
+    let result = CodeOutlineTool
+        .execute(json!({ "file_path": "app.py", "symbol": "name" }), &ctx)
+        .await
+        .unwrap();
+    assert!(result.output.starts_with("    5\u{2192}    @property\n"));
+    assert!(result.output.contains("(function App.name: lines 5-7 of app.py)"));
+
+    let missing = CodeOutlineTool
+        .execute(json!({ "file_path": "app.py", "symbol": "nope" }), &ctx)
+        .await;
+    assert!(matches!(missing, Err(ToolError::InvalidParams(_))));
+
+    let unsupported = CodeOutlineTool
+        .execute(json!({ "file_path": "notes.txt" }), &ctx)
+        .await;
+    assert!(matches!(unsupported, Err(ToolError::InvalidParams(_))));
+
+    let not_found = CodeOutlineTool
+        .execute(json!({ "file_path": "missing.rs" }), &ctx)
+        .await;
+    assert!(matches!(not_found, Err(ToolError::FileNotFound(_))));
+}
@@ -3,7 +3,7 @@
 mod common;
 
 use common::TestFixture;
-use ok::tool::{base::*, edit::EditTool};
+use ok::tool::{base::*, edit::EditTool, read::ReadTool};
 use serde_json::json;
 use std::sync::Arc;
 
@@ -18,6 +18,14 @@ fn create_test_context(working_dir: std::path::PathBuf) -> ToolContext {
     )
 }
 
+/// Read a file through the read tool, as the model must before modifying it
+async fn read_first(ctx: &ToolContext, file_path: impl serde::Serialize) {
+    ReadTool::new()
+        .execute(json!({ "file_path": file_path }), ctx)
+        .await
+        .expect("read before modifying");
+}
+
 #[tokio::test]
 async fn test_edit_simple_replacement() {
     let fixture = TestFixture::new();
@@ -25,6 +33,7 @@ async fn test_edit_simple_replacement() {
 
     let tool = EditTool;
     let ctx = create_test_context(fixture.path());
+    read_first(&ctx, "test.txt").await;
 
     let params = json!({
         "file_path": "test.txt",
@@ -56,6 +65,7 @@ async fn test_edit_replace_all() {
 
     let tool = EditTool;
     let ctx = create_test_context(fixture.path());
+    read_first(&ctx, "test.txt").await;
 
     let params = json!({
         "file_path": "test.txt",
@@ -87,6 +97,7 @@ async fn test_edit_multiple_matches_error() {
 
     let tool = EditTool;
     let ctx = create_test_context(fixture.path());
+    read_first(&ctx, "test.txt").await;
 
     let params = json!({
         "file_path": "test.txt",
@@ -110,6 +121,7 @@ async fn test_edit_string_not_found() {
 
     let tool = EditTool;
     let ctx = create_test_context(fixture.path());
+    read_first(&ctx, "test.txt").await;
 
     let params = json!({
         "file_path": "test.txt",
@@ -175,6 +187,7 @@ async fn test_edit_preserves_indentation() {
 
     let tool = EditTool;
     let ctx = create_test_context(fixture.path());
+    read_first(&ctx, "code.rs").await;
 
     let params = json!({
         "file_path": "code.rs",
@@ -202,6 +215,7 @@ async fn test_edit_multiline_replacement() {
 
     let tool = EditTool;
     let ctx = create_test_context(fixture.path());
+    read_first(&ctx, "test.txt").await;
 
     let params = json!({
         "file_path": "test.txt",
@@ -227,6 +241,7 @@ async fn test_edit_with_special_characters() {
 
     let tool = EditTool;
     let ctx = create_test_context(fixture.path());
+    read_first(&ctx, "code.rs").await;
 
     let params = json!({
         "file_path": "code.rs",
@@ -249,6 +264,7 @@ async fn test_edit_absolute_path() {
     let tool = EditTool;
     // Use empty working dir - absolute path should work regardless
     let ctx = create_test_context(std::path::PathBuf::from("/tmp"));
+    read_first(&ctx, &file_path).await;
 
     let params = json!({
         "file_path": file_path,
@@ -295,6 +311,7 @@ async fn test_edit_shows_diff() {
 
     let tool = EditTool;
     let ctx = create_test_context(fixture.path());
+    read_first(&ctx, "test.txt").await;
 
     let params = json!({
         "file_path": "test.txt",
@@ -318,6 +335,7 @@ async fn test_edit_metadata() {
 
     let tool = EditTool;
     let ctx = create_test_context(fixture.path());
+    read_first(&ctx, "test.txt").await;
 
     let params = json!({
         "file_path": "test.txt",
@@ -336,3 +354,154 @@ async fn test_edit_metadata() {
     assert_eq!(result.metadata.get("replacements").unwrap(), &json!(2));
     assert_eq!(result.metadata.get("replace_all").unwrap(), &json!(true));
 }
+
+#[tokio::test]
+async fn test_edit_requires_read() {
+    let fixture = TestFixture::new();
+    fixture.create_file("test.txt", "Hello World\n");
+
+    let tool = EditTool;
+    let ctx = create_test_context(fixture.path());
+
+    let params = json!({
+        "file_path": "test.txt",
+        "old_string": "Hello",
+        "new_string": "Hi"
+    });
+
+    let result = tool.execute(params, &ctx).await;
+    assert!(matches!(result, Err(ToolError::FileNotRead(_))));
+    assert_eq!(fixture.read_file("test.txt"), "Hello World\n");
+}
+
+#[tokio::test]
+async fn test_edit_detects_external_modification() {
+    let fixture = TestFixture::new();
+    let path = fixture.create_file("test.txt", "Hello World\n");
+
+    let tool = EditTool;
+    let ctx = create_test_context(fixture.path());
+    read_first(&ctx, "test.txt").await;
+
+    // The user changes the file in their editor after the model read it
+    std::fs::write(&path, "Hello World\nuser line\n").unwrap();
+    let later = std::time::SystemTime::now() + std::time::Duration::from_secs(5);
+    std::fs::File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(later)
+        .unwrap();
+
+    let params = json!({
+        "file_path": "test.txt",
+        "old_string": "Hello",
+        "new_string": "Hi"
+    });
+    let result = tool.execute(params.clone(), &ctx).await;
+    assert!(matches!(result, Err(ToolError::FileModifiedSinceRead(_))));
+    assert_eq!(fixture.read_file("test.txt"), "Hello World\nuser line\n");
+
+    // Re-reading picks up the change and allows the edit
+    read_first(&ctx, "test.txt").await;
+    tool.execute(params, &ctx).await.unwrap();
+    assert_eq!(fixture.read_file("test.txt"), "Hi World\nuser line\n");
+}
+
+#[tokio::test]
+async fn test_consecutive_edits_need_one_read() {
+    let fixture = TestFixture::new();
+    fixture.create_file("test.txt", "one two\n");
+
+    let tool = EditTool;
+    let ctx = create_test_context(fixture.path());
+    read_first(&ctx, "test.txt").await;
+
+    for (old, new) in [("one", "1"), ("two", "2")] {
+        let params = json!({ "file_path": "test.txt", "old_string": old, "new_string": new });
+        tool.execute(params, &ctx).await.unwrap();
+    }
+    assert_eq!(fixture.read_file("test.txt"), "1 2\n");
+}
+
+#[tokio::test]
+async fn test_edit_preserves_crlf_and_bom() {
+    let fixture = TestFixture::new();
+    let path = fixture.path().join("win.txt");
+    std::fs::write(&path, b"\xEF\xBB\xBFfirst\r\nsecond\r\nthird\r\n").unwrap();
+
+    let tool = EditTool;
+    let ctx = create_test_context(fixture.path());
+    read_first(&ctx, "win.txt").await;
+
+    // The model sees (and sends) LF line endings
+    let params = json!({
+        "file_path": "win.txt",
+        "old_string": "first\nsecond",
+        "new_string": "first\ninserted\nsecond"
+    });
+    let result = tool.execute(params, &ctx).await.unwrap();
+    assert_eq!(result.metadata.get("line_ending"), Some(&json!("CRLF")));
+    assert_eq!(result.metadata.get("bom"), Some(&json!(true)));
+
+    assert_eq!(
+        std::fs::read(&path).unwrap(),
+        b"\xEF\xBB\xBFfirst\r\ninserted\r\nsecond\r\nthird\r\n"
+    );
+}
+
+#[tokio::test]
+async fn test_edit_preserves_utf16() {
+    let fixture = TestFixture::new();
+    let path = fixture.path().join("wide.txt");
+    let encode = |text: &str| -> Vec<u8> {
+        let mut bytes = vec![0xFE, 0xFF];
+        bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+        bytes
+    };
+    std::fs::write(&path, encode("name = \"old\"\n")).unwrap();
+
+    let tool = EditTool;
+    let ctx = create_test_context(fixture.path());
+    read_first(&ctx, "wide.txt").await;
+
+    let params = json!({
+        "file_path": "wide.txt",
+        "old_string": "\"old\"",
+        "new_string": "\"nüe\""
+    });
+    let result = tool.execute(params, &ctx).await.unwrap();
+    assert_eq!(result.metadata.get("encoding"), Some(&json!("UTF-16BE")));
+    assert_eq!(std::fs::read(&path).unwrap(), encode("name = \"nüe\"\n"));
+}
+
+#[tokio::test]
+async fn test_edit_refuses_mixed_line_endings_and_stray_bytes() {
+    let fixture = TestFixture::new();
+    let tool = EditTool;
+    let ctx = create_test_context(fixture.path());
+
+    // Writing either line ending throughout would change lines the edit didn't touch
+    let mixed = b"first\r\nsecond\nthird\r\n";
+    std::fs::write(fixture.path().join("mixed.txt"), mixed).unwrap();
+    let read = ReadTool::new().execute(json!({ "file_path": "mixed.txt" }), &ctx).await.unwrap();
+    assert_eq!(read.metadata.get("line_ending"), Some(&json!("mixed")));
+    let params = json!({ "file_path": "mixed.txt", "old_string": "third", "new_string": "3" });
+    match tool.execute(params, &ctx).await {
+        Err(ToolError::UnsupportedFormat { reason, .. }) => assert!(reason.contains("LF and CRLF")),
+        other => panic!("Expected UnsupportedFormat, got {:?}", other),
+    }
+    assert_eq!(std::fs::read(fixture.path().join("mixed.txt")).unwrap(), mixed);
+
+    // One stray Latin-1 byte in UTF-8 text
+    let stray = "na\u{ef}ve\ncaf".bytes().chain([0xE9, b'\n']).collect::<Vec<u8>>();
+    std::fs::write(fixture.path().join("stray.txt"), &stray).unwrap();
+    let read = ReadTool::new().execute(json!({ "file_path": "stray.txt" }), &ctx).await.unwrap();
+    assert_eq!(read.metadata.get("encoding"), Some(&json!("UTF-8")));
+    let params = json!({ "file_path": "stray.txt", "old_string": "naïve", "new_string": "naive" });
+    assert!(matches!(
+        tool.execute(params, &ctx).await,
+        Err(ToolError::UnsupportedFormat { .. })
+    ));
+    assert_eq!(std::fs::read(fixture.path().join("stray.txt")).unwrap(), stray);
+}
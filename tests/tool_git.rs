@@ -0,0 +1,252 @@
+//! Integration tests for the git tools
+
+mod common;
+
+use common::TestFixture;
+use ok::tool::base::*;
+use ok::tool::git::{
+    blame::GitBlameTool, diff::GitDiffTool, log::GitLogTool, show::GitShowTool, status::GitStatusTool,
+};
+use serde_json::json;
+use std::process::Command;
+use std::sync::Arc;
+
+fn create_test_context(working_dir: std::path::PathBuf) -> ToolContext {
+    ToolContext::new(
+        "test_session",
+        "test_msg",
+        "test_station",
+        working_dir,
+        Arc::new(ok::process::BackgroundShellManager::new()),
+    )
+}
+
+fn git(fixture: &TestFixture, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(fixture.path())
+        .status()
+        .expect("Failed to run git");
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+fn commit(fixture: &TestFixture, message: &str) {
+    git(fixture, &["add", "-A"]);
+    git(fixture, &["commit", "-q", "-m", message]);
+}
+
+/// A repository on branch `main` with two commits:
+/// "Add greeting" (hello.txt, lib.rs) and "Say goodbye" (changes line 2 of hello.txt)
+fn repo() -> TestFixture {
+    let fixture = TestFixture::new();
+    fixture.git_init();
+    git(&fixture, &["symbolic-ref", "HEAD", "refs/heads/main"]);
+    fixture.create_tree(vec![("hello.txt", "hello\nworld\n"), ("src/lib.rs", "pub fn f() {}\n")]);
+    commit(&fixture, "Add greeting");
+    fixture.create_file("hello.txt", "hello\ngoodbye\n");
+    commit(&fixture, "Say goodbye\n\nThe world was too big.");
+    fixture
+}
+
+#[tokio::test]
+async fn test_git_status_groups_changes() {
+    let fixture = repo();
+    let ctx = create_test_context(fixture.path());
+
+    git(&fixture, &["mv", "hello.txt", "greeting.txt"]);
+    fixture.create_file("src/lib.rs", "pub fn g() {}\n");
+    git(&fixture, &["add", "src/lib.rs"]);
+    fixture.create_file("src/lib.rs", "pub fn h() {}\n");
+    fixture.create_file("notes.md", "todo\n");
+
+    let result = GitStatusTool.execute(json!({}), &ctx).await.unwrap();
+    let out = &result.output;
+    assert!(out.starts_with("On branch main\n"), "{}", out);
+    assert!(
+        out.contains("Staged (2):\n  renamed: hello.txt -> greeting.txt\n  modified: src/lib.rs\n"),
+        "{}",
+        out
+    );
+    assert!(out.contains("Unstaged (1):\n  modified: src/lib.rs\n"), "{}", out);
+    assert!(out.contains("Untracked (1):\n  notes.md\n"), "{}", out);
+    assert_eq!(result.metadata.get("staged"), Some(&json!(2)));
+    assert_eq!(result.metadata.get("conflicted"), Some(&json!(0)));
+
+    // Limited to a path
+    let result = GitStatusTool.execute(json!({"path": "src"}), &ctx).await.unwrap();
+    assert_eq!(result.metadata.get("staged"), Some(&json!(1)));
+    assert_eq!(result.metadata.get("unstaged"), Some(&json!(1)));
+    assert_eq!(result.metadata.get("untracked"), Some(&json!(0)));
+}
+
+#[tokio::test]
+async fn test_git_status_clean_and_outside_repo() {
+    let fixture = repo();
+    let ctx = create_test_context(fixture.path());
+    let result = GitStatusTool.execute(json!({}), &ctx).await.unwrap();
+    assert!(result.output.contains("working tree clean"), "{}", result.output);
+
+    let plain = TestFixture::new();
+    let ctx = create_test_context(plain.path());
+    match GitStatusTool.execute(json!({}), &ctx).await {
+        Err(ToolError::CommandFailed { message, .. }) => assert!(message.contains("not a git repository")),
+        other => panic!("expected CommandFailed, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_git_diff_stats_and_patch() {
+    let fixture = repo();
+    let ctx = create_test_context(fixture.path());
+
+    fixture.create_file("hello.txt", "hello\ngoodbye\nagain\n");
+    fixture.create_file("src/lib.rs", "pub fn f() {}\npub fn g() {}\n");
+    git(&fixture, &["add", "src/lib.rs"]);
+
+    let unstaged = GitDiffTool.execute(json!({}), &ctx).await.unwrap();
+    assert!(unstaged.output.starts_with("1 files changed, 1 insertions(+), 0 deletions(-)\n  hello.txt  +1 -0\n"), "{}", unstaged.output);
+    assert!(unstaged.output.contains("+again"));
+    assert!(!unstaged.output.contains("pub fn g"));
+
+    let staged = GitDiffTool.execute(json!({"staged": true, "stat_only": true}), &ctx).await.unwrap();
+    assert!(staged.output.contains("  src/lib.rs  +1 -0\n"), "{}", staged.output);
+    assert!(!staged.output.contains("pub fn g"));
+    assert_eq!(staged.metadata.get("files"), Some(&json!(["src/lib.rs"])));
+
+    let range = GitDiffTool
+        .execute(json!({"range": "HEAD~1..HEAD", "path": "hello.txt"}), &ctx)
+        .await
+        .unwrap();
+    assert!(range.output.contains("-world\n+goodbye\n"), "{}", range.output);
+    assert_eq!(range.metadata.get("insertions"), Some(&json!(1)));
+    assert_eq!(range.metadata.get("deletions"), Some(&json!(1)));
+
+    // Working tree against a commit
+    let against_head = GitDiffTool.execute(json!({"range": "HEAD", "path": "src"}), &ctx).await.unwrap();
+    assert!(against_head.output.contains("+pub fn g() {}"), "{}", against_head.output);
+    let none = GitDiffTool.execute(json!({"range": "HEAD~1..HEAD", "path": "src"}), &ctx).await.unwrap();
+    assert!(none.output.starts_with("No differences"), "{}", none.output);
+}
+
+#[tokio::test]
+async fn test_git_diff_rejects_options_and_outside_paths() {
+    let fixture = repo();
+    let ctx = create_test_context(fixture.path());
+
+    let result = GitDiffTool.execute(json!({"range": "--output=/tmp/x"}), &ctx).await;
+    assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+
+    let result = GitDiffTool.execute(json!({"path": "/etc/passwd"}), &ctx).await;
+    match result {
+        Err(ToolError::InvalidParams(msg)) => assert!(msg.contains("outside allowed roots")),
+        other => panic!("expected InvalidParams, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_git_log_with_filters() {
+    let fixture = repo();
+    let ctx = create_test_context(fixture.path());
+
+    let result = GitLogTool.execute(json!({}), &ctx).await.unwrap();
+    let lines: Vec<&str> = result.output.lines().collect();
+    assert_eq!(lines.len(), 2, "{}", result.output);
+    assert!(lines[0].ends_with(" Test: Say goodbye"), "{}", lines[0]);
+    assert!(lines[1].ends_with(" Test: Add greeting"));
+    assert_eq!(result.metadata.get("count"), Some(&json!(2)));
+    assert_eq!(result.metadata["commits"][0]["subject"], json!("Say goodbye"));
+    assert_eq!(result.metadata["commits"][0]["email"], json!("test@example.com"));
+
+    let by_path = GitLogTool.execute(json!({"path": "src/lib.rs"}), &ctx).await.unwrap();
+    assert_eq!(by_path.metadata.get("count"), Some(&json!(1)));
+    assert!(by_path.output.contains("Add greeting"));
+
+    let by_message = GitLogTool.execute(json!({"grep": "^Say"}), &ctx).await.unwrap();
+    assert_eq!(by_message.metadata.get("count"), Some(&json!(1)));
+
+    let limited = GitLogTool.execute(json!({"max_count": 1}), &ctx).await.unwrap();
+    assert_eq!(limited.metadata.get("has_more"), Some(&json!(true)));
+    assert!(limited.output.contains("Showing the first 1 commits"));
+
+    let nobody = GitLogTool.execute(json!({"author": "nobody"}), &ctx).await.unwrap();
+    assert!(nobody.output.contains("No commits found"));
+}
+
+#[tokio::test]
+async fn test_git_blame_line_range() {
+    let fixture = repo();
+    let ctx = create_test_context(fixture.path());
+
+    let result = GitBlameTool
+        .execute(json!({"file_path": "hello.txt", "start_line": 2, "end_line": 2}), &ctx)
+        .await
+        .unwrap();
+    let out = &result.output;
+    assert!(out.contains("2\u{2192}goodbye\n"), "{}", out);
+    assert!(!out.contains("hello\n"), "{}", out);
+    assert!(out.contains("Commits:\n") && out.contains(" Say goodbye\n"), "{}", out);
+    assert!(!out.contains("Showing lines"));
+    assert_eq!(result.metadata.get("lines"), Some(&json!(1)));
+
+    // Whole file, as of the first commit
+    let result = GitBlameTool
+        .execute(json!({"file_path": "hello.txt", "rev": "HEAD~1"}), &ctx)
+        .await
+        .unwrap();
+    assert!(result.output.contains("2\u{2192}world\n"), "{}", result.output);
+    assert_eq!(result.metadata.get("commits"), Some(&json!(1)));
+
+    let missing = GitBlameTool.execute(json!({"file_path": "missing.txt"}), &ctx).await;
+    assert!(matches!(missing, Err(ToolError::FileNotFound(_))));
+}
+
+#[tokio::test]
+async fn test_git_show_commit() {
+    let fixture = repo();
+    let ctx = create_test_context(fixture.path());
+
+    let result = GitShowTool.execute(json!({}), &ctx).await.unwrap();
+    let out = &result.output;
+    assert!(out.starts_with("commit "), "{}", out);
+    assert!(out.contains("Author: Test <test@example.com>\n"));
+    assert!(out.contains("    Say goodbye\n    \n    The world was too big.\n"), "{}", out);
+    assert!(out.contains("1 files changed, 1 insertions(+), 1 deletions(-)\n  hello.txt  +1 -1\n"), "{}", out);
+    assert!(out.contains("+goodbye"));
+    assert_eq!(result.metadata.get("subject"), Some(&json!("Say goodbye")));
+    assert_eq!(result.metadata["parents"].as_array().unwrap().len(), 1);
+
+    let first = GitShowTool
+        .execute(json!({"rev": "HEAD~1", "stat_only": true}), &ctx)
+        .await
+        .unwrap();
+    assert!(first.output.contains("2 files changed"), "{}", first.output);
+    assert!(!first.output.contains("+hello"));
+    assert_eq!(first.metadata["parents"], json!([]));
+
+    let bad = GitShowTool.execute(json!({"rev": "no-such-branch"}), &ctx).await;
+    assert!(matches!(bad, Err(ToolError::CommandFailed { .. })));
+}
+
+#[tokio::test]
+async fn test_git_tools_do_not_run_repository_hooks() {
+    let fixture = repo();
+    let ctx = create_test_context(fixture.path());
+
+    // A cloned repository can name programs to run for diffs and status; none may run
+    let marker = fixture.path().join("ran");
+    let command = format!("touch {} && cat", marker.display());
+    git(&fixture, &["config", "diff.conv.textconv", &command]);
+    git(&fixture, &["config", "core.fsmonitor", &command]);
+    fixture.create_file(".gitattributes", "*.txt diff=conv\n");
+    fixture.create_file("hello.txt", "hello\nagain\n");
+
+    GitStatusTool.execute(json!({}), &ctx).await.unwrap();
+    let diff = GitDiffTool.execute(json!({}), &ctx).await.unwrap();
+    assert!(diff.output.contains("+again"), "{}", diff.output);
+    GitShowTool.execute(json!({}), &ctx).await.unwrap();
+    GitBlameTool
+        .execute(json!({"file_path": "hello.txt", "rev": "HEAD"}), &ctx)
+        .await
+        .unwrap();
+    assert!(!marker.exists(), "a repository-configured program ran");
+}
@@ -365,3 +365,184 @@ async fn test_grep_file_not_found() {
         _ => panic!("Expected FileNotFound error"),
     }
 }
+
+/// Set a file's modification time to `secs_ago` seconds in the past
+fn set_mtime(path: &std::path::Path, secs_ago: u64) {
+    let file = std::fs::OpenOptions::new().write(true).open(path).unwrap();
+    let time = std::time::SystemTime::now() - std::time::Duration::from_secs(secs_ago);
+    file.set_modified(time).unwrap();
+}
+
+#[tokio::test]
+async fn test_grep_files_with_matches_sorted_by_mtime() {
+    let fixture = TestFixture::new();
+    fixture.create_tree(vec![
+        ("old.rs", "fn target() {}\nfn target2() {}"),
+        ("new.rs", "// calls target()"),
+        ("mid.rs", "target"),
+        ("none.rs", "nothing"),
+    ]);
+    set_mtime(&fixture.path().join("old.rs"), 300);
+    set_mtime(&fixture.path().join("mid.rs"), 200);
+    set_mtime(&fixture.path().join("new.rs"), 100);
+
+    let tool = GrepTool::new();
+    let ctx = create_test_context(fixture.path());
+
+    let result = tool
+        .execute(json!({ "pattern": "target", "output_mode": "files_with_matches" }), &ctx)
+        .await
+        .unwrap();
+
+    let paths: Vec<&str> = result.output.lines().skip(1).collect();
+    assert_eq!(paths, vec!["new.rs", "mid.rs", "old.rs"]);
+    assert!(!result.output.contains("fn target"));
+    assert_eq!(result.metadata.get("total_files"), Some(&json!(3)));
+    assert_eq!(result.metadata.get("output_mode"), Some(&json!("files_with_matches")));
+    assert!(!result.metadata.contains_key("total_matches"));
+}
+
+#[tokio::test]
+async fn test_grep_count_mode() {
+    let fixture = TestFixture::new();
+    fixture.create_tree(vec![
+        ("a.txt", "foo\nfoo\nbar"),
+        ("b.txt", "foo"),
+        ("c.txt", "bar"),
+    ]);
+
+    let tool = GrepTool::new();
+    let ctx = create_test_context(fixture.path());
+
+    let result = tool
+        .execute(json!({ "pattern": "foo", "output_mode": "count" }), &ctx)
+        .await
+        .unwrap();
+
+    assert!(result.output.contains("Found 3 matches in 2 files"));
+    assert!(result.output.contains("a.txt: 2\n"));
+    assert!(result.output.contains("b.txt: 1\n"));
+    assert!(!result.output.contains("c.txt"));
+    assert_eq!(result.metadata.get("total_matches"), Some(&json!(3)));
+}
+
+#[tokio::test]
+async fn test_grep_multiline() {
+    let fixture = TestFixture::new();
+    fixture.create_file(
+        "lib.rs",
+        "struct Config {\n    name: String,\n}\n\nstruct Other;\n",
+    );
+
+    let tool = GrepTool::new();
+    let ctx = create_test_context(fixture.path());
+
+    // Without multiline, a pattern spanning lines cannot match
+    let result = tool
+        .execute(json!({ "pattern": r"Config \{\s+name" }), &ctx)
+        .await
+        .unwrap();
+    assert_eq!(result.metadata.get("total_matches"), Some(&json!(0)));
+
+    let result = tool
+        .execute(json!({ "pattern": r"Config \{.*?\}", "multiline": true }), &ctx)
+        .await
+        .unwrap();
+    assert_eq!(result.metadata.get("total_matches"), Some(&json!(1)));
+    assert!(result.output.contains(">    1\u{2502} struct Config {"));
+    assert!(result.output.contains(">    2\u{2502}     name: String,"));
+    assert!(result.output.contains(">    3\u{2502} }"));
+    assert!(!result.output.contains("struct Other"));
+}
+
+#[tokio::test]
+async fn test_grep_type_filter() {
+    let fixture = TestFixture::new();
+    fixture.create_tree(vec![
+        ("src/main.rs", "TODO: rust"),
+        ("script.py", "TODO: python"),
+        ("notes.md", "TODO: docs"),
+    ]);
+
+    let tool = GrepTool::new();
+    let ctx = create_test_context(fixture.path());
+
+    let result = tool
+        .execute(json!({ "pattern": "TODO", "type": "rust" }), &ctx)
+        .await
+        .unwrap();
+    assert!(result.output.contains("TODO: rust"));
+    assert!(!result.output.contains("python"));
+    assert!(!result.output.contains("docs"));
+
+    let result = tool
+        .execute(json!({ "pattern": "TODO", "type": "py" }), &ctx)
+        .await
+        .unwrap();
+    assert!(result.output.contains("TODO: python"));
+    assert!(!result.output.contains("TODO: rust"));
+
+    let err = tool
+        .execute(json!({ "pattern": "TODO", "type": "not-a-language" }), &ctx)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::InvalidParams(msg) if msg.contains("Invalid type filter")));
+}
+
+#[tokio::test]
+async fn test_grep_head_limit_and_offset() {
+    let fixture = TestFixture::new();
+    let content: String = (1..=10).map(|i| format!("MATCH {}\n", i)).collect();
+    fixture.create_file("test.txt", &content);
+
+    let tool = GrepTool::new();
+    let ctx = create_test_context(fixture.path());
+
+    let result = tool
+        .execute(json!({ "pattern": "MATCH", "head_limit": 3, "offset": 4 }), &ctx)
+        .await
+        .unwrap();
+
+    assert_eq!(result.metadata.get("shown_matches"), Some(&json!(3)));
+    assert!(!result.output.contains("MATCH 4\n"));
+    assert!(result.output.contains("MATCH 5"));
+    assert!(result.output.contains("MATCH 7"));
+    assert!(!result.output.contains("MATCH 8"));
+    assert!(result.output.contains("starting at offset 4"));
+
+    // Pagination also applies to file lists
+    fixture.create_tree(vec![("a.txt", "MATCH"), ("b.txt", "MATCH")]);
+    let result = tool
+        .execute(
+            json!({ "pattern": "MATCH", "output_mode": "count", "head_limit": 1, "offset": 1 }),
+            &ctx,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.metadata.get("shown_files"), Some(&json!(1)));
+    assert!(result.output.contains("b.txt: 1\n"));
+    assert!(!result.output.contains("a.txt"));
+    assert!(!result.output.contains("test.txt"));
+}
+
+#[tokio::test]
+async fn test_grep_content_total_counts_matches_past_the_page() {
+    let fixture = TestFixture::new();
+    for i in 0..5 {
+        fixture.create_file(&format!("f{}.txt", i), "MATCH a
+MATCH b
+");
+    }
+
+    let tool = GrepTool::new();
+    let ctx = create_test_context(fixture.path());
+    let result = tool
+        .execute(json!({ "pattern": "MATCH", "head_limit": 1 }), &ctx)
+        .await
+        .unwrap();
+
+    assert_eq!(result.metadata.get("shown_matches"), Some(&json!(1)));
+    assert_eq!(result.metadata.get("total_matches"), Some(&json!(10)));
+    assert_eq!(result.metadata.get("total_files"), Some(&json!(5)));
+    assert!(result.output.contains("(Showing 1 of 10 matches"), "{}", result.output);
+}
@@ -0,0 +1,123 @@
+//! Integration tests for the multi_edit tool
+
+mod common;
+
+use common::TestFixture;
+use ok::tool::{base::*, multi_edit::MultiEditTool, read::ReadTool};
+use serde_json::json;
+use std::sync::Arc;
+
+/// Helper to create a tool context for testing
+fn create_test_context(working_dir: std::path::PathBuf) -> ToolContext {
+    ToolContext::new(
+        "test_session",
+        "test_msg",
+        "test_station",
+        working_dir,
+        Arc::new(ok::process::BackgroundShellManager::new()),
+    )
+}
+
+/// Read a file through the read tool, as the model must before modifying it
+async fn read_first(ctx: &ToolContext, file_path: &str) {
+    ReadTool::new()
+        .execute(json!({ "file_path": file_path }), ctx)
+        .await
+        .expect("read before modifying");
+}
+
+#[tokio::test]
+async fn test_multi_edit_applies_edits_in_sequence() {
+    let fixture = TestFixture::new();
+    fixture.create_file(
+        "lib.rs",
+        "fn old_name() {}\nfn caller() { old_name(); old_name(); }\nconst LIMIT: u32 = 10;\n",
+    );
+
+    let tool = MultiEditTool;
+    let ctx = create_test_context(fixture.path());
+    read_first(&ctx, "lib.rs").await;
+
+    let params = json!({
+        "file_path": "lib.rs",
+        "edits": [
+            { "old_string": "old_name", "new_string": "new_name", "replace_all": true },
+            // Only matches once the first edit has been applied
+            { "old_string": "fn new_name() {}", "new_string": "fn new_name() -> u32 { LIMIT }" },
+            { "old_string": "10", "new_string": "20" }
+        ]
+    });
+
+    let result = tool.execute(params, &ctx).await.unwrap();
+    assert_eq!(result.metadata.get("edits"), Some(&json!(3)));
+    assert_eq!(result.metadata.get("replacements"), Some(&json!(5)));
+
+    // One diff covering every change
+    assert_eq!(result.output.matches("+++ ").count(), 1);
+    assert!(result.output.contains("-fn old_name() {}"));
+    assert!(result.output.contains("+fn new_name() -> u32 { LIMIT }"));
+    assert!(result.output.contains("+const LIMIT: u32 = 20;"));
+
+    assert_eq!(
+        fixture.read_file("lib.rs"),
+        "fn new_name() -> u32 { LIMIT }\nfn caller() { new_name(); new_name(); }\nconst LIMIT: u32 = 20;\n"
+    );
+}
+
+#[tokio::test]
+async fn test_multi_edit_failure_writes_nothing() {
+    let fixture = TestFixture::new();
+    let original = "alpha\nbeta\nbeta\n";
+    fixture.create_file("test.txt", original);
+
+    let tool = MultiEditTool;
+    let ctx = create_test_context(fixture.path());
+    read_first(&ctx, "test.txt").await;
+
+    let params = json!({
+        "file_path": "test.txt",
+        "edits": [
+            { "old_string": "alpha", "new_string": "ALPHA" },
+            { "old_string": "beta", "new_string": "BETA" }
+        ]
+    });
+
+    match tool.execute(params, &ctx).await {
+        Err(ToolError::EditFailed { index, total, source }) => {
+            assert_eq!((index, total), (2, 2));
+            assert!(matches!(*source, ToolError::MultipleMatches { count: 2, .. }));
+        }
+        other => panic!("Expected EditFailed error, got {:?}", other),
+    }
+    assert_eq!(fixture.read_file("test.txt"), original);
+
+    let params = json!({
+        "file_path": "test.txt",
+        "edits": [{ "old_string": "gamma", "new_string": "GAMMA" }]
+    });
+    let err = tool.execute(params, &ctx).await.unwrap_err();
+    assert!(err.to_string().starts_with("Edit 1 of 1 failed: String not found"));
+    assert_eq!(fixture.read_file("test.txt"), original);
+}
+
+#[tokio::test]
+async fn test_multi_edit_requires_read_and_edits() {
+    let fixture = TestFixture::new();
+    fixture.create_file("test.txt", "Hello\n");
+
+    let tool = MultiEditTool;
+    let ctx = create_test_context(fixture.path());
+
+    let params = json!({
+        "file_path": "test.txt",
+        "edits": [{ "old_string": "Hello", "new_string": "Hi" }]
+    });
+    let result = tool.execute(params, &ctx).await;
+    assert!(matches!(result, Err(ToolError::FileNotRead(_))));
+
+    read_first(&ctx, "test.txt").await;
+    let params = json!({ "file_path": "test.txt", "edits": [] });
+    let result = tool.execute(params, &ctx).await;
+    assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+    assert_eq!(fixture.read_file("test.txt"), "Hello\n");
+}
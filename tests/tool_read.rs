@@ -169,6 +169,220 @@ async fn test_read_empty_file() {
     assert_eq!(output.metadata.get("total_lines"), Some(&json!(0)));
 }
 
+/// Build a PDF with one line of text per page
+fn build_pdf(page_texts: &[&str]) -> Vec<u8> {
+    use lopdf::content::{Content, Operation};
+    use lopdf::{dictionary, Document, Object, Stream};
+
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Courier",
+    });
+    let resources_id = doc.add_object(dictionary! {
+        "Font" => dictionary! { "F1" => font_id },
+    });
+
+    let kids: Vec<Object> = page_texts
+        .iter()
+        .map(|text| {
+            let content = Content {
+                operations: vec![
+                    Operation::new("BT", vec![]),
+                    Operation::new("Tf", vec!["F1".into(), 24.into()]),
+                    Operation::new("Td", vec![72.into(), 700.into()]),
+                    Operation::new("Tj", vec![Object::string_literal(*text)]),
+                    Operation::new("ET", vec![]),
+                ],
+            };
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+            doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+            })
+            .into()
+        })
+        .collect();
+
+    let count = kids.len() as i64;
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => count,
+            "Resources" => resources_id,
+            "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+
+    let mut bytes = Vec::new();
+    doc.save_to(&mut bytes).unwrap();
+    bytes
+}
+
+#[tokio::test]
+async fn test_read_pdf_extracts_selected_pages() {
+    let fixture = TestFixture::new();
+    std::fs::write(
+        fixture.path().join("doc.pdf"),
+        build_pdf(&["Alpha page", "Bravo page", "Charlie page"]),
+    )
+    .unwrap();
+
+    let tool = ReadTool::new();
+    let ctx = create_test_context(fixture.path());
+
+    let result = tool.execute(json!({ "file_path": "doc.pdf" }), &ctx).await.unwrap();
+    assert!(result.output.contains("--- Page 1 of 3 ---"));
+    assert!(result.output.contains("Alpha page"));
+    assert!(result.output.contains("Charlie page"));
+    assert_eq!(result.metadata.get("total_pages"), Some(&json!(3)));
+
+    let result = tool
+        .execute(json!({ "file_path": "doc.pdf", "pages": "2-3" }), &ctx)
+        .await
+        .unwrap();
+    assert!(!result.output.contains("Alpha page"));
+    assert!(result.output.contains("--- Page 2 of 3 ---"));
+    assert!(result.output.contains("Bravo page"));
+    assert!(result.output.contains("Charlie page"));
+    assert_eq!(result.metadata.get("pages_read"), Some(&json!([2, 3])));
+
+    let result = tool
+        .execute(json!({ "file_path": "doc.pdf", "pages": "4" }), &ctx)
+        .await;
+    assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+}
+
+#[tokio::test]
+async fn test_read_corrupt_pdf_fails() {
+    let fixture = TestFixture::new();
+    fixture.create_file("broken.pdf", "not really a pdf");
+
+    let tool = ReadTool::new();
+    let ctx = create_test_context(fixture.path());
+
+    let result = tool.execute(json!({ "file_path": "broken.pdf" }), &ctx).await;
+    assert!(matches!(result, Err(ToolError::Other(_))));
+}
+
+#[tokio::test]
+async fn test_read_notebook_renders_cells() {
+    let fixture = TestFixture::new();
+    let long_output: Vec<String> = (0..80).map(|i| format!("row {}\n", i)).collect();
+    let notebook = json!({
+        "cells": [
+            {
+                "id": "intro",
+                "cell_type": "markdown",
+                "metadata": {},
+                "source": ["# Analysis\n", "Some notes"]
+            },
+            {
+                "id": "load",
+                "cell_type": "code",
+                "metadata": {},
+                "execution_count": 3,
+                "source": "print('hi')",
+                "outputs": [
+                    { "output_type": "stream", "name": "stdout", "text": ["hi\n"] },
+                    {
+                        "output_type": "display_data",
+                        "metadata": {},
+                        "data": { "image/png": "iVBORw0KGgo=" }
+                    }
+                ]
+            },
+            {
+                "id": "dump",
+                "cell_type": "code",
+                "metadata": {},
+                "execution_count": 4,
+                "source": "df",
+                "outputs": [
+                    {
+                        "output_type": "execute_result",
+                        "execution_count": 4,
+                        "metadata": {},
+                        "data": { "text/plain": long_output }
+                    },
+                    {
+                        "output_type": "error",
+                        "ename": "ValueError",
+                        "evalue": "bad value",
+                        "traceback": []
+                    }
+                ]
+            }
+        ],
+        "metadata": {},
+        "nbformat": 4,
+        "nbformat_minor": 5
+    });
+    fixture.create_file("analysis.ipynb", &notebook.to_string());
+
+    let tool = ReadTool::new();
+    let ctx = create_test_context(fixture.path());
+
+    let result = tool
+        .execute(json!({ "file_path": "analysis.ipynb" }), &ctx)
+        .await
+        .unwrap();
+    let output = &result.output;
+    assert!(output.contains("--- Cell 1/3 · id: intro · markdown ---"));
+    assert!(output.contains("# Analysis\nSome notes"));
+    assert!(output.contains("--- Cell 2/3 · id: load · code [3] ---"));
+    assert!(output.contains("print('hi')\n[output]\nhi"));
+    assert!(output.contains("[image/png output omitted]"));
+    assert!(output.contains("row 49"));
+    assert!(!output.contains("row 50"));
+    assert!(output.contains("... (output truncated)"));
+    assert!(output.contains("ValueError: bad value"));
+    assert!(!output.contains("\"nbformat\""));
+    assert_eq!(result.metadata.get("total_cells"), Some(&json!(3)));
+
+    let result = tool
+        .execute(json!({ "file_path": "analysis.ipynb", "offset": 1, "limit": 1 }), &ctx)
+        .await
+        .unwrap();
+    assert!(!result.output.contains("id: intro"));
+    assert!(result.output.contains("id: load"));
+    assert!(!result.output.contains("id: dump"));
+    assert!(result.output.contains("Use offset=2"));
+}
+
+#[tokio::test]
+async fn test_read_reports_encoding() {
+    let fixture = TestFixture::new();
+    let mut utf16 = vec![0xFF, 0xFE];
+    utf16.extend("grüße\r\nzwei\r\n".encode_utf16().flat_map(u16::to_le_bytes));
+    std::fs::write(fixture.path().join("wide.txt"), &utf16).unwrap();
+    std::fs::write(fixture.path().join("latin1.txt"), b"caf\xE9\n").unwrap();
+
+    let tool = ReadTool::new();
+    let ctx = create_test_context(fixture.path());
+
+    let result = tool.execute(json!({ "file_path": "wide.txt" }), &ctx).await.unwrap();
+    assert!(result.output.contains("    1→grüße\n    2→zwei"));
+    assert_eq!(result.metadata.get("encoding"), Some(&json!("UTF-16LE")));
+    assert_eq!(result.metadata.get("line_ending"), Some(&json!("CRLF")));
+    assert_eq!(result.metadata.get("bom"), Some(&json!(true)));
+
+    let result = tool.execute(json!({ "file_path": "latin1.txt" }), &ctx).await.unwrap();
+    assert!(result.output.contains("    1→café"));
+    assert_eq!(result.metadata.get("encoding"), Some(&json!("windows-1252")));
+    assert_eq!(result.metadata.get("line_ending"), Some(&json!("LF")));
+}
+
 // TODO: Add more edge case tests
 // - Test line truncation (lines > 2000 chars)
 // - Test byte limit truncation
@@ -34,17 +34,29 @@ fn test_all_tools_registered() {
     assert!(registry.get("write").is_some(), "Write tool should be registered");
     assert!(registry.get("grep").is_some(), "Grep tool should be registered");
     assert!(registry.get("glob").is_some(), "Glob tool should be registered");
+    assert!(registry.get("code_intel").is_some(), "CodeIntel tool should be registered");
+    assert!(registry.get("code_outline").is_some(), "CodeOutline tool should be registered");
 
     // Phase 1 extended tools
     assert!(registry.get("edit").is_some(), "Edit tool should be registered");
+    assert!(registry.get("multi_edit").is_some(), "MultiEdit tool should be registered");
+    assert!(registry.get("apply_patch").is_some(), "ApplyPatch tool should be registered");
     assert!(registry.get("todo_write").is_some(), "TodoWrite tool should be registered");
 
     // Additional tools
     assert!(registry.get("notebook_edit").is_some(), "NotebookEdit tool should be registered");
     assert!(registry.get("bash_output").is_some(), "BashOutput tool should be registered");
+    assert!(registry.get("bash_input").is_some(), "BashInput tool should be registered");
     assert!(registry.get("kill_shell").is_some(), "KillShell tool should be registered");
     assert!(registry.get("web_fetch").is_some(), "WebFetch tool should be registered");
 
+    // Git tools
+    assert!(registry.get("git_status").is_some(), "GitStatus tool should be registered");
+    assert!(registry.get("git_diff").is_some(), "GitDiff tool should be registered");
+    assert!(registry.get("git_log").is_some(), "GitLog tool should be registered");
+    assert!(registry.get("git_blame").is_some(), "GitBlame tool should be registered");
+    assert!(registry.get("git_show").is_some(), "GitShow tool should be registered");
+
     // Interactive tools (Phase 4)
     assert!(registry.get("ask_user_question").is_some(), "AskUserQuestion tool should be registered");
     assert!(registry.get("enter_plan_mode").is_some(), "EnterPlanMode tool should be registered");
@@ -54,9 +66,9 @@ fn test_all_tools_registered() {
     assert!(registry.get("web_search").is_some(), "WebSearch tool should be registered");
     // Note: TaskTool is registered dynamically in AgentRunner, not in ToolRegistry::new()
 
-    // Total count should be 15
+    // Total count should be 25
     let definitions = registry.list_tool_definitions();
-    assert_eq!(definitions.len(), 15, "Should have exactly 15 tools registered");
+    assert_eq!(definitions.len(), 25, "Should have exactly 25 tools registered");
 }
 
 #[test]
@@ -3,7 +3,7 @@
 mod common;
 
 use common::TestFixture;
-use ok::tool::{base::*, write::WriteTool};
+use ok::tool::{base::*, read::ReadTool, write::WriteTool};
 use serde_json::json;
 use std::sync::Arc;
 
@@ -18,6 +18,14 @@ fn create_test_context(working_dir: std::path::PathBuf) -> ToolContext {
     )
 }
 
+/// Read a file through the read tool, as the model must before modifying it
+async fn read_first(ctx: &ToolContext, file_path: impl serde::Serialize) {
+    ReadTool::new()
+        .execute(json!({ "file_path": file_path }), ctx)
+        .await
+        .expect("read before modifying");
+}
+
 #[tokio::test]
 async fn test_write_new_file() {
     let fixture = TestFixture::new();
@@ -50,6 +58,7 @@ async fn test_write_overwrite_existing() {
 
     let tool = WriteTool;
     let ctx = create_test_context(fixture.path());
+    read_first(&ctx, "existing.txt").await;
 
     let params = json!({
         "file_path": "existing.txt",
@@ -74,6 +83,7 @@ async fn test_write_shows_diff() {
 
     let tool = WriteTool;
     let ctx = create_test_context(fixture.path());
+    read_first(&ctx, "diff.txt").await;
 
     let params = json!({
         "file_path": "diff.txt",
@@ -173,3 +183,55 @@ async fn test_write_multiline_content() {
 // - Test with very large files
 // - Test concurrent writes
 // - Test permission errors (if applicable)
+
+#[tokio::test]
+async fn test_write_existing_file_requires_read() {
+    let fixture = TestFixture::new();
+    fixture.create_file("existing.txt", "Old content");
+
+    let tool = WriteTool;
+    let ctx = create_test_context(fixture.path());
+
+    let params = json!({
+        "file_path": "existing.txt",
+        "content": "New content"
+    });
+
+    let result = tool.execute(params.clone(), &ctx).await;
+    assert!(matches!(result, Err(ToolError::FileNotRead(_))));
+    assert_eq!(fixture.read_file("existing.txt"), "Old content");
+
+    // A file the tool wrote itself can be overwritten again without a read
+    read_first(&ctx, "existing.txt").await;
+    tool.execute(params, &ctx).await.unwrap();
+    let params = json!({ "file_path": "existing.txt", "content": "Newer content" });
+    tool.execute(params, &ctx).await.unwrap();
+    assert_eq!(fixture.read_file("existing.txt"), "Newer content");
+}
+
+#[tokio::test]
+async fn test_write_keeps_existing_encoding_and_line_endings() {
+    let fixture = TestFixture::new();
+    let path = fixture.path().join("legacy.txt");
+    std::fs::write(&path, b"caf\xE9\r\nbar\r\n").unwrap();
+
+    let tool = WriteTool;
+    let ctx = create_test_context(fixture.path());
+    read_first(&ctx, "legacy.txt").await;
+
+    let params = json!({
+        "file_path": "legacy.txt",
+        "content": "café\nbar\nnaïve\n"
+    });
+    let result = tool.execute(params, &ctx).await.unwrap();
+    assert_eq!(result.metadata.get("encoding"), Some(&json!("windows-1252")));
+    assert_eq!(result.metadata.get("line_ending"), Some(&json!("CRLF")));
+    assert!(result.output.contains("+naïve"));
+    assert_eq!(std::fs::read(&path).unwrap(), b"caf\xE9\r\nbar\r\nna\xEFve\r\n");
+
+    // Characters the encoding cannot represent are rejected rather than mangled
+    let params = json!({ "file_path": "legacy.txt", "content": "snow ☃\n" });
+    let result = tool.execute(params, &ctx).await;
+    assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+    assert_eq!(std::fs::read(&path).unwrap(), b"caf\xE9\r\nbar\r\nna\xEFve\r\n");
+}